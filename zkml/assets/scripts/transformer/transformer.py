@@ -0,0 +1,93 @@
+#!/usr/bin/env python
+"""Exports a tiny transformer encoder layer, written with the operations supported by the onnx
+parser: the projections are linear layers applied to each token, the keys are transposed and the
+scores are divided by sqrt(d) before the softmax. With several heads, the tokens are split into a
+batch of heads, which the parser rejects."""
+
+import argparse
+import math
+from pathlib import Path
+
+import torch
+import torch.nn as nn
+
+parser = argparse.ArgumentParser(description="tiny transformer encoder generator")
+parser.add_argument("--seq-len", type=int, default=6, help="Number of tokens")
+parser.add_argument("--model-dim", type=int, default=12,
+                    help="Dimension of the tokens")
+parser.add_argument("--hidden-dim", type=int, default=20,
+                    help="Dimension of the hidden layer of the feed-forward network")
+parser.add_argument("--num-heads", type=int, default=1,
+                    help="Number of heads of the attention")
+parser.add_argument("--export", type=Path, default=Path('bench'),
+                    help="Directory to export the model to (default: bench)")
+
+args = parser.parse_args()
+if not args.export.exists() or not args.export.is_dir():
+    print(
+        f"❌ Error: export folder '{args.export}' does not exist or is not a directory.")
+    exit(1)
+
+
+class Attention(nn.Module):
+    def __init__(self, seq_len, model_dim, num_heads):
+        super(Attention, self).__init__()
+        self.query = nn.Linear(model_dim, model_dim)
+        self.key = nn.Linear(model_dim, model_dim)
+        self.value = nn.Linear(model_dim, model_dim)
+        self.output = nn.Linear(model_dim, model_dim)
+        self.seq_len = seq_len
+        self.model_dim = model_dim
+        self.num_heads = num_heads
+        self.scale = math.sqrt(model_dim // num_heads)
+
+    def split_heads(self, x):
+        # [batch, seq_len, model_dim] -> [batch, num_heads, seq_len, head_dim]
+        x = x.reshape(-1, self.seq_len, self.num_heads, self.model_dim // self.num_heads)
+        return x.transpose(1, 2)
+
+    def forward(self, x):
+        q = self.query(x)
+        k = self.key(x)
+        v = self.value(x)
+        if self.num_heads > 1:
+            q, k, v = self.split_heads(q), self.split_heads(k), self.split_heads(v)
+        scores = torch.matmul(q, k.transpose(-2, -1)) / self.scale
+        attention = torch.softmax(scores, dim=-1)
+        out = torch.matmul(attention, v)
+        if self.num_heads > 1:
+            out = out.transpose(1, 2).reshape(-1, self.seq_len, self.model_dim)
+        return self.output(out)
+
+
+class EncoderLayer(nn.Module):
+    def __init__(self, seq_len, model_dim, hidden_dim, num_heads):
+        super(EncoderLayer, self).__init__()
+        self.attention = Attention(seq_len, model_dim, num_heads)
+        self.hidden = nn.Linear(model_dim, hidden_dim)
+        self.relu = nn.ReLU()
+        self.ffn = nn.Linear(hidden_dim, model_dim)
+
+    def forward(self, x):
+        x = x + self.attention(x)
+        return x + self.ffn(self.relu(self.hidden(x)))
+
+
+model = EncoderLayer(args.seq_len, args.model_dim, args.hidden_dim, args.num_heads)
+model.eval()
+
+heads = "" if args.num_heads == 1 else f"-{args.num_heads}heads"
+model_path = args.export / f"encoder{heads}-01.onnx"
+x = torch.rand(1, args.seq_len, args.model_dim) * 2 - 1
+torch.onnx.export(model,
+                  x,
+                  model_path,
+                  export_params=True,
+                  opset_version=12,
+                  do_constant_folding=True,
+                  input_names=['input'],
+                  output_names=['output'],
+                  dynamic_axes={'input': {0: 'batch_size'},
+                                'output': {0: 'batch_size'}})
+
+print(f"Model onnx exported to {model_path}")
@@ -8,7 +8,7 @@ use std::{
 use timed_core::Output;
 use zkml::{
     model::Model,
-    quantization::{AbsoluteMax, InferenceObserver, ModelMetadata},
+    quantization::{AbsoluteMax, Calibration, DEFAULT_BIT_LEN, InferenceObserver, ModelMetadata},
 };
 
 use anyhow::{Context as CC, Result, ensure};
@@ -44,10 +44,15 @@ struct Args {
     #[arg(short, long, default_value_t = false)]
     skip_proving: bool,
 
-    /// Quantization strategy to use
+    /// Quantization strategy to use: "inference" (min/max of the calibration inputs), "percentile"
+    /// (99.99th percentile), "entropy" (KL divergence minimization) or "maxabs"
     #[arg(short, long, default_value_t = {"inference".to_string()})]
     quantization: String,
 
+    /// Number of bits of the quantized values, only supported by the calibrated strategies
+    #[arg(long, default_value_t = DEFAULT_BIT_LEN)]
+    bit_len: usize,
+
     /// Specific input indices to run inference on (comma-separated list)
     #[arg(long, value_delimiter = ',', value_parser = parse_usize)]
     run_indices: Option<Vec<usize>>,
@@ -225,27 +230,34 @@ fn run_float_model(raw_inputs: &InputJSON, model: &Model<f32>) -> Result<f32> {
 
 fn read_model(args: &Args, inputs: &InputJSON) -> Result<(Model<Element>, ModelMetadata)> {
     let calibration_inputs = inputs.filter(args.calibration_indices.as_ref());
-    match args.quantization.as_ref() {
-        "inference" => {
-            let strategy = InferenceObserver::new_with_representative_input(
-                calibration_inputs
-                    .input_data
-                    .iter()
-                    .map(|inp| vec![inp.clone()])
-                    .collect(),
-            );
-            FloatOnnxLoader::new_with_scaling_strategy(&args.onnx, strategy)
-                .with_keep_float(true)
-                .build()
-        }
+    let calibration = match args.quantization.as_ref() {
+        "inference" => Calibration::MinMax,
+        "percentile" => Calibration::Percentile(99.99),
+        "entropy" => Calibration::Entropy,
         "maxabs" => {
+            ensure!(
+                args.bit_len == DEFAULT_BIT_LEN,
+                "maxabs quantization only supports {DEFAULT_BIT_LEN} bits"
+            );
             let strategy = AbsoluteMax::new();
-            FloatOnnxLoader::new_with_scaling_strategy(&args.onnx, strategy)
+            return FloatOnnxLoader::new_with_scaling_strategy(&args.onnx, strategy)
                 .with_keep_float(true)
-                .build()
+                .build();
         }
         _ => panic!("Unsupported quantization strategy: {}", args.quantization),
-    }
+    };
+    let strategy = InferenceObserver::new_with_representative_input(
+        calibration_inputs
+            .input_data
+            .iter()
+            .map(|inp| vec![inp.clone()])
+            .collect(),
+    )
+    .with_calibration(calibration)
+    .with_bit_len(args.bit_len);
+    FloatOnnxLoader::new_with_scaling_strategy(&args.onnx, strategy)
+        .with_keep_float(true)
+        .build()
 }
 
 fn run(args: Args) -> anyhow::Result<()> {
@@ -4,6 +4,7 @@ use rayon::iter::{
     IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
 };
 
+pub mod poly_id;
 pub mod precommit;
 pub mod same_poly;
 
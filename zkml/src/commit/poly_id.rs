@@ -0,0 +1,70 @@
+//! Offsets of the ids of the polynomials committed for each node of the model.
+//! The main polynomial of a node, i.e. its weights or its output, has the id of the node. Any
+//! other polynomial committed for the node gets the id of the node plus the offset of its kind,
+//! so that the ids of different kinds never collide as long as the model has at most
+//! [`MAX_NUM_NODES`] nodes.
+use super::precommit::PolyID;
+
+/// Maximum number of nodes of a model, which is the size of the range of ids of each kind of
+/// polynomial. It also bounds the number of lookup tables, which are all of a different type.
+pub const MAX_NUM_NODES: usize = 100_000;
+
+/// Offset of the id of the polynomial of the bias of a dense layer, committed along with the matrix.
+pub(crate) const DENSE_BIAS_POLY_ID: PolyID = 100_000;
+/// Offset of the id of the polynomial of the bias of a convolution, committed along with the filter.
+pub(crate) const CONV_BIAS_POLY_ID: PolyID = 200_000;
+/// Offset of the ids of the committed table columns, which are committed along with the weights
+/// of the model: the id of a table is the offset plus its index in the lookup context.
+pub const COMMITTED_TABLE_POLY_ID: PolyID = 300_000;
+/// Offset of the ids of the polynomials of the softmax exponentials, which are committed along
+/// with the output of the layer.
+pub(crate) const SOFTMAX_EXP_POLY_ID: PolyID = 400_000;
+/// Offset of the id of the polynomial of the shift `beta` of a normalization, committed along with
+/// `gamma`.
+pub(crate) const NORM_BETA_POLY_ID: PolyID = 500_000;
+/// Offset of the ids of the polynomials of the inverse square roots of each row of a
+/// normalization, committed along with the output of the layer.
+pub(crate) const NORM_INV_SQRT_POLY_ID: PolyID = 600_000;
+/// Offset of the ids of the polynomials of the inputs of the inverse square root table of a
+/// normalization, committed along with the output of the layer.
+pub(crate) const NORM_VARIANCE_POLY_ID: PolyID = 700_000;
+/// Offset of the id of the polynomial of the bias of an affine layer, committed along with the
+/// scale.
+pub(crate) const AFFINE_BIAS_POLY_ID: PolyID = 800_000;
+/// Offset of the id of the witness polynomial of the multiplicities of the entries of an embedding
+/// table, committed along with the output of the layer.
+pub(crate) const EMBEDDING_MULTIPLICITY_POLY_ID: PolyID = 900_000;
+/// Offset of the ids of the witness polynomials of the multiplicities of the lookup tables: the id
+/// of a table is the offset plus its index in the lookup context.
+pub const TABLE_POLY_ID_OFFSET: PolyID = 1_000_000;
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+
+    #[test]
+    fn test_poly_id_offsets_disjoint() {
+        // the ids of the nodes themselves start at zero
+        let offsets = [
+            0,
+            DENSE_BIAS_POLY_ID,
+            CONV_BIAS_POLY_ID,
+            COMMITTED_TABLE_POLY_ID,
+            SOFTMAX_EXP_POLY_ID,
+            NORM_BETA_POLY_ID,
+            NORM_INV_SQRT_POLY_ID,
+            NORM_VARIANCE_POLY_ID,
+            AFFINE_BIAS_POLY_ID,
+            EMBEDDING_MULTIPLICITY_POLY_ID,
+            TABLE_POLY_ID_OFFSET,
+        ];
+        for (a, b) in offsets.iter().sorted().tuple_windows() {
+            assert!(
+                a + MAX_NUM_NODES <= *b,
+                "ids from offset {a} overlap with the ids from offset {b}"
+            );
+        }
+    }
+}
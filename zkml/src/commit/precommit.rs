@@ -9,6 +9,7 @@ use crate::{
     Claim, Element, VectorTranscript,
     commit::{aggregated_rlc, compute_beta_eval_poly, compute_betas_eval},
     layers::provable::ProveInfo,
+    lookup::context::LookupContext,
     model::Model,
 };
 use anyhow::{Context as CC, ensure};
@@ -81,12 +82,15 @@ where
     E::BaseField: Serialize + DeserializeOwned,
     E: Serialize + DeserializeOwned,
 {
+    /// Generates the context for the weights of the model and the committed columns of the
+    /// lookup tables.
     /// NOTE: it assumes the model's layers are already padded to power of two
-    pub fn generate_from_model(m: &Model<Element>) -> anyhow::Result<Self> {
+    pub fn generate_from_model(m: &Model<Element>, lookup: &LookupContext) -> anyhow::Result<Self> {
         Self::generate(
             m.provable_nodes()
                 .flat_map(|(id, l)| l.operation.commit_info(*id))
                 .flatten()
+                .chain(lookup.committed_polys())
                 .collect_vec(),
         )
     }
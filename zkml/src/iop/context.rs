@@ -1,8 +1,11 @@
 use crate::{
     Element,
+    commit::poly_id::MAX_NUM_NODES,
     iop::precommit::{self, PolyID},
     layers::{
         LayerCtx,
+        convolution::ConvPadding,
+        pooling::Pooling,
         provable::{NodeCtx, NodeId, OpInfo},
     },
     lookup::context::{LookupContext, TableType},
@@ -31,6 +34,11 @@ where
 }
 
 pub const RESHAPE_FS_ID: u64 = 0xdeadbeef;
+pub const TRANSPOSE_FS_ID: u64 = 0x7a2590e;
+pub const CONCAT_FS_ID: u64 = 0xc0ca7;
+pub const SPLIT_FS_ID: u64 = 0x5917;
+pub const AVGPOOL_FS_ID: u64 = 0xa7e9a6e;
+pub const GLOBAL_AVGPOOL_FS_ID: u64 = 0x61ba1a7e;
 
 /// Common information between prover and verifier
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -163,6 +171,12 @@ where
                 })
                 .collect::<anyhow::Result<Vec<_>>>()?;
             ctx_aux.last_output_shape = node_input_shapes;
+            ensure!(
+                id < MAX_NUM_NODES,
+                "Node id {} is beyond the maximum number of nodes {}",
+                id,
+                MAX_NUM_NODES
+            );
             let (info, new_aux) = node.step_info(id as PolyID, ctx_aux)?;
             step_infos.insert(id, NodeCtx {
                 inputs: node.inputs.clone(),
@@ -173,11 +187,11 @@ where
             shapes.insert(id, ctx_aux.last_output_shape.clone());
         }
 
-        debug!("Context : commitment generating ...");
-        let commit_ctx = precommit::Context::generate_from_model(model)
-            .context("can't generate context for commitment part")?;
         debug!("Context : lookup generation ...");
         let lookup_ctx = LookupContext::new(&ctx_aux.tables);
+        debug!("Context : commitment generating ...");
+        let commit_ctx = precommit::Context::generate_from_model(model, &lookup_ctx)
+            .context("can't generate context for commitment part")?;
         Ok(Self {
             steps_info: ModelCtx { nodes: step_infos },
             weights: commit_ctx,
@@ -192,19 +206,54 @@ where
                 LayerCtx::Dense(info) => {
                     t.append_field_element(&E::BaseField::from(info.matrix_poly_id as u64));
                     info.matrix_poly_aux.write_to_transcript(t);
+                    t.append_field_element(&E::BaseField::from(info.input_rows_num_vars as u64));
                 }
                 LayerCtx::Requant(info) => {
                     t.append_field_element(&E::BaseField::from(info.poly_id as u64));
                     t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    info.requant.write_to_transcript::<E, T>(t);
                 }
-                LayerCtx::Activation(info) => {
+                LayerCtx::Rescale(info) => {
                     t.append_field_element(&E::BaseField::from(info.poly_id as u64));
                     t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    info.multipliers_shape.iter().for_each(|d| {
+                        t.append_field_element(&E::BaseField::from(*d as u64));
+                    });
                 }
-                LayerCtx::Pooling(info) => {
-                    t.append_field_element(&E::BaseField::from(info.poolinfo.kernel_size as u64));
-                    t.append_field_element(&E::BaseField::from(info.poolinfo.stride as u64));
+                LayerCtx::Activation(info) => {
+                    t.append_field_element(&E::BaseField::from(info.poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.op.bit_len() as u64));
+                    if let Ok(TableType::Sigmoid(table)) = info.op.table_type() {
+                        t.append_field_element(&E::BaseField::from(
+                            table.input_scale().to_bits() as u64
+                        ));
+                    }
                 }
+                LayerCtx::Pooling(info) => match &info.poolinfo {
+                    Pooling::Maxpool2D(maxpool) => {
+                        t.append_field_element(&E::BaseField::from(maxpool.kernel_size as u64));
+                        t.append_field_element(&E::BaseField::from(maxpool.stride as u64));
+                        t.append_field_element(&E::BaseField::from(maxpool.pad_output as u64));
+                        let (real_height, real_width) = maxpool.real_output.unwrap_or_default();
+                        t.append_field_element(&E::BaseField::from(real_height as u64));
+                        t.append_field_element(&E::BaseField::from(real_width as u64));
+                        t.append_field_element(&E::BaseField::from(maxpool.bit_len as u64));
+                    }
+                    Pooling::AvgPool2D(avgpool) => {
+                        t.append_field_element(&E::BaseField::from(AVGPOOL_FS_ID));
+                        t.append_field_element(&E::BaseField::from(avgpool.kernel_size as u64));
+                        t.append_field_element(&E::BaseField::from(avgpool.stride as u64));
+                        let (real_height, real_width) = avgpool.real_output.unwrap_or_default();
+                        t.append_field_element(&E::BaseField::from(real_height as u64));
+                        t.append_field_element(&E::BaseField::from(real_width as u64));
+                    }
+                    Pooling::GlobalAveragePool(global) => {
+                        t.append_field_element(&E::BaseField::from(GLOBAL_AVGPOOL_FS_ID));
+                        t.append_field_element(&E::BaseField::from(global.height as u64));
+                        t.append_field_element(&E::BaseField::from(global.width as u64));
+                    }
+                },
                 LayerCtx::Table(info) => {
                     t.append_field_element(&E::BaseField::from(info.poly_id as u64));
                     t.append_field_element(&E::BaseField::from(info.num_vars as u64));
@@ -213,6 +262,14 @@ where
                 LayerCtx::Convolution(info) => {
                     t.append_field_element(&E::BaseField::from(info.poly_id as u64));
                     t.append_field_element(&E::BaseField::from(info.bias_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.stride as u64));
+                    t.append_field_element(&E::BaseField::from(info.groups as u64));
+                    // the zero padding is fixed when the convolution is padded for proving
+                    if let ConvPadding::Explicit(pads) = info.padding {
+                        pads.iter().for_each(|pad| {
+                            t.append_field_element(&E::BaseField::from(*pad as u64))
+                        });
+                    }
 
                     for i in 0..info.delegation_fft.len() {
                         info.delegation_fft[i].write_to_transcript(t);
@@ -224,10 +281,95 @@ where
                     info.ifft_aux.write_to_transcript(t);
                     info.hadamard.write_to_transcript(t);
                 }
+                LayerCtx::Add(info) => {
+                    t.append_field_element(&E::BaseField::from(info.poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    t.append_field_element_exts(&info.multipliers_as_fields::<E>());
+                }
+                LayerCtx::MatMul(info) => {
+                    t.append_field_element(&E::BaseField::from(info.inner_dim as u64));
+                    t.append_field_element(&E::BaseField::from(info.transpose_right as u64));
+                    info.num_vars
+                        .iter()
+                        .for_each(|nv| t.append_field_element(&E::BaseField::from(*nv as u64)));
+                }
+                LayerCtx::Softmax(info) => {
+                    t.append_field_element(&E::BaseField::from(info.poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.exp_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.row_num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(
+                        info.table.input_scale().to_bits() as u64
+                    ));
+                    t.append_field_element(&E::BaseField::from(info.table.bit_len() as u64));
+                }
+                LayerCtx::TopK(info) => {
+                    t.append_field_element(&E::BaseField::from(info.k as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_entries as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.bit_len as u64));
+                }
+                LayerCtx::Normalization(info) => {
+                    t.append_field_element(&E::BaseField::from(info.kind as u64));
+                    t.append_field_element(&E::BaseField::from(info.gamma_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.beta_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.inv_sqrt_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.variance_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.row_num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.table.num_entries() as u64));
+                    t.append_field_element(&E::BaseField::from(
+                        info.table.epsilon().to_bits() as u64
+                    ));
+                    t.append_field_element(&E::BaseField::from(info.table.bit_len() as u64));
+                }
+                LayerCtx::Affine(info) => {
+                    t.append_field_element(&E::BaseField::from(info.scale_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.bias_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.num_vars as u64));
+                    info.inner_shape.iter().for_each(|d| {
+                        t.append_field_element(&E::BaseField::from(*d as u64));
+                    });
+                }
+                LayerCtx::Embedding(info) => {
+                    t.append_field_element(&E::BaseField::from(info.table_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.multiplicity_poly_id as u64));
+                    t.append_field_element(&E::BaseField::from(info.dim_num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.table_num_vars as u64));
+                    t.append_field_element(&E::BaseField::from(info.output_num_vars as u64));
+                }
                 LayerCtx::SchoolBookConvolution(_info) => {}
                 LayerCtx::Flatten => {
                     t.append_field_element(&E::BaseField::from(RESHAPE_FS_ID as u64));
                 }
+                LayerCtx::Reshape(reshape) => {
+                    t.append_field_element(&E::BaseField::from(RESHAPE_FS_ID as u64));
+                    reshape.new_shape().iter().for_each(|d| {
+                        t.append_field_element(&E::BaseField::from(*d as u64));
+                    });
+                }
+                LayerCtx::Transpose(transpose) => {
+                    t.append_field_element(&E::BaseField::from(TRANSPOSE_FS_ID));
+                    transpose.perm().iter().for_each(|p| {
+                        t.append_field_element(&E::BaseField::from(*p as u64));
+                    });
+                }
+                LayerCtx::Concat(concat) => {
+                    t.append_field_element(&E::BaseField::from(CONCAT_FS_ID));
+                    t.append_field_element(&E::BaseField::from(concat.axis() as u64));
+                    if let Some(multipliers) = concat.multipliers() {
+                        multipliers.iter().for_each(|m| {
+                            t.append_field_element(&E::BaseField::from(*m as u64));
+                        });
+                    }
+                }
+                LayerCtx::Split(split) => {
+                    t.append_field_element(&E::BaseField::from(SPLIT_FS_ID));
+                    t.append_field_element(&E::BaseField::from(split.axis() as u64));
+                    split.sizes().iter().for_each(|s| {
+                        t.append_field_element(&E::BaseField::from(*s as u64));
+                    });
+                }
             }
         }
         self.weights.write_to_transcript(t)?;
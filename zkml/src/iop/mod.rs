@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::{
-    commit::precommit,
+    commit::{precommit, same_poly},
     layers::{LayerProof, provable::NodeId},
     lookup::logup_gkr::structs::LogUpProof,
 };
@@ -24,6 +24,10 @@ where
 {
     /// The successive sumchecks proofs. From output layer to input.
     steps: HashMap<NodeId, LayerProof<E>>,
+    /// The proofs batching the claims about output wires used as input by multiple nodes.
+    /// There is an entry only for the nodes having at least one such output wire, with
+    /// one item per output wire of the node (`None` if the wire has a single edge).
+    fan_out: HashMap<NodeId, Vec<Option<same_poly::Proof<E>>>>,
     /// The proofs for any lookup tables used
     table_proofs: Vec<TableProof<E>>,
     /// the commitment proofs related to the weights
@@ -3,19 +3,23 @@ use std::collections::HashMap;
 use super::{ChallengeStorage, Context, Proof, TableProof};
 use crate::{
     Claim, Element, VectorTranscript,
-    commit::{compute_betas_eval, precommit},
+    commit::{
+        compute_betas_eval,
+        poly_id::{COMMITTED_TABLE_POLY_ID, TABLE_POLY_ID_OFFSET},
+        precommit, same_poly,
+    },
     layers::{
         LayerProof,
         provable::{NodeId, OpInfo, ProvableOp},
     },
     lookup::{
-        context::{TABLE_POLY_ID_OFFSET, generate_lookup_witnesses},
+        context::generate_lookup_witnesses,
         logup_gkr::{prover::batch_prove as logup_batch_prove, structs::LogUpInput},
     },
-    model::{InferenceStep, InferenceTrace, ToIterator},
+    model::{InferenceStep, InferenceTrace, StepData, ToIterator},
     tensor::get_root_of_unity,
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, ensure};
 use ff_ext::ExtensionField;
 
 use itertools::Itertools;
@@ -39,6 +43,8 @@ where
     ctx: &'a Context<E>,
     // proofs for each layer being filled
     proofs: HashMap<NodeId, LayerProof<E>>,
+    // proofs batching the claims about output wires with multiple edges
+    fan_out_proofs: HashMap<NodeId, Vec<Option<same_poly::Proof<E>>>>,
     table_proofs: Vec<TableProof<E>>,
     pub(crate) transcript: &'a mut T,
     pub(crate) commit_prover: precommit::CommitProver<E>,
@@ -50,7 +56,7 @@ where
     /// The prover related to proving multiple claims about different witness polyy (io of lookups etc)
     pub(crate) witness_prover: precommit::CommitProver<E>,
     /// The lookup witnesses
-    pub(crate) lookup_witness: HashMap<NodeId, LogUpInput<E>>,
+    pub(crate) lookup_witness: HashMap<NodeId, Vec<LogUpInput<E>>>,
     /// The Lookup table witness
     pub(crate) table_witness: Vec<LogUpInput<E>>,
     /// Stores all the challenges for the different lookup/table types
@@ -69,6 +75,7 @@ where
             ctx,
             transcript,
             proofs: Default::default(),
+            fan_out_proofs: Default::default(),
             table_proofs: Vec::default(),
             commit_prover: precommit::CommitProver::new(),
             // at this step, we can't build the ctx since we don't know the individual polys
@@ -80,7 +87,19 @@ where
         }
     }
 
+    /// Returns the lookup witness of node `id`, for nodes looking up into a single table
     pub(crate) fn lookup_witness(&mut self, id: NodeId) -> anyhow::Result<LogUpInput<E>> {
+        let mut witnesses = self.lookup_witnesses(id)?;
+        ensure!(
+            witnesses.len() == 1,
+            "Found {} lookup witnesses for node {id}, expected 1",
+            witnesses.len()
+        );
+        Ok(witnesses.pop().unwrap())
+    }
+
+    /// Returns the lookup witnesses of node `id`, in the order they were generated
+    pub(crate) fn lookup_witnesses(&mut self, id: NodeId) -> anyhow::Result<Vec<LogUpInput<E>>> {
         self.lookup_witness
             .remove(&id)
             .ok_or(anyhow!("No lookup witness found for node {id}!"))
@@ -90,28 +109,81 @@ where
         self.proofs.insert(node_id, proof);
     }
 
+    /// Batches the claims found for each output wire of node `node_id`. If an output wire is used
+    /// as input by more than one node, there are several claims about the same output tensor: these
+    /// are accumulated in a single claim with the `same_poly` protocol. The resulting claims, one
+    /// per output wire, are the ones to be employed to prove the node.
+    fn batch_output_claims(
+        &mut self,
+        node_id: NodeId,
+        claims: Vec<Vec<&Claim<E>>>,
+        step_data: &StepData<E, E>,
+    ) -> anyhow::Result<Vec<Claim<E>>> {
+        let outputs = step_data.outputs.outputs();
+        ensure!(
+            outputs.len() == claims.len(),
+            "Found {} output claims for node {node_id}, but {} outputs in the trace",
+            claims.len(),
+            outputs.len(),
+        );
+        let mut proofs = Vec::with_capacity(claims.len());
+        let mut batched_claims = Vec::with_capacity(claims.len());
+        for (wire_claims, output) in claims.into_iter().zip(outputs) {
+            if wire_claims.len() == 1 {
+                proofs.push(None);
+                batched_claims.push(wire_claims[0].clone());
+                continue;
+            }
+            let output_mle = output.get_data().to_vec().into_mle();
+            let sp_ctx = same_poly::Context::<E>::new(output_mle.num_vars());
+            let mut sp_prover = same_poly::Prover::<E>::new(output_mle);
+            for claim in wire_claims {
+                sp_prover.add_claim(claim.clone())?;
+            }
+            let proof = sp_prover.prove(&sp_ctx, self.transcript)?;
+            batched_claims.push(proof.extract_claim());
+            proofs.push(Some(proof));
+        }
+        if proofs.iter().any(Option::is_some) {
+            self.fan_out_proofs.insert(node_id, proofs);
+        }
+        Ok(batched_claims)
+    }
+
     #[timed::timed_instrument(level = "debug")]
     fn prove_tables(&mut self) -> anyhow::Result<()> {
-        let mut poly_id = TABLE_POLY_ID_OFFSET;
-
+        // local tables are proven by the nodes looking up into them
+        let tables = self
+            .ctx
+            .lookup
+            .iter()
+            .enumerate()
+            .filter(|(_, table_type)| !table_type.is_local());
         self.table_witness
             .iter()
-            .zip(self.ctx.lookup.iter())
-            .try_for_each(|(table_witness, _table_type)| {
+            .zip(tables)
+            .try_for_each(|(table_witness, (i, table_type))| {
                 // Make the proof for the table
                 let table_proof = logup_batch_prove(&table_witness, self.transcript)?;
 
                 // Add the multiplicity poly claim
                 self.witness_prover.add_claim(
-                    poly_id,
+                    TABLE_POLY_ID_OFFSET + i,
                     table_proof.output_claims().first().unwrap().clone(),
                 )?;
 
+                // The output column of a committed table is opened along with the weights
+                if table_type.is_committed() {
+                    self.commit_prover.add_claim(
+                        COMMITTED_TABLE_POLY_ID + i,
+                        table_proof.output_claims().last().unwrap().clone(),
+                    )?;
+                }
+
                 self.table_proofs.push(TableProof {
                     lookup: table_proof,
                 });
 
-                poly_id += 1;
                 Ok(())
             })
     }
@@ -419,13 +491,20 @@ where
                 "Proving node with id {node_id}: {:?}",
                 node_operation.describe()
             );
-            let claims_for_prove = ctx.claims_for_node(&claims_by_layer, &out_claims)?;
+            let claims_for_node = ctx.claims_for_node(&claims_by_layer, &out_claims)?;
+            let claims_for_prove = self.batch_output_claims(node_id, claims_for_node, step_data)?;
             let claims = if node_operation.is_provable() {
-                node_operation.prove(node_id, &ctx.ctx, claims_for_prove, step_data, &mut self)?
+                node_operation.prove(
+                    node_id,
+                    &ctx.ctx,
+                    claims_for_prove.iter().collect(),
+                    step_data,
+                    &mut self,
+                )?
             } else {
                 // we only propagate the claims, without changing them, as a non-provable layer
                 // shouldn't change the input values
-                claims_for_prove.into_iter().cloned().collect()
+                claims_for_prove
             };
             claims_by_layer.insert(node_id, claims);
         }
@@ -441,6 +520,7 @@ where
             .prove(&self.ctx.weights, self.transcript)?;
         let mut output_proof = Proof {
             steps: self.proofs,
+            fan_out: self.fan_out_proofs,
             table_proofs: self.table_proofs,
             commit: commit_proof,
             witness: None,
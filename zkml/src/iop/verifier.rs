@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     Claim, VectorTranscript,
-    commit::{self, precommit},
+    commit::{self, poly_id::COMMITTED_TABLE_POLY_ID, precommit, same_poly},
     iop::{ChallengeStorage, context::ShapeStep},
     layers::{
         LayerProof,
@@ -59,6 +59,37 @@ where
         }
     }
 
+    /// Verifies the batching of the claims found for each output wire of node `node_id`
+    /// which is used as input by more than one node. Returns one claim per output wire.
+    fn batch_output_claims(
+        &mut self,
+        node_id: NodeId,
+        claims: Vec<Vec<&Claim<E>>>,
+        proofs: Option<&Vec<Option<same_poly::Proof<E>>>>,
+    ) -> anyhow::Result<Vec<Claim<E>>> {
+        claims
+            .into_iter()
+            .enumerate()
+            .map(|(i, wire_claims)| {
+                if wire_claims.len() == 1 {
+                    return Ok(wire_claims[0].clone());
+                }
+                let proof = proofs
+                    .and_then(|proofs| proofs.get(i))
+                    .and_then(Option::as_ref)
+                    .ok_or(anyhow!(
+                        "Batching proof for output {i} of node {node_id} not found"
+                    ))?;
+                let sp_ctx = same_poly::Context::<E>::new(wire_claims[0].point.len());
+                let mut sp_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+                for claim in wire_claims {
+                    sp_verifier.add_claim(claim.clone())?;
+                }
+                sp_verifier.verify(proof, self.transcript)
+            })
+            .collect()
+    }
+
     pub(crate) fn verify(
         mut self,
         ctx: Context<E>,
@@ -179,16 +210,22 @@ where
                 "VERIFIER: Verifying proof {} for node {node_id}",
                 node_proof.variant_name(),
             );
-            let claims_for_verify = step.claims_for_node(&claims_by_layer, &out_claims)?;
+            let claims_for_node = step.claims_for_node(&claims_by_layer, &out_claims)?;
+            let claims_for_verify =
+                self.batch_output_claims(node_id, claims_for_node, proof.fan_out.get(&node_id))?;
             let claims = {
                 if step.ctx.is_provable() {
                     // we verify the proof
-                    step.ctx
-                        .verify(node_proof, &claims_for_verify, &mut self, shape_step)?
+                    step.ctx.verify(
+                        node_proof,
+                        &claims_for_verify.iter().collect_vec(),
+                        &mut self,
+                        shape_step,
+                    )?
                 } else {
                     // we only propagate the claims, without changing them, as a non-provable layer
                     // shouldn't change the input values
-                    claims_for_verify.into_iter().cloned().collect()
+                    claims_for_verify
                 }
             };
             claims_by_layer.insert(node_id, claims);
@@ -197,12 +234,18 @@ where
         let input_claims = NodeCtx::input_claims(ctx.steps_info.nodes.iter(), &claims_by_layer)?;
 
         // 5. Verify the lookup table proofs
+        // local tables are verified by the nodes looking up into them
         let mut table_poly_id = proof.steps.len();
+        let tables = ctx
+            .lookup
+            .iter()
+            .enumerate()
+            .filter(|(_, table_type)| !table_type.is_local());
         proof
             .table_proofs
             .iter()
-            .zip(ctx.lookup.iter())
-            .try_for_each(|(table_proof, table_type)| {
+            .zip(tables)
+            .try_for_each(|(table_proof, (i, table_type))| {
                 let (constant_challenge, column_separation_challenge) = self
                     .challenge_storage
                     .as_ref()
@@ -217,7 +260,9 @@ where
                     table_proof,
                     *table_type,
                     table_poly_id,
+                    COMMITTED_TABLE_POLY_ID + i,
                     &mut self.witness_verifier,
+                    &mut self.commit_verifier,
                     self.transcript,
                     constant_challenge,
                     column_separation_challenge,
@@ -232,15 +277,18 @@ where
             .iter()
             .zip(input_claims)
             .enumerate()
-            .map(|(i, (input, claim))| {
+            .map(|(i, (input, claims))| {
                 let input_mle = input.get_data().to_vec().into_mle();
-                let computed_randomized_input = input_mle.evaluate(&claim.point);
-                let given_randomized_input = claim.eval;
-                ensure!(
-                    computed_randomized_input == given_randomized_input,
-                    "input {} not valid from proof",
-                    i
-                );
+                // an input can be used by several nodes, so we check all the claims about it
+                for claim in claims {
+                    let computed_randomized_input = input_mle.evaluate(&claim.point);
+                    let given_randomized_input = claim.eval;
+                    ensure!(
+                        computed_randomized_input == given_randomized_input,
+                        "input {} not valid from proof",
+                        i
+                    );
+                }
                 Ok(())
             })
             .fold_ok((), |_, _| ())?;
@@ -290,7 +338,9 @@ fn verify_table<E: ExtensionField, T: Transcript<E>>(
     proof: &TableProof<E>,
     table_type: TableType,
     poly_id: usize,
+    committed_poly_id: usize,
     witness_verifier: &mut commit::precommit::CommitVerifier<E>,
+    commit_verifier: &mut commit::precommit::CommitVerifier<E>,
     t: &mut T,
     constant_challenge: E,
     column_separation_challenge: E,
@@ -319,11 +369,13 @@ where
     )?;
     // Hard indexing is okay here because we checked above that at least one claim exists
     let expected_claim_evals = table_type.evaluate_table_columns::<E>(&poly_claims[0].point)?;
+    // the output column of a committed table has no closed form evaluation
+    let num_committed_columns = if table_type.is_committed() { 1 } else { 0 };
 
     ensure!(
-        expected_claim_evals.len() == (poly_claims.len() - 1),
+        expected_claim_evals.len() + num_committed_columns == (poly_claims.len() - 1),
         "Expected {} table column evaluation claims, got {}",
-        expected_claim_evals.len(),
+        expected_claim_evals.len() + num_committed_columns,
         poly_claims.len() - 1
     );
     for (poly_claim, expected) in poly_claims[1..].iter().zip(expected_claim_evals.iter()) {
@@ -334,5 +386,12 @@ where
             expected
         );
     }
+    // 3. Accumulate the committed column claim into the commitment protocol of the weights
+    if table_type.is_committed() {
+        commit_verifier.add_claim(
+            committed_poly_id,
+            poly_claims[poly_claims.len() - 1].clone(),
+        )?;
+    }
     Ok(())
 }
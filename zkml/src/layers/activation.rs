@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::{
-    Claim, Element, Prover,
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy,
     commit::same_poly,
     iop::{
         context::{ContextAux, ShapeStep},
@@ -16,8 +16,8 @@ use crate::{
         },
     },
     model::StepData,
-    padding::PaddingMode,
-    quantization::Fieldizer,
+    padding::{PaddingMode, ShapeInfo, activation},
+    quantization::{self, Fieldizer},
     tensor::Number,
 };
 use ff_ext::ExtensionField;
@@ -27,10 +27,11 @@ use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use transcript::Transcript;
 
-use crate::{quantization::BIT_LEN, tensor::Tensor};
+use crate::tensor::Tensor;
 
 use super::provable::{
-    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx,
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx, ZeroPointSupport,
 };
 
 use anyhow::{Result, anyhow, ensure};
@@ -38,9 +39,15 @@ use anyhow::{Result, anyhow, ensure};
 #[derive(Clone, Debug, Serialize, Deserialize, Copy)]
 pub enum Activation {
     Relu(Relu),
+    Sigmoid(Sigmoid),
+    // activations proven with a committed lookup table, e.g. GELU
+    Committed(CommittedActivation),
+    LeakyRelu(LeakyRelu),
+    // also covers ReLU6, see `Clip::relu6`
+    Clip(Clip),
 }
 
-/// Currently holds the poly info for the output polynomial of the RELU
+/// Currently holds the poly info for the output polynomial of the activation
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActivationCtx {
     pub op: Activation,
@@ -56,7 +63,7 @@ where
     /// proof for the accumulation of the claim from m2v + claim from lookup for the same poly
     /// e.g. the "link" between a m2v and relu layer
     pub(crate) io_accumulation: same_poly::Proof<E>,
-    /// the lookup proof for the relu
+    /// the lookup proof for the activation
     pub(crate) lookup: LogUpProof<E>,
 }
 
@@ -66,7 +73,35 @@ impl OpInfo for Activation {
     }
 
     fn describe(&self) -> String {
-        format!("RELU: {}", 1 << Relu::num_vars())
+        match self {
+            Activation::Relu(relu) => format!(
+                "RELU: {}, zero point {}",
+                1 << relu.num_vars(),
+                relu.zero_point
+            ),
+            Activation::Sigmoid(sigmoid) => match sigmoid.table {
+                Some(table) => format!(
+                    "SIGMOID: {}, input scale {}",
+                    1 << self.bit_len(),
+                    table.input_scale()
+                ),
+                None => "SIGMOID".to_string(),
+            },
+            Activation::Committed(activation) => {
+                format!("{}: {}", activation.function.name(), 1 << self.bit_len())
+            }
+            Activation::LeakyRelu(leaky_relu) => format!(
+                "LEAKY_RELU: {}, alpha {}",
+                1 << self.bit_len(),
+                leaky_relu.alpha
+            ),
+            Activation::Clip(clip) => format!(
+                "CLIP: {}, bounds [{:?}, {:?}]",
+                1 << self.bit_len(),
+                clip.min,
+                clip.max
+            ),
+        }
     }
 
     fn output_shapes(
@@ -82,12 +117,12 @@ impl OpInfo for Activation {
     }
 }
 
-impl<N: Number> Evaluate<N> for Activation {
+impl Evaluate<f32> for Activation {
     fn evaluate<E: ExtensionField>(
         &self,
-        inputs: &[&Tensor<N>],
+        inputs: &[&Tensor<f32>],
         _unpadded_input_shapes: Vec<Vec<usize>>,
-    ) -> Result<LayerOut<N, E>> {
+    ) -> Result<LayerOut<f32, E>> {
         ensure!(
             inputs.len() == 1,
             "Found more than 1 input when evaluating activation layer"
@@ -95,18 +130,148 @@ impl<N: Number> Evaluate<N> for Activation {
         let input = inputs[0];
         let output = match self {
             Activation::Relu(relu) => relu.op(input),
+            Activation::Sigmoid(sigmoid) => sigmoid.op(input),
+            Activation::Committed(activation) => activation.op(input),
+            Activation::LeakyRelu(leaky_relu) => leaky_relu.op(input),
+            Activation::Clip(clip) => clip.op(input),
         };
         Ok(LayerOut::from_vec(vec![output]))
     }
 }
 
+impl Evaluate<Element> for Activation {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found more than 1 input when evaluating activation layer"
+        );
+        let input = inputs[0];
+        let output = match self {
+            Activation::Relu(relu) => relu.quantized_op(input),
+            Activation::Sigmoid(sigmoid) => sigmoid.quantized_op(input)?,
+            Activation::Committed(activation) => activation.quantized_op(input)?,
+            Activation::LeakyRelu(leaky_relu) => leaky_relu.quantized_op(input),
+            Activation::Clip(clip) => clip.quantized_op(input)?,
+        };
+        Ok(LayerOut::from_vec(vec![output]))
+    }
+}
+
+impl QuantizeOp for Activation {
+    type QuantizedOp = Activation;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        _data: &S::AuxData,
+        _node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        Ok(match self {
+            Activation::Relu(_) => {
+                ensure!(
+                    input_scaling.len() == 1,
+                    "Found {} input scaling factors for relu layer, expected 1",
+                    input_scaling.len()
+                );
+                // the inputs are compared to the quantized float zero, i.e. the zero point of the
+                // input, and the output keeps the scaling factor of the input
+                QuantizeOutput {
+                    quanzited_op: Activation::Relu(Relu {
+                        zero_point: input_scaling[0].zero_point(),
+                        bit_len: input_scaling[0].bit_len(),
+                    }),
+                    output_scalings: input_scaling.to_vec(),
+                    requant_layer: None,
+                    rescale_layer: None,
+                }
+            }
+            Activation::Sigmoid(_) => {
+                ensure!(
+                    input_scaling.len() == 1,
+                    "Found {} input scaling factors for sigmoid layer, expected 1",
+                    input_scaling.len()
+                );
+                // the table maps the quantized inputs to the quantized outputs, so there is no
+                // need to requantize the output
+                let table = SigmoidTable::new(&input_scaling[0]);
+                QuantizeOutput {
+                    quanzited_op: Activation::Sigmoid(Sigmoid { table: Some(table) }),
+                    output_scalings: vec![table.output_scaling()],
+                    requant_layer: None,
+                    rescale_layer: None,
+                }
+            }
+            Activation::Committed(activation) => {
+                ensure!(
+                    input_scaling.len() == 1,
+                    "Found {} input scaling factors for {} layer, expected 1",
+                    input_scaling.len(),
+                    activation.function.name()
+                );
+                let table = CommittedTable::new(activation.function, &input_scaling[0]);
+                QuantizeOutput {
+                    quanzited_op: Activation::Committed(CommittedActivation {
+                        function: activation.function,
+                        table: Some(table),
+                    }),
+                    output_scalings: vec![table.output_scaling()],
+                    requant_layer: None,
+                    rescale_layer: None,
+                }
+            }
+            Activation::LeakyRelu(leaky_relu) => {
+                // the negative inputs are scaled down, so the outputs stay in the quantized range
+                ensure!(
+                    (0.0..=1.0).contains(&leaky_relu.alpha),
+                    "Leaky ReLU slope {} must be in [0, 1] to be quantized",
+                    leaky_relu.alpha
+                );
+                ensure!(
+                    input_scaling.len() == 1,
+                    "Found {} input scaling factors for leaky relu layer, expected 1",
+                    input_scaling.len()
+                );
+                QuantizeOutput {
+                    quanzited_op: Activation::LeakyRelu(LeakyRelu {
+                        bit_len: input_scaling[0].bit_len(),
+                        ..leaky_relu
+                    }),
+                    output_scalings: input_scaling.to_vec(),
+                    requant_layer: None,
+                    rescale_layer: None,
+                }
+            }
+            Activation::Clip(clip) => {
+                ensure!(
+                    input_scaling.len() == 1,
+                    "Found {} input scaling factors for clip layer, expected 1",
+                    input_scaling.len()
+                );
+                QuantizeOutput {
+                    quanzited_op: Activation::Clip(Clip {
+                        table: Some(ClipTable::new(clip.min, clip.max, &input_scaling[0])),
+                        ..clip
+                    }),
+                    output_scalings: input_scaling.to_vec(),
+                    requant_layer: None,
+                    rescale_layer: None,
+                }
+            }
+        })
+    }
+}
+
 impl<E> ProveInfo<E> for Activation
 where
     E: ExtensionField + DeserializeOwned,
     E::BaseField: Serialize + DeserializeOwned,
 {
     fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
-        aux.tables.insert(TableType::Relu);
+        aux.tables.insert(self.table_type()?);
         let num_vars = aux
             .last_output_shape
             .iter_mut()
@@ -121,18 +286,23 @@ where
                 Ok(Some(num_vars))
             })?
             .expect("No input shape found for activation layer?");
-        let info = match self {
-            Activation::Relu(relu) => LayerCtx::Activation(ActivationCtx {
-                op: Activation::Relu(*relu),
-                poly_id: id,
-                num_vars,
-            }),
-        };
+        let info = LayerCtx::Activation(ActivationCtx {
+            op: *self,
+            poly_id: id,
+            num_vars,
+        });
         Ok((info, aux))
     }
 }
 
-impl PadOp for Activation {}
+impl PadOp for Activation {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        activation(self, si)
+    }
+}
 
 impl<E> ProvableOp<E> for Activation
 where
@@ -165,7 +335,8 @@ where
         gen: &mut LookupWitnessGen<E>,
         step_data: &StepData<Element, E>,
     ) -> Result<()> {
-        gen.tables.insert(TableType::Relu);
+        let table_type = self.table_type()?;
+        gen.tables.insert(table_type);
 
         ensure!(
             step_data.inputs.len() == 1,
@@ -195,7 +366,7 @@ where
         let (col_one, col_two): (Vec<E::BaseField>, Vec<E::BaseField>) = field.into_iter().unzip();
         let table_lookup_map = gen
             .lookups
-            .entry(TableType::Relu)
+            .entry(table_type)
             .or_insert_with(|| HashMap::default());
 
         merged_lookups
@@ -211,7 +382,7 @@ where
                 .collect(),
         ));
         gen.lookups_no_challenges
-            .insert(id, (vec![col_one, col_two], 2, TableType::Relu));
+            .insert(id, vec![(vec![col_one, col_two], 2, table_type)]);
 
         Ok(())
     }
@@ -223,19 +394,19 @@ impl OpInfo for ActivationCtx {
         input_shapes: &[Vec<usize>],
         padding_mode: PaddingMode,
     ) -> Vec<Vec<usize>> {
-        Activation::Relu(Relu).output_shapes(input_shapes, padding_mode)
+        self.op.output_shapes(input_shapes, padding_mode)
     }
 
     fn num_outputs(&self, num_inputs: usize) -> usize {
-        Activation::Relu(Relu).num_outputs(num_inputs)
+        self.op.num_outputs(num_inputs)
     }
 
     fn describe(&self) -> String {
-        Activation::Relu(Relu).describe()
+        self.op.describe()
     }
 
     fn is_provable(&self) -> bool {
-        Activation::Relu(Relu).is_provable()
+        self.op.is_provable()
     }
 }
 
@@ -254,14 +425,15 @@ where
         verifier: &mut Verifier<E, T>,
         _shape_step: &ShapeStep,
     ) -> Result<Vec<Claim<E>>> {
+        let table_type = self.op.table_type()?;
         let (constant_challenge, column_separation_challenge) = verifier
             .challenge_storage
             .as_ref()
             .unwrap()
-            .get_challenges_by_name(&TableType::Relu.name())
+            .get_challenges_by_name(&table_type.name())
             .ok_or(anyhow!(
                 "Couldn't get challenges for LookupType: {}",
-                TableType::Relu.name()
+                table_type.name()
             ))?;
         Ok(vec![self.verify_activation(
             verifier,
@@ -274,6 +446,55 @@ where
 }
 
 impl Activation {
+    /// Returns how the activation deals with the zero point of its input
+    pub(crate) fn zero_point_support(&self) -> ZeroPointSupport {
+        match self {
+            // the zero point is quantized along with the bounds of the activation
+            Activation::Relu(_) | Activation::Clip(_) => ZeroPointSupport::Forwarded,
+            Activation::Sigmoid(_) | Activation::Committed(_) | Activation::LeakyRelu(_) => {
+                ZeroPointSupport::Unsupported
+            }
+        }
+    }
+
+    /// Number of bits of the quantized inputs, over which the lookup table of the activation is defined
+    pub(crate) fn bit_len(&self) -> usize {
+        let table_bit_len = |table: Option<TableType>| {
+            table.map_or(quantization::DEFAULT_BIT_LEN, |table| table.bit_len())
+        };
+        match self {
+            Activation::Relu(relu) => relu.bit_len,
+            Activation::Sigmoid(sigmoid) => table_bit_len(sigmoid.table.map(TableType::Sigmoid)),
+            Activation::Committed(activation) => {
+                table_bit_len(activation.table.map(TableType::Committed))
+            }
+            Activation::LeakyRelu(leaky_relu) => leaky_relu.bit_len,
+            Activation::Clip(clip) => table_bit_len(clip.table.map(TableType::Clip)),
+        }
+    }
+
+    /// Returns the lookup table used to prove the activation
+    pub(crate) fn table_type(&self) -> Result<TableType> {
+        match self {
+            Activation::Relu(relu) => Ok(TableType::Relu(relu.table())),
+            Activation::Sigmoid(sigmoid) => sigmoid
+                .table
+                .map(TableType::Sigmoid)
+                .ok_or(anyhow!("Sigmoid activation must be quantized to be proven")),
+            Activation::Committed(activation) => {
+                activation.table.map(TableType::Committed).ok_or(anyhow!(
+                    "{} activation must be quantized to be proven",
+                    activation.function.name()
+                ))
+            }
+            Activation::LeakyRelu(leaky_relu) => Ok(TableType::LeakyRelu(leaky_relu.table())),
+            Activation::Clip(clip) => clip
+                .table
+                .map(TableType::Clip)
+                .ok_or(anyhow!("Clip activation must be quantized to be proven")),
+        }
+    }
+
     #[timed::timed_instrument(name = "Prover::prove_activation_step")]
     pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
         &self,
@@ -360,21 +581,41 @@ impl ActivationCtx {
     }
 }
 
+/// ReLU activation. Once quantized, it carries the zero point of its input, i.e. the quantized
+/// value of the float zero the inputs are compared to, and the bit length of its input.
 #[derive(Clone, Debug, Copy, Serialize, Deserialize)]
-pub struct Relu;
+pub struct Relu {
+    #[serde(default)]
+    zero_point: Element,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl Default for Relu {
+    fn default() -> Self {
+        Self {
+            zero_point: 0,
+            bit_len: quantization::DEFAULT_BIT_LEN,
+        }
+    }
+}
 
 impl Relu {
     pub fn new() -> Relu {
-        Self
+        Self::default()
+    }
+
+    pub fn table(&self) -> ReluTable {
+        ReluTable::new(self.zero_point, self.bit_len)
     }
-    pub fn num_vars() -> usize {
-        *BIT_LEN
+    pub fn num_vars(&self) -> usize {
+        self.bit_len
     }
-    pub fn poly_len() -> usize {
-        1 << Self::num_vars()
+    pub fn poly_len(&self) -> usize {
+        1 << self.num_vars()
     }
-    pub fn shape() -> Vec<usize> {
-        vec![2, Self::poly_len()]
+    pub fn shape(&self) -> Vec<usize> {
+        vec![2, self.poly_len()]
     }
 
     pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
@@ -388,18 +629,640 @@ impl Relu {
         )
     }
 
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Tensor<Element> {
+        let table = self.table();
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| table.apply(*e))
+                .collect::<Vec<_>>(),
+        )
+    }
+
     #[inline(always)]
     pub fn apply<T: Number>(e: T) -> T {
         if e.is_negative() { T::default() } else { e }
     }
 }
 
+/// Lookup table of a quantized ReLU, which maps each input to the max of the input and the zero point.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ReluTable {
+    zero_point: Element,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl ReluTable {
+    pub fn new(zero_point: Element, bit_len: usize) -> Self {
+        Self {
+            zero_point,
+            bit_len,
+        }
+    }
+
+    pub fn zero_point(&self) -> Element {
+        self.zero_point
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        e.max(self.zero_point)
+    }
+}
+
+/// Sigmoid activation `1 / (1 + e^-x)`. Once quantized, it carries the lookup table mapping each
+/// quantized input to the quantized sigmoid of the corresponding float value.
+/// The output column of its table is committed. Note the sigmoid of the zero padding is not zero,
+/// so padding rejects a sigmoid whose output is consumed by any layer but a dense one.
+#[derive(Clone, Debug, Copy, Default, Serialize, Deserialize)]
+pub struct Sigmoid {
+    /// Lookup table of the quantized activation, `None` before quantization
+    table: Option<SigmoidTable>,
+}
+
+impl Sigmoid {
+    pub fn new() -> Sigmoid {
+        Self::default()
+    }
+
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| Self::apply(*e))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Result<Tensor<Element>> {
+        let table = self.table.ok_or(anyhow!(
+            "Sigmoid activation must be quantized to be evaluated over integers"
+        ))?;
+        Ok(Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| table.apply(*e))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    #[inline(always)]
+    pub fn apply(e: f32) -> f32 {
+        1.0 / (1.0 + (-e).exp())
+    }
+}
+
+/// Lookup table of a quantized sigmoid. The inputs are quantized with the scaling factor of the
+/// input of the activation, while the outputs, which lie in `[0, 1]`, are always quantized with
+/// [`SigmoidTable::output_scaling`], over the same number of bits as the inputs.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SigmoidTable {
+    /// Bit representation of the scale of the inputs, so that the table can be used as a key
+    input_scale: u32,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl SigmoidTable {
+    pub fn new(input_scaling: &ScalingFactor) -> Self {
+        Self {
+            input_scale: input_scaling.scale().to_bits(),
+            bit_len: input_scaling.bit_len(),
+        }
+    }
+
+    pub fn input_scale(&self) -> f32 {
+        f32::from_bits(self.input_scale)
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn output_scaling(&self) -> ScalingFactor {
+        ScalingFactor::from_absolute_max(1.0, Some(quantization::domain(self.bit_len)))
+    }
+
+    /// Maps a quantized input to the quantized output of the sigmoid
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        self.output_scaling()
+            .quantize(&Sigmoid::apply(e as f32 * self.input_scale()))
+    }
+}
+
+/// Activation functions whose quantized table has no closed form multilinear extension which is
+/// cheap to evaluate. They are proven with a lookup into a table whose output column is committed
+/// at setup time, along with the weights of the model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TableFunction {
+    Gelu,
+    Tanh,
+    Silu,
+}
+
+impl TableFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableFunction::Gelu => "GELU",
+            TableFunction::Tanh => "TANH",
+            TableFunction::Silu => "SILU",
+        }
+    }
+
+    #[inline(always)]
+    pub fn apply(&self, e: f32) -> f32 {
+        match self {
+            // tanh approximation of GELU, which is within the quantization error of the exact one
+            TableFunction::Gelu => {
+                0.5 * e
+                    * (1.0
+                        + ((2.0 / std::f32::consts::PI).sqrt() * (e + 0.044715 * e.powi(3))).tanh())
+            }
+            TableFunction::Tanh => e.tanh(),
+            TableFunction::Silu => e * Sigmoid::apply(e),
+        }
+    }
+
+    /// Derives the scaling factor of the outputs from the one of the inputs. GELU and SiLU are
+    /// bounded by their input in absolute value, while tanh is bounded by its value on the biggest input.
+    fn output_scaling(&self, input_scaling: &ScalingFactor) -> ScalingFactor {
+        match self {
+            TableFunction::Gelu | TableFunction::Silu => *input_scaling,
+            TableFunction::Tanh => ScalingFactor::from_absolute_max(
+                input_scaling
+                    .min()
+                    .abs()
+                    .max(input_scaling.max().abs())
+                    .tanh(),
+                Some(input_scaling.quantized_domain()),
+            ),
+        }
+    }
+}
+
+/// Activation proven with a committed lookup table, see [`TableFunction`].
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct CommittedActivation {
+    function: TableFunction,
+    /// Lookup table of the quantized activation, `None` before quantization
+    table: Option<CommittedTable>,
+}
+
+impl CommittedActivation {
+    pub fn new(function: TableFunction) -> Self {
+        Self {
+            function,
+            table: None,
+        }
+    }
+
+    pub fn function(&self) -> TableFunction {
+        self.function
+    }
+
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| self.function.apply(*e))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Result<Tensor<Element>> {
+        let table = self.table.ok_or(anyhow!(
+            "{} activation must be quantized to be evaluated over integers",
+            self.function.name()
+        ))?;
+        Ok(Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| table.apply(*e))
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// Lookup table of a quantized activation proven with a committed table.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CommittedTable {
+    function: TableFunction,
+    /// Bit representation of the scales of the inputs and outputs, so that the table can be used as a key
+    input_scale: u32,
+    output_scale: u32,
+    /// Number of bits of both the inputs and the outputs
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl CommittedTable {
+    pub fn new(function: TableFunction, input_scaling: &ScalingFactor) -> Self {
+        Self {
+            function,
+            input_scale: input_scaling.scale().to_bits(),
+            output_scale: function.output_scaling(input_scaling).scale().to_bits(),
+            bit_len: input_scaling.bit_len(),
+        }
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn function(&self) -> TableFunction {
+        self.function
+    }
+
+    pub fn input_scale(&self) -> f32 {
+        f32::from_bits(self.input_scale)
+    }
+
+    pub fn output_scaling(&self) -> ScalingFactor {
+        ScalingFactor::from_scale(
+            f32::from_bits(self.output_scale),
+            Some(quantization::domain(self.bit_len)),
+        )
+    }
+
+    /// Maps a quantized input to the quantized output of the activation
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        self.output_scaling()
+            .quantize(&self.function.apply(e as f32 * self.input_scale()))
+    }
+}
+
+/// Number of fractional bits of the quantized slope of a leaky ReLU.
+pub const LEAKY_RELU_ALPHA_BITS: usize = 16;
+
+/// Leaky ReLU activation, i.e. `x` for positive inputs and `alpha * x` otherwise. The quantized
+/// activation keeps the scaling factor of its input.
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct LeakyRelu {
+    alpha: f32,
+    /// Number of bits of the quantized inputs
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl LeakyRelu {
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha,
+            bit_len: quantization::DEFAULT_BIT_LEN,
+        }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn table(&self) -> LeakyReluTable {
+        LeakyReluTable::new(self.alpha, self.bit_len)
+    }
+
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| if *e < 0.0 { self.alpha * e } else { *e })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Tensor<Element> {
+        let table = self.table();
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| table.apply(*e))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Lookup table of a quantized leaky ReLU, whose slope is quantized with [`LEAKY_RELU_ALPHA_BITS`]
+/// fractional bits.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct LeakyReluTable {
+    alpha: Element,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl LeakyReluTable {
+    pub fn new(alpha: f32, bit_len: usize) -> Self {
+        Self {
+            alpha: (alpha * (1 << LEAKY_RELU_ALPHA_BITS) as f32).round() as Element,
+            bit_len,
+        }
+    }
+
+    /// Quantized slope, i.e. `round(alpha * 2^LEAKY_RELU_ALPHA_BITS)`
+    pub fn alpha(&self) -> Element {
+        self.alpha
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Maps a quantized input to the quantized output, rounding the scaled negative inputs
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        if e.is_negative() {
+            (e * self.alpha + (1 << (LEAKY_RELU_ALPHA_BITS - 1))) >> LEAKY_RELU_ALPHA_BITS
+        } else {
+            e
+        }
+    }
+}
+
+/// Clips its input to `[min, max]`, as ONNX `Clip`: a bound which is not set is not enforced.
+/// The quantized activation keeps the scaling factor of its input.
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct Clip {
+    min: Option<f32>,
+    max: Option<f32>,
+    /// Lookup table of the quantized activation, `None` before quantization
+    table: Option<ClipTable>,
+}
+
+impl Clip {
+    pub fn new(min: Option<f32>, max: Option<f32>) -> Self {
+        Self {
+            min,
+            max,
+            table: None,
+        }
+    }
+
+    /// ReLU6, i.e. a clip to `[0, 6]`
+    pub fn relu6() -> Self {
+        Self::new(Some(0.0), Some(6.0))
+    }
+
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| {
+                    let e = self.min.map_or(*e, |min| e.max(min));
+                    self.max.map_or(e, |max| e.min(max))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Result<Tensor<Element>> {
+        let table = self.table.ok_or(anyhow!(
+            "Clip activation must be quantized to be evaluated over integers"
+        ))?;
+        Ok(Tensor::new(
+            input.get_shape(),
+            input
+                .get_data()
+                .par_iter()
+                .map(|e| table.apply(*e))
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// Lookup table of a quantized clip, with the bounds quantized with the scaling factor of the input,
+/// zero point included.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ClipTable {
+    min: Element,
+    max: Element,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl ClipTable {
+    pub fn new(min: Option<f32>, max: Option<f32>, input_scaling: &ScalingFactor) -> Self {
+        let bit_len = input_scaling.bit_len();
+        let (min_input, max_input) = (quantization::min(bit_len) - 1, quantization::max(bit_len));
+        // bounds outside of the quantized range can't be reached by the quantized inputs
+        let quantize = |bound: f32| {
+            ((bound / input_scaling.scale()).round() as Element + input_scaling.zero_point())
+                .clamp(min_input, max_input)
+        };
+        Self {
+            min: min.map_or(min_input, quantize),
+            max: max.map_or(max_input, quantize),
+            bit_len,
+        }
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn min(&self) -> Element {
+        self.min
+    }
+
+    pub fn max(&self) -> Element {
+        self.max
+    }
+
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        e.max(self.min).min(self.max)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::Element;
+    use crate::{Element, testing::random_field_vector};
+    use goldilocks::GoldilocksExt2;
+    use multilinear_extensions::mle::MultilinearExtension;
 
     use super::*;
 
+    #[test]
+    fn test_activation_sigmoid_table() {
+        let input_scaling = ScalingFactor::from_absolute_max(6.0, None);
+        let table = SigmoidTable::new(&input_scaling);
+        let output_scaling = table.output_scaling();
+        assert_eq!(table.input_scale(), input_scaling.scale());
+        let inputs = (quantization::MIN - 1..=quantization::MAX).collect::<Vec<_>>();
+        let outputs = inputs.iter().map(|i| table.apply(*i)).collect::<Vec<_>>();
+        // the quantized sigmoid is increasing, positive, and half of the output range at zero
+        assert!(outputs.windows(2).all(|w| w[0] <= w[1]));
+        assert!(outputs.iter().all(|o| *o >= 0 && *o <= quantization::MAX));
+        assert_eq!(table.apply(0), (quantization::MAX + 1) / 2);
+        for (i, o) in inputs.iter().zip(&outputs) {
+            let expected = Sigmoid::apply(input_scaling.dequantize(i));
+            assert!((output_scaling.dequantize(o) - expected).abs() <= output_scaling.scale());
+        }
+
+        // the verifier only evaluates the input column, the output column is committed
+        let point = random_field_vector::<GoldilocksExt2>(quantization::DEFAULT_BIT_LEN);
+        let table_type = TableType::Sigmoid(table);
+        assert!(table_type.is_committed());
+        let columns = table_type.evaluate_table_columns(&point).unwrap();
+        let input_mle = inputs
+            .iter()
+            .map(Fieldizer::<GoldilocksExt2>::to_field)
+            .collect::<Vec<_>>()
+            .into_mle();
+        assert_eq!(columns, vec![input_mle.evaluate(&point)]);
+        let committed = table_type.committed_column::<GoldilocksExt2>().unwrap();
+        let expected = outputs
+            .iter()
+            .map(Fieldizer::<GoldilocksExt2>::to_field)
+            .collect::<Vec<_>>();
+        assert_eq!(committed, expected);
+    }
+
+    #[test]
+    fn test_activation_committed_tables() {
+        let input_scaling = ScalingFactor::from_absolute_max(4.0, None);
+        let point = random_field_vector::<GoldilocksExt2>(quantization::DEFAULT_BIT_LEN);
+        for function in [
+            TableFunction::Gelu,
+            TableFunction::Tanh,
+            TableFunction::Silu,
+        ] {
+            let table = CommittedTable::new(function, &input_scaling);
+            let output_scaling = table.output_scaling();
+            // the zero padding is preserved by the activation
+            assert_eq!(table.apply(0), 0);
+            for i in quantization::MIN..=quantization::MAX {
+                let expected = function.apply(input_scaling.dequantize(&i));
+                let output = output_scaling.dequantize(&table.apply(i));
+                assert!(
+                    (output - expected).abs() <= output_scaling.scale(),
+                    "{}: {output} too far from {expected}",
+                    function.name()
+                );
+            }
+            // only the input column has a closed form evaluation, the output column is committed
+            let table_type = TableType::Committed(table);
+            assert!(table_type.is_committed());
+            let columns = table_type.evaluate_table_columns(&point).unwrap();
+            assert_eq!(columns.len(), 1);
+            let committed = table_type.committed_column::<GoldilocksExt2>().unwrap();
+            let expected = (quantization::MIN - 1..=quantization::MAX)
+                .map(|i| Fieldizer::<GoldilocksExt2>::to_field(&table.apply(i)))
+                .collect::<Vec<_>>();
+            assert_eq!(committed, expected);
+        }
+    }
+
+    #[test]
+    fn test_activation_leaky_relu_and_clip_tables() {
+        let input_scaling = ScalingFactor::from_absolute_max(12.7, None);
+        let leaky_relu = LeakyRelu::new(0.1).table();
+        assert_eq!(leaky_relu.apply(0), 0);
+        assert_eq!(leaky_relu.apply(57), 57);
+        assert_eq!(leaky_relu.apply(-100), -10);
+        assert_eq!(leaky_relu.apply(-14), -1);
+        // ReLU6 is a clip whose upper bound is quantized with the input scaling factor
+        let relu6 = ClipTable::new(Some(0.0), Some(6.0), &input_scaling);
+        assert_eq!((relu6.min(), relu6.max()), (0, 60));
+        assert_eq!(relu6.apply(-20), 0);
+        assert_eq!(relu6.apply(20), 20);
+        assert_eq!(relu6.apply(100), 60);
+        // unset or unreachable bounds don't change the inputs
+        let clip = ClipTable::new(None, Some(20.0), &input_scaling);
+        assert!((quantization::MIN - 1..=quantization::MAX).all(|i| clip.apply(i) == i));
+
+        // the closed form evaluation of the table columns matches their multilinear extensions, for
+        // slopes whose products need rounding and over more bits than the fractional bits of alpha
+        let wide_scaling = ScalingFactor::from_absolute_max(12.7, Some(quantization::domain(18)));
+        for table_type in [
+            TableType::LeakyRelu(leaky_relu),
+            TableType::LeakyRelu(LeakyReluTable::new(0.01, 12)),
+            TableType::LeakyRelu(LeakyReluTable::new(0.37, 18)),
+            TableType::Clip(relu6),
+            TableType::Clip(clip),
+            TableType::Clip(ClipTable::new(Some(-3.1), Some(6.0), &wide_scaling)),
+        ] {
+            let bit_len = table_type.bit_len();
+            let point = random_field_vector::<GoldilocksExt2>(bit_len);
+            let columns = table_type.evaluate_table_columns(&point).unwrap();
+            let inputs =
+                (quantization::min(bit_len) - 1..=quantization::max(bit_len)).collect::<Vec<_>>();
+            let outputs = inputs
+                .iter()
+                .map(|i| match table_type {
+                    TableType::LeakyRelu(table) => table.apply(*i),
+                    TableType::Clip(table) => table.apply(*i),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            for (column, eval) in [inputs, outputs].into_iter().zip(columns) {
+                let mle = column
+                    .iter()
+                    .map(Fieldizer::<GoldilocksExt2>::to_field)
+                    .collect::<Vec<_>>()
+                    .into_mle();
+                assert_eq!(mle.evaluate(&point), eval);
+            }
+        }
+    }
+
+    #[test]
+    fn test_activation_relu_zero_point_table() {
+        // the float zero is quantized to the zero point, so the quantized relu matches the float one
+        let input_scaling = ScalingFactor::from_asymmetric_span(-1.0, 3.0, None);
+        let relu = Relu {
+            zero_point: input_scaling.zero_point(),
+            bit_len: input_scaling.bit_len(),
+        };
+        for i in quantization::MIN..=quantization::MAX {
+            let output = relu.table().apply(i);
+            assert_eq!(
+                input_scaling.dequantize(&output),
+                input_scaling.dequantize(&i).max(0.0)
+            );
+        }
+
+        // the verifier evaluation of the table columns matches their multilinear extensions, with
+        // and without a zero point
+        let point = random_field_vector::<GoldilocksExt2>(quantization::DEFAULT_BIT_LEN);
+        for table in [relu.table(), Relu::new().table()] {
+            let columns = TableType::Relu(table)
+                .evaluate_table_columns(&point)
+                .unwrap();
+            let inputs = (quantization::MIN - 1..=quantization::MAX).collect::<Vec<_>>();
+            let outputs = inputs.iter().map(|i| table.apply(*i)).collect::<Vec<_>>();
+            for (column, eval) in [inputs, outputs].into_iter().zip(columns) {
+                let mle = column
+                    .iter()
+                    .map(Fieldizer::<GoldilocksExt2>::to_field)
+                    .collect::<Vec<_>>()
+                    .into_mle();
+                assert_eq!(mle.evaluate(&point), eval);
+            }
+        }
+    }
+
     #[test]
     fn test_activation_relu_apply() {
         struct TestCase {
@@ -0,0 +1,446 @@
+//! Element-wise addition of two tensors, e.g. for residual connections.
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::precommit::PolyID,
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof, requant::Requant, rescale::MULTIPLIER_BITS},
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    quantization::Fieldizer,
+};
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use itertools::Itertools;
+use multilinear_extensions::mle::{IntoMLE, MultilinearExtension};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const NUM_INPUTS: usize = 2;
+const IS_PROVABLE: bool = true;
+
+/// Adds element-wise two tensors of the same shape.
+/// When the inputs are quantized with different scaling factors, they can't be added directly:
+/// in this case, each input is first multiplied by a public integer multiplier, and the sum is
+/// then brought back to the output scaling factor by the requant layer following the addition.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Add {
+    /// Multipliers applied to each input before the addition, if any
+    multipliers: Option<Vec<Element>>,
+}
+
+/// Info about the add layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddCtx {
+    pub poly_id: PolyID,
+    pub multipliers: Option<Vec<Element>>,
+    pub num_vars: usize,
+}
+
+/// Proof for the add layer: the evaluations of the inputs at the point of the output claim.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AddProof<E: ExtensionField> {
+    input_evals: Vec<E>,
+}
+
+impl Add {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn new_with_multipliers(multipliers: Vec<Element>) -> Self {
+        Self {
+            multipliers: Some(multipliers),
+        }
+    }
+
+    fn num_outputs(num_inputs: usize) -> usize {
+        assert_eq!(num_inputs, NUM_INPUTS);
+        1
+    }
+
+    /// Computes the integer multipliers to be applied to the inputs with scaling factors `input_scaling`
+    /// and the right shift to be applied to their sum to get an output with scaling factor `output_scaling`.
+    /// Each multiplier approximates `2^shift * s_i / s_out`, where the shift is chosen such that the
+    /// biggest multiplier has `MULTIPLIER_BITS + 1` bits, as the fixed-point multipliers of the
+    /// requant layer. The requant layer then only divides by the exact power of two `2^shift`.
+    pub(crate) fn multipliers_and_shift(
+        input_scaling: &[ScalingFactor],
+        output_scaling: &ScalingFactor,
+    ) -> (Vec<Element>, usize) {
+        let ratios = input_scaling
+            .iter()
+            .map(|s| s.scale() / output_scaling.scale())
+            .collect_vec();
+        let max_ratio = ratios.iter().cloned().fold(f32::MIN_POSITIVE, f32::max);
+        let shift = (MULTIPLIER_BITS as f32 - max_ratio.log2().ceil()).max(0.0) as usize;
+        let multipliers = ratios
+            .into_iter()
+            .map(|ratio| ((ratio * (1u64 << shift) as f32).round() as Element).max(1))
+            .collect();
+        (multipliers, shift)
+    }
+}
+
+impl OpInfo for Add {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        Self::num_outputs(num_inputs)
+    }
+
+    fn describe(&self) -> String {
+        match &self.multipliers {
+            Some(multipliers) => format!("Add: multipliers {:?}", multipliers),
+            None => "Add".to_string(),
+        }
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for Add {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        ensure!(
+            inputs.len() == NUM_INPUTS,
+            "Found {} inputs when evaluating add layer, expected {NUM_INPUTS}",
+            inputs.len()
+        );
+        ensure!(
+            inputs[0].get_shape() == inputs[1].get_shape(),
+            "Inputs of add layer have different shapes: {:?} and {:?}",
+            inputs[0].get_shape(),
+            inputs[1].get_shape()
+        );
+        Ok(LayerOut::from_vec(vec![inputs[0].add(inputs[1])]))
+    }
+}
+
+impl Evaluate<Element> for Add {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == NUM_INPUTS,
+            "Found {} inputs when evaluating add layer, expected {NUM_INPUTS}",
+            inputs.len()
+        );
+        ensure!(
+            inputs[0].get_shape() == inputs[1].get_shape(),
+            "Inputs of add layer have different shapes: {:?} and {:?}",
+            inputs[0].get_shape(),
+            inputs[1].get_shape()
+        );
+        let output = match &self.multipliers {
+            Some(multipliers) => inputs[0]
+                .scalar_mul(&multipliers[0])
+                .add(&inputs[1].scalar_mul(&multipliers[1])),
+            None => inputs[0].add(inputs[1]),
+        };
+        Ok(LayerOut::from_vec(vec![output]))
+    }
+}
+
+impl QuantizeOp for Add {
+    type QuantizedOp = Add;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == NUM_INPUTS,
+            "Found {} input scaling factors for add layer, expected {NUM_INPUTS}",
+            input_scaling.len()
+        );
+        let num_outputs = self.num_outputs(input_scaling.len());
+        let mut output_scalings = S::scaling_factors_for_node(data, node_id, num_outputs);
+        ensure!(
+            output_scalings.len() == 1,
+            "Output scaling for add layer different from 1"
+        );
+        let output_scaling = output_scalings.pop().unwrap();
+        if input_scaling
+            .iter()
+            .all(|s| s.scale() == output_scaling.scale())
+        {
+            // inputs can be added directly, no need to requantize the output
+            return Ok(QuantizeOutput {
+                quanzited_op: self,
+                output_scalings: vec![output_scaling],
+                requant_layer: None,
+                rescale_layer: None,
+            });
+        }
+        let (multipliers, shift) = Self::multipliers_and_shift(input_scaling, &output_scaling);
+        // the output before requantization lies in sum_i |m_i| * [MIN_i, MAX_i]
+        let min_output = multipliers
+            .iter()
+            .zip(input_scaling)
+            .map(|(m, s)| m.abs() * s.quantized_domain().0.abs())
+            .sum::<Element>();
+        let requant = Requant::new(min_output as usize, shift, output_scaling.bit_len());
+        Ok(QuantizeOutput {
+            quanzited_op: Self::new_with_multipliers(multipliers),
+            output_scalings: vec![output_scaling],
+            requant_layer: Some(requant),
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for Add
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == NUM_INPUTS,
+            "Found {} input shapes for add layer, expected {NUM_INPUTS}",
+            aux.last_output_shape.len()
+        );
+        ensure!(
+            aux.last_output_shape[0] == aux.last_output_shape[1],
+            "Inputs of add layer have different shapes: {:?} and {:?}",
+            aux.last_output_shape[0],
+            aux.last_output_shape[1]
+        );
+        aux.last_output_shape.truncate(1);
+        let num_vars = aux.last_output_shape[0].iter().product::<usize>().ilog2() as usize;
+        Ok((
+            LayerCtx::Add(AddCtx {
+                poly_id: id,
+                multipliers: self.multipliers.clone(),
+                num_vars,
+            }),
+            aux,
+        ))
+    }
+}
+
+impl PadOp for Add {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::add(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Add
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = AddCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for add layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == NUM_INPUTS,
+            "Found {} inputs in inference step of add layer, expected {NUM_INPUTS}",
+            step_data.inputs.len()
+        );
+        let last_claim = last_claims[0];
+        // the output is a linear combination of the inputs, so we just need the evaluations of the
+        // inputs over the same point of the output claim
+        let input_evals = step_data
+            .inputs
+            .iter()
+            .map(|input| {
+                input
+                    .get_data()
+                    .to_vec()
+                    .into_mle()
+                    .evaluate(&last_claim.point)
+            })
+            .collect_vec();
+        debug_assert_eq!(
+            ctx.combine_evals(&input_evals),
+            last_claim.eval,
+            "Inconsistent input evaluations for add layer"
+        );
+        prover.transcript.append_field_element_exts(&input_evals);
+        let claims = input_evals
+            .iter()
+            .map(|eval| Claim::new(last_claim.point.clone(), *eval))
+            .collect();
+        prover.push_proof(node_id, LayerProof::Add(AddProof { input_evals }));
+        Ok(claims)
+    }
+}
+
+impl AddCtx {
+    /// Multipliers of the inputs, as field elements
+    pub(crate) fn multipliers_as_fields<E: ExtensionField>(&self) -> Vec<E> {
+        match &self.multipliers {
+            Some(multipliers) => multipliers.iter().map(|m| m.to_field()).collect(),
+            None => vec![E::ONE; NUM_INPUTS],
+        }
+    }
+
+    /// Evaluation of the output given the evaluations of the inputs over the same point
+    fn combine_evals<E: ExtensionField>(&self, input_evals: &[E]) -> E {
+        self.multipliers_as_fields::<E>()
+            .into_iter()
+            .zip(input_evals)
+            .fold(E::ZERO, |acc, (m, eval)| acc + m * *eval)
+    }
+}
+
+impl OpInfo for AddCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        Add::num_outputs(num_inputs)
+    }
+
+    fn describe(&self) -> String {
+        format!("Add ctx: multipliers {:?}", self.multipliers)
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for AddCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = AddProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for add layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            proof.input_evals.len() == NUM_INPUTS,
+            "Found {} input evaluations in add proof, expected {NUM_INPUTS}",
+            proof.input_evals.len()
+        );
+        let last_claim = last_claims[0];
+        ensure!(
+            last_claim.point.len() == self.num_vars,
+            "Invalid output claim for add layer: expected {} variables, found {}",
+            self.num_vars,
+            last_claim.point.len()
+        );
+        ensure!(
+            self.combine_evals(&proof.input_evals) == last_claim.eval,
+            "Input evaluations of add layer don't match the output claim"
+        );
+        verifier
+            .transcript
+            .append_field_element_exts(&proof.input_evals);
+        Ok(proof
+            .input_evals
+            .iter()
+            .map(|eval| Claim::new(last_claim.point.clone(), *eval))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use crate::layers::provable::evaluate_layer;
+
+    use super::*;
+
+    #[test]
+    fn test_add_quantized_multipliers() {
+        let shape = vec![4, 8];
+        let input_scaling = vec![
+            ScalingFactor::from_absolute_max(1.0, None),
+            ScalingFactor::from_absolute_max(4.0, None),
+        ];
+        let output_scaling = ScalingFactor::from_absolute_max(5.0, None);
+        let (multipliers, shift) = Add::multipliers_and_shift(&input_scaling, &output_scaling);
+        let add = Add::new_with_multipliers(multipliers);
+        let float_inputs = vec![
+            Tensor::<f32>::random(&shape).scalar_mul(&1.0),
+            Tensor::<f32>::random(&shape).scalar_mul(&4.0),
+        ];
+        let float_output =
+            evaluate_layer::<GoldilocksExt2, _, _>(&add, &float_inputs.iter().collect_vec(), None)
+                .unwrap()
+                .outputs()[0]
+                .clone();
+        let quantized_inputs = float_inputs
+            .into_iter()
+            .zip(&input_scaling)
+            .map(|(t, s)| t.quantize(s))
+            .collect_vec();
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(
+            &add,
+            &quantized_inputs.iter().collect_vec(),
+            None,
+        )
+        .unwrap()
+        .outputs()[0]
+            .clone();
+        // dequantize the output after the shift and compare with the float addition
+        let tolerance = 4.0 * output_scaling.scale();
+        for (q, f) in output.get_data().iter().zip(float_output.get_data()) {
+            let dequantized = output_scaling.dequantize(&(q >> shift));
+            assert!(
+                (dequantized - f).abs() <= tolerance,
+                "dequantized {dequantized} too far from {f}"
+            );
+        }
+    }
+}
@@ -0,0 +1,589 @@
+//! Per-channel affine transformation of its input, e.g. an inference-time BatchNormalization which
+//! can't be folded into the weights of the previous layer.
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{compute_betas_eval, identity_eval, poly_id::AFFINE_BIAS_POLY_ID, precommit::PolyID},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof, requant::Requant, topk::prefix_eq_eval},
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo, affine},
+    quantization,
+    tensor::Number,
+};
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::Itertools;
+use multilinear_extensions::{
+    mle::{IntoMLE, MultilinearExtension},
+    virtual_poly::{VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Computes `scale[c] * x + bias[c]` for each entry `x` of the channel `c` of the input, where the
+/// channels are the first dimension of the input, e.g. the output channels of a convolution or the
+/// entries of a vector.
+///
+/// The bias only depends on the channel variables, so its contribution to the output claim is
+/// evaluated directly, while the product of the input with the scale broadcast over each channel is
+/// proven with a sumcheck, reducing the output claim to claims about the input and the scale at the
+/// point of the sumcheck.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Affine<T> {
+    /// scale of each channel
+    scale: Tensor<T>,
+    /// bias added to each channel
+    bias: Tensor<T>,
+    /// Unpadded shape of the entries of each channel, set when the layer is padded: the bias is
+    /// only added over these entries, so that the padding of the input stays zero
+    inner_shape: Option<Vec<usize>>,
+}
+
+/// Info about the affine layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AffineCtx {
+    pub scale_poly_id: PolyID,
+    pub bias_poly_id: PolyID,
+    /// Number of variables of the output
+    pub num_vars: usize,
+    /// Unpadded shape of the entries of each channel
+    pub inner_shape: Vec<usize>,
+    /// Padded shape of the entries of each channel
+    pub padded_inner_shape: Vec<usize>,
+}
+
+/// Proof for the affine layer: the sumcheck for the product of the input with the scale, the
+/// evaluations of the input and of the scale at the point of the sumcheck, and the evaluation of the
+/// bias at the channel variables of the output claim.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AffineProof<E: ExtensionField> {
+    sumcheck: IOPProof<E>,
+    input_eval: E,
+    scale_eval: E,
+    bias_eval: E,
+}
+
+impl<T: Number> Affine<T> {
+    pub fn new(scale: Tensor<T>, bias: Tensor<T>) -> Self {
+        assert_eq!(scale.get_shape().len(), 1, "scale must be a vector");
+        assert_eq!(
+            scale.get_shape(),
+            bias.get_shape(),
+            "scale and bias must have the same shape"
+        );
+        Self {
+            scale,
+            bias,
+            inner_shape: None,
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.scale.get_shape()[0]
+    }
+
+    pub(crate) fn scale(&self) -> &Tensor<T> {
+        &self.scale
+    }
+
+    pub(crate) fn bias(&self) -> &Tensor<T> {
+        &self.bias
+    }
+
+    /// Pads the scale and the bias with zeros to the padded number of channels, so that the padded
+    /// channels of the output are zero. `inner_shape` is the unpadded shape of each channel.
+    pub(crate) fn padded(mut self, num_channels: usize, inner_shape: Vec<usize>) -> Result<Self> {
+        ensure!(
+            num_channels >= self.num_channels(),
+            "Padded input with {} channels is smaller than the {} channels of affine layer",
+            num_channels,
+            self.num_channels()
+        );
+        self.scale = self.scale.pad_1d(num_channels);
+        self.bias = self.bias.pad_1d(num_channels);
+        self.inner_shape = Some(inner_shape);
+        Ok(self)
+    }
+
+    fn check_input_shape(&self, input_shape: &[usize]) -> Result<()> {
+        ensure!(
+            input_shape.first() == Some(&self.num_channels()),
+            "Input shape {:?} doesn't match the {} channels of affine layer",
+            input_shape,
+            self.num_channels()
+        );
+        Ok(())
+    }
+
+    /// Whether the bias is added to the `i`-th entry of a channel of shape `shape`
+    fn in_inner_shape(&self, i: usize, shape: &[usize]) -> bool {
+        let Some(inner_shape) = &self.inner_shape else {
+            return true;
+        };
+        // the last dimension varies the fastest
+        shape
+            .iter()
+            .zip(inner_shape)
+            .rev()
+            .try_fold(i, |i, (dim, og_dim)| (i % dim < *og_dim).then_some(i / dim))
+            .is_some()
+    }
+
+    pub fn op(&self, input: &Tensor<T>) -> Result<Tensor<T>> {
+        let shape = input.get_shape();
+        self.check_input_shape(&shape)?;
+        let channel_shape = &shape[1..];
+        let output = input
+            .get_data()
+            .chunks(channel_shape.iter().product())
+            .zip(self.scale.get_data().iter().zip(self.bias.get_data()))
+            .flat_map(|(channel, (s, b))| {
+                channel.iter().enumerate().map(move |(i, x)| {
+                    if self.in_inner_shape(i, channel_shape) {
+                        *x * *s + *b
+                    } else {
+                        *x * *s
+                    }
+                })
+            })
+            .collect_vec();
+        Ok(Tensor::new(shape, output))
+    }
+}
+
+impl Affine<f32> {
+    /// Quantizes the layer given the scaling factors of its input and output. The bias is quantized
+    /// with the scale of the products, like the bias of a dense layer.
+    pub(crate) fn quantize_from_scalings(
+        self,
+        input_scaling: &ScalingFactor,
+        output_scaling: ScalingFactor,
+    ) -> Result<QuantizeOutput<Affine<Element>>> {
+        // the scale is quantized over the bit length of the input
+        let bit_len = input_scaling.bit_len();
+        let scale_scaling =
+            ScalingFactor::from_tensor(&self.scale, Some(quantization::domain(bit_len)));
+        // bias has to be quantized over integers with double bit length
+        let bias_scaling = ScalingFactor::from_scale(
+            input_scaling.scale() * scale_scaling.scale(),
+            Some(quantization::domain(2 * bit_len)),
+        );
+        let quantized = Affine {
+            scale: self.scale.quantize(&scale_scaling),
+            bias: self.bias.quantize(&bias_scaling),
+            inner_shape: self.inner_shape,
+        };
+        let max_output = quantized.scale.max_abs_output() * quantization::min(bit_len).abs()
+            + quantized.bias.max_abs_output();
+        Ok(QuantizeOutput {
+            quanzited_op: quantized,
+            output_scalings: vec![output_scaling],
+            requant_layer: Some(Requant::from_multiplier(
+                max_output as usize,
+                input_scaling.m(&scale_scaling, &output_scaling) as f64,
+                output_scaling.bit_len(),
+            )),
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<N: Number> OpInfo for Affine<N> {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!("Affine: {} channels", self.num_channels())
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<N: Number> Evaluate<N> for Affine<N> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<N>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<N, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found {} inputs for affine layer, expected 1",
+            inputs.len()
+        );
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])?]))
+    }
+}
+
+impl QuantizeOp for Affine<f32> {
+    type QuantizedOp = Affine<Element>;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == 1,
+            "Found {} input scaling factors for affine layer, expected 1",
+            input_scaling.len()
+        );
+        let mut output_scalings =
+            S::scaling_factors_for_node(data, node_id, self.num_outputs(input_scaling.len()));
+        ensure!(
+            output_scalings.len() == 1,
+            "Output scaling for affine layer different from 1"
+        );
+        self.quantize_from_scalings(&input_scaling[0], output_scalings.pop().unwrap())
+    }
+}
+
+impl<E> ProveInfo<E> for Affine<Element>
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for affine layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let shape = &aux.last_output_shape[0];
+        self.check_input_shape(shape)?;
+        let padded_inner_shape = shape[1..].to_vec();
+        let inner_shape = self
+            .inner_shape
+            .clone()
+            .unwrap_or_else(|| padded_inner_shape.clone());
+        ensure!(
+            inner_shape.len() == padded_inner_shape.len(),
+            "Unpadded shape {:?} of the channels of affine layer doesn't match the padded shape {:?}",
+            inner_shape,
+            padded_inner_shape
+        );
+        Ok((
+            LayerCtx::Affine(AffineCtx {
+                scale_poly_id: id,
+                bias_poly_id: AFFINE_BIAS_POLY_ID + id,
+                num_vars: ceil_log2(shape.iter().product()),
+                inner_shape,
+                padded_inner_shape,
+            }),
+            aux,
+        ))
+    }
+
+    fn commit_info(&self, id: NodeId) -> Vec<Option<(PolyID, Vec<E>)>> {
+        let id = id as PolyID;
+        vec![
+            Some((id, self.scale.evals_flat())),
+            Some((AFFINE_BIAS_POLY_ID + id, self.bias.evals_flat())),
+        ]
+    }
+}
+
+impl PadOp for Affine<Element> {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        affine(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Affine<Element>
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = AffineCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for affine layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of affine layer, expected 1",
+            step_data.inputs.len()
+        );
+        let last_claim = last_claims[0];
+        let (inner_point, channel_point) = last_claim.point.split_at(ctx.inner_num_vars());
+        // the bias is constant over each channel, so it only depends on the channel variables
+        let bias_eval = self.bias.evals_flat().into_mle().evaluate(channel_point);
+        prover.transcript.append_field_element_ext(&bias_eval);
+        let scale = self
+            .scale
+            .evals_flat::<E>()
+            .into_iter()
+            .flat_map(|s| std::iter::repeat(s).take(1 << ctx.inner_num_vars()))
+            .collect_vec();
+        let mut vp = VirtualPolynomial::<E>::new(ctx.num_vars);
+        vp.add_mle_list(
+            vec![
+                compute_betas_eval(&last_claim.point).into_mle().into(),
+                step_data.inputs[0].get_data().to_vec().into_mle().into(),
+                scale.into_mle().into(),
+            ],
+            E::ONE,
+        );
+        #[allow(deprecated)]
+        let (sumcheck, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        let evals = state.get_mle_final_evaluations();
+        let (input_eval, scale_eval) = (evals[1], evals[2]);
+        debug_assert_eq!(
+            sumcheck.extract_sum(),
+            last_claim.eval - bias_eval * ctx.mask_eval(inner_point),
+            "Inconsistent evaluations for affine layer"
+        );
+        let point = sumcheck.point.clone();
+        let (_, scale_point) = point.split_at(ctx.inner_num_vars());
+        prover
+            .transcript
+            .append_field_element_exts(&[input_eval, scale_eval]);
+        prover.commit_prover.add_claim(
+            ctx.scale_poly_id,
+            Claim::new(scale_point.to_vec(), scale_eval),
+        )?;
+        prover.commit_prover.add_claim(
+            ctx.bias_poly_id,
+            Claim::new(channel_point.to_vec(), bias_eval),
+        )?;
+        prover.push_proof(
+            node_id,
+            LayerProof::Affine(AffineProof {
+                sumcheck,
+                input_eval,
+                scale_eval,
+                bias_eval,
+            }),
+        );
+        Ok(vec![Claim::new(point, input_eval)])
+    }
+}
+
+impl AffineCtx {
+    /// Number of variables of the entries of each channel, which come before the variables of the
+    /// channels
+    fn inner_num_vars(&self) -> usize {
+        self.padded_inner_shape.iter().map(|d| ceil_log2(*d)).sum()
+    }
+
+    /// Evaluation of the polynomial which is 1 over the unpadded entries of a channel, and 0 over
+    /// the padded ones
+    fn mask_eval<E: ExtensionField>(&self, inner_point: &[E]) -> E {
+        let mut point = inner_point;
+        // the variables of the last dimension come first
+        self.padded_inner_shape
+            .iter()
+            .zip(&self.inner_shape)
+            .rev()
+            .fold(E::ONE, |acc, (dim, og_dim)| {
+                let (dim_point, rest) = point.split_at(ceil_log2(*dim));
+                point = rest;
+                if dim == og_dim {
+                    acc
+                } else {
+                    acc * prefix_eq_eval(&[dim_point], *og_dim)
+                }
+            })
+    }
+}
+
+impl OpInfo for AffineCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Affine ctx: channels of shape {:?}, padded to {:?}",
+            self.inner_shape, self.padded_inner_shape
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for AffineCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = AffineProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for affine layer, expected 1",
+            last_claims.len()
+        );
+        let last_claim = last_claims[0];
+        ensure!(
+            last_claim.point.len() == self.num_vars,
+            "Invalid output claim for affine layer: expected {} variables, found {}",
+            self.num_vars,
+            last_claim.point.len()
+        );
+        let (inner_point, channel_point) = last_claim.point.split_at(self.inner_num_vars());
+        verifier
+            .transcript
+            .append_field_element_ext(&proof.bias_eval);
+        let subclaim = IOPVerifierState::<E>::verify(
+            last_claim.eval - proof.bias_eval * self.mask_eval(inner_point),
+            &proof.sumcheck,
+            &VPAuxInfo::from_mle_list_dimensions(&[vec![self.num_vars; 3]]),
+            verifier.transcript,
+        );
+        let point = subclaim.point_flat();
+        ensure!(
+            identity_eval(&last_claim.point, &point) * proof.input_eval * proof.scale_eval
+                == subclaim.expected_evaluation,
+            "Affine sumcheck claim failed"
+        );
+        let (_, scale_point) = point.split_at(self.inner_num_vars());
+        verifier
+            .transcript
+            .append_field_element_exts(&[proof.input_eval, proof.scale_eval]);
+        verifier.commit_verifier.add_claim(
+            self.scale_poly_id,
+            Claim::new(scale_point.to_vec(), proof.scale_eval),
+        )?;
+        verifier.commit_verifier.add_claim(
+            self.bias_poly_id,
+            Claim::new(channel_point.to_vec(), proof.bias_eval),
+        )?;
+        Ok(vec![Claim::new(point, proof.input_eval)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use goldilocks::GoldilocksExt2;
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_affine_quantized_output() {
+        let shape = vec![3, 2, 5];
+        let affine = Affine::new(
+            Tensor::<f32>::random(&vec![3]),
+            Tensor::<f32>::random(&vec![3]),
+        );
+        let input_scaling = ScalingFactor::from_absolute_max(1.0, None);
+        let scale_scaling = ScalingFactor::from_tensor(affine.scale(), None);
+        let float_input = Tensor::<f32>::random(&shape);
+        let float_output = affine.op(&float_input).unwrap();
+        let output = affine
+            .quantize_from_scalings(&input_scaling, ScalingFactor::from_absolute_max(2.0, None))
+            .unwrap()
+            .quanzited_op
+            .op(&float_input.quantize(&input_scaling))
+            .unwrap();
+        // before requantization, the output has the scale of the products
+        let scale = input_scaling.scale() * scale_scaling.scale();
+        for (q, f) in output.get_data().iter().zip(float_output.get_data()) {
+            let dequantized = *q as f32 * scale;
+            assert!(
+                (dequantized - f).abs() <= 0.05,
+                "dequantized {dequantized} too far from {f}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_affine_padding_mask() {
+        let affine = Affine::new(
+            Tensor::<Element>::new(vec![3], vec![2, -1, 3]),
+            Tensor::<Element>::new(vec![3], vec![5, 7, -2]),
+        )
+        .padded(4, vec![3, 2])
+        .unwrap();
+        let input = Tensor::<Element>::random(&vec![3, 3, 2]).pad_next_power_of_two();
+        let output = affine.op(&input).unwrap();
+        assert_eq!(output.get_shape(), vec![4, 4, 2]);
+        for (i, (x, y)) in input.get_data().iter().zip(output.get_data()).enumerate() {
+            let (channel, row, col) = (i / 8, (i / 2) % 4, i % 2);
+            let expected = match channel {
+                0..=2 if row < 3 && col < 2 => {
+                    x * affine.scale.get_data()[channel] + affine.bias.get_data()[channel]
+                }
+                0..=2 => x * affine.scale.get_data()[channel],
+                _ => 0,
+            };
+            assert_eq!(*y, expected);
+        }
+        // the mask evaluated over the boolean hypercube matches the entries to which the bias is
+        // added
+        let ctx = AffineCtx {
+            scale_poly_id: 0,
+            bias_poly_id: AFFINE_BIAS_POLY_ID,
+            num_vars: 5,
+            inner_shape: vec![3, 2],
+            padded_inner_shape: vec![4, 2],
+        };
+        for i in 0..8 {
+            let point = (0..3).map(|b| F::from(((i >> b) & 1) as u64)).collect_vec();
+            let expected = if affine.in_inner_shape(i, &[4, 2]) {
+                F::ONE
+            } else {
+                F::ZERO
+            };
+            assert_eq!(ctx.mask_eval(&point), expected);
+        }
+    }
+}
@@ -0,0 +1,527 @@
+//! Concatenation of several tensors along one dimension, e.g. to merge the branches of an inception block.
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use itertools::Itertools;
+use multilinear_extensions::mle::{IntoMLE, MultilinearExtension};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use transcript::Transcript;
+
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{identity_eval, precommit::PolyID},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof, add::Add, requant::Requant},
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    quantization::Fieldizer,
+    tensor::Number,
+};
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Concatenates its inputs along dimension `axis`. All the inputs must have the same shape, except
+/// along `axis`.
+///
+/// Once padded, all the inputs must have the same padded shape and all of them but the last one must
+/// not be padded along `axis`: the padded output is the concatenation of the padded inputs, padded
+/// along `axis` to the next power of two number of inputs. The variables of the output MLE are then
+/// the variables of the inputs, with the bits of the index of the input inserted as selector variables
+/// right after the variables of `axis`. A claim about the output is proven with the evaluations of all
+/// the inputs over the output point without the selector variables.
+/// As for the add layer, inputs quantized with different scaling factors are first multiplied by
+/// public integer multipliers, and the output is requantized by the following requant layer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Concat {
+    axis: usize,
+    /// Multipliers applied to each input before the concatenation, if any
+    multipliers: Option<Vec<Element>>,
+    /// Whether the layer is applied to padded inputs, in which case the output is padded along `axis`
+    padded: bool,
+}
+
+/// Proof for the concat layer: the evaluations of the inputs at the point of the output claim without
+/// the selector variables.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConcatProof<E: ExtensionField> {
+    input_evals: Vec<E>,
+}
+
+impl Concat {
+    pub fn new(axis: usize) -> Self {
+        Self {
+            axis,
+            multipliers: None,
+            padded: false,
+        }
+    }
+
+    pub fn axis(&self) -> usize {
+        self.axis
+    }
+
+    pub(crate) fn multipliers(&self) -> Option<&[Element]> {
+        self.multipliers.as_deref()
+    }
+
+    /// Returns the layer to be proven over padded inputs
+    pub(crate) fn padded(self) -> Self {
+        Self {
+            padded: true,
+            ..self
+        }
+    }
+
+    fn output_shape(&self, input_shapes: &[Vec<usize>], padding_mode: PaddingMode) -> Vec<usize> {
+        let mut output_shape = input_shapes[0].clone();
+        output_shape[self.axis] = match padding_mode {
+            PaddingMode::NoPadding => input_shapes.iter().map(|s| s[self.axis]).sum(),
+            PaddingMode::Padding => {
+                input_shapes.len().next_power_of_two() * input_shapes[0][self.axis]
+            }
+        };
+        output_shape
+    }
+
+    /// Splits the point `output_point` of an output claim into the point for the inputs, of padded shape
+    /// `padded_input_shape`, and the selector variables addressing one of the `num_inputs` inputs
+    fn split_point<E: Clone>(
+        &self,
+        output_point: &[E],
+        padded_input_shape: &[usize],
+        num_inputs: usize,
+    ) -> Result<(Vec<E>, Vec<E>)> {
+        let offset = selector_offset(padded_input_shape, self.axis)?;
+        let num_selector_vars = selector_num_vars(num_inputs);
+        let num_input_vars = padded_input_shape.iter().product::<usize>().ilog2() as usize;
+        ensure!(
+            output_point.len() == num_input_vars + num_selector_vars,
+            "Invalid output claim for concat layer: expected {} variables, found {}",
+            num_input_vars + num_selector_vars,
+            output_point.len()
+        );
+        let selector = output_point[offset..offset + num_selector_vars].to_vec();
+        let input_point = output_point[..offset]
+            .iter()
+            .chain(&output_point[offset + num_selector_vars..])
+            .cloned()
+            .collect();
+        Ok((input_point, selector))
+    }
+
+    /// Concatenates the inputs, padding the output along `axis` when the layer is padded
+    fn concat<T: Number>(&self, inputs: &[&Tensor<T>]) -> Result<Tensor<T>> {
+        ensure!(
+            !inputs.is_empty(),
+            "No input found when evaluating concat layer"
+        );
+        let shape = inputs[0].get_shape();
+        ensure!(
+            self.axis < shape.len()
+                && inputs.iter().all(|input| {
+                    let s = input.get_shape();
+                    s.len() == shape.len()
+                        && (0..s.len()).all(|i| i == self.axis || s[i] == shape[i])
+                }),
+            "Can't concatenate inputs of shapes {:?} along axis {}",
+            inputs.iter().map(|input| input.get_shape()).collect_vec(),
+            self.axis
+        );
+        let mut output = Tensor::concat_axis(inputs, self.axis);
+        if self.padded {
+            ensure!(
+                inputs.iter().all(|input| input.get_shape() == shape),
+                "Padded inputs of concat layer have different shapes: {:?}",
+                inputs.iter().map(|input| input.get_shape()).collect_vec()
+            );
+            let input_shapes = vec![shape; inputs.len()];
+            output.pad_to_shape(self.output_shape(&input_shapes, PaddingMode::Padding));
+        }
+        Ok(output)
+    }
+
+    /// Evaluation of the output given the evaluations of the inputs and the selector variables
+    fn combine_evals<E: ExtensionField>(&self, selector: &[E], input_evals: &[E]) -> E {
+        let multipliers: Vec<E> = match &self.multipliers {
+            Some(multipliers) => multipliers.iter().map(|m| m.to_field()).collect(),
+            None => vec![E::ONE; input_evals.len()],
+        };
+        input_evals
+            .iter()
+            .zip(multipliers)
+            .enumerate()
+            .fold(E::ZERO, |acc, (i, (eval, m))| {
+                acc + identity_eval(selector, &selector_point::<E>(i, selector.len())) * m * *eval
+            })
+    }
+}
+
+/// Number of selector variables needed to address `num_parts` tensors
+pub(crate) fn selector_num_vars(num_parts: usize) -> usize {
+    num_parts.next_power_of_two().ilog2() as usize
+}
+
+/// Position of the selector variables in the MLE of the concatenation, along `axis`, of tensors of
+/// padded shape `part_shape`: the variables are in little endian order, so the selector variables come
+/// after the ones of `axis` and of all the following dimensions
+pub(crate) fn selector_offset(part_shape: &[usize], axis: usize) -> Result<usize> {
+    ensure!(
+        axis < part_shape.len(),
+        "Invalid axis {} for shape {:?}",
+        axis,
+        part_shape
+    );
+    ensure!(
+        part_shape.iter().all(|d| d.is_power_of_two()),
+        "Shape {:?} is not padded",
+        part_shape
+    );
+    Ok(part_shape[axis..].iter().map(|d| d.ilog2() as usize).sum())
+}
+
+/// Selector variables addressing the `index`-th tensor, i.e. the bits of `index` in little endian order
+pub(crate) fn selector_point<E: ExtensionField>(index: usize, num_vars: usize) -> Vec<E> {
+    (0..num_vars)
+        .map(|i| {
+            if (index >> i) & 1 == 1 {
+                E::ONE
+            } else {
+                E::ZERO
+            }
+        })
+        .collect()
+}
+
+impl OpInfo for Concat {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        vec![self.output_shape(input_shapes, padding_mode)]
+    }
+
+    fn num_outputs(&self, _num_inputs: usize) -> usize {
+        1
+    }
+
+    fn describe(&self) -> String {
+        match &self.multipliers {
+            Some(multipliers) => {
+                format!("Concat: axis {}, multipliers {:?}", self.axis, multipliers)
+            }
+            None => format!("Concat: axis {}", self.axis),
+        }
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for Concat {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        Ok(LayerOut::from_vec(vec![self.concat(inputs)?]))
+    }
+}
+
+impl Evaluate<Element> for Concat {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        let output = match &self.multipliers {
+            Some(multipliers) => {
+                ensure!(
+                    multipliers.len() == inputs.len(),
+                    "Found {} inputs when evaluating concat layer, expected {}",
+                    inputs.len(),
+                    multipliers.len()
+                );
+                let scaled = inputs
+                    .iter()
+                    .zip(multipliers)
+                    .map(|(input, m)| input.scalar_mul(m))
+                    .collect_vec();
+                self.concat(&scaled.iter().collect_vec())?
+            }
+            None => self.concat(inputs)?,
+        };
+        Ok(LayerOut::from_vec(vec![output]))
+    }
+}
+
+impl QuantizeOp for Concat {
+    type QuantizedOp = Concat;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        let num_outputs = self.num_outputs(input_scaling.len());
+        let mut output_scalings = S::scaling_factors_for_node(data, node_id, num_outputs);
+        ensure!(
+            output_scalings.len() == 1,
+            "Output scaling for concat layer different from 1"
+        );
+        let output_scaling = output_scalings.pop().unwrap();
+        if input_scaling.iter().all(|s| {
+            s.scale() == output_scaling.scale() && s.zero_point() == output_scaling.zero_point()
+        }) {
+            // inputs can be concatenated directly, no need to requantize the output
+            return Ok(QuantizeOutput {
+                quanzited_op: self,
+                output_scalings: vec![output_scaling],
+                requant_layer: None,
+                rescale_layer: None,
+            });
+        }
+        let (multipliers, shift) = Add::multipliers_and_shift(input_scaling, &output_scaling);
+        // the output before requantization lies in max_i |m_i| * [MIN_i, MAX_i]
+        let min_output = multipliers
+            .iter()
+            .zip(input_scaling)
+            .map(|(m, s)| m.abs() * s.quantized_domain().0.abs())
+            .max()
+            .unwrap_or_default();
+        let requant = Requant::new(min_output as usize, shift, output_scaling.bit_len());
+        Ok(QuantizeOutput {
+            quanzited_op: Self {
+                multipliers: Some(multipliers),
+                ..self
+            },
+            output_scalings: vec![output_scaling],
+            requant_layer: Some(requant),
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for Concat
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            !aux.last_output_shape.is_empty()
+                && aux
+                    .last_output_shape
+                    .iter()
+                    .all(|s| *s == aux.last_output_shape[0]),
+            "Padded inputs of concat layer have different shapes: {:?}",
+            aux.last_output_shape
+        );
+        aux.last_output_shape = self.output_shapes(&aux.last_output_shape, PaddingMode::Padding);
+        Ok((LayerCtx::Concat(self.clone()), aux))
+    }
+}
+
+impl PadOp for Concat {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::concat(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Concat
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = Concat;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for concat layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            !step_data.inputs.is_empty(),
+            "Found no input in inference step of concat layer"
+        );
+        let last_claim = last_claims[0];
+        let (point, selector) = ctx.split_point(
+            &last_claim.point,
+            &step_data.inputs[0].get_shape(),
+            step_data.inputs.len(),
+        )?;
+        let input_evals = step_data
+            .inputs
+            .iter()
+            .map(|input| input.get_data().to_vec().into_mle().evaluate(&point))
+            .collect_vec();
+        debug_assert_eq!(
+            ctx.combine_evals(&selector, &input_evals),
+            last_claim.eval,
+            "Inconsistent input evaluations for concat layer"
+        );
+        prover.transcript.append_field_element_exts(&input_evals);
+        let claims = input_evals
+            .iter()
+            .map(|eval| Claim::new(point.clone(), *eval))
+            .collect();
+        prover.push_proof(node_id, LayerProof::Concat(ConcatProof { input_evals }));
+        Ok(claims)
+    }
+}
+
+impl<E> VerifiableCtx<E> for Concat
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = ConcatProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for concat layer, expected 1",
+            last_claims.len()
+        );
+        let num_inputs = shape_step.padded_input_shape.len();
+        ensure!(
+            proof.input_evals.len() == num_inputs,
+            "Found {} input evaluations in concat proof, expected {}",
+            proof.input_evals.len(),
+            num_inputs
+        );
+        if let Some(multipliers) = &self.multipliers {
+            ensure!(
+                multipliers.len() == num_inputs,
+                "Found {} multipliers for concat layer with {} inputs",
+                multipliers.len(),
+                num_inputs
+            );
+        }
+        ensure!(
+            shape_step
+                .padded_input_shape
+                .iter()
+                .all(|s| *s == shape_step.padded_input_shape[0]),
+            "Padded inputs of concat layer have different shapes: {:?}",
+            shape_step.padded_input_shape
+        );
+        let last_claim = last_claims[0];
+        let (point, selector) = self.split_point(
+            &last_claim.point,
+            &shape_step.padded_input_shape[0],
+            num_inputs,
+        )?;
+        ensure!(
+            self.combine_evals(&selector, &proof.input_evals) == last_claim.eval,
+            "Input evaluations of concat layer don't match the output claim"
+        );
+        verifier
+            .transcript
+            .append_field_element_exts(&proof.input_evals);
+        Ok(proof
+            .input_evals
+            .iter()
+            .map(|eval| Claim::new(point.clone(), *eval))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use crate::{
+        layers::provable::evaluate_layer, quantization::TensorFielder, testing::random_field_vector,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_concat_split_point() {
+        let shape = vec![2, 4];
+        let inputs = (0..3)
+            .map(|_| Tensor::<Element>::random(&shape))
+            .collect_vec();
+        let inputs = inputs.iter().collect_vec();
+        let concat = Concat::new(0);
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(&concat, &inputs, None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_shape(), vec![6, 4]);
+
+        // the padded output has the padded inputs in the first half and zeros in the last quarter
+        let concat = concat.padded();
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(&concat, &inputs, None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_shape(), vec![8, 4]);
+
+        let output_point = random_field_vector::<GoldilocksExt2>(5);
+        let (point, selector) = concat.split_point(&output_point, &shape, 3).unwrap();
+        let input_evals = inputs
+            .iter()
+            .map(|input| {
+                let input: Tensor<GoldilocksExt2> = (*input).clone().to_fields();
+                input.get_data().to_vec().into_mle().evaluate(&point)
+            })
+            .collect_vec();
+        let output: Tensor<GoldilocksExt2> = output.to_fields();
+        let output_eval = output
+            .get_data()
+            .to_vec()
+            .into_mle()
+            .evaluate(&output_point);
+        assert_eq!(concat.combine_evals(&selector, &input_evals), output_eval);
+    }
+
+    #[test]
+    fn test_concat_inner_axis() {
+        let inputs = [
+            Tensor::<Element>::new(vec![2, 2], vec![1, 2, 3, 4]),
+            Tensor::<Element>::new(vec![2, 2], vec![5, 6, 7, 8]),
+        ];
+        let concat = Concat::new(1);
+        let output =
+            evaluate_layer::<GoldilocksExt2, _, _>(&concat, &[&inputs[0], &inputs[1]], None)
+                .unwrap()
+                .outputs()[0]
+                .clone();
+        assert_eq!(
+            output,
+            Tensor::<Element>::new(vec![2, 4], vec![1, 2, 5, 6, 3, 4, 7, 8])
+        );
+        assert_eq!(selector_offset(&[2, 2], 1).unwrap(), 1);
+        assert!(selector_offset(&[2, 3], 1).is_err());
+    }
+}
@@ -1,16 +1,16 @@
 use crate::{
     ScalingStrategy, VectorTranscript,
     iop::context::ShapeStep,
-    layers::{hadamard, requant::Requant},
+    layers::{hadamard, requant::Requant, rescale::Rescale},
     model::StepData,
     padding::{PaddingMode, ShapeInfo, pad_conv},
-    quantization::{BIT_LEN, TensorFielder},
+    quantization::TensorFielder,
 };
 use core::f32;
 
 use crate::{
     Claim, Prover,
-    commit::{compute_betas_eval, identity_eval},
+    commit::{compute_betas_eval, identity_eval, poly_id::CONV_BIAS_POLY_ID},
     iop::{context::ContextAux, verifier::Verifier},
     layers::{LayerProof, PolyID},
     quantization::{self, ScalingFactor},
@@ -43,8 +43,6 @@ use super::{
     },
 };
 
-pub(crate) const BIAS_POLY_ID: PolyID = 200_000;
-
 const IS_PROVABLE: bool = true;
 /// Convolution layer description (weights)
 #[derive(Clone, Debug)]
@@ -56,6 +54,62 @@ pub struct Convolution<T> {
     pub bias: Tensor<T>,
     /// Unpadded shape of the filter. This is set to filter's shape in case of no padding.
     pub unpadded_shape: Vec<usize>,
+    /// Stride of the convolution, the same along both spatial dimensions.
+    pub stride: usize,
+    /// Zero padding added around the input before the convolution.
+    pub padding: ConvPadding,
+    /// Number of groups of consecutive channels the input and the filters are split into. Only a
+    /// [`GroupedConv`] has more than one group.
+    pub groups: usize,
+}
+
+/// Zero padding added around the spatial dimensions of the input of a convolution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConvPadding {
+    /// No padding
+    #[default]
+    Valid,
+    /// Number of zero rows and columns added around the input, as `[top, left, bottom, right]`
+    Explicit([usize; 4]),
+    /// Pads the input such that the output has `ceil(input / stride)` rows and columns, with the
+    /// extra row and column (if any) at the end
+    SameUpper,
+    /// Same as `SameUpper`, with the extra row and column (if any) at the beginning
+    SameLower,
+}
+
+impl ConvPadding {
+    /// Returns the padding `[top, left, bottom, right]` for the given input shape, filter shape and
+    /// stride.
+    pub fn pads(&self, input_shape: &[usize], filter_shape: &[usize], stride: usize) -> [usize; 4] {
+        match self {
+            ConvPadding::Valid => [0; 4],
+            ConvPadding::Explicit(pads) => *pads,
+            ConvPadding::SameUpper | ConvPadding::SameLower => {
+                let dims = input_shape.len();
+                let total = |input: usize, kernel: usize| {
+                    ((input.div_ceil(stride) - 1) * stride + kernel).saturating_sub(input)
+                };
+                let height = total(input_shape[dims - 2], filter_shape[2]);
+                let width = total(input_shape[dims - 1], filter_shape[3]);
+                if *self == ConvPadding::SameUpper {
+                    [
+                        height / 2,
+                        width / 2,
+                        height - height / 2,
+                        width - width / 2,
+                    ]
+                } else {
+                    [
+                        height - height / 2,
+                        width - width / 2,
+                        height / 2,
+                        width / 2,
+                    ]
+                }
+            }
+        }
+    }
 }
 
 /// Info about the convolution layer derived during the setup phase
@@ -72,11 +126,16 @@ pub struct ConvCtx<E> {
     pub hadamard: VPAuxInfo<E>,
     pub kw: usize,
     pub kx: usize,
+    pub real_nh: usize,
     pub real_nw: usize,
+    pub nh: usize,
     pub nw: usize,
     pub filter_size: usize,
     pub unpadded_filter_shape: Vec<usize>,
     pub padded_filter_shape: Vec<usize>,
+    pub stride: usize,
+    pub padding: ConvPadding,
+    pub groups: usize,
 }
 
 pub fn to_bits<E: ExtensionField>(mut num: usize, bitlen: usize) -> Vec<E> {
@@ -117,6 +176,14 @@ pub struct ConvProof<E: ExtensionField> {
     hadamard_clams: Vec<E>,
     bias_claim: E,
     clearing_proof: hadamard::HadamardProof<E>,
+    // Sumcheck proof for the subsampling of the FFT convolution output, only for stride > 1
+    subsampling_proof: Option<IOPProof<E>>,
+    // Evaluation of the cleared FFT convolution output claimed by the subsampling sumcheck
+    subsampling_eval: E,
+    // Sumcheck proof for the zero padding of the input, only for padded convolutions
+    padding_proof: Option<IOPProof<E>>,
+    // Evaluation of the input claimed by the zero padding sumcheck
+    padding_eval: E,
 }
 
 impl<T: Number> Convolution<T> {
@@ -138,14 +205,91 @@ impl<T: Number> Convolution<T> {
             filter,
             bias,
             unpadded_shape: unpadded_shape.to_vec(),
+            stride: 1,
+            padding: ConvPadding::Valid,
+            groups: 1,
         }
     }
+
+    /// Sets the stride of the convolution.
+    pub fn with_stride(mut self, stride: usize) -> Result<Self> {
+        ensure!(stride > 0, "convolution stride must be positive");
+        self.stride = stride;
+        Ok(self)
+    }
+
+    /// Sets the zero padding added around the input.
+    pub fn with_padding(mut self, padding: ConvPadding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Returns the zero padding `[top, left, bottom, right]` of the input for the given input shape.
+    pub fn pads(&self, input_shape: &[usize]) -> [usize; 4] {
+        self.padding
+            .pads(input_shape, &self.unpadded_shape, self.stride)
+    }
+
+    /// Returns true if zeros may be added around the input, whatever its shape
+    pub(crate) fn pads_input(&self) -> bool {
+        self.padding != ConvPadding::Valid && self.padding != ConvPadding::Explicit([0; 4])
+    }
+
     pub fn output_shape(&self, input_shape: &[usize], padding_mode: PaddingMode) -> Vec<usize> {
+        let pads = self.pads(input_shape);
         match padding_mode {
             // unpadded shape is the shape found in onxx file for example
-            PaddingMode::NoPadding => conv2d_shape(input_shape, &self.unpadded_shape),
-            PaddingMode::Padding => padded_conv2d_shape(input_shape, &self.filter.real_shape()),
+            PaddingMode::NoPadding => strided_conv2d_shape(
+                &zero_padded_shape(input_shape, pads),
+                &self.unpadded_shape,
+                self.stride,
+            ),
+            PaddingMode::Padding => padded_strided_conv2d_shape(
+                &fft_input_shape(input_shape, pads),
+                &self.filter.real_shape(),
+                self.stride,
+            ),
+        }
+    }
+
+    /// Evaluates the convolution on an unpadded input with the naive algorithm.
+    fn conv2d(&self, input: &Tensor<T>) -> Tensor<T> {
+        let input_shape = input.get_shape();
+        let pads = self.pads(&input_shape);
+        // a grouped convolution is evaluated as a regular one with a block diagonal filter
+        let dense_filter;
+        let filter = if self.groups == 1 {
+            &self.filter
+        } else {
+            dense_filter = self.dense_filter();
+            &dense_filter
+        };
+        if pads == [0; 4] {
+            return input.conv2d(filter, &self.bias, self.stride);
+        }
+        Selection::zero_padding(pads, &input_shape)
+            .apply(input, &zero_padded_shape(&input_shape, pads))
+            .conv2d(filter, &self.bias, self.stride)
+    }
+
+    /// Returns the filter of the regular convolution equivalent to this grouped convolution, i.e. the
+    /// filter which is zero on all the input channels outside of the group of each output channel.
+    fn dense_filter(&self) -> Tensor<T> {
+        let (k_n, k_c, k_h, k_w) = self.filter.get4d();
+        let filters_per_group = k_n / self.groups;
+        let channels = k_c * self.groups;
+        let kernel_size = k_h * k_w;
+        let mut data = vec![T::default(); k_n * channels * kernel_size];
+        for i in 0..k_n {
+            let group = i / filters_per_group;
+            for j in 0..k_c {
+                let src = (i * k_c + j) * kernel_size;
+                let dst = (i * channels + group * k_c + j) * kernel_size;
+                data[dst..dst + kernel_size]
+                    .copy_from_slice(&self.filter.get_data()[src..src + kernel_size]);
+            }
         }
+        Tensor::new(vec![k_n, channels, k_h, k_w], data)
     }
 
     pub fn add_bias(&self, conv_out: &Tensor<T>) -> Tensor<T> {
@@ -185,6 +329,15 @@ impl<T: Number> Convolution<T> {
         self.filter.kx()
     }
 
+    /// Number of input channels of the convolution, i.e. the input channels of all the groups.
+    pub fn input_channels(&self) -> usize {
+        self.filter.kx() * self.groups
+    }
+
+    pub fn nh(&self) -> usize {
+        self.filter.nh()
+    }
+
     pub fn nw(&self) -> usize {
         self.filter.nw()
     }
@@ -224,11 +377,13 @@ impl<T: Number> OpInfo for Convolution<T> {
 
     fn describe(&self) -> String {
         format!(
-            "Conv: ({},{},{},{})",
+            "Conv: ({},{},{},{}), stride {}, padding {:?}",
             self.filter.kw(),
             self.filter.kx(),
+            self.filter.nh(),
             self.filter.nw(),
-            self.filter.nw()
+            self.stride,
+            self.padding,
         )
     }
 
@@ -247,12 +402,7 @@ impl Evaluate<f32> for Convolution<f32> {
             inputs.len() == 1,
             "Found more than 1 input when evaluating convolution layer"
         );
-        let input = inputs[0];
-        Ok(LayerOut::from_vec(vec![input.conv2d(
-            &self.filter,
-            &self.bias,
-            1,
-        )]))
+        Ok(LayerOut::from_vec(vec![self.conv2d(inputs[0])]))
     }
 }
 
@@ -260,14 +410,38 @@ impl Convolution<f32> {
     /// Quantizes the filter and the bias.
     /// It uses a custom scaling factor `bias_s` for the bias, if provided,
     /// otherwise the same scaling factor of the weights (i.e., `s`) is used
-    pub fn quantize(self, s: &ScalingFactor, bias_s: &ScalingFactor) -> Convolution<Element> {
+    pub fn quantize(
+        self,
+        s: &ScalingFactor,
+        bias_s: &ScalingFactor,
+    ) -> Result<Convolution<Element>> {
         let quantized_filter = self.filter.quantize(s);
         let bias = self.bias.quantize(bias_s);
-        Convolution::<Element>::new(quantized_filter, bias)
+        let mut conv = Convolution::<Element>::new(quantized_filter, bias)
+            .with_stride(self.stride)?
+            .with_padding(self.padding);
+        conv.groups = self.groups;
+        Ok(conv)
+    }
+
+    /// Quantizes the filter and the bias with one scaling factor per output channel: the filter of
+    /// the channel `i` is quantized with `s[i]`, and its bias with `bias_s[i]`
+    pub fn quantize_rows(
+        self,
+        s: &[ScalingFactor],
+        bias_s: &[ScalingFactor],
+    ) -> Result<Convolution<Element>> {
+        let quantized_filter = self.filter.quantize_rows(s);
+        let bias = self.bias.quantize_rows(bias_s);
+        let mut conv = Convolution::<Element>::new(quantized_filter, bias)
+            .with_stride(self.stride)?
+            .with_padding(self.padding);
+        conv.groups = self.groups;
+        Ok(conv)
     }
 
     pub fn op<E: ExtensionField>(&self, input: &Tensor<f32>) -> Tensor<f32> {
-        input.conv2d(&self.filter, &self.bias, 1)
+        self.conv2d(input)
     }
 
     pub fn max_abs_weight(&self) -> f32 {
@@ -309,14 +483,22 @@ impl Evaluate<Element> for Convolution<Element> {
 
 impl Convolution<Element> {
     /// Pads the filter and bias, and adapt the filter to the convolution fft operation.
+    /// The zero padding is fixed for the given input shape, since the FFT filter depends on it.
     pub fn into_padded_and_ffted(mut self, unpadded_input_shape: &[usize]) -> Self {
+        let pads = self.pads(unpadded_input_shape);
+        self.padding = ConvPadding::Explicit(pads);
+        let filters_per_group = self.filter.kw() / self.groups;
         self.filter = self.filter.pad_next_power_of_two();
         self.bias = self.bias.pad_next_power_of_two();
+        // the channels per group are powers of two, so padding the channels only adds empty groups
+        self.groups = self.filter.kw() / filters_per_group;
         let padded_input_shape = unpadded_input_shape
             .iter()
             .map(|&x| x.next_power_of_two())
             .collect::<Vec<usize>>();
-        self.filter = self.filter.into_fft_conv(&padded_input_shape);
+        self.filter = self
+            .filter
+            .into_fft_conv(&fft_input_shape(&padded_input_shape, pads));
         self
     }
 
@@ -325,14 +507,29 @@ impl Convolution<Element> {
         input: &Tensor<Element>,
         unpadded_input_shape: &[usize],
     ) -> (Tensor<Element>, ConvData<E>) {
-        let (output, mut proving_data) = self.filter.fft_conv(&input);
+        // the FFT convolution runs over the input surrounded by the zero padding of the layer
+        let pads = self.pads(unpadded_input_shape);
+        let zero_padded_input;
+        let input = if pads == [0; 4] {
+            input
+        } else {
+            zero_padded_input = Selection::zero_padding(pads, unpadded_input_shape)
+                .apply(input, &fft_input_shape(&input.get_shape(), pads));
+            &zero_padded_input
+        };
+        let (output, mut proving_data) = self.filter.grouped_fft_conv(&input, self.groups);
+        proving_data.unpadded_input_shape = unpadded_input_shape.to_vec();
         let conv_output = self.add_bias(&output);
         // we record here the output _after_ the bias addition. During proving it's necessary since we're proving the clearing garbage
         // and that produces a new claim on this output.
         proving_data.set_output(conv_output.get_data());
         // At this stage, we're creating a "garbage clearing" tensor that sets all garbage values to 0. This is necessary
         // since the garbage might be of any value and we need to restrict the range of the output due to requantization proving logic.
-        let unpadded_output_shape = conv2d_shape(unpadded_input_shape, &self.unpadded_shape);
+        // With a stride, we only keep the region of the FFT output spanned by the subsampled entries.
+        let unpadded_output_shape = unstrided_output_shape(
+            &self.output_shape(unpadded_input_shape, PaddingMode::NoPadding),
+            self.stride,
+        );
         debug_assert_eq!(
             {
                 let fft_output_shape =
@@ -351,15 +548,23 @@ impl Convolution<Element> {
             let cleared_tensor2 = conv_output.flatten().mul(&clearing_tensor);
             cleared_tensor.get_data() == cleared_tensor2.get_data()
         });
-        (cleared_tensor, proving_data)
+        let output = if self.stride > 1 {
+            let output_shape = padded_strided_shape(&cleared_tensor.get_shape(), self.stride);
+            Selection::subsampling(self.stride).apply(&cleared_tensor, &output_shape)
+        } else {
+            cleared_tensor
+        };
+        (output, proving_data)
     }
 
     /// Returns the min and max output range of the convolution layer for a given input range.
     /// NOTE: it assumes the weights in float are NOT fft'd
-    pub fn output_range(&self, _min_input: Element, _max_input: Element) -> (Element, Element) {
+    /// The weights are assumed to be quantized over the same bit length as the input.
+    pub fn output_range(&self, min_input: Element, max_input: Element) -> (Element, Element) {
         // 2^{BIT_LEN + log2(k_h * k_w * k_c)}
         let (_k_n, k_c, k_h, k_w) = self.filter.get4d();
-        let exp = 2 * *quantization::BIT_LEN + ceil_log2(k_h * k_w * k_c + 1) as usize;
+        let bit_len = quantization::bit_len((min_input, max_input));
+        let exp = 2 * bit_len + ceil_log2(k_h * k_w * k_c + 1) as usize;
         let min = -(2u64.pow(exp as u32) as Element);
         let max = 2u64.pow(exp as u32) as Element;
         (min, max)
@@ -379,8 +584,8 @@ impl Convolution<Element> {
         E::BaseField: Serialize + DeserializeOwned,
         E: Serialize + DeserializeOwned,
     {
-        let padded_rows = 2 * self.filter.nw() * self.filter.nw();
-        let mut w1_reduced: Vec<E> = vec![E::ZERO; self.filter.real_nw() * self.filter.real_nw()];
+        let padded_rows = 2 * self.filter_size();
+        let mut w1_reduced: Vec<E> = vec![E::ZERO; self.filter.real_nh() * self.filter.real_nw()];
 
         // Partition r in (r1,r2)
         let mut r1 = vec![E::ZERO; padded_rows.ilog2() as usize];
@@ -405,7 +610,7 @@ impl Convolution<Element> {
             false,
         );
         // compute X(i,r2)
-        let filter_size = self.filter.real_nw() * self.filter.real_nw();
+        let filter_size = self.filter.real_nh() * self.filter.real_nw();
         (0..self.filter.kw()).for_each(|i| {
             (0..self.filter.kx()).for_each(|j| {
                 (0..filter_size).for_each(|k| {
@@ -427,6 +632,7 @@ impl Convolution<Element> {
         let partial_evals = w1_reduced.clone();
         w1_reduced = index_wf(
             &w1_reduced.clone(),
+            self.filter.real_nh(),
             self.filter.real_nw(),
             self.filter.nw(),
             padded_rows,
@@ -465,9 +671,10 @@ where
     fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
         let mut filter_shape = self.filter.get_shape();
         filter_shape.remove(1);
+        let output_shape = padded_strided_shape(&filter_shape, self.stride);
         aux.last_output_shape
             .iter_mut()
-            .for_each(|shape| *shape = filter_shape.clone());
+            .for_each(|shape| *shape = output_shape.clone());
 
         let mut delegation_fft: Vec<VPAuxInfo<E>> = Vec::new();
         let mut delegation_fft_weights: Vec<VPAuxInfo<E>> = Vec::new();
@@ -492,7 +699,7 @@ where
 
         let conv_info = LayerCtx::Convolution(ConvCtx {
             poly_id: id,
-            bias_poly_id: BIAS_POLY_ID + id,
+            bias_poly_id: CONV_BIAS_POLY_ID + id,
             ifft_aux: VPAuxInfo::<E>::from_mle_list_dimensions(&vec![vec![
                 ((self.filter_size()).ilog2() as usize) + 1,
                 ((self.filter_size()).ilog2() as usize) + 1,
@@ -506,20 +713,25 @@ where
                 ((self.filter_size()).ilog2() as usize) + 1,
             ]]),
             hadamard: VPAuxInfo::<E>::from_mle_list_dimensions(&vec![vec![
-                ((self.kx() * self.filter_size()).ilog2() as usize) + 1,
-                ((self.kx() * self.filter_size()).ilog2() as usize) + 1,
-                ((self.kx() * self.filter_size()).ilog2() as usize) + 1,
+                ((self.input_channels() * self.filter_size()).ilog2() as usize) + 1,
+                ((self.input_channels() * self.filter_size()).ilog2() as usize) + 1,
+                ((self.input_channels() * self.filter_size()).ilog2() as usize) + 1,
             ]]),
             delegation_fft,
             delegation_fft_weights,
             delegation_ifft,
             kw: self.kw(),
             kx: self.kx(),
+            nh: self.filter.nh(),
             nw: self.filter.nw(),
+            real_nh: self.filter.real_nh(),
             real_nw: self.filter.real_nw(),
             filter_size: self.filter_size(),
             unpadded_filter_shape: self.unpadded_shape.clone(),
             padded_filter_shape: self.filter.real_shape(),
+            stride: self.stride,
+            padding: self.padding,
+            groups: self.groups,
         });
         Ok((conv_info, aux))
     }
@@ -535,47 +747,72 @@ where
         );
         debug!(
             "Commitment : conv layer bias ID {}: size {}",
-            BIAS_POLY_ID + id,
+            CONV_BIAS_POLY_ID + id,
             bias_evals.len().ilog2()
         );
         vec![
             Some((id, filter_evals)),
-            Some((BIAS_POLY_ID + id, bias_evals)),
+            Some((CONV_BIAS_POLY_ID + id, bias_evals)),
         ]
     }
 }
 
 impl Convolution<f32> {
+    /// Quantizes the convolution using the scaling factors of its input and output, with one scaling
+    /// factor for each output channel of the filter
     fn quantize_from_scalings(
         self,
         input_scaling: &[ScalingFactor],
         output_scaling: ScalingFactor,
     ) -> anyhow::Result<QuantizeOutput<Convolution<Element>>> {
-        let model_scaling = ScalingFactor::from_absolute_max(self.max_abs_weight(), None);
         let num_inputs = input_scaling.len();
         ensure!(
             num_inputs == 1,
             "Number of input scaling factor for convolution layer different from 1"
         );
         let input_scaling = &input_scaling[0];
-        let bias_scaling = {
-            // bias has to be quantized over integers with double bit length
-            let min_quantized = -(1 << (2 * (*BIT_LEN) - 1)) + 1;
-            let max_quantized = (1 << (2 * (*BIT_LEN) - 1)) - 1;
-            ScalingFactor::from_scale(
-                input_scaling.scale() * model_scaling.scale(),
-                Some((min_quantized, max_quantized)),
-            )
-        };
-        let quantized_conv = self.quantize(&model_scaling, &bias_scaling);
-        let shift = input_scaling.shift(&model_scaling, &output_scaling);
+        // the filter is quantized over the bit length of the input
+        let bit_len = input_scaling.bit_len();
+        let channel_scalings = ScalingFactor::from_rows(&self.filter, &self.bias, bit_len);
+        let bias_scalings = channel_scalings
+            .iter()
+            .map(|channel_scaling| {
+                // bias has to be quantized over integers with double bit length
+                ScalingFactor::from_scale(
+                    input_scaling.scale() * channel_scaling.scale(),
+                    Some(quantization::domain(2 * bit_len)),
+                )
+            })
+            .collect::<Vec<_>>();
+        // the output channels are brought to the scale of the output by the rescale layer, with
+        // multipliers of shape [channels, 1, 1]
+        let (rescale, shift) =
+            Rescale::from_scalings(input_scaling, &channel_scalings, &output_scaling, 3);
+        let mut quantized_conv = self.quantize_rows(&channel_scalings, &bias_scalings)?;
+        if !input_scaling.is_symmetric() {
+            // the zero padding of the input is not quantized with the zero point
+            ensure!(
+                !quantized_conv.pads_input(),
+                "Zero padded convolution does not support inputs quantized with a zero point"
+            );
+            quantized_conv.bias = quantization::bias_with_zero_point(
+                &quantized_conv.filter,
+                &quantized_conv.bias,
+                input_scaling.zero_point(),
+            );
+        }
         let (quantized_min, _quantized_max) =
-            quantized_conv.output_range(*quantization::MIN, *quantization::MAX);
-        let requant = Requant::new(quantized_min.abs() as usize, shift);
+            quantized_conv.output_range(quantization::min(bit_len), quantization::max(bit_len));
+        let requant = Requant::new(
+            (quantized_min.abs() * rescale.max_multiplier()) as usize,
+            shift,
+            output_scaling.bit_len(),
+        );
         Ok(QuantizeOutput {
             quanzited_op: quantized_conv,
             output_scalings: vec![output_scaling],
             requant_layer: Some(requant),
+            rescale_layer: Some(rescale),
         })
     }
 }
@@ -628,6 +865,7 @@ where
         Ok(vec![self.prove_convolution_step(
             prover,
             last_claims[0],
+            &step_data.inputs[0],
             &step_data.outputs.outputs()[0],
             &step_data.unpadded_output_shapes[0],
             &step_data.outputs.proving_data.as_ref().unwrap(),
@@ -659,8 +897,8 @@ where
 
     fn describe(&self) -> String {
         format!(
-            "Conv Ctx: ({},{},{},{})",
-            self.kw, self.kx, self.nw, self.nw,
+            "Conv Ctx: ({},{},{},{}), stride {}, padding {:?}, groups {}",
+            self.kw, self.kx, self.nh, self.nw, self.stride, self.padding, self.groups,
         )
     }
 
@@ -697,12 +935,15 @@ impl Convolution<Element> {
     // Prove convolution of a CNN network. This is a convolution between in a 3D matrix X of dimension k_x * n_x * n_x
     // and a 4D filter matrix W of dimension k_w * k_x * n_w * n_w. The output is a 3D matrix Y of dimension k_w * n_x * n_x
     // We want to batch prove the following: Y[i] = iFFT(sum_{j \in [n_x]}(FFT(X[j]) o FFT(W[i][j])).
+    // For a grouped convolution, the sum only runs over the input channels X[j] in the group of the filter W[i].
     #[timed::timed_instrument(name = "Prover::prove_convolution_step")]
     pub fn prove_convolution_step<E: ExtensionField, T: Transcript<E>>(
         &self,
         prover: &mut Prover<E, T>,
         // last random claim made
         last_claim: &Claim<E>,
+        // input of the layer, before the zero padding
+        input: &Tensor<E>,
         // Struct containing all necessary information
         // to generate a convolution proof
         output: &Tensor<E>,
@@ -720,11 +961,24 @@ impl Convolution<Element> {
         // This results in two claims: one for the non-cleared tensor and one for the clearing tensor (only 1s and 0s)
         // The non-cleared tensor claim gets passed to the main regular logic of convolution
         // The clearing tensor one gets stored in the proof and will be checked manually by the verifier (CURRENTLY)
-        let clearing_tensor = new_clearing_tensor(unpadded_output_shape, &output.get_shape());
+        // With a stride, the output is a subsampling of the cleared FFT output of shape [k_w, n_h, n_w].
+        let conv_shape = vec![self.kw(), self.nh(), self.nw()];
+        let clearing_tensor = new_clearing_tensor(
+            &unstrided_output_shape(unpadded_output_shape, self.stride),
+            &conv_shape,
+        );
         // Take the elements BEFORE bias addition - this is what the rest of the convolution proving step expects.
         // TODO: could trade off less memory by directly recomputing it from conv data with the input shape as well.
         let conv_after_bias =
-            Tensor::new(output.get_shape(), proving_data.output_as_element.clone());
+            Tensor::new(conv_shape.clone(), proving_data.output_as_element.clone());
+        let cleared_out = Tensor::new(
+            conv_shape,
+            conv_after_bias
+                .flatten()
+                .mul(&clearing_tensor)
+                .get_data()
+                .to_vec(),
+        );
         debug_assert!({
             println!(
                 "PROVE: conv_after_bias.shape(): {:?}",
@@ -736,10 +990,30 @@ impl Convolution<Element> {
             );
             println!("PROVE: unpadded_output_shape: {:?}", unpadded_output_shape);
             println!("PROVE: output.shape(): {:?}", output.get_shape());
-            let cleared_out = conv_after_bias.flatten().mul(&clearing_tensor);
-            let fielded: Tensor<E> = cleared_out.to_fields();
+            let fielded: Tensor<E> = if self.stride > 1 {
+                Selection::subsampling(self.stride)
+                    .apply(&cleared_out, &output.get_shape())
+                    .to_fields()
+            } else {
+                cleared_out.to_fields()
+            };
             fielded.get_data().to_vec() == output.get_data()
         });
+        // The output of a strided convolution is proven to be the subsampling of the cleared FFT output, which
+        // reduces the claim on the output to a claim on the cleared FFT output.
+        let (last_claim, subsampling_proof) = if self.stride > 1 {
+            let fielded: Tensor<E> = cleared_out.to_fields();
+            let (proof, claim) = Selection::subsampling(self.stride).prove(
+                prover.transcript,
+                last_claim,
+                &fielded,
+                &output.get_shape(),
+            )?;
+            (claim, Some(proof))
+        } else {
+            (last_claim.clone(), None)
+        };
+        let subsampling_eval = last_claim.eval;
         let clearing_proof = hadamard::prove(
             prover.transcript,
             &last_claim,
@@ -858,45 +1132,65 @@ impl Convolution<Element> {
 
         let r1 = &r_ifft[(proving_data.output[0].len().ilog2() as usize)..];
         let r2 = &r_ifft[..(proving_data.output[0].len().ilog2() as usize)];
-        let beta1 = compute_betas_eval(r1);
+        // The filters of a grouped convolution are ordered by group, so r1 splits into the variables of the
+        // filter inside its group and the variables of the group.
+        let filters_per_group = filter.kw() / filter.groups;
+        let (r1_filters, r1_groups) = r1.split_at(filters_per_group.ilog2() as usize);
+        let beta1 = compute_betas_eval(r1_filters);
+        let beta_groups = compute_betas_eval(r1_groups);
         let beta2 = compute_betas_eval(r2);
         // Given beta1,beta2 observe that :
         // \sum_{i \in [k_w]} beta1[i]prod[i] = \sum_{i \in [k_w]}sum_{j \in [k_x]} x[j] o w[i][j] =
         // = sum_{j \in [k_x]}x[j]o(\sum_{i \in [k_w]}(beta[i]*w[i][j])). We let w_reduced[j] = \sum_{i \in [k_w]}(beta[i]*w[i][j])
         // We have  \sum_{i \in [k_w]} beta1[i]prod[i] = sum_{j \in [k_x]} x[j]o w_{reduced[j]}.
         // So here we compute w_reduced
+        // With groups, the input channel j of group g is only combined with the filters of group g, so we sum
+        // over the filters of each group, and the input channels of group g are weighted by beta_groups[g].
 
-        let beta_acc = vec![beta2.clone(); filter.kx()].concat();
+        let beta_acc = beta_groups
+            .iter()
+            .flat_map(|beta_group| {
+                let group_beta = beta2.iter().map(|b| *b * beta_group).collect::<Vec<_>>();
+                vec![group_beta; filter.kx()]
+            })
+            .flatten()
+            .collect::<Vec<_>>();
 
         // After computing w_reduced, observe that y = \sum_{k \in [n_x^2]} sum_{j \in [k_x]} beta2[k]*x[j][k]*w_reduced[j][k]
         // This is a cubic sumcheck where v1 = [x[0][0],...,x[k_x][n_x^2]], v2 = [w_reduced[0][0],...,w_reduced[k_x][n_x^2]]
         // and v3 = [beta2,..(k_x times)..,beta2]. So, first initialzie v3 and then invoke the cubic sumceck.
         let mut aggregated_filter =
-            vec![vec![E::ZERO; self.filter.real_nw() * self.filter.real_nw()]; self.filter.kx()];
-        let filter_size = self.filter.real_nw() * self.filter.real_nw();
+            vec![
+                vec![E::ZERO; self.filter.real_nh() * self.filter.real_nw()];
+                self.input_channels()
+            ];
+        let filter_size = self.filter.real_nh() * self.filter.real_nw();
         // Compute aggregated_filter using iterators
         // TO DO: PARALLELIZE
-        (0..self.filter.kx()).for_each(|i| {
-            (0..self.filter.kw()).for_each(|j| {
-                aggregated_filter[i]
+        (0..self.input_channels()).for_each(|c| {
+            let (group, i) = (c / self.filter.kx(), c % self.filter.kx());
+            (0..filters_per_group).for_each(|o| {
+                let j = group * filters_per_group + o;
+                aggregated_filter[c]
                     .iter_mut()
                     .enumerate()
                     .for_each(|(k, v)| {
                         let index = j * self.filter.kx() * filter_size + i * filter_size + k;
                         let v_field: E = self.filter.data[index].to_field();
-                        *v += beta1[j] * v_field;
+                        *v += beta1[o] * v_field;
                     });
             });
 
-            aggregated_filter[i] = index_wf(
-                &aggregated_filter[i],
+            aggregated_filter[c] = index_wf(
+                &aggregated_filter[c],
+                self.filter.real_nh(),
                 self.filter.real_nw(),
                 self.filter.nw(),
-                2 * self.filter.nw() * self.filter.nw(),
+                2 * self.filter_size(),
             )
             .collect::<Vec<E>>();
 
-            fft(&mut aggregated_filter[i], false);
+            fft(&mut aggregated_filter[c], false);
         });
 
         // We need to fix the high variables in place for the filter at r1.
@@ -924,7 +1218,13 @@ impl Convolution<Element> {
         let (hadamard_proof, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
         let hadamard_claims = state.get_mle_final_evaluations();
 
-        let point = [hadamard_proof.point.as_slice(), r1].concat();
+        // The claim on the aggregated filter is a claim on the filters at r1_filters, for the input channel and
+        // the group given by the hadamard point. Since all the FFTs of the filters are batched together, a
+        // single sumcheck proves the FFT of the filters of all the groups.
+        let (channel_point, group_point) = hadamard_proof
+            .point
+            .split_at(hadamard_proof.point.len() - filter.groups.ilog2() as usize);
+        let point = [channel_point, r1_filters, group_point].concat();
         // let eval = hadamard_claims[0];
 
         // Finally prove the correct computation of the x_fft and get an evaluation claim of the input
@@ -938,7 +1238,7 @@ impl Convolution<Element> {
 
         let weights_rand: Vec<E> = prover
             .transcript
-            .read_challenges((self.filter.real_nw() * self.filter.real_nw()).ilog2() as usize);
+            .read_challenges((self.filter.real_nh() * self.filter.real_nw()).ilog2() as usize);
         debug_assert!({
             let mut weights_point = fft_proof_weights.point.clone();
             let mut v_weights = weights_point.pop().unwrap();
@@ -946,7 +1246,7 @@ impl Convolution<Element> {
 
             let mut r = [
                 weights_rand.clone(),
-                point[(2 * self.filter.nw() * self.filter.nw()).ilog2() as usize..].to_vec(),
+                point[(2 * self.filter_size()).ilog2() as usize..].to_vec(),
             ]
             .concat();
             // println!("({},{}), {}",proving_data.input.len(),proving_data.input[0].len(),p.len());
@@ -956,14 +1256,14 @@ impl Convolution<Element> {
                 partial_evals.clone().into_mle().evaluate(&weights_rand),
                 "Error in fft_weights eval"
             );
-            let mut indexes = vec![0 as usize; self.filter.real_nw() * self.filter.real_nw()];
-            for i in 0..self.filter.real_nw() {
+            let mut indexes = vec![0 as usize; self.filter.real_nh() * self.filter.real_nw()];
+            for i in 0..self.filter.real_nh() {
                 for j in 0..self.filter.real_nw() {
                     indexes[i * self.filter.real_nw() + j] = i * self.filter.nw() + j;
                 }
             }
-            r = weights_point[..(self.filter.nw() * self.filter.nw()).ilog2() as usize].to_vec();
-            let mut betas = vec![E::ZERO; self.filter.real_nw() * self.filter.real_nw()];
+            r = weights_point[..self.filter_size().ilog2() as usize].to_vec();
+            let mut betas = vec![E::ZERO; self.filter.real_nh() * self.filter.real_nw()];
             for i in 0..betas.len() {
                 betas[i] = identity_eval(&r, &to_bits(indexes[i], r.len()));
             }
@@ -986,8 +1286,7 @@ impl Convolution<Element> {
                 Claim::new(
                     [
                         weights_rand.clone(),
-                        point[(2 * self.filter.nw() * self.filter.nw()).ilog2() as usize..]
-                            .to_vec(),
+                        point[(2 * self.filter_size()).ilog2() as usize..].to_vec(),
                     ]
                     .concat(),
                     partial_evals.clone().into_mle().evaluate(&weights_rand),
@@ -999,28 +1298,29 @@ impl Convolution<Element> {
             .add_claim(info.bias_poly_id, Claim::new(bias_point, bias_eval))
             .context("unable to add bias claim in convolution")?;
 
-        prover.push_proof(
-            id,
-            LayerProof::Convolution(ConvProof {
-                fft_proof: fft_proof.clone(),
-                fft_claims: fft_claim.clone(),
-                fft_proof_weights,
-                ifft_proof,
-                fft_delegation_proof: fft_del_proof.0,
-                fft_delegation_proof_weights: fft_weights_del_proof.0,
-                ifft_delegation_proof: ifft_del_proof.0,
-                hadamard_proof: hadamard_proof.clone(),
-                ifft_claims: ifft_claim,
-                fft_weight_claims,
-                fft_delegation_claims: fft_del_proof.1,
-                fft_delegation_weights_claims: fft_weights_del_proof.1,
-                ifft_delegation_claims: ifft_del_proof.1,
-                hadamard_clams: hadamard_claims,
-                bias_claim: bias_eval,
-                partial_evals,
-                clearing_proof,
-            }),
-        );
+        let mut proof = ConvProof {
+            fft_proof: fft_proof.clone(),
+            fft_claims: fft_claim.clone(),
+            fft_proof_weights,
+            ifft_proof,
+            fft_delegation_proof: fft_del_proof.0,
+            fft_delegation_proof_weights: fft_weights_del_proof.0,
+            ifft_delegation_proof: ifft_del_proof.0,
+            hadamard_proof: hadamard_proof.clone(),
+            ifft_claims: ifft_claim,
+            fft_weight_claims,
+            fft_delegation_claims: fft_del_proof.1,
+            fft_delegation_weights_claims: fft_weights_del_proof.1,
+            ifft_delegation_claims: ifft_del_proof.1,
+            hadamard_clams: hadamard_claims,
+            bias_claim: bias_eval,
+            partial_evals,
+            clearing_proof,
+            subsampling_proof,
+            subsampling_eval,
+            padding_proof: None,
+            padding_eval: E::ZERO,
+        };
         let mut input_point = fft_proof.point.clone();
         let mut v = input_point.pop().unwrap();
         v = (E::ONE - v).invert().unwrap();
@@ -1061,6 +1361,24 @@ impl Convolution<Element> {
             .concat(),
             eval: fft_claim[0] * v,
         };
+        // The claim is about the input surrounded by the zero padding, so we reduce it to a claim about
+        // the actual input of the layer.
+        let pads = self.pads(&proving_data.unpadded_input_shape);
+        let final_claim = if pads == [0; 4] {
+            final_claim
+        } else {
+            let (padding_proof, claim) =
+                Selection::zero_padding(pads, &proving_data.unpadded_input_shape).prove(
+                    prover.transcript,
+                    &final_claim,
+                    input,
+                    &fft_input_shape(&input.get_shape(), pads),
+                )?;
+            proof.padding_proof = Some(padding_proof);
+            proof.padding_eval = claim.eval;
+            claim
+        };
+        prover.push_proof(id, LayerProof::Convolution(proof));
 
         Ok(final_claim)
     }
@@ -1071,10 +1389,25 @@ where
     E::BaseField: Serialize + DeserializeOwned,
     E: ExtensionField + Serialize + DeserializeOwned,
 {
+    /// Returns the zero padding `[top, left, bottom, right]` of the input for the given input shape.
+    pub fn pads(&self, input_shape: &[usize]) -> [usize; 4] {
+        self.padding
+            .pads(input_shape, &self.unpadded_filter_shape, self.stride)
+    }
+
     pub fn output_shape(&self, input_shape: &[usize], padding_mode: PaddingMode) -> Vec<usize> {
+        let pads = self.pads(input_shape);
         match padding_mode {
-            PaddingMode::NoPadding => conv2d_shape(input_shape, &self.unpadded_filter_shape),
-            PaddingMode::Padding => padded_conv2d_shape(input_shape, &self.padded_filter_shape),
+            PaddingMode::NoPadding => strided_conv2d_shape(
+                &zero_padded_shape(input_shape, pads),
+                &self.unpadded_filter_shape,
+                self.stride,
+            ),
+            PaddingMode::Padding => padded_strided_conv2d_shape(
+                &fft_input_shape(input_shape, pads),
+                &self.padded_filter_shape,
+                self.stride,
+            ),
         }
     }
     pub(crate) fn verify_fft_delegation<T: Transcript<E>>(
@@ -1154,12 +1487,35 @@ where
         // OR find a closed formula
         //
         // To recreat it, we need the unpadded output shape and the real output shape.
-        let unpadded_output_shape = conv2d_shape(
-            &shape_step.unpadded_input_shape[0],
-            &self.unpadded_filter_shape,
+        let unpadded_output_shape = unstrided_output_shape(
+            &self.output_shape(&shape_step.unpadded_input_shape[0], PaddingMode::NoPadding),
+            self.stride,
         );
-        let real_output_shape =
-            padded_conv2d_shape(&shape_step.padded_input_shape[0], &self.padded_filter_shape);
+        let real_output_shape = padded_conv2d_shape(
+            &fft_input_shape(
+                &shape_step.padded_input_shape[0],
+                self.pads(&shape_step.unpadded_input_shape[0]),
+            ),
+            &self.padded_filter_shape,
+        );
+        // With a stride, the claim is about the subsampled output, so we first reduce it to a claim
+        // about the cleared FFT output.
+        let last_claim = if self.stride > 1 {
+            let subsampling_proof = proof
+                .subsampling_proof
+                .as_ref()
+                .context("missing subsampling proof for strided convolution")?;
+            Selection::subsampling(self.stride).verify(
+                verifier.transcript,
+                last_claim,
+                subsampling_proof,
+                proof.subsampling_eval,
+                &real_output_shape,
+                &padded_strided_shape(&real_output_shape, self.stride),
+            )?
+        } else {
+            last_claim.clone()
+        };
         let clearing_tensor = new_clearing_tensor(&unpadded_output_shape, &real_output_shape);
         // now we need to verify the hadamard proof for the sumcheck part.
         let hctx = hadamard::HadamardCtx::from_len(real_output_shape.iter().product());
@@ -1240,9 +1596,18 @@ where
             &self.hadamard,
             verifier.transcript,
         );
+        // For a grouped convolution, the input channels of each group are weighted by the equality between
+        // the group and the group variables of the filters claimed by the iFFT sumcheck.
+        let r1 = &last_claim.point[(self.filter_size.ilog2() as usize)..];
+        let (r1_filters, r1_groups) = r1.split_at((self.kw / self.groups).ilog2() as usize);
+        let (channel_point, group_point) = proof
+            .hadamard_proof
+            .point
+            .split_at(proof.hadamard_proof.point.len() - self.groups.ilog2() as usize);
         assert_eq!(
             proof.hadamard_clams[2],
-            identity_eval(&proof.ifft_proof.point, &proof.hadamard_proof.point),
+            identity_eval(&proof.ifft_proof.point, &proof.hadamard_proof.point)
+                * identity_eval(r1_groups, group_point),
             "Error in Beta evaluation"
         );
 
@@ -1293,12 +1658,12 @@ where
         let mut v = weights_point.pop().unwrap();
         v = (E::ONE - v).invert().unwrap();
 
-        let y_weights = (0..self.real_nw)
+        let y_weights = (0..self.real_nh)
             .flat_map(|i| (0..self.real_nw).map(move |j| (i, j)))
             .fold(E::ZERO, |acc, (i, j)| {
                 acc + proof.partial_evals[i * self.real_nw + j]
                     * identity_eval(
-                        &to_bits(i * self.nw + j, (self.nw.ilog2() as usize) * 2),
+                        &to_bits(i * self.nw + j, self.filter_size.ilog2() as usize),
                         &weights_point,
                     )
             });
@@ -1311,20 +1676,16 @@ where
 
         let weights_rand: Vec<E> = verifier
             .transcript
-            .read_challenges((self.real_nw * self.real_nw).ilog2() as usize);
+            .read_challenges((self.real_nh * self.real_nw).ilog2() as usize);
 
-        let point = [
-            proof.hadamard_proof.point.as_slice(),
-            &last_claim.point[((self.filter_size).ilog2() as usize)..],
-        ]
-        .concat();
+        let point = [channel_point, r1_filters, group_point].concat();
 
         verifier.commit_verifier.add_claim(
             self.poly_id,
             Claim::new(
                 [
                     weights_rand.clone(),
-                    point[(2 * self.nw * self.nw).ilog2() as usize..].to_vec(),
+                    point[(2 * self.filter_size).ilog2() as usize..].to_vec(),
                 ]
                 .concat(),
                 proof
@@ -1350,7 +1711,7 @@ where
             input_point[i] = E::ONE - input_point[i];
         }
         // the output claim for this step that is going to be verified at next step
-        Ok(Claim {
+        let input_claim = Claim {
             // the new randomness to fix at next layer is the randomness from the sumcheck !
             point: [
                 input_point.clone(),
@@ -1360,7 +1721,26 @@ where
             // the claimed sum for the next sumcheck is MLE of the current vector evaluated at the
             // random point. 1 because vector is secondary.
             eval: proof.fft_claims[0] * v,
-        })
+        };
+        // The claim is about the input surrounded by the zero padding, which is reduced to a claim about
+        // the output of the previous layer.
+        let pads = self.pads(&shape_step.unpadded_input_shape[0]);
+        if pads == [0; 4] {
+            return Ok(input_claim);
+        }
+        let padding_proof = proof
+            .padding_proof
+            .as_ref()
+            .context("missing zero padding proof for padded convolution")?;
+        let padded_input_shape = &shape_step.padded_input_shape[0];
+        Selection::zero_padding(pads, &shape_step.unpadded_input_shape[0]).verify(
+            verifier.transcript,
+            &input_claim,
+            padding_proof,
+            proof.padding_eval,
+            padded_input_shape,
+            &fft_input_shape(padded_input_shape, pads),
+        )
     }
 }
 
@@ -1396,12 +1776,7 @@ impl<T: Number> Evaluate<T> for SchoolBookConv<T> {
             inputs.len() == 1,
             "Found more than 1 input when evaluating schoolbook convolution layer"
         );
-        let input = inputs[0];
-        Ok(LayerOut::from_vec(vec![input.conv2d(
-            &self.0.filter,
-            &self.0.bias,
-            1,
-        )]))
+        Ok(LayerOut::from_vec(vec![self.0.conv2d(inputs[0])]))
     }
 }
 
@@ -1421,9 +1796,10 @@ impl QuantizeOp for SchoolBookConv<f32> {
                 // we don't care about accurate quantization for schoolbook conv
                 &input_scaling[0],
                 &input_scaling[0],
-            )),
+            )?),
             output_scalings: input_scaling.to_vec(),
             requant_layer: None,
+            rescale_layer: None,
         })
     }
 }
@@ -1442,6 +1818,171 @@ where
     }
 }
 
+/// Grouped convolution: the input channels and the filters are split into `groups` groups of consecutive
+/// channels, and each filter only convolves the input channels of its group. A depthwise convolution is a
+/// grouped convolution with as many groups as input channels.
+/// It is proven like a regular convolution, where the FFTs of the filters of all the groups are batched
+/// into a single sumcheck.
+#[derive(Clone, Debug)]
+pub struct GroupedConv<T>(pub(crate) Convolution<T>);
+
+impl<T: Number> GroupedConv<T> {
+    /// Creates a grouped convolution from a filter of shape `[k_w, k_x, n_h, n_w]`, where `k_x` is the
+    /// number of input channels of each group.
+    pub fn new(filter: Tensor<T>, bias: Tensor<T>, groups: usize) -> Result<Self> {
+        ensure!(
+            groups > 0,
+            "grouped convolution must have at least one group"
+        );
+        ensure!(
+            filter.get_shape().len() == 4,
+            "grouped convolution filter must be 4D: {:?}",
+            filter.get_shape()
+        );
+        ensure!(
+            filter.kw() % groups == 0,
+            "number of filters {} is not a multiple of the number of groups {}",
+            filter.kw(),
+            groups
+        );
+        // the channels of each group must remain consecutive once the channels are padded
+        ensure!(
+            filter.kx().is_power_of_two() && (filter.kw() / groups).is_power_of_two(),
+            "channels per group must be a power of two: {} input and {} output channels",
+            filter.kx(),
+            filter.kw() / groups
+        );
+        let mut conv = Convolution::new(filter, bias);
+        conv.groups = groups;
+        Ok(Self(conv))
+    }
+
+    /// Sets the stride of the convolution.
+    pub fn with_stride(self, stride: usize) -> Result<Self> {
+        Ok(Self(self.0.with_stride(stride)?))
+    }
+
+    /// Sets the zero padding added around the input.
+    pub fn with_padding(self, padding: ConvPadding) -> Self {
+        Self(self.0.with_padding(padding))
+    }
+
+    pub fn groups(&self) -> usize {
+        self.0.groups
+    }
+}
+
+impl GroupedConv<Element> {
+    /// Pads the filter and bias, and adapt the filter to the convolution fft operation.
+    pub fn into_padded_and_ffted(self, unpadded_input_shape: &[usize]) -> Self {
+        Self(self.0.into_padded_and_ffted(unpadded_input_shape))
+    }
+}
+
+impl<T: Number> OpInfo for GroupedConv<T> {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        self.0.output_shapes(input_shapes, padding_mode)
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        self.0.num_outputs(num_inputs)
+    }
+
+    fn describe(&self) -> String {
+        format!("Grouped{}, groups {}", self.0.describe(), self.0.groups)
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for GroupedConv<f32> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        self.0.evaluate(inputs, unpadded_input_shapes)
+    }
+}
+
+impl Evaluate<Element> for GroupedConv<Element> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        self.0.evaluate(inputs, unpadded_input_shapes)
+    }
+}
+
+impl QuantizeOp for GroupedConv<f32> {
+    type QuantizedOp = GroupedConv<Element>;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> anyhow::Result<QuantizeOutput<Self::QuantizedOp>> {
+        let output = self.0.quantize_op::<S>(data, node_id, input_scaling)?;
+        Ok(QuantizeOutput {
+            quanzited_op: GroupedConv(output.quanzited_op),
+            output_scalings: output.output_scalings,
+            requant_layer: output.requant_layer,
+            rescale_layer: output.rescale_layer,
+        })
+    }
+}
+
+impl PadOp for GroupedConv<Element> {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(pad_conv(self.0, si)?))
+    }
+}
+
+impl<E> ProveInfo<E> for GroupedConv<Element>
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        self.0.step_info(id, aux)
+    }
+
+    fn commit_info(&self, id: NodeId) -> Vec<Option<(PolyID, Vec<E>)>> {
+        self.0.commit_info(id)
+    }
+}
+
+impl<E> ProvableOp<E> for GroupedConv<Element>
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = ConvCtx<E>;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        self.0.prove(id, ctx, last_claims, step_data, prover)
+    }
+}
+
 pub fn pow_two_omegas<E: ExtensionField>(n: usize, is_fft: bool) -> Vec<E> {
     let mut pows = vec![E::ZERO; n - 1];
     let mut rou: E = get_root_of_unity(n);
@@ -1525,18 +2066,20 @@ pub fn new_clearing_tensor(mut og_shape: &[usize], padded_shape: &[usize]) -> Te
 /// Properly pad a filter
 /// We use this function so that filter is amenable to FFT based conv2d
 /// Usually vec and n are powers of 2
-/// Output: [[F[0][0],…,F[0][n_w],0,…,0],[F[1][0],…,F[1][n_w],0,…,0],…]
+/// The filter has `h_real` rows of `w_real` entries, which are laid out with a row stride of `n`
+/// Output: [[F[0][0],…,F[0][w_real],0,…,0],[F[1][0],…,F[1][w_real],0,…,0],…]
 pub fn index_wf<E: ExtensionField>(
     w: &[E],
-    n_real: usize,
+    h_real: usize,
+    w_real: usize,
     n: usize,
     output_len: usize,
 ) -> impl ParallelIterator<Item = E> + use<'_, E> {
     (0..output_len).into_par_iter().map(move |idx| {
         let i = idx / n;
         let j = idx % n;
-        if i < n_real && j < n_real {
-            w[i * n_real + j]
+        if i < h_real && j < w_real {
+            w[i * w_real + j]
         } else {
             E::ZERO
         }
@@ -1557,18 +2100,27 @@ pub fn conv2d_shape_mode(
 /// Assumes stride=1, padding=0, and dilation=1
 /// https://pytorch.org/docs/stable/generated/torch.nn.Conv2d.html
 pub fn conv2d_shape(input_shape: &[usize], filter_shape: &[usize]) -> Vec<usize> {
-    let stride = 1usize;
+    strided_conv2d_shape(input_shape, filter_shape, 1)
+}
+
+/// Same as conv2d_shape but for a convolution with the given stride
+pub fn strided_conv2d_shape(
+    input_shape: &[usize],
+    filter_shape: &[usize],
+    stride: usize,
+) -> Vec<usize> {
     let padding = 0usize;
     let dilation = 1usize;
 
-    let h_in = if input_shape.len() == 3 {
-        input_shape[1]
+    let (h_in, w_in) = if input_shape.len() == 3 {
+        (input_shape[1], input_shape[2])
     } else {
-        input_shape[2]
+        (input_shape[2], input_shape[3])
     };
-    let kernel = filter_shape[2];
-    let h_out = (h_in + 2 * padding - dilation * (kernel - 1) - 1) / stride + 1;
-    vec![filter_shape[0], h_out, h_out]
+    let (kernel_h, kernel_w) = (filter_shape[2], filter_shape[3]);
+    let h_out = (h_in + 2 * padding - dilation * (kernel_h - 1) - 1) / stride + 1;
+    let w_out = (w_in + 2 * padding - dilation * (kernel_w - 1) - 1) / stride + 1;
+    vec![filter_shape[0], h_out, w_out]
 }
 
 /// Similar to conv2d_shape but pads the output shape such that it matches what the padded inference and proving expects
@@ -1579,20 +2131,304 @@ pub fn padded_conv2d_shape(input_shape: &[usize], filter_shape: &[usize]) -> Vec
         .collect::<Vec<usize>>()
 }
 
+/// Similar to padded_conv2d_shape but for a convolution with the given stride: the output of the FFT
+/// convolution gets subsampled and padded again to the next power of two.
+pub fn padded_strided_conv2d_shape(
+    input_shape: &[usize],
+    filter_shape: &[usize],
+    stride: usize,
+) -> Vec<usize> {
+    padded_strided_shape(&padded_conv2d_shape(input_shape, filter_shape), stride)
+}
+
+/// Returns the shape of the subsampling with the given stride of the FFT convolution output of shape
+/// `conv_output_shape`, padded to the next power of two. It is the identity when `stride == 1`.
+pub fn padded_strided_shape(conv_output_shape: &[usize], stride: usize) -> Vec<usize> {
+    let mut shape = conv_output_shape.to_vec();
+    let dims = shape.len();
+    shape[dims - 2..]
+        .iter_mut()
+        .for_each(|dim| *dim = dim.div_ceil(stride).next_power_of_two());
+    shape
+}
+
+/// Returns the shape of the region of the FFT convolution output which is kept when clearing the garbage,
+/// given the unpadded output shape of a convolution with the given stride. This region ends at the last
+/// row and column selected by the subsampling, so it is the unpadded output shape when `stride == 1`.
+fn unstrided_output_shape(unpadded_output_shape: &[usize], stride: usize) -> Vec<usize> {
+    let mut shape = unpadded_output_shape.to_vec();
+    let dims = shape.len();
+    shape[dims - 2..]
+        .iter_mut()
+        .for_each(|dim| *dim = (*dim - 1) * stride + 1);
+    shape
+}
+
+/// Returns the shape `input_shape` with the zero padding `[top, left, bottom, right]` added to its
+/// last two dimensions.
+pub(crate) fn zero_padded_shape(input_shape: &[usize], pads: [usize; 4]) -> Vec<usize> {
+    let mut shape = input_shape.to_vec();
+    let dims = shape.len();
+    shape[dims - 2] += pads[0] + pads[2];
+    shape[dims - 1] += pads[1] + pads[3];
+    shape
+}
+
+/// Returns the shape of the input of the FFT convolution, given the padded shape `input_shape` of
+/// the output of the previous layer and the zero padding of the convolution.
+pub(crate) fn fft_input_shape(input_shape: &[usize], pads: [usize; 4]) -> Vec<usize> {
+    zero_padded_shape(input_shape, pads)
+        .into_iter()
+        .map(|dim| dim.next_power_of_two())
+        .collect()
+}
+
+/// Describes where the entries of the input are copied along one spatial dimension in a [`Selection`].
+#[derive(Clone, Copy, Debug)]
+enum AxisSelection {
+    /// Index `x` is copied to `x / stride` if it is a multiple of `stride`
+    Subsampling(usize),
+    /// Index `x` is copied to `x + offset` if `x < len`
+    Shift { offset: usize, len: usize },
+}
+
+impl AxisSelection {
+    fn target(&self, x: usize) -> Option<usize> {
+        match *self {
+            AxisSelection::Subsampling(stride) => (x % stride == 0).then(|| x / stride),
+            AxisSelection::Shift { offset, len } => (x < len).then(|| x + offset),
+        }
+    }
+
+    /// Returns the vector `v` of size `input_dim` such that `v[x] = betas[t]` if `x` is copied to
+    /// the output index `t`, and 0 otherwise.
+    fn selector<E: ExtensionField>(&self, betas: &[E], input_dim: usize) -> Vec<E> {
+        (0..input_dim)
+            .map(|x| {
+                self.target(x)
+                    .and_then(|t| betas.get(t))
+                    .copied()
+                    .unwrap_or(E::ZERO)
+            })
+            .collect()
+    }
+}
+
+/// Operation over the last two dimensions of a tensor where every output entry is either zero or a
+/// copy of an input entry of the same channel. It is used to prove the zero padding of the input and
+/// the subsampling of the output of strided convolutions, via a sumcheck on `input(x) * S(r, x)`.
+#[derive(Clone, Copy, Debug)]
+struct Selection {
+    rows: AxisSelection,
+    cols: AxisSelection,
+}
+
+impl Selection {
+    /// `output[c][i][j] = input[c][i * stride][j * stride]`
+    fn subsampling(stride: usize) -> Self {
+        Self {
+            rows: AxisSelection::Subsampling(stride),
+            cols: AxisSelection::Subsampling(stride),
+        }
+    }
+
+    /// `output[c][i + top][j + left] = input[c][i][j]` for all the entries in the unpadded input,
+    /// and zeros elsewhere.
+    fn zero_padding(pads: [usize; 4], unpadded_input_shape: &[usize]) -> Self {
+        let dims = unpadded_input_shape.len();
+        Self {
+            rows: AxisSelection::Shift {
+                offset: pads[0],
+                len: unpadded_input_shape[dims - 2],
+            },
+            cols: AxisSelection::Shift {
+                offset: pads[1],
+                len: unpadded_input_shape[dims - 1],
+            },
+        }
+    }
+
+    /// Applies the selection to the last two dimensions of `input`.
+    fn apply<T: Number>(&self, input: &Tensor<T>, output_shape: &[usize]) -> Tensor<T> {
+        let shape = input.get_shape();
+        let dims = shape.len();
+        let (height, width) = (shape[dims - 2], shape[dims - 1]);
+        let (out_height, out_width) = (output_shape[dims - 2], output_shape[dims - 1]);
+        let mut data = vec![T::default(); output_shape.iter().product()];
+        input
+            .get_data()
+            .chunks(height * width)
+            .zip(data.chunks_mut(out_height * out_width))
+            .for_each(|(input, output)| {
+                for x in 0..height {
+                    let Some(i) = self.rows.target(x).filter(|i| *i < out_height) else {
+                        continue;
+                    };
+                    for y in 0..width {
+                        if let Some(j) = self.cols.target(y).filter(|j| *j < out_width) {
+                            output[i * out_width + j] = input[x * width + y].clone();
+                        }
+                    }
+                }
+            });
+        Tensor::new(output_shape.to_vec(), data)
+    }
+
+    /// Splits `output_point` in its column, row and channel variables and builds the row and column
+    /// selectors over the input.
+    fn selectors<'a, E: ExtensionField>(
+        &self,
+        output_point: &'a [E],
+        input_shape: &[usize],
+        output_shape: &[usize],
+    ) -> Result<(Vec<E>, Vec<E>, &'a [E])> {
+        ensure!(
+            output_point.len()
+                == output_shape
+                    .iter()
+                    .map(|dim| ceil_log2(*dim))
+                    .sum::<usize>(),
+            "Invalid output point size for selection: expected shape {:?}, got {} vars",
+            output_shape,
+            output_point.len()
+        );
+        let (output_cols, rest) = output_point.split_at(ceil_log2(output_shape[2]));
+        let (output_rows, output_channels) = rest.split_at(ceil_log2(output_shape[1]));
+        let row_selector = self
+            .rows
+            .selector(&compute_betas_eval(output_rows), input_shape[1]);
+        let col_selector = self
+            .cols
+            .selector(&compute_betas_eval(output_cols), input_shape[2]);
+        Ok((row_selector, col_selector, output_channels))
+    }
+
+    /// Computes the evaluations of the polynomial `S` such that `output(r) = sum_x input(x) * S(x)`,
+    /// where `r` is `output_point`.
+    fn selector_evals<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_shape: &[usize],
+        output_shape: &[usize],
+    ) -> Result<Vec<E>> {
+        let (row_selector, col_selector, output_channels) =
+            self.selectors(output_point, input_shape, output_shape)?;
+        let betas_channel = compute_betas_eval(output_channels);
+        let (height, width) = (input_shape[1], input_shape[2]);
+        Ok((0..input_shape.iter().product::<usize>())
+            .into_par_iter()
+            .map(|idx| {
+                betas_channel[idx / (height * width)]
+                    * row_selector[(idx / width) % height]
+                    * col_selector[idx % width]
+            })
+            .collect())
+    }
+
+    /// Evaluates the polynomial `S` of `selector_evals` over `input_point`, without iterating over
+    /// the whole input.
+    fn selector_eval<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_point: &[E],
+        input_shape: &[usize],
+        output_shape: &[usize],
+    ) -> Result<E> {
+        let (row_selector, col_selector, output_channels) =
+            self.selectors(output_point, input_shape, output_shape)?;
+        let (input_cols, rest) = input_point.split_at(ceil_log2(input_shape[2]));
+        let (input_rows, input_channels) = rest.split_at(ceil_log2(input_shape[1]));
+        let dot = |selector: Vec<E>, point: &[E]| {
+            selector
+                .into_iter()
+                .zip(compute_betas_eval(point))
+                .fold(E::ZERO, |acc, (s, b)| acc + s * b)
+        };
+        Ok(identity_eval(output_channels, input_channels)
+            * dot(row_selector, input_rows)
+            * dot(col_selector, input_cols))
+    }
+
+    /// Proves that the claimed output is the selection of `input`, by running a sumcheck on
+    /// `input(x) * S(x)`. Returns the proof together with the claim about `input`.
+    fn prove<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        transcript: &mut T,
+        claim: &Claim<E>,
+        input: &Tensor<E>,
+        output_shape: &[usize],
+    ) -> Result<(IOPProof<E>, Claim<E>)> {
+        let input_shape = input.get_shape();
+        let num_vars = input_shape.iter().map(|dim| ceil_log2(*dim)).sum::<usize>();
+        let selector = self
+            .selector_evals(&claim.point, &input_shape, output_shape)
+            .context("invalid claim for selection output")?;
+        let mut vp = VirtualPolynomial::<E>::new(num_vars);
+        vp.add_mle_list(
+            vec![
+                input.get_data().to_vec().into_mle().into(),
+                selector.into_mle().into(),
+            ],
+            E::ONE,
+        );
+        #[allow(deprecated)]
+        let (proof, state) = IOPProverState::<E>::prove_parallel(vp, transcript);
+        let eval = state.get_mle_final_evaluations()[0];
+        let point = proof.point.clone();
+        Ok((proof, Claim::new(point, eval)))
+    }
+
+    /// Verifies the selection sumcheck and returns the claim about the input of shape `input_shape`.
+    fn verify<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        transcript: &mut T,
+        claim: &Claim<E>,
+        proof: &IOPProof<E>,
+        input_eval: E,
+        input_shape: &[usize],
+        output_shape: &[usize],
+    ) -> Result<Claim<E>> {
+        let num_vars = input_shape.iter().map(|dim| ceil_log2(*dim)).sum::<usize>();
+        let subclaim = IOPVerifierState::<E>::verify(
+            claim.eval,
+            proof,
+            &VPAuxInfo::from_mle_list_dimensions(&[vec![num_vars; 2]]),
+            transcript,
+        );
+        let input_point = subclaim
+            .point
+            .iter()
+            .map(|challenge| challenge.elements)
+            .collect::<Vec<_>>();
+        let selector_eval =
+            self.selector_eval(&claim.point, &input_point, input_shape, output_shape)?;
+        ensure!(
+            input_eval * selector_eval == subclaim.expected_evaluation,
+            "Selection sumcheck of convolution failed"
+        );
+        Ok(Claim::new(input_point, input_eval))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         NextPowerOfTwo,
+        commit::poly_id::DENSE_BIAS_POLY_ID,
         layers::{
             activation::{Activation, Relu},
-            dense::{self, Dense},
+            dense::Dense,
             pooling::{Maxpool2D, Pooling, maxpool2d_shape},
             provable::evaluate_layer,
         },
     };
 
     use super::*;
+    use ark_std::rand::thread_rng;
+    use ff::Field;
     use goldilocks::GoldilocksExt2;
+    use itertools::Itertools;
+    use multilinear_extensions::mle::DenseMultilinearExtension;
 
     fn split_garbage(
         fft_output: &Tensor<Element>,
@@ -1800,12 +2636,256 @@ mod test {
         // assert!(fft_output.data == normal_output.data);
     }
 
+    #[test]
+    fn test_conv_strided_fft_vs_naive() {
+        let input_shape: Vec<usize> = vec![3, 23, 23];
+        let conv_shape_og: Vec<usize> = vec![5, 3, 3, 3];
+        let weight = Tensor::random(&conv_shape_og);
+        let bias = Tensor::random(&vec![conv_shape_og[0]]);
+        let input = Tensor::random(&input_shape);
+        let padded_input = input.pad_next_power_of_two();
+        for stride in [1, 2, 3, 4] {
+            let output = input.conv2d(&weight, &bias, stride);
+            let output_shape = strided_conv2d_shape(&input_shape, &conv_shape_og, stride);
+            assert_eq!(output.get_shape()[1..], output_shape[..]);
+            let fft_conv = Convolution::new(weight.clone(), bias.clone())
+                .with_stride(stride)
+                .unwrap()
+                .into_padded_and_ffted(&input_shape);
+            let (fft_output, _) = fft_conv.op::<GoldilocksExt2>(&padded_input, &input_shape);
+            assert_eq!(
+                fft_output.get_shape(),
+                fft_conv.output_shape(&padded_input.get_shape(), PaddingMode::Padding)
+            );
+            // the subsampled output matches the naive strided convolution and is zero elsewhere
+            let (valid, garbage) = split_garbage(&fft_output, &output.get_shape());
+            assert_eq!(valid, output.get_data().to_vec());
+            assert!(garbage.iter().all(|x| *x == 0));
+        }
+    }
+
+    #[test]
+    fn test_conv_selection() {
+        type F = GoldilocksExt2;
+        let mut rng = thread_rng();
+        let input_shape = vec![4usize, 32, 32];
+        let num_vars = input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        let input = Tensor::<Element>::random(&input_shape);
+        let input_evals = input.evals_flat::<F>();
+        let subsamplings = [2, 3, 5].map(|stride| {
+            (
+                Selection::subsampling(stride),
+                padded_strided_shape(&input_shape, stride),
+            )
+        });
+        let paddings = [
+            ([1, 2, 1, 0], vec![4, 29, 30]),
+            ([2, 2, 2, 2], vec![4, 28, 28]),
+            ([1, 0, 0, 1], vec![3, 20, 17]),
+        ]
+        .map(|(pads, unpadded_shape)| {
+            (
+                Selection::zero_padding(pads, &unpadded_shape),
+                fft_input_shape(&input_shape, pads),
+            )
+        });
+        for (selection, output_shape) in subsamplings.into_iter().chain(paddings) {
+            let output = selection.apply(&input, &output_shape);
+            let output_vars = output_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+            let output_point = (0..output_vars).map(|_| F::random(&mut rng)).collect_vec();
+            let output_eval = output.to_mle_flat::<F>().evaluate(&output_point);
+            // the output is the sum of the input weighted by the selector
+            let selector = selection
+                .selector_evals(&output_point, &input_shape, &output_shape)
+                .unwrap();
+            let sum = input_evals
+                .iter()
+                .zip(selector.iter())
+                .fold(F::ZERO, |acc, (c, s)| acc + *c * s);
+            assert_eq!(sum, output_eval);
+            // the verifier evaluates the selector without iterating over the input
+            let input_point = (0..num_vars).map(|_| F::random(&mut rng)).collect_vec();
+            let expected =
+                DenseMultilinearExtension::<F>::from_evaluations_ext_vec(num_vars, selector)
+                    .evaluate(&input_point);
+            assert_eq!(
+                selection
+                    .selector_eval(&output_point, &input_point, &input_shape, &output_shape)
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_conv_zero_padding_fft_vs_naive() {
+        let input_shape: Vec<usize> = vec![3, 23, 23];
+        let conv_shape_og: Vec<usize> = vec![5, 3, 3, 3];
+        let weight = Tensor::random(&conv_shape_og);
+        let bias = Tensor::random(&vec![conv_shape_og[0]]);
+        let input = Tensor::random(&input_shape);
+        let padded_input = input.pad_next_power_of_two();
+        for (padding, stride, pads) in [
+            (ConvPadding::Explicit([1, 2, 3, 2]), 1, [1, 2, 3, 2]),
+            (ConvPadding::Explicit([1, 2, 3, 2]), 2, [1, 2, 3, 2]),
+            (ConvPadding::SameUpper, 1, [1, 1, 1, 1]),
+            (ConvPadding::SameUpper, 2, [1, 1, 1, 1]),
+            (ConvPadding::SameUpper, 3, [0, 0, 1, 1]),
+            (ConvPadding::SameLower, 3, [1, 1, 0, 0]),
+        ] {
+            let conv = Convolution::new(weight.clone(), bias.clone())
+                .with_stride(stride)
+                .unwrap()
+                .with_padding(padding);
+            assert_eq!(conv.pads(&input_shape), pads);
+            // pad the input by hand and run the naive convolution
+            let (height, width) = (23 + pads[0] + pads[2], 23 + pads[1] + pads[3]);
+            let mut data = vec![0; 3 * height * width];
+            for c in 0..3 {
+                for i in 0..23 {
+                    for j in 0..23 {
+                        data[c * height * width + (i + pads[0]) * width + j + pads[1]] =
+                            input.get_data()[c * 23 * 23 + i * 23 + j];
+                    }
+                }
+            }
+            let output = Tensor::new(vec![3, height, width], data).conv2d(&weight, &bias, stride);
+            assert_eq!(
+                output.get_shape()[1..],
+                conv.output_shape(&input_shape, PaddingMode::NoPadding)[..]
+            );
+            if let ConvPadding::SameUpper | ConvPadding::SameLower = padding {
+                assert_eq!(output.get_shape()[2], 23usize.div_ceil(stride));
+            }
+            let fft_conv = conv.into_padded_and_ffted(&input_shape);
+            let (fft_output, _) = fft_conv.op::<GoldilocksExt2>(&padded_input, &input_shape);
+            assert_eq!(
+                fft_output.get_shape(),
+                fft_conv.output_shape(&padded_input.get_shape(), PaddingMode::Padding)
+            );
+            let (valid, garbage) = split_garbage(&fft_output, &output.get_shape());
+            assert_eq!(valid, output.get_data().to_vec());
+            assert!(garbage.iter().all(|x| *x == 0));
+        }
+    }
+
+    #[test]
+    fn test_conv_rectangular_fft_vs_naive() {
+        let input_shape: Vec<usize> = vec![3, 21, 13];
+        let conv_shape_og: Vec<usize> = vec![4, 3, 3, 2];
+        let weight = Tensor::random(&conv_shape_og);
+        let bias = Tensor::random(&vec![conv_shape_og[0]]);
+        let input = Tensor::random(&input_shape);
+        let padded_input = input.pad_next_power_of_two();
+        for (padding, stride) in [
+            (ConvPadding::Valid, 1),
+            (ConvPadding::Valid, 2),
+            (ConvPadding::Explicit([0, 1, 2, 0]), 1),
+            (ConvPadding::SameUpper, 2),
+            (ConvPadding::SameLower, 1),
+        ] {
+            let conv = Convolution::new(weight.clone(), bias.clone())
+                .with_stride(stride)
+                .unwrap()
+                .with_padding(padding);
+            let output = conv.conv2d(&input);
+            assert_eq!(
+                output.get_shape()[1..],
+                conv.output_shape(&input_shape, PaddingMode::NoPadding)[..]
+            );
+            let fft_conv = conv.into_padded_and_ffted(&input_shape);
+            let (fft_output, _) = fft_conv.op::<GoldilocksExt2>(&padded_input, &input_shape);
+            assert_eq!(
+                fft_output.get_shape(),
+                fft_conv.output_shape(&padded_input.get_shape(), PaddingMode::Padding)
+            );
+            let (valid, garbage) = split_garbage(&fft_output, &output.get_shape());
+            assert_eq!(valid, output.get_data().to_vec());
+            assert!(garbage.iter().all(|x| *x == 0));
+        }
+    }
+
+    #[test]
+    fn test_grouped_conv_fft_vs_naive() {
+        for (input_shape, filter_shape, groups) in [
+            // two groups of two input channels, with four filters each
+            (vec![4, 12, 12], vec![8, 2, 3, 3], 2),
+            // depthwise convolution, with an empty group added by the channel padding
+            (vec![3, 10, 10], vec![3, 1, 3, 3], 3),
+            // depthwise convolution with a channel multiplier of 2
+            (vec![4, 9, 11], vec![8, 1, 2, 3], 4),
+        ] {
+            let weight = Tensor::random(&filter_shape);
+            let bias = Tensor::random(&vec![filter_shape[0]]);
+            let input = Tensor::random(&input_shape);
+            let padded_input = input.pad_next_power_of_two();
+            let conv = GroupedConv::new(weight.clone(), bias.clone(), groups).unwrap();
+            let output = conv.0.conv2d(&input);
+            // each filter only sees the input channels of its group
+            let (height, width) = (input_shape[1], input_shape[2]);
+            let filters_per_group = filter_shape[0] / groups;
+            let kernel_size = filter_shape[1] * filter_shape[2] * filter_shape[3];
+            let output_size = output.get_data().len() / filter_shape[0];
+            for i in 0..filter_shape[0] {
+                let group = i / filters_per_group;
+                let channels = group * filter_shape[1]..(group + 1) * filter_shape[1];
+                let group_input = Tensor::new(
+                    vec![filter_shape[1], height, width],
+                    input.get_data()
+                        [channels.start * height * width..channels.end * height * width]
+                        .to_vec(),
+                );
+                let mut single_filter_shape = filter_shape.clone();
+                single_filter_shape[0] = 1;
+                let single_filter = Tensor::new(
+                    single_filter_shape,
+                    weight.get_data()[i * kernel_size..(i + 1) * kernel_size].to_vec(),
+                );
+                let expected = group_input.conv2d(
+                    &single_filter,
+                    &Tensor::new(vec![1], vec![bias.get_data()[i]]),
+                    1,
+                );
+                assert_eq!(
+                    expected.get_data(),
+                    &output.get_data()[i * output_size..(i + 1) * output_size]
+                );
+            }
+            let fft_conv = conv.into_padded_and_ffted(&input_shape);
+            assert_eq!(
+                fft_conv.groups(),
+                filter_shape[0].next_power_of_two() / filters_per_group
+            );
+            let (fft_output, _) = fft_conv.0.op::<GoldilocksExt2>(&padded_input, &input_shape);
+            let (valid, garbage) = split_garbage(&fft_output, &output.get_shape());
+            assert_eq!(valid, output.get_data().to_vec());
+            assert!(garbage.iter().all(|x| *x == 0));
+        }
+        // the channels of each group must be a power of two
+        assert!(
+            GroupedConv::new(
+                Tensor::<Element>::random(&vec![6, 3, 3, 3]),
+                Tensor::random(&vec![6]),
+                2
+            )
+            .is_err()
+        );
+        assert!(
+            GroupedConv::new(
+                Tensor::<Element>::random(&vec![6, 1, 3, 3]),
+                Tensor::random(&vec![6]),
+                4
+            )
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_conv_offset_poly_id() {
         // just a large difference so we're guaranteed that the IDs won't overlap.
         // TODO: change that process by a deterministic ID depending on the position and additional info
         // not necessarily seuential
-        assert!(BIAS_POLY_ID >= dense::BIAS_POLY_ID + 100_000);
+        assert!(CONV_BIAS_POLY_ID >= DENSE_BIAS_POLY_ID + 100_000);
     }
 
     #[test]
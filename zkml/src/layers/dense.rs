@@ -2,14 +2,15 @@ use std::cmp::Ordering;
 
 use crate::{
     Claim, NextPowerOfTwo, Prover, ScalingStrategy,
+    commit::poly_id::DENSE_BIAS_POLY_ID,
     iop::{
         context::{ContextAux, ShapeStep},
         verifier::Verifier,
     },
-    layers::{LayerCtx, LayerProof, PolyID, requant::Requant},
+    layers::{LayerCtx, LayerProof, PolyID, requant::Requant, rescale::Rescale},
     model::StepData,
     padding::{PaddingMode, ShapeInfo, pad_dense},
-    quantization::{self, BIT_LEN, ScalingFactor},
+    quantization::{self, ScalingFactor},
     tensor::Number,
 };
 use anyhow::{Context, Result, ensure};
@@ -31,11 +32,6 @@ use super::provable::{
     VerifiableCtx,
 };
 
-/// Bias to compute the bias ID polynomials. Since originally we take the index of each
-/// layer to be the index of the layer, we need to add a bias to avoid collision with other
-/// layers poly id.
-pub(crate) const BIAS_POLY_ID: PolyID = 100_000;
-
 /// Description of the layer
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Dense<T> {
@@ -53,6 +49,9 @@ pub struct DenseCtx<E> {
     pub bias_poly_id: PolyID,
     pub unpadded_matrix_shape: Vec<usize>,
     pub padded_matrix_shape: Vec<usize>,
+    /// Number of variables of the rows of the input, when the layer is applied to each row of a
+    /// matrix, 0 otherwise
+    pub input_rows_num_vars: usize,
 }
 
 /// Proof of the layer.
@@ -70,7 +69,16 @@ pub struct DenseProof<E: ExtensionField> {
     individual_claims: Vec<E>,
 }
 
+/// Whether a dense layer with the given matrix shape is applied to each row of an input with the given
+/// shape, e.g. to each token of a sequence, rather than to the flattened input
+pub(crate) fn is_row_wise(input_shape: &[usize], matrix_shape: &[usize]) -> bool {
+    input_shape.len() == 2 && input_shape[0] > 1 && input_shape[1] == matrix_shape[1]
+}
+
 fn output_shape(input_shape: &[usize], matrix_shape: &[usize]) -> Vec<usize> {
+    if is_row_wise(input_shape, matrix_shape) {
+        return vec![input_shape[0], matrix_shape[0]];
+    }
     assert_eq!(
         input_shape.iter().product::<usize>(),
         matrix_shape[1],
@@ -177,13 +185,23 @@ impl<N: Number> Evaluate<N> for Dense<N> {
             "Found more than 1 input when evaluating dense layer"
         );
         let input = inputs[0];
-        Ok(LayerOut::from_vec(vec![if input.get_shape().len() != 1 {
-            let flat_input = input.flatten();
-            let matvec = self.matrix.matvec(&flat_input);
-            matvec.add(&self.bias)
-        } else {
-            self.matrix.matvec(input).add(&self.bias)
-        }]))
+        Ok(LayerOut::from_vec(vec![
+            if is_row_wise(&input.get_shape(), &self.matrix.get_shape()) {
+                // the bias is added to each row of the output
+                let nrows = input.get_shape()[0];
+                let bias = Tensor::new(
+                    vec![nrows, self.bias.get_data().len()],
+                    self.bias.get_data().repeat(nrows),
+                );
+                input.matmul(&self.matrix.transpose()).add(&bias)
+            } else if input.get_shape().len() != 1 {
+                let flat_input = input.flatten();
+                let matvec = self.matrix.matvec(&flat_input);
+                matvec.add(&self.bias)
+            } else {
+                self.matrix.matvec(input).add(&self.bias)
+            },
+        ]))
     }
 }
 
@@ -196,9 +214,22 @@ where
     fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
         // construct dimension of the polynomial given to the sumcheck
         let ncols = self.matrix.ncols_2d();
-        aux.last_output_shape
-            .iter_mut()
-            .for_each(|shape| *shape = vec![self.matrix.nrows_2d()]);
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for dense layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let input_shape = &aux.last_output_shape[0];
+        let (input_rows_num_vars, output_shape) =
+            if is_row_wise(input_shape, &self.matrix.get_shape()) {
+                (input_shape[0].ilog2() as usize, vec![
+                    input_shape[0],
+                    self.matrix.nrows_2d(),
+                ])
+            } else {
+                (0, vec![self.matrix.nrows_2d()])
+            };
+        aux.last_output_shape[0] = output_shape;
         // each poly is only two polynomial right now: matrix and vector
         // for matrix, each time we fix the variables related to rows so we are only left
         // with the variables related to columns
@@ -211,9 +242,10 @@ where
                 matrix_num_vars,
                 vector_num_vars,
             ]]),
-            bias_poly_id: BIAS_POLY_ID + id,
+            bias_poly_id: DENSE_BIAS_POLY_ID + id,
             unpadded_matrix_shape: self.unpadded_matrix_shape.clone(),
             padded_matrix_shape: self.matrix.get_shape().to_vec(),
+            input_rows_num_vars,
         });
         Ok((dense_info, aux))
     }
@@ -229,10 +261,13 @@ where
         );
         debug!(
             "Commitment : dense layer bias ID {}: size {}",
-            BIAS_POLY_ID + id,
+            DENSE_BIAS_POLY_ID + id,
             bias_evals.len().ilog2()
         );
-        vec![Some((id, evals)), Some((BIAS_POLY_ID + id, bias_evals))]
+        vec![
+            Some((id, evals)),
+            Some((DENSE_BIAS_POLY_ID + id, bias_evals)),
+        ]
     }
 }
 
@@ -246,38 +281,57 @@ impl PadOp for Dense<Element> {
 }
 
 impl Dense<f32> {
-    // Quantize a dense layer using scaling factor of input and output
+    // Quantize a dense layer using scaling factor of input and output, with one scaling factor for
+    // each row of the matrix
     fn quantize_from_scalings(
         self,
         input_scaling: &[ScalingFactor],
         output_scaling: ScalingFactor,
     ) -> anyhow::Result<QuantizeOutput<Dense<Element>>> {
-        let model_scaling = ScalingFactor::from_absolute_max(self.max_abs_weight(), None);
         let num_inputs = input_scaling.len();
         ensure!(
             num_inputs == 1,
             "Number of input scaling factor for dense layer different from 1"
         );
         let input_scaling = &input_scaling[0];
-        let bias_scaling = {
-            // bias has to be quantized over integers with double bit length
-            let min_quantized = -(1 << (2 * (*BIT_LEN) - 1)) + 1;
-            let max_quantized = (1 << (2 * (*BIT_LEN) - 1)) - 1;
-            ScalingFactor::from_scale(
-                input_scaling.scale() * model_scaling.scale(),
-                Some((min_quantized, max_quantized)),
-            )
-        };
-        let shift = input_scaling.shift(&model_scaling, &output_scaling);
-        let quantized_dense = self.quantize(&model_scaling, &bias_scaling);
+        // the weights are quantized over the bit length of the input
+        let bit_len = input_scaling.bit_len();
+        let row_scalings = ScalingFactor::from_rows(&self.matrix, &self.bias, bit_len);
+        let bias_scalings = row_scalings
+            .iter()
+            .map(|row_scaling| {
+                // bias has to be quantized over integers with double bit length
+                ScalingFactor::from_scale(
+                    input_scaling.scale() * row_scaling.scale(),
+                    Some(quantization::domain(2 * bit_len)),
+                )
+            })
+            .collect_vec();
+        // the rows of the output have different scales, which are all brought to the scale of the
+        // output by the rescale layer, before the common shift of the requant layer
+        let (rescale, shift) =
+            Rescale::from_scalings(input_scaling, &row_scalings, &output_scaling, 1);
+        let mut quantized_dense = self.quantize_rows(&row_scalings, &bias_scalings);
+        if !input_scaling.is_symmetric() {
+            quantized_dense.bias = quantization::bias_with_zero_point(
+                &quantized_dense.matrix,
+                &quantized_dense.bias,
+                input_scaling.zero_point(),
+            );
+        }
         let (quantized_min, _quantized_max) =
-            quantized_dense.output_range(*quantization::MIN, *quantization::MAX);
-        let requant = Requant::new(quantized_min.abs() as usize, shift);
+            quantized_dense.output_range(quantization::min(bit_len), quantization::max(bit_len));
+        let requant = Requant::new(
+            (quantized_min.abs() * rescale.max_multiplier()) as usize,
+            shift,
+            output_scaling.bit_len(),
+        );
 
         Ok(QuantizeOutput {
             quanzited_op: quantized_dense,
             output_scalings: vec![output_scaling],
             requant_layer: Some(requant),
+            rescale_layer: Some(rescale),
         })
     }
 }
@@ -394,6 +448,18 @@ impl Dense<f32> {
         }
     }
 
+    /// Quantize the parameters of the dense layer with one scaling factor per row: the row `i` of
+    /// the matrix is quantized with `s[i]`, and the bias of this row with `bias_s[i]`
+    pub fn quantize_rows(self, s: &[ScalingFactor], bias_s: &[ScalingFactor]) -> Dense<Element> {
+        let matrix = self.matrix.quantize_rows(s);
+        let bias = self.bias.quantize_rows(bias_s);
+        Dense::<Element> {
+            matrix,
+            bias,
+            unpadded_matrix_shape: self.unpadded_matrix_shape.to_vec(),
+        }
+    }
+
     pub fn new_from_weights(weights: Tensor<f32>, bias: Tensor<f32>) -> Self {
         let unpadded_matrix_shape = weights.get_shape().to_vec();
         Self {
@@ -403,7 +469,8 @@ impl Dense<f32> {
         }
     }
 
-    /// TODO: compute two different scaling factors for weights and bias
+    /// Absolute max over the weights and the bias, to quantize the whole layer with a single
+    /// scaling factor
     pub fn max_abs_weight(&self) -> f32 {
         let max_weight = self.matrix.max_abs_output();
         let max_bias = self.bias.max_abs_output();
@@ -420,11 +487,13 @@ impl Dense<f32> {
 
 impl Dense<Element> {
     /// Returns the (min,max) output range of the dense layer for a given input range.
-    pub fn output_range(&self, _min_input: Element, _max_input: Element) -> (Element, Element) {
+    /// The weights are assumed to be quantized over the same bit length as the input.
+    pub fn output_range(&self, min_input: Element, max_input: Element) -> (Element, Element) {
         // formula is 2^{2 * BIT_LEN + log(c) + 1} where c is the number of columns and +1 because of the bias
+        let bit_len = quantization::bit_len((min_input, max_input)) as u32;
         let ncols = self.matrix.ncols_2d() as u32;
         // - 1 because numbers are signed so only half of the range is used when doing multiplication
-        let power = 2 * (*quantization::BIT_LEN as u32 - 1) + ncols.ilog2() + 1;
+        let power = 2 * (bit_len - 1) + ncols.ilog2() + 1;
         let min = -(2u64.pow(power as u32) as Element);
         let max = 2u64.pow(power as u32) as Element;
         return (min, max);
@@ -446,43 +515,50 @@ impl Dense<Element> {
     {
         let matrix = &self.matrix;
         let (nrows, ncols) = (matrix.nrows_2d(), matrix.ncols_2d());
+        let input_rows = 1 << info.input_rows_num_vars;
         assert_eq!(
-            nrows,
+            nrows * input_rows,
             output.get_data().len(),
             "dense proving: nrows {} vs output {}",
             nrows,
             output.get_data().len()
         );
         assert_eq!(
-            nrows.ilog2() as usize,
+            nrows.ilog2() as usize + info.input_rows_num_vars,
             last_claim.point.len(),
             "something's wrong with the randomness"
         );
         assert_eq!(
-            ncols,
+            ncols * input_rows,
             input.get_data().len(),
             "something's wrong with the input"
         );
+        // when the layer is applied to each row of the input, the last coordinates of the point
+        // select the row of the input
+        let (output_point, input_rows_point) = info.split_output_point(&last_claim.point);
         // Evaluates the bias at the random point so verifier can substract the evaluation
         // from the sumcheck claim that is only about the matrix2vec product.
         assert_eq!(
             self.bias.get_data().len().ilog2() as usize,
-            last_claim.point.len(),
+            output_point.len(),
             "something's wrong with the randomness"
         );
         let bias_eval = self
             .bias
             .evals_flat::<E>()
             .into_mle()
-            .evaluate(&last_claim.point);
+            .evaluate(output_point);
         // contruct the MLE combining the input and the matrix
         let mut mat_mle = matrix.to_mle_2d();
         // fix the variables from the random input
         // NOTE: here we must fix the HIGH variables because the MLE is addressing in little
         // endian so (rows,cols) is actually given in (cols, rows)
         // mat_mle.fix_variables_in_place_parallel(partial_point);
-        mat_mle.fix_high_variables_in_place(&last_claim.point);
-        let input_mle = input.get_data().to_vec().into_mle();
+        mat_mle.fix_high_variables_in_place(output_point);
+        let mut input_mle = input.get_data().to_vec().into_mle();
+        if !input_rows_point.is_empty() {
+            input_mle.fix_high_variables_in_place(input_rows_point);
+        }
 
         assert_eq!(mat_mle.num_vars(), input_mle.num_vars());
         let num_vars = input_mle.num_vars();
@@ -536,7 +612,7 @@ impl Dense<Element> {
 
         // PCS part: here we need to create an opening proof for the final evaluation of the matrix poly
         // Note we need the _full_ input to the matrix since the matrix MLE has (row,column) vars space
-        let point = [proof.point.as_slice(), last_claim.point.as_slice()].concat();
+        let point = [proof.point.as_slice(), output_point].concat();
         let eval = state.get_mle_final_evaluations()[0];
         prover
             .commit_prover
@@ -548,14 +624,14 @@ impl Dense<Element> {
             .commit_prover
             .add_claim(
                 info.bias_poly_id,
-                Claim::new(last_claim.point.clone(), bias_eval),
+                Claim::new(output_point.to_vec(), bias_eval),
             )
             .context("unable to add bias claim")?;
 
         // the claim that this proving step outputs is the claim about not the matrix but the vector poly.
         // at next step, that claim will be proven over this vector poly (either by the next dense layer proving, or RELU etc).
         let claim = Claim {
-            point: proof.point.clone(),
+            point: [proof.point.as_slice(), input_rows_point].concat(),
             eval: state.get_mle_final_evaluations()[1],
         };
         prover.push_proof(
@@ -582,6 +658,11 @@ where
         };
         output_shape(input_shape, mat_shape)
     }
+    /// Splits the point of a claim about the output into the coordinates of the output of the
+    /// matrix and the ones of the rows of the input, if the layer is applied to each row
+    fn split_output_point<'a>(&self, point: &'a [E]) -> (&'a [E], &'a [E]) {
+        point.split_at(point.len() - self.input_rows_num_vars)
+    }
     pub(crate) fn verify_dense<T: Transcript<E>>(
         &self,
         verifier: &mut Verifier<E, T>,
@@ -589,7 +670,9 @@ where
         proof: &DenseProof<E>,
     ) -> anyhow::Result<Claim<E>> {
         let info = self;
-        // Subtract the bias evaluation from the previous claim to remove the bias
+        let (output_point, input_rows_point) = info.split_output_point(&last_claim.point);
+        // Subtract the bias evaluation from the previous claim to remove the bias: since the same
+        // bias is added to every row, its contribution doesn't depend on the rows of the input
         let eval_no_bias = last_claim.eval - proof.bias_eval;
         // TODO: currently that API can panic - should remove panic for error
         let subclaim = IOPVerifierState::<E>::verify(
@@ -604,7 +687,7 @@ where
         let pcs_eval_input = subclaim
             .point_flat()
             .iter()
-            .chain(last_claim.point.iter())
+            .chain(output_point.iter())
             .cloned()
             .collect_vec();
         // 0 because Matrix comes first in Matrix x Vector
@@ -617,7 +700,7 @@ where
         )?;
         verifier.commit_verifier.add_claim(
             info.bias_poly_id,
-            Claim::new(last_claim.point.clone(), proof.bias_eval),
+            Claim::new(output_point.to_vec(), proof.bias_eval),
         )?;
 
         // SUMCHECK verification part
@@ -640,7 +723,7 @@ where
         // the output claim for this step that is going to be verified at next step
         Ok(Claim {
             // the new randomness to fix at next layer is the randomness from the sumcheck !
-            point: subclaim.point_flat(),
+            point: [subclaim.point_flat().as_slice(), input_rows_point].concat(),
             // the claimed sum for the next sumcheck is MLE of the current vector evaluated at the
             // random point. 1 because vector is secondary.
             eval: proof.individual_claims[1],
@@ -833,4 +916,72 @@ mod test {
             assert_eq!(output.get_data()[i], padded_output.get_data()[i]);
         }
     }
+
+    #[test]
+    fn test_dense_quantize_per_row() {
+        // the weights of the second row are much smaller than the ones of the first row, so they would
+        // be quantized to a handful of values with a single scaling factor for the whole matrix
+        let matrix = Tensor::<f32>::new(vec![2, 4], vec![
+            0.9, -0.5, 0.3, 0.7, 0.009, -0.005, 0.003, 0.007,
+        ]);
+        let bias = Tensor::<f32>::new(vec![2], vec![0.1, 0.001]);
+        let dense = Dense::new(matrix, bias);
+        let input = Tensor::<f32>::new(vec![4], vec![0.5, -0.25, 1.0, 0.75]);
+        let float_output = evaluate_layer::<GoldilocksExt2, _, _>(&dense, &[&input], None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+
+        let input_scaling = ScalingFactor::from_absolute_max(1.0, None);
+        let output_scaling = ScalingFactor::from_absolute_max(2.0, None);
+        let quantized = dense
+            .quantize_from_scalings(&[input_scaling], output_scaling)
+            .unwrap();
+        let requant = quantized.requant_layer.unwrap();
+        let rescale = quantized.rescale_layer.unwrap();
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(
+            &quantized.quanzited_op,
+            &[&input.quantize(&input_scaling)],
+            None,
+        )
+        .unwrap()
+        .outputs()[0]
+            .clone();
+        // before the shift of the requant layer, both rows are at the scale of the output times
+        // 2^shift, with a small relative error
+        let rescaled = rescale.op(&output).unwrap();
+        let scale = output_scaling.scale() / (1u64 << requant.right_shift) as f32;
+        for (q, f) in rescaled.get_data().iter().zip(float_output.get_data()) {
+            let dequantized = *q as f32 * scale;
+            assert!(
+                (dequantized - f).abs() <= f.abs() / 50.0,
+                "dequantized {dequantized} too far from {f}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dense_row_wise() {
+        let dense = Dense::<Element>::random(vec![5, 3]);
+        let input = Tensor::<Element>::random(&vec![4, 3]);
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(&dense, &vec![&input], None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_shape(), vec![4, 5]);
+        assert_eq!(
+            dense.output_shape(&input.get_shape(), PaddingMode::NoPadding),
+            vec![4, 5]
+        );
+        // each row of the output is the dense layer applied to the same row of the input
+        for i in 0..4 {
+            let row =
+                Tensor::<Element>::new(vec![3], input.get_data()[i * 3..(i + 1) * 3].to_vec());
+            let expected = evaluate_layer::<GoldilocksExt2, _, _>(&dense, &vec![&row], None)
+                .unwrap()
+                .outputs()[0]
+                .clone();
+            assert_eq!(&output.get_data()[i * 5..(i + 1) * 5], expected.get_data());
+        }
+    }
 }
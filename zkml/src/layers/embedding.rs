@@ -0,0 +1,632 @@
+//! Embedding layer, mapping each token index of its input to the corresponding row of a table, e.g.
+//! the input stage of NLP and recommender models, which is an ONNX `Gather` over the rows of a
+//! constant matrix.
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{poly_id::EMBEDDING_MULTIPLICITY_POLY_ID, precommit::PolyID, same_poly},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof},
+    lookup::{
+        context::{LookupWitnessGen, TableType},
+        logup_gkr::{
+            prover::batch_prove as logup_batch_prove, structs::LogUpProof,
+            verifier::verify_logup_proof,
+        },
+    },
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo, embedding},
+    quantization::{self, Fieldizer},
+    tensor::Number,
+};
+use anyhow::{Result, anyhow, ensure};
+use ff::Field;
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::Itertools;
+use multilinear_extensions::mle::IntoMLE;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Replaces each index `i` of its input with the `i`-th row of a table of shape
+/// `[num_embeddings, embedding_dim]`, so an input of shape `s` gives an output of shape
+/// `[s, embedding_dim]`.
+///
+/// The table is committed along with the weights of the model. Each output entry `y` at column `j`
+/// of the row selected by `i` is proven with a lookup of the tuple `(i * d + j, y)` into the tuples
+/// `(p, table[p])` for each position `p` of the flattened table, where `d` is the padded embedding
+/// dimension. As the table is specific to the layer, the layer proves both sides of the lookup
+/// argument, and checks on its own that the lookups are matched by its table: all the embedding
+/// layers share the same challenges, so a global check would let the lookups of a layer be matched
+/// by the table of another one. The indices are not scaled by the quantization, so they are given
+/// as they are to the quantized model.
+///
+/// Note the padded entries of the input select the first row of the table, so the padded rows of
+/// the output are garbage.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Embedding<T> {
+    /// One row per token, padded once the layer is padded
+    table: Tensor<T>,
+    unpadded_table_shape: Vec<usize>,
+}
+
+/// Info about the embedding layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmbeddingCtx {
+    pub table_poly_id: PolyID,
+    /// Id of the witness polynomial of the output
+    pub output_poly_id: PolyID,
+    /// Id of the witness polynomial of the multiplicities of the entries of the table
+    pub multiplicity_poly_id: PolyID,
+    /// Number of variables of the padded embedding dimension
+    pub dim_num_vars: usize,
+    /// Number of variables of the padded table
+    pub table_num_vars: usize,
+    /// Number of variables of the output
+    pub output_num_vars: usize,
+    pub unpadded_table_shape: Vec<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EmbeddingProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// the lookup proof for the tuples of positions and output entries
+    pub(crate) lookup: LogUpProof<E>,
+    /// the lookup proof for the tuples of positions and entries of the table
+    pub(crate) table: LogUpProof<E>,
+    /// proof for the accumulation of the claim about the output and the claim from the lookup
+    pub(crate) io_accumulation: same_poly::Proof<E>,
+}
+
+impl<T: Number> Embedding<T> {
+    pub fn new(table: Tensor<T>) -> Self {
+        assert_eq!(
+            table.get_shape().len(),
+            2,
+            "embedding table must be a matrix"
+        );
+        let unpadded_table_shape = table.get_shape();
+        Self {
+            table,
+            unpadded_table_shape,
+        }
+    }
+
+    pub fn num_embeddings(&self) -> usize {
+        self.unpadded_table_shape[0]
+    }
+
+    pub fn embedding_dim(&self) -> usize {
+        self.unpadded_table_shape[1]
+    }
+
+    /// Pads the table with zeros to the next power of two in each dimension
+    pub(crate) fn padded(self) -> Self {
+        Self {
+            table: self.table.pad_next_power_of_two(),
+            unpadded_table_shape: self.unpadded_table_shape,
+        }
+    }
+
+    pub fn output_shape(&self, input_shape: &[usize], padding_mode: PaddingMode) -> Vec<usize> {
+        let dim = match padding_mode {
+            PaddingMode::NoPadding => self.embedding_dim(),
+            PaddingMode::Padding => self.embedding_dim().next_power_of_two(),
+        };
+        input_shape.iter().copied().chain([dim]).collect()
+    }
+
+    /// Returns the rows of the table selected by the entries of `input`
+    fn indices(&self, input: &Tensor<T>) -> Result<Vec<usize>> {
+        let num_rows = self.table.get_shape()[0];
+        input
+            .get_data()
+            .iter()
+            .map(|x| {
+                let index = x.to_f32()?;
+                ensure!(
+                    index >= 0.0 && index.fract() == 0.0 && (index as usize) < num_rows,
+                    "Invalid index {index} for embedding table with {num_rows} rows"
+                );
+                Ok(index as usize)
+            })
+            .collect()
+    }
+
+    pub fn op(&self, input: &Tensor<T>) -> Result<Tensor<T>> {
+        let dim = self.table.get_shape()[1];
+        let output = self
+            .indices(input)?
+            .into_iter()
+            .flat_map(|i| {
+                self.table.get_data()[i * dim..(i + 1) * dim]
+                    .iter()
+                    .copied()
+            })
+            .collect();
+        let mut shape = input.get_shape();
+        shape.push(dim);
+        Ok(Tensor::new(shape, output))
+    }
+}
+
+/// Evaluation of the polynomial whose entries are their positions, e.g. the positions of the
+/// flattened table
+fn position_eval<E: ExtensionField>(point: &[E]) -> E {
+    point
+        .iter()
+        .enumerate()
+        .fold(E::ZERO, |acc, (index, p)| acc + *p * E::from(1u64 << index))
+}
+
+impl<N: Number> OpInfo for Embedding<N> {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes
+            .iter()
+            .map(|shape| self.output_shape(shape, padding_mode))
+            .collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Embedding: ({},{})",
+            self.num_embeddings(),
+            self.embedding_dim()
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<N: Number> Evaluate<N> for Embedding<N> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<N>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<N, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found {} inputs for embedding layer, expected 1",
+            inputs.len()
+        );
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])?]))
+    }
+}
+
+impl QuantizeOp for Embedding<f32> {
+    type QuantizedOp = Embedding<Element>;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == 1,
+            "Found {} input scaling factors for embedding layer, expected 1",
+            input_scaling.len()
+        );
+        // the indices are not affected by the scale of the input, so the output is made of entries
+        // of the table, quantized with the scaling factor of the table over the bit length of the
+        // output of the node
+        let bit_len = S::scaling_factors_for_node(data, node_id, 1)[0].bit_len();
+        let scaling = ScalingFactor::from_tensor(&self.table, Some(quantization::domain(bit_len)));
+        Ok(QuantizeOutput {
+            quanzited_op: Embedding {
+                table: self.table.quantize(&scaling),
+                unpadded_table_shape: self.unpadded_table_shape,
+            },
+            output_scalings: vec![scaling],
+            requant_layer: None,
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for Embedding<Element>
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for embedding layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let table_shape = self.table.get_shape();
+        ensure!(
+            table_shape.iter().all(|d| d.is_power_of_two()),
+            "Embedding table must be padded to be proven, found shape {:?}",
+            table_shape
+        );
+        let output_shape = self.output_shape(&aux.last_output_shape[0], PaddingMode::Padding);
+        aux.tables.insert(TableType::Embedding);
+        let ctx = EmbeddingCtx {
+            table_poly_id: id,
+            output_poly_id: id,
+            multiplicity_poly_id: EMBEDDING_MULTIPLICITY_POLY_ID + id,
+            dim_num_vars: ceil_log2(table_shape[1]),
+            table_num_vars: ceil_log2(table_shape.iter().product()),
+            output_num_vars: ceil_log2(output_shape.iter().product()),
+            unpadded_table_shape: self.unpadded_table_shape.clone(),
+        };
+        aux.last_output_shape = vec![output_shape];
+        Ok((LayerCtx::Embedding(ctx), aux))
+    }
+
+    fn commit_info(&self, id: NodeId) -> Vec<Option<(PolyID, Vec<E>)>> {
+        vec![Some((id as PolyID, self.table.evals_flat()))]
+    }
+}
+
+impl PadOp for Embedding<Element> {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        embedding(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Embedding<Element>
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = EmbeddingCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for embedding layer, expected 1",
+            last_claims.len()
+        );
+        let mut witnesses = prover.lookup_witnesses(node_id)?;
+        ensure!(
+            witnesses.len() == 2,
+            "Found {} lookup witnesses for embedding layer, expected 2",
+            witnesses.len()
+        );
+        // the witness of the table comes after the one of the lookup
+        let table_witness = witnesses.pop().unwrap();
+        let lookup_witness = witnesses.pop().unwrap();
+        let lookup_proof = logup_batch_prove(&lookup_witness, prover.transcript)?;
+        let table_proof = logup_batch_prove(&table_witness, prover.transcript)?;
+
+        // The lookup columns are the positions and the output
+        let input_claim = ctx.input_claim(&lookup_proof.output_claims()[0]);
+        let mut same_poly_prover = same_poly::Prover::<E>::new(
+            step_data.outputs.outputs()[0]
+                .get_data()
+                .to_vec()
+                .into_mle(),
+        );
+        let same_poly_ctx = same_poly::Context::<E>::new(ctx.output_num_vars);
+        same_poly_prover.add_claim(last_claims[0].clone())?;
+        same_poly_prover.add_claim(lookup_proof.output_claims()[1].clone())?;
+        let claim_acc_proof = same_poly_prover.prove(&same_poly_ctx, prover.transcript)?;
+        prover
+            .witness_prover
+            .add_claim(ctx.output_poly_id, claim_acc_proof.extract_claim())?;
+
+        // The claims of the table are about the multiplicities, the positions and the entries
+        let table_claims = table_proof.output_claims();
+        prover
+            .witness_prover
+            .add_claim(ctx.multiplicity_poly_id, table_claims[0].clone())?;
+        prover
+            .commit_prover
+            .add_claim(ctx.table_poly_id, table_claims[2].clone())?;
+
+        prover.push_proof(
+            node_id,
+            LayerProof::Embedding(EmbeddingProof {
+                lookup: lookup_proof,
+                table: table_proof,
+                io_accumulation: claim_acc_proof,
+            }),
+        );
+        Ok(vec![input_claim])
+    }
+
+    fn gen_lookup_witness(
+        &self,
+        id: NodeId,
+        gen: &mut LookupWitnessGen<E>,
+        step_data: &StepData<Element, E>,
+    ) -> Result<()> {
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of embedding layer, expected 1",
+            step_data.inputs.len()
+        );
+        ensure!(
+            step_data.outputs.outputs().len() == 1,
+            "Found {} outputs in inference step of embedding layer, expected 1",
+            step_data.outputs.outputs().len()
+        );
+        gen.tables.insert(TableType::Embedding);
+        let (num_rows, dim) = (self.table.get_shape()[0], self.table.get_shape()[1]);
+        let to_base = |x: &Element| Fieldizer::<E>::to_field(x).as_bases()[0];
+
+        let indices = self.indices(&step_data.inputs[0])?;
+        let output = step_data.outputs.outputs()[0];
+        let positions = indices
+            .iter()
+            .flat_map(|i| (0..dim).map(move |j| E::BaseField::from((i * dim + j) as u64)))
+            .collect_vec();
+        let values = output.get_data().iter().map(to_base).collect_vec();
+
+        // each row is looked up once per index selecting it
+        let mut counts = vec![0u64; num_rows];
+        indices.iter().for_each(|i| counts[*i] += 1);
+        let multiplicities = counts
+            .into_iter()
+            .flat_map(|count| std::iter::repeat(count).take(dim))
+            .collect_vec();
+
+        gen.polys_with_id.push((
+            id as PolyID,
+            output
+                .get_data()
+                .iter()
+                .map(Fieldizer::<E>::to_field)
+                .collect(),
+        ));
+        gen.polys_with_id.push((
+            EMBEDDING_MULTIPLICITY_POLY_ID + id,
+            multiplicities.iter().map(|m| E::from(*m)).collect(),
+        ));
+        gen.lookups_no_challenges.insert(id, vec![(
+            vec![positions, values],
+            2,
+            TableType::Embedding,
+        )]);
+        let table_positions = (0..num_rows * dim)
+            .map(|p| E::BaseField::from(p as u64))
+            .collect_vec();
+        let table_values = self.table.get_data().iter().map(to_base).collect_vec();
+        gen.node_tables_no_challenges.insert(id, vec![(
+            vec![table_positions, table_values],
+            multiplicities.into_iter().map(E::BaseField::from).collect(),
+            TableType::Embedding,
+        )]);
+        Ok(())
+    }
+}
+
+impl EmbeddingCtx {
+    /// Claim about the indices given a claim about the positions `i * d + j` looked up in the
+    /// table, which depend on the indices `i` and on the column `j` of the output
+    fn input_claim<E: ExtensionField>(&self, position_claim: &Claim<E>) -> Claim<E> {
+        let (dim_point, index_point) = position_claim.point.split_at(self.dim_num_vars);
+        let dim_inv = E::from(1u64 << self.dim_num_vars).invert().unwrap();
+        Claim::new(
+            index_point.to_vec(),
+            (position_claim.eval - position_eval(dim_point)) * dim_inv,
+        )
+    }
+}
+
+impl OpInfo for EmbeddingCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        let dim = match padding_mode {
+            PaddingMode::NoPadding => self.unpadded_table_shape[1],
+            PaddingMode::Padding => 1 << self.dim_num_vars,
+        };
+        input_shapes
+            .iter()
+            .map(|shape| shape.iter().copied().chain([dim]).collect())
+            .collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Embedding ctx: ({},{})",
+            self.unpadded_table_shape[0], self.unpadded_table_shape[1]
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for EmbeddingCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = EmbeddingProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for embedding layer, expected 1",
+            last_claims.len()
+        );
+        let table_type = TableType::Embedding;
+        let (constant_challenge, column_separation_challenge) = verifier
+            .challenge_storage
+            .as_ref()
+            .unwrap()
+            .get_challenges_by_name(&table_type.name())
+            .ok_or(anyhow!(
+                "Couldn't get challenges for LookupType: {}",
+                table_type.name()
+            ))?;
+
+        // 1. Verify the lookup proofs of the output and of the table
+        let lookup_claims = verify_logup_proof(
+            &proof.lookup,
+            1,
+            constant_challenge,
+            column_separation_challenge,
+            verifier.transcript,
+        )?;
+        let table_claims = verify_logup_proof(
+            &proof.table,
+            1,
+            constant_challenge,
+            column_separation_challenge,
+            verifier.transcript,
+        )?;
+        let (lookup_claims, table_claims) = (lookup_claims.claims(), table_claims.claims());
+        // the lookups must be matched by the table of this layer only
+        let (lookup_numerators, lookup_denominators) = proof.lookup.fractional_outputs();
+        let (table_numerators, table_denominators) = proof.table.fractional_outputs();
+        let (numerator, denominator) = lookup_numerators
+            .into_iter()
+            .chain(table_numerators)
+            .zip(lookup_denominators.into_iter().chain(table_denominators))
+            .fold((E::ZERO, E::ONE), |(acc_num, acc_denom), (num, denom)| {
+                (acc_num * denom + num * acc_denom, acc_denom * denom)
+            });
+        ensure!(
+            numerator == E::ZERO && denominator != E::ZERO,
+            "Lookups of embedding layer are not matched by its table"
+        );
+        ensure!(
+            lookup_claims.len() == 2 && lookup_claims[0].point.len() == self.output_num_vars,
+            "Invalid lookup claims for embedding layer"
+        );
+        ensure!(
+            table_claims.len() == 3 && table_claims[0].point.len() == self.table_num_vars,
+            "Invalid table claims for embedding layer"
+        );
+        // the positions of the table are the only column with a closed form
+        ensure!(
+            table_claims[1].eval == position_eval(&table_claims[1].point),
+            "Claimed evaluation of the positions of the embedding table is wrong"
+        );
+
+        // 2. Verify the accumulation of the output claim and of the lookup claim
+        let sp_ctx = same_poly::Context::<E>::new(self.output_num_vars);
+        let mut sp_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        sp_verifier.add_claim(last_claims[0].clone())?;
+        sp_verifier.add_claim(lookup_claims[1].clone())?;
+        let output_claim = sp_verifier.verify(&proof.io_accumulation, verifier.transcript)?;
+
+        // 3. Accumulate the claims about the witness polys and the table
+        verifier
+            .witness_verifier
+            .add_claim(self.output_poly_id, output_claim)?;
+        verifier
+            .witness_verifier
+            .add_claim(self.multiplicity_poly_id, table_claims[0].clone())?;
+        verifier
+            .commit_verifier
+            .add_claim(self.table_poly_id, table_claims[2].clone())?;
+
+        // 4. The claim about the positions gives the claim about the indices
+        Ok(vec![self.input_claim(&lookup_claims[0])])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+    use multilinear_extensions::mle::MultilinearExtension;
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_embedding_op() {
+        let table = Tensor::<Element>::new(vec![3, 2], vec![1, 2, 3, 4, 5, 6]);
+        let embedding = Embedding::new(table);
+        let output = embedding
+            .op(&Tensor::new(vec![2, 2], vec![2, 0, 1, 2]))
+            .unwrap();
+        assert_eq!(output.get_shape(), vec![2, 2, 2]);
+        assert_eq!(output.get_data(), &[5, 6, 1, 2, 3, 4, 5, 6]);
+        assert!(embedding.op(&Tensor::new(vec![1], vec![3])).is_err());
+        assert!(embedding.op(&Tensor::new(vec![1], vec![-1])).is_err());
+
+        // the padded layer pads the rows of the output, and keeps the unpadded indices
+        let padded = embedding.padded();
+        let output = padded.op(&Tensor::new(vec![2], vec![2, 0])).unwrap();
+        assert_eq!(output.get_shape(), vec![2, 2]);
+        assert_eq!(output.get_data(), &[5, 6, 1, 2]);
+        assert_eq!(padded.table.get_shape(), vec![4, 2]);
+    }
+
+    #[test]
+    fn test_embedding_input_claim() {
+        let (num_tokens, dim) = (4, 8);
+        let indices = vec![3, 0, 7, 3];
+        let positions = indices
+            .iter()
+            .flat_map(|i| (0..dim).map(move |j| F::from((i * dim + j) as u64)))
+            .collect_vec();
+        let ctx = EmbeddingCtx {
+            table_poly_id: 0,
+            output_poly_id: 0,
+            multiplicity_poly_id: EMBEDDING_MULTIPLICITY_POLY_ID,
+            dim_num_vars: ceil_log2(dim),
+            table_num_vars: ceil_log2(8 * dim),
+            output_num_vars: ceil_log2(num_tokens * dim),
+            unpadded_table_shape: vec![8, dim],
+        };
+        let point = (0..ctx.output_num_vars)
+            .map(|i| F::from(i as u64 + 2))
+            .collect_vec();
+        let position_claim = Claim::new(point.clone(), positions.into_mle().evaluate(&point));
+        let input_claim = ctx.input_claim(&position_claim);
+        let expected = indices
+            .into_iter()
+            .map(|i| F::from(i as u64))
+            .collect_vec()
+            .into_mle()
+            .evaluate(&input_claim.point);
+        assert_eq!(input_claim.point, point[ctx.dim_num_vars..].to_vec());
+        assert_eq!(input_claim.eval, expected);
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     commit::precommit::PolyID,
     iop::context::ContextAux,
     layers::LayerCtx,
-    padding::{PaddingMode, ShapeInfo, reshape},
+    padding::{PaddingMode, ShapeInfo, flatten},
     tensor::Number,
 };
 
@@ -32,7 +32,7 @@ impl OpInfo for Flatten {
     }
 
     fn describe(&self) -> String {
-        "Reshape".to_string()
+        "Flatten".to_string()
     }
 
     fn is_provable(&self) -> bool {
@@ -48,7 +48,7 @@ impl<N: Number> Evaluate<N> for Flatten {
     ) -> Result<LayerOut<N, E>> {
         ensure!(
             inputs.len() == 1,
-            "Found more than 1 input when evaluating reshape layer"
+            "Found more than 1 input when evaluating flatten layer"
         );
         let input = inputs[0];
         Ok(LayerOut::from_vec(vec![input.flatten()]))
@@ -73,6 +73,6 @@ impl PadOp for Flatten {
     where
         Self: Sized,
     {
-        reshape(si)
+        flatten(si)
     }
 }
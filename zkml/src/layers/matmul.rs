@@ -0,0 +1,661 @@
+//! Matrix multiplication between two tensors computed at inference time, e.g. the products between
+//! queries, keys and values in an attention layer.
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::precommit::PolyID,
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof, requant::Requant, topk::prefix_eq_eval},
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    tensor::Number,
+};
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use itertools::Itertools;
+use multilinear_extensions::{
+    mle::{DenseMultilinearExtension, IntoMLE, MultilinearExtension},
+    virtual_poly::{VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const NUM_INPUTS: usize = 2;
+const IS_PROVABLE: bool = true;
+
+/// Multiplies a `[m, n]` matrix by a `[n, p]` matrix, where both matrices are inputs of the layer
+/// rather than weights of the model. The second matrix can also be given transposed, i.e. as a
+/// `[p, n]` matrix, like the keys in the `Q * K^T` product of an attention layer.
+///
+/// The product is proven with a sumcheck over the shared dimension, which reduces the claim about the
+/// output to a claim about each input. Since the padded entries of the inputs are not guaranteed to
+/// be zero, the sumcheck only sums over the first `n` entries of the shared dimension.
+#[derive(Clone, Debug, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MatMul {
+    /// Unpadded size of the dimension shared by both inputs
+    inner_dim: usize,
+    /// Whether the second input is given transposed
+    transpose_right: bool,
+    /// Constant factor multiplying the output of the product, e.g. `1/sqrt(d)` for the attention
+    /// scores. It is only applied to float outputs: the quantized layer folds it in the
+    /// requantization that follows it
+    scale: f32,
+}
+
+/// Info about the matmul layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatMulCtx {
+    pub(crate) inner_dim: usize,
+    pub(crate) transpose_right: bool,
+    /// Number of variables of the rows of the output, of the shared dimension and of the columns
+    /// of the output, respectively
+    pub(crate) num_vars: [usize; 3],
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MatMulProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// sumcheck proof over the shared dimension of the inputs
+    pub(crate) sumcheck: IOPProof<E>,
+    /// Evaluations of both inputs at the point of the sumcheck
+    pub(crate) input_evals: Vec<E>,
+}
+
+/// Returns the shape of the product between the inputs with the given shapes
+fn output_shape(input_shapes: &[Vec<usize>], transpose_right: bool) -> Vec<usize> {
+    let ncols = if transpose_right {
+        input_shapes[1][0]
+    } else {
+        input_shapes[1][1]
+    };
+    vec![input_shapes[0][0], ncols]
+}
+
+/// Returns the size of the shared dimension in the second input with the given shape
+fn right_inner_dim(right_shape: &[usize], transpose_right: bool) -> usize {
+    if transpose_right {
+        right_shape[1]
+    } else {
+        right_shape[0]
+    }
+}
+
+impl MatMul {
+    pub fn new(inner_dim: usize) -> Self {
+        Self {
+            inner_dim,
+            transpose_right: false,
+            scale: 1.0,
+        }
+    }
+
+    /// Multiplies a `[m, n]` matrix by the transpose of a `[p, n]` matrix
+    pub fn new_transposed(inner_dim: usize) -> Self {
+        Self {
+            transpose_right: true,
+            ..Self::new(inner_dim)
+        }
+    }
+
+    /// Multiplies the output of the product by `scale`
+    pub fn with_scale(self, scale: f32) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn inner_dim(&self) -> usize {
+        self.inner_dim
+    }
+
+    pub fn is_right_transposed(&self) -> bool {
+        self.transpose_right
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    fn num_outputs(num_inputs: usize) -> usize {
+        assert_eq!(num_inputs, NUM_INPUTS);
+        1
+    }
+
+    /// Multiplies `left` by `right`, or by its transpose, ignoring the entries of the shared
+    /// dimension beyond `inner_dim`, which are only padding. The scale is not applied here.
+    pub fn op<T: Number>(&self, left: &Tensor<T>, right: &Tensor<T>) -> Result<Tensor<T>> {
+        ensure!(
+            left.is_matrix() && right.is_matrix(),
+            "MatMul inputs must be matrices, found shapes {:?} and {:?}",
+            left.get_shape(),
+            right.get_shape()
+        );
+        let (nrows, ncols) = (left.get_shape()[0], left.get_shape()[1]);
+        ensure!(
+            ncols == right_inner_dim(&right.get_shape(), self.transpose_right)
+                && self.inner_dim <= ncols,
+            "Incompatible shapes for MatMul with shared dimension {}: {:?} and {:?}",
+            self.inner_dim,
+            left.get_shape(),
+            right.get_shape()
+        );
+        let transposed;
+        let right = if self.transpose_right {
+            transposed = right.transpose();
+            &transposed
+        } else {
+            right
+        };
+        if self.inner_dim == ncols {
+            return Ok(left.matmul(right));
+        }
+        let masked = left
+            .get_data()
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                if i % ncols < self.inner_dim {
+                    *x
+                } else {
+                    T::default()
+                }
+            })
+            .collect_vec();
+        Ok(Tensor::new(vec![nrows, ncols], masked).matmul(right))
+    }
+}
+
+impl OpInfo for MatMul {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        vec![output_shape(input_shapes, self.transpose_right)]
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        Self::num_outputs(num_inputs)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "MatMul: shared dimension {}, transposed right input {}, scale {}",
+            self.inner_dim, self.transpose_right, self.scale
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for MatMul {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        ensure!(
+            inputs.len() == NUM_INPUTS,
+            "Found {} inputs when evaluating matmul layer, expected {NUM_INPUTS}",
+            inputs.len()
+        );
+        let output = self.op(inputs[0], inputs[1])?;
+        Ok(LayerOut::from_vec(vec![if self.scale == 1.0 {
+            output
+        } else {
+            output.scalar_mul(&self.scale)
+        }]))
+    }
+}
+
+impl Evaluate<Element> for MatMul {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == NUM_INPUTS,
+            "Found {} inputs when evaluating matmul layer, expected {NUM_INPUTS}",
+            inputs.len()
+        );
+        // the scale is applied by the requantization layer following this one
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0], inputs[1])?]))
+    }
+}
+
+impl QuantizeOp for MatMul {
+    type QuantizedOp = MatMul;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == NUM_INPUTS,
+            "Found {} input scaling factors for matmul layer, expected {NUM_INPUTS}",
+            input_scaling.len()
+        );
+        let num_outputs = self.num_outputs(input_scaling.len());
+        let mut output_scalings = S::scaling_factors_for_node(data, node_id, num_outputs);
+        ensure!(
+            output_scalings.len() == 1,
+            "Output scaling for matmul layer different from 1"
+        );
+        let output_scaling = output_scalings.pop().unwrap();
+        // M = S1 * S2 * scale / S3, applied as a fixed-point multiplier by the requant layer
+        let m = input_scaling[0].m(&input_scaling[1], &output_scaling) * self.scale;
+        // each output entry is the sum of `inner_dim` products of quantized values
+        let min_output = self.inner_dim as Element
+            * input_scaling[0].quantized_domain().0.abs()
+            * input_scaling[1].quantized_domain().0.abs();
+        Ok(QuantizeOutput {
+            quanzited_op: self,
+            output_scalings: vec![output_scaling],
+            requant_layer: Some(Requant::from_multiplier(
+                min_output as usize,
+                m as f64,
+                output_scaling.bit_len(),
+            )),
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for MatMul
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == NUM_INPUTS,
+            "Found {} input shapes for matmul layer, expected {NUM_INPUTS}",
+            aux.last_output_shape.len()
+        );
+        let (left, right) = (&aux.last_output_shape[0], &aux.last_output_shape[1]);
+        ensure!(
+            left.len() == 2
+                && right.len() == 2
+                && left[1] == right_inner_dim(right, self.transpose_right),
+            "Incompatible input shapes for matmul layer: {:?} and {:?}",
+            left,
+            right
+        );
+        ensure!(
+            left.iter().chain(right).all(|d| d.is_power_of_two()),
+            "Input shapes of matmul layer are not padded"
+        );
+        let output_shape = output_shape(&aux.last_output_shape, self.transpose_right);
+        let num_vars = [output_shape[0], left[1], output_shape[1]].map(|d| d.ilog2() as usize);
+        aux.last_output_shape = vec![output_shape];
+        Ok((
+            LayerCtx::MatMul(MatMulCtx {
+                inner_dim: self.inner_dim,
+                transpose_right: self.transpose_right,
+                num_vars,
+            }),
+            aux,
+        ))
+    }
+}
+
+impl PadOp for MatMul {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::matmul(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for MatMul
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = MatMulCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for matmul layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == NUM_INPUTS,
+            "Found {} inputs in inference step of matmul layer, expected {NUM_INPUTS}",
+            step_data.inputs.len()
+        );
+        ctx.prove_step(
+            prover,
+            last_claims[0],
+            step_data.inputs[0].get_data(),
+            step_data.inputs[1].get_data(),
+            node_id,
+        )
+    }
+}
+
+impl MatMulCtx {
+    /// Splits the point of the output claim into the coordinates of the columns and of the rows
+    fn split_output_point<'a, E: ExtensionField>(&self, point: &'a [E]) -> (&'a [E], &'a [E]) {
+        // the MLE of a matrix is addressed in little endian, so the column variables come first
+        point.split_at(self.num_vars[2])
+    }
+
+    /// Builds the claims about the inputs from the point of the sumcheck over the shared dimension
+    /// and the evaluations of the inputs
+    fn input_claims<E: ExtensionField>(
+        &self,
+        inner_point: &[E],
+        col_point: &[E],
+        row_point: &[E],
+        input_evals: &[E],
+    ) -> Vec<Claim<E>> {
+        let right_point = if self.transpose_right {
+            [inner_point, col_point].concat()
+        } else {
+            [col_point, inner_point].concat()
+        };
+        vec![
+            Claim::new([inner_point, row_point].concat(), input_evals[0]),
+            Claim::new(right_point, input_evals[1]),
+        ]
+    }
+
+    /// Evaluations of the mask selecting the unpadded entries of the shared dimension
+    fn mask_evals<E: ExtensionField>(&self) -> Vec<E> {
+        (0..1 << self.num_vars[1])
+            .map(|k| if k < self.inner_dim { E::ONE } else { E::ZERO })
+            .collect()
+    }
+
+    #[timed::timed_instrument(name = "Prover::prove_matmul_step")]
+    pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        prover: &mut Prover<E, T>,
+        last_claim: &Claim<E>,
+        left: &[E],
+        right: &[E],
+        node_id: NodeId,
+    ) -> Result<Vec<Claim<E>>>
+    where
+        E: ExtensionField + Serialize + DeserializeOwned,
+        E::BaseField: Serialize + DeserializeOwned,
+    {
+        let (col_point, row_point) = self.split_output_point(&last_claim.point);
+        // output(c, r) = sum_k left(k, r) * right(c, k) * mask(k), so we fix the row variables of
+        // the left input and the column variables of the right input
+        let mut left_mle = left.to_vec().into_mle();
+        left_mle.fix_high_variables_in_place(row_point);
+        let right_mle = if self.transpose_right {
+            // the right input is given as right(k, c)
+            let mut right_mle = right.to_vec().into_mle();
+            right_mle.fix_high_variables_in_place(col_point);
+            right_mle
+        } else {
+            right.to_vec().into_mle().fix_variables(col_point)
+        };
+        let mask_mle = DenseMultilinearExtension::<E>::from_evaluations_ext_vec(
+            self.num_vars[1],
+            self.mask_evals(),
+        );
+        let mut vp = VirtualPolynomial::<E>::new(self.num_vars[1]);
+        vp.add_mle_list(
+            vec![left_mle.into(), right_mle.into(), mask_mle.into()],
+            E::ONE,
+        );
+        #[allow(deprecated)]
+        let (sumcheck, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        debug_assert_eq!(
+            sumcheck.extract_sum(),
+            last_claim.eval,
+            "Inconsistent output claim for matmul layer"
+        );
+        let input_evals = state.get_mle_final_evaluations()[..NUM_INPUTS].to_vec();
+        let claims = self.input_claims(&sumcheck.point, col_point, row_point, &input_evals);
+        prover.push_proof(
+            node_id,
+            LayerProof::MatMul(MatMulProof {
+                sumcheck,
+                input_evals,
+            }),
+        );
+        Ok(claims)
+    }
+}
+
+impl OpInfo for MatMulCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        vec![output_shape(input_shapes, self.transpose_right)]
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        MatMul::num_outputs(num_inputs)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "MatMul ctx: shared dimension {}, transposed right input {}",
+            self.inner_dim, self.transpose_right
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for MatMulCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = MatMulProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for matmul layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            proof.input_evals.len() == NUM_INPUTS,
+            "Found {} input evaluations in matmul proof, expected {NUM_INPUTS}",
+            proof.input_evals.len()
+        );
+        let last_claim = last_claims[0];
+        ensure!(
+            last_claim.point.len() == self.num_vars[0] + self.num_vars[2],
+            "Invalid output claim for matmul layer: expected {} variables, found {}",
+            self.num_vars[0] + self.num_vars[2],
+            last_claim.point.len()
+        );
+        let (col_point, row_point) = self.split_output_point(&last_claim.point);
+        let aux = VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![self.num_vars[1]; 3]]);
+        let subclaim = IOPVerifierState::<E>::verify(
+            last_claim.eval,
+            &proof.sumcheck,
+            &aux,
+            verifier.transcript,
+        );
+        let inner_point = subclaim
+            .point
+            .iter()
+            .map(|chal| chal.elements)
+            .collect_vec();
+        let mask_eval = prefix_eq_eval(&[&inner_point], self.inner_dim);
+        ensure!(
+            proof.input_evals[0] * proof.input_evals[1] * mask_eval == subclaim.expected_evaluation,
+            "MatMul sumcheck claim failed"
+        );
+        Ok(self.input_claims(&inner_point, col_point, row_point, &proof.input_evals))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use goldilocks::GoldilocksExt2;
+
+    use crate::{
+        layers::provable::evaluate_layer, quantization::Fieldizer, testing::random_field_vector,
+    };
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_matmul_padded_inner_dim() {
+        let left = Tensor::<Element>::new(vec![2, 3], vec![1, 2, 3, 4, 5, 6]);
+        let right = Tensor::<Element>::new(vec![3, 2], vec![7, 8, 9, 10, 11, 12]);
+        let expected = left.matmul(&right);
+        assert_eq!(MatMul::new(3).op(&left, &right).unwrap(), expected);
+        // the padded entries of the shared dimension are ignored, whatever their value
+        let padded_left = Tensor::<Element>::new(vec![2, 4], vec![1, 2, 3, -5, 4, 5, 6, 9]);
+        let padded_right = Tensor::<Element>::new(vec![4, 2], vec![7, 8, 9, 10, 11, 12, 100, -100]);
+        assert_eq!(
+            MatMul::new(3).op(&padded_left, &padded_right).unwrap(),
+            expected
+        );
+        assert!(MatMul::new(4).op(&left, &right).is_err());
+    }
+
+    #[test]
+    fn test_matmul_output_claim() {
+        let ctx = MatMulCtx {
+            inner_dim: 5,
+            transpose_right: false,
+            num_vars: [2, 3, 1],
+        };
+        let padded_left = Tensor::<Element>::random(&vec![4, 8]);
+        let padded_right = Tensor::<Element>::random(&vec![8, 2]);
+        let output = MatMul::new(ctx.inner_dim)
+            .op(&padded_left, &padded_right)
+            .unwrap();
+        let fields = |t: &Tensor<Element>| {
+            t.get_data()
+                .iter()
+                .map(Fieldizer::<F>::to_field)
+                .collect_vec()
+        };
+        let output_point = random_field_vector::<F>(3);
+        let output_eval = fields(&output).into_mle().evaluate(&output_point);
+        // the output claim is the sum over the shared dimension of the product of the inputs
+        // with the row and column variables fixed, masked to the unpadded entries
+        let (col_point, row_point) = ctx.split_output_point(&output_point);
+        let mut left_mle = fields(&padded_left).into_mle();
+        left_mle.fix_high_variables_in_place(row_point);
+        let right_mle = fields(&padded_right).into_mle().fix_variables(col_point);
+        let sum = ctx
+            .mask_evals::<F>()
+            .into_iter()
+            .zip(
+                left_mle
+                    .get_ext_field_vec()
+                    .iter()
+                    .zip(right_mle.get_ext_field_vec()),
+            )
+            .fold(F::ZERO, |acc, (m, (l, r))| acc + m * *l * *r);
+        assert_eq!(sum, output_eval);
+        // the verifier evaluates the mask without iterating over the shared dimension
+        let inner_point = random_field_vector::<F>(3);
+        assert_eq!(
+            DenseMultilinearExtension::<F>::from_evaluations_ext_vec(3, ctx.mask_evals())
+                .evaluate(&inner_point),
+            prefix_eq_eval(&[&inner_point], ctx.inner_dim)
+        );
+    }
+
+    #[test]
+    fn test_matmul_transposed_right() {
+        let ctx = MatMulCtx {
+            inner_dim: 3,
+            transpose_right: true,
+            num_vars: [1, 2, 2],
+        };
+        let padded_left = Tensor::<Element>::random(&vec![2, 4]);
+        let padded_right = Tensor::<Element>::random(&vec![4, 4]);
+        let matmul = MatMul::new_transposed(ctx.inner_dim);
+        let output = matmul.op(&padded_left, &padded_right).unwrap();
+        assert_eq!(
+            output,
+            MatMul::new(ctx.inner_dim)
+                .op(&padded_left, &padded_right.transpose())
+                .unwrap()
+        );
+        assert_eq!(
+            matmul.output_shapes(&[vec![2, 4], vec![8, 4]], PaddingMode::NoPadding),
+            vec![vec![2, 8]]
+        );
+        // the claims about the inputs are at the same point of the shared dimension
+        let fields = |t: &Tensor<Element>| {
+            t.get_data()
+                .iter()
+                .map(Fieldizer::<F>::to_field)
+                .collect_vec()
+        };
+        let output_point = random_field_vector::<F>(3);
+        let (col_point, row_point) = ctx.split_output_point(&output_point);
+        let inner_point = random_field_vector::<F>(2);
+        let mut left_mle = fields(&padded_left).into_mle();
+        left_mle.fix_high_variables_in_place(row_point);
+        let mut right_mle = fields(&padded_right).into_mle();
+        right_mle.fix_high_variables_in_place(col_point);
+        let input_evals = vec![
+            left_mle.evaluate(&inner_point),
+            right_mle.evaluate(&inner_point),
+        ];
+        let claims = ctx.input_claims(&inner_point, col_point, row_point, &input_evals);
+        assert_eq!(
+            fields(&padded_left).into_mle().evaluate(&claims[0].point),
+            claims[0].eval
+        );
+        assert_eq!(
+            fields(&padded_right).into_mle().evaluate(&claims[1].point),
+            claims[1].eval
+        );
+        // the scale only affects float outputs
+        let scaled = MatMul::new(2).with_scale(0.5);
+        let left = Tensor::<f32>::new(vec![1, 2], vec![1.0, 2.0]);
+        let right = Tensor::<f32>::new(vec![2, 1], vec![3.0, 4.0]);
+        let output = evaluate_layer::<F, _, _>(&scaled, &vec![&left, &right], None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_data(), &[5.5]);
+    }
+}
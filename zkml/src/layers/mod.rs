@@ -1,24 +1,49 @@
 pub mod activation;
+pub mod add;
+pub mod affine;
+pub mod concat;
 pub mod convolution;
 pub mod dense;
+pub mod embedding;
 pub mod flatten;
 pub mod hadamard;
+pub mod matmul;
 pub mod matvec;
+pub mod norm;
 pub mod pooling;
 pub mod provable;
 pub mod requant;
+pub mod rescale;
+pub mod reshape;
+pub mod softmax;
+pub mod split;
+pub mod topk;
+pub mod transpose;
 
 use std::fmt::Debug;
 
-use anyhow::{Result, bail};
+use add::{AddCtx, AddProof};
+use affine::{Affine, AffineCtx, AffineProof};
+use anyhow::{Result, bail, ensure};
+use concat::{Concat, ConcatProof};
+use embedding::{Embedding, EmbeddingCtx, EmbeddingProof};
 use ff_ext::ExtensionField;
 use flatten::Flatten;
-use pooling::{PoolingCtx, PoolingProof};
+use matmul::{MatMul, MatMulCtx, MatMulProof};
+use norm::{Normalization, NormalizationCtx, NormalizationProof};
+use pooling::{MaxPoolingProof, PoolingCtx, PoolingProof};
 use provable::{
     Evaluate, LayerOut, Node, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    ZeroPointSupport,
 };
 use requant::RequantCtx;
+use rescale::{Rescale, RescaleCtx, RescaleProof};
+use reshape::Reshape;
+use softmax::{Softmax, SoftmaxCtx, SoftmaxProof};
+use split::{Split, SplitProof};
+use topk::{TopK, TopKCtx, TopKProof};
 use transcript::Transcript;
+use transpose::Transpose;
 
 use crate::{
     Element, ScalingStrategy,
@@ -26,6 +51,7 @@ use crate::{
     iop::context::{ContextAux, ShapeStep, TableCtx},
     layers::{
         activation::{Activation, ActivationProof},
+        add::Add,
         convolution::Convolution,
         dense::Dense,
         pooling::Pooling,
@@ -38,7 +64,7 @@ use crate::{
     tensor::{Number, Tensor},
 };
 use activation::ActivationCtx;
-use convolution::{ConvCtx, ConvProof, SchoolBookConv, SchoolBookConvCtx};
+use convolution::{ConvCtx, ConvProof, GroupedConv, SchoolBookConv, SchoolBookConvCtx};
 use dense::{DenseCtx, DenseProof};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
@@ -50,13 +76,39 @@ pub enum Layer<T> {
     // Traditional convolution is used for debug purposes. That is because the actual convolution
     // we use relies on the FFT algorithm. This convolution does not have a snark implementation.
     SchoolBookConvolution(SchoolBookConv<T>),
+    // grouped and depthwise convolutions, proven like a regular convolution
+    GroupedConvolution(GroupedConv<T>),
     Activation(Activation),
     // this is the output quant info. Since we always do a requant layer after each dense,
     // then we assume the inputs requant info are default()
     Requant(Requant),
+    // multiplies each output channel of a layer quantized per channel, before its requant layer
+    Rescale(Rescale),
     Pooling(Pooling),
-    // TODO: so far it's only flattening the input tensor, e.g. new_shape = vec![shape.iter().product()]
+    // flattens the input tensor into a vector, e.g. new_shape = vec![shape.iter().product()]
     Flatten(Flatten),
+    // splits or merges dimensions of the input tensor without moving its entries
+    Reshape(Reshape),
+    // permutes the dimensions of the input tensor
+    Transpose(Transpose),
+    // concatenates the input tensors along one dimension
+    Concat(Concat),
+    // splits the input tensor into several tensors along one dimension
+    Split(Split),
+    // element-wise addition of two tensors, e.g. for residual connections
+    Add(Add),
+    // matrix multiplication between two tensors computed at inference time
+    MatMul(MatMul),
+    // normalizes the logits of a model into probabilities
+    Softmax(Softmax),
+    // outputs the indices of the biggest entries of its input, e.g. the label of a classifier
+    TopK(TopK),
+    // LayerNorm or RMSNorm over the last dimension of its input
+    Normalization(Normalization<T>),
+    // per-channel scale and bias, e.g. a batch normalization not folded into the previous layer
+    Affine(Affine<T>),
+    // rows of a committed table selected by token indices
+    Embedding(Embedding<T>),
 }
 
 /// Describes a steps wrt the polynomial to be proven/looked at. Verifier needs to know
@@ -75,9 +127,21 @@ where
     SchoolBookConvolution(SchoolBookConvCtx),
     Activation(ActivationCtx),
     Requant(RequantCtx),
+    Rescale(RescaleCtx),
     Pooling(PoolingCtx),
     Table(TableCtx<E>),
     Flatten,
+    Reshape(Reshape),
+    Transpose(Transpose),
+    Concat(Concat),
+    Split(Split),
+    Add(AddCtx),
+    MatMul(MatMulCtx),
+    Softmax(SoftmaxCtx),
+    TopK(TopKCtx),
+    Normalization(NormalizationCtx),
+    Affine(AffineCtx),
+    Embedding(EmbeddingCtx),
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -89,7 +153,17 @@ where
     Convolution(ConvProof<E>),
     Activation(ActivationProof<E>),
     Requant(RequantProof<E>),
+    Rescale(RescaleProof<E>),
     Pooling(PoolingProof<E>),
+    Add(AddProof<E>),
+    Concat(ConcatProof<E>),
+    Split(SplitProof<E>),
+    MatMul(MatMulProof<E>),
+    Softmax(SoftmaxProof<E>),
+    TopK(TopKProof<E>),
+    Normalization(NormalizationProof<E>),
+    Affine(AffineProof<E>),
+    Embedding(EmbeddingProof<E>),
     Dummy, // To be used for non-provable layers
 }
 
@@ -105,15 +179,31 @@ where
             Self::Convolution(_) => "Convolution".to_string(),
             Self::Activation(_) => "Activation".to_string(),
             Self::Requant(_) => "Requant".to_string(),
+            Self::Rescale(_) => "Rescale".to_string(),
             Self::Pooling(_) => "Pooling".to_string(),
             Self::Table(..) => "Table".to_string(),
-            Self::Flatten => "Reshape".to_string(),
+            Self::Flatten => "Flatten".to_string(),
+            Self::Reshape(_) => "Reshape".to_string(),
+            Self::Transpose(_) => "Transpose".to_string(),
+            Self::Concat(_) => "Concat".to_string(),
+            Self::Split(_) => "Split".to_string(),
+            Self::Add(_) => "Add".to_string(),
+            Self::MatMul(_) => "MatMul".to_string(),
+            Self::Softmax(_) => "Softmax".to_string(),
+            Self::TopK(_) => "TopK".to_string(),
+            Self::Normalization(_) => "Normalization".to_string(),
+            Self::Affine(_) => "Affine".to_string(),
+            Self::Embedding(_) => "Embedding".to_string(),
         }
     }
 
     pub fn has_proof(&self) -> bool {
         match self {
-            Self::Flatten | Self::Table(_) | Self::SchoolBookConvolution(_) => false,
+            Self::Flatten
+            | Self::Reshape(_)
+            | Self::Transpose(_)
+            | Self::Table(_)
+            | Self::SchoolBookConvolution(_) => false,
             _ => true,
         }
     }
@@ -127,27 +217,61 @@ where
             }
             Self::Activation(..) => input_shape.to_vec(),
             Self::Requant(..) => input_shape.to_vec(),
-            Self::Pooling(ref pooling) => pooling.output_shape(input_shape),
+            Self::Rescale(..) => input_shape.to_vec(),
+            Self::Pooling(ref pooling) => {
+                pooling.output_shapes(&[input_shape.to_vec()], padding_mode)[0].clone()
+            }
             Self::Flatten => <Flatten as OpInfo>::output_shapes(
                 &Flatten,
                 &vec![input_shape.to_vec()],
                 padding_mode,
             )[0]
             .clone(),
+            Self::Reshape(ref reshape) => {
+                reshape.output_shapes(&[input_shape.to_vec()], padding_mode)[0].clone()
+            }
+            Self::Transpose(ref transpose) => {
+                transpose.output_shapes(&[input_shape.to_vec()], padding_mode)[0].clone()
+            }
             Self::Table(..) => panic!("Table should NOT be used in proving"),
+            Self::Add(..) => input_shape.to_vec(),
+            Self::MatMul(..) => panic!("MatMul output shape depends on both input shapes"),
+            Self::Concat(..) => panic!("Concat output shape depends on all the input shapes"),
+            Self::Split(..) => panic!("Split has several output shapes"),
+            Self::Softmax(..) => input_shape.to_vec(),
+            Self::Normalization(..) => input_shape.to_vec(),
+            Self::Affine(..) => input_shape.to_vec(),
+            Self::Embedding(ref embedding) => {
+                embedding.output_shapes(&[input_shape.to_vec()], padding_mode)[0].clone()
+            }
+            Self::TopK(ref topk) => {
+                topk.output_shapes(&[input_shape.to_vec()], padding_mode)[0].clone()
+            }
+        }
+    }
+    /// Output shapes of the layer for the given input shapes. Most layers map each input shape to an output shape,
+    /// while layers combining several inputs, like `Add`, produce a single output shape
+    fn output_shapes_for_step(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        match self {
+            Self::Add(..) => input_shapes.first().into_iter().cloned().collect(),
+            Self::MatMul(ref matmul) => matmul.output_shapes(input_shapes, padding_mode),
+            Self::Concat(ref concat) => concat.output_shapes(input_shapes, padding_mode),
+            Self::Split(ref split) => split.output_shapes(input_shapes, padding_mode),
+            _ => input_shapes
+                .iter()
+                .map(|shape| self.output_shape(&shape, padding_mode))
+                .collect(),
         }
     }
     pub fn next_shape_step(&self, last_step: &ShapeStep) -> ShapeStep {
-        let unpadded_output = last_step
-            .unpadded_output_shape
-            .iter()
-            .map(|shape| self.output_shape(&shape, PaddingMode::NoPadding))
-            .collect();
-        let padded_output = last_step
-            .padded_output_shape
-            .iter()
-            .map(|shape| self.output_shape(&shape, PaddingMode::Padding))
-            .collect();
+        let unpadded_output =
+            self.output_shapes_for_step(&last_step.unpadded_output_shape, PaddingMode::NoPadding);
+        let padded_output =
+            self.output_shapes_for_step(&last_step.padded_output_shape, PaddingMode::Padding);
         ShapeStep::next_step(last_step, unpadded_output, padded_output)
     }
     pub fn shape_step(
@@ -155,14 +279,8 @@ where
         unpadded_input: &[Vec<usize>],
         padded_input: &[Vec<usize>],
     ) -> ShapeStep {
-        let unpadded_output = unpadded_input
-            .iter()
-            .map(|shape| self.output_shape(&shape, PaddingMode::NoPadding))
-            .collect();
-        let padded_output = padded_input
-            .iter()
-            .map(|shape| self.output_shape(&shape, PaddingMode::Padding))
-            .collect();
+        let unpadded_output = self.output_shapes_for_step(unpadded_input, PaddingMode::NoPadding);
+        let padded_output = self.output_shapes_for_step(padded_input, PaddingMode::Padding);
         ShapeStep::new(
             unpadded_input.to_vec(),
             padded_input.to_vec(),
@@ -234,10 +352,25 @@ impl<N: Number> OpInfo for Layer<N> {
             Layer::SchoolBookConvolution(convolution) => {
                 convolution.output_shapes(input_shapes, padding_mode)
             }
+            Layer::GroupedConvolution(convolution) => {
+                convolution.output_shapes(input_shapes, padding_mode)
+            }
             Layer::Activation(activation) => activation.output_shapes(input_shapes, padding_mode),
             Layer::Requant(requant) => requant.output_shapes(input_shapes, padding_mode),
+            Layer::Rescale(rescale) => rescale.output_shapes(input_shapes, padding_mode),
             Layer::Pooling(pooling) => pooling.output_shapes(input_shapes, padding_mode),
             Layer::Flatten(reshape) => reshape.output_shapes(input_shapes, padding_mode),
+            Layer::Reshape(reshape) => reshape.output_shapes(input_shapes, padding_mode),
+            Layer::Transpose(transpose) => transpose.output_shapes(input_shapes, padding_mode),
+            Layer::Concat(concat) => concat.output_shapes(input_shapes, padding_mode),
+            Layer::Split(split) => split.output_shapes(input_shapes, padding_mode),
+            Layer::Add(add) => add.output_shapes(input_shapes, padding_mode),
+            Layer::MatMul(matmul) => matmul.output_shapes(input_shapes, padding_mode),
+            Layer::Softmax(softmax) => softmax.output_shapes(input_shapes, padding_mode),
+            Layer::TopK(topk) => topk.output_shapes(input_shapes, padding_mode),
+            Layer::Normalization(norm) => norm.output_shapes(input_shapes, padding_mode),
+            Layer::Affine(affine) => affine.output_shapes(input_shapes, padding_mode),
+            Layer::Embedding(embedding) => embedding.output_shapes(input_shapes, padding_mode),
         }
     }
 
@@ -246,10 +379,23 @@ impl<N: Number> OpInfo for Layer<N> {
             Layer::Dense(dense) => dense.num_outputs(num_inputs),
             Layer::Convolution(convolution) => convolution.num_outputs(num_inputs),
             Layer::SchoolBookConvolution(convolution) => convolution.num_outputs(num_inputs),
+            Layer::GroupedConvolution(convolution) => convolution.num_outputs(num_inputs),
             Layer::Activation(activation) => activation.num_outputs(num_inputs),
             Layer::Requant(requant) => requant.num_outputs(num_inputs),
+            Layer::Rescale(rescale) => rescale.num_outputs(num_inputs),
             Layer::Pooling(pooling) => pooling.num_outputs(num_inputs),
             Layer::Flatten(reshape) => reshape.num_outputs(num_inputs),
+            Layer::Reshape(reshape) => reshape.num_outputs(num_inputs),
+            Layer::Transpose(transpose) => transpose.num_outputs(num_inputs),
+            Layer::Concat(concat) => concat.num_outputs(num_inputs),
+            Layer::Split(split) => split.num_outputs(num_inputs),
+            Layer::Add(add) => add.num_outputs(num_inputs),
+            Layer::MatMul(matmul) => matmul.num_outputs(num_inputs),
+            Layer::Softmax(softmax) => softmax.num_outputs(num_inputs),
+            Layer::TopK(topk) => topk.num_outputs(num_inputs),
+            Layer::Normalization(norm) => norm.num_outputs(num_inputs),
+            Layer::Affine(affine) => affine.num_outputs(num_inputs),
+            Layer::Embedding(embedding) => embedding.num_outputs(num_inputs),
         }
     }
 
@@ -258,10 +404,23 @@ impl<N: Number> OpInfo for Layer<N> {
             Layer::Dense(dense) => dense.describe(),
             Layer::Convolution(convolution) => convolution.describe(),
             Layer::SchoolBookConvolution(convolution) => convolution.describe(),
+            Layer::GroupedConvolution(convolution) => convolution.describe(),
             Layer::Activation(activation) => activation.describe(),
             Layer::Requant(requant) => requant.describe(),
+            Layer::Rescale(rescale) => rescale.describe(),
             Layer::Pooling(pooling) => pooling.describe(),
             Layer::Flatten(reshape) => reshape.describe(),
+            Layer::Reshape(reshape) => reshape.describe(),
+            Layer::Transpose(transpose) => transpose.describe(),
+            Layer::Concat(concat) => concat.describe(),
+            Layer::Split(split) => split.describe(),
+            Layer::Add(add) => add.describe(),
+            Layer::MatMul(matmul) => matmul.describe(),
+            Layer::Softmax(softmax) => softmax.describe(),
+            Layer::TopK(topk) => topk.describe(),
+            Layer::Normalization(norm) => norm.describe(),
+            Layer::Affine(affine) => affine.describe(),
+            Layer::Embedding(embedding) => embedding.describe(),
         }
     }
 
@@ -270,10 +429,23 @@ impl<N: Number> OpInfo for Layer<N> {
             Layer::Dense(dense) => dense.is_provable(),
             Layer::Convolution(convolution) => convolution.is_provable(),
             Layer::SchoolBookConvolution(school_book_conv) => school_book_conv.is_provable(),
+            Layer::GroupedConvolution(convolution) => convolution.is_provable(),
             Layer::Activation(activation) => activation.is_provable(),
             Layer::Requant(requant) => requant.is_provable(),
+            Layer::Rescale(rescale) => rescale.is_provable(),
             Layer::Pooling(pooling) => pooling.is_provable(),
             Layer::Flatten(reshape) => reshape.is_provable(),
+            Layer::Reshape(reshape) => reshape.is_provable(),
+            Layer::Transpose(transpose) => transpose.is_provable(),
+            Layer::Concat(concat) => concat.is_provable(),
+            Layer::Split(split) => split.is_provable(),
+            Layer::Add(add) => add.is_provable(),
+            Layer::MatMul(matmul) => matmul.is_provable(),
+            Layer::Softmax(softmax) => softmax.is_provable(),
+            Layer::TopK(topk) => topk.is_provable(),
+            Layer::Normalization(norm) => norm.is_provable(),
+            Layer::Affine(affine) => affine.is_provable(),
+            Layer::Embedding(embedding) => embedding.is_provable(),
         }
     }
 }
@@ -290,10 +462,25 @@ impl Evaluate<f32> for Layer<f32> {
             Layer::SchoolBookConvolution(school_book_conv) => {
                 school_book_conv.evaluate(inputs, unpadded_input_shapes)
             }
+            Layer::GroupedConvolution(convolution) => {
+                convolution.evaluate(inputs, unpadded_input_shapes)
+            }
             Layer::Activation(activation) => activation.evaluate(inputs, unpadded_input_shapes),
             Layer::Requant(_) => unreachable!("Requant layer found when evaluating over float"),
+            Layer::Rescale(_) => unreachable!("Rescale layer found when evaluating over float"),
             Layer::Pooling(pooling) => pooling.evaluate(inputs, unpadded_input_shapes),
             Layer::Flatten(reshape) => reshape.evaluate(inputs, unpadded_input_shapes),
+            Layer::Reshape(reshape) => reshape.evaluate(inputs, unpadded_input_shapes),
+            Layer::Transpose(transpose) => transpose.evaluate(inputs, unpadded_input_shapes),
+            Layer::Concat(concat) => concat.evaluate(inputs, unpadded_input_shapes),
+            Layer::Split(split) => split.evaluate(inputs, unpadded_input_shapes),
+            Layer::Add(add) => add.evaluate(inputs, unpadded_input_shapes),
+            Layer::MatMul(matmul) => matmul.evaluate(inputs, unpadded_input_shapes),
+            Layer::Softmax(softmax) => softmax.evaluate(inputs, unpadded_input_shapes),
+            Layer::TopK(topk) => topk.evaluate(inputs, unpadded_input_shapes),
+            Layer::Normalization(norm) => norm.evaluate(inputs, unpadded_input_shapes),
+            Layer::Affine(affine) => affine.evaluate(inputs, unpadded_input_shapes),
+            Layer::Embedding(embedding) => embedding.evaluate(inputs, unpadded_input_shapes),
         }
     }
 }
@@ -310,10 +497,25 @@ impl Evaluate<Element> for Layer<Element> {
             Layer::SchoolBookConvolution(school_book_conv) => {
                 school_book_conv.evaluate(inputs, unpadded_input_shapes)
             }
+            Layer::GroupedConvolution(convolution) => {
+                convolution.evaluate(inputs, unpadded_input_shapes)
+            }
             Layer::Activation(activation) => activation.evaluate(inputs, unpadded_input_shapes),
             Layer::Requant(requant) => requant.evaluate(inputs, unpadded_input_shapes),
+            Layer::Rescale(rescale) => rescale.evaluate(inputs, unpadded_input_shapes),
             Layer::Pooling(pooling) => pooling.evaluate(inputs, unpadded_input_shapes),
             Layer::Flatten(reshape) => reshape.evaluate(inputs, unpadded_input_shapes),
+            Layer::Reshape(reshape) => reshape.evaluate(inputs, unpadded_input_shapes),
+            Layer::Transpose(transpose) => transpose.evaluate(inputs, unpadded_input_shapes),
+            Layer::Concat(concat) => concat.evaluate(inputs, unpadded_input_shapes),
+            Layer::Split(split) => split.evaluate(inputs, unpadded_input_shapes),
+            Layer::Add(add) => add.evaluate(inputs, unpadded_input_shapes),
+            Layer::MatMul(matmul) => matmul.evaluate(inputs, unpadded_input_shapes),
+            Layer::Softmax(softmax) => softmax.evaluate(inputs, unpadded_input_shapes),
+            Layer::TopK(topk) => topk.evaluate(inputs, unpadded_input_shapes),
+            Layer::Normalization(norm) => norm.evaluate(inputs, unpadded_input_shapes),
+            Layer::Affine(affine) => affine.evaluate(inputs, unpadded_input_shapes),
+            Layer::Embedding(embedding) => embedding.evaluate(inputs, unpadded_input_shapes),
         }
     }
 }
@@ -328,10 +530,23 @@ where
             Layer::Dense(dense) => dense.step_info(id, aux),
             Layer::Convolution(convolution) => convolution.step_info(id, aux),
             Layer::SchoolBookConvolution(convolution) => convolution.step_info(id, aux),
+            Layer::GroupedConvolution(convolution) => convolution.step_info(id, aux),
             Layer::Activation(activation) => activation.step_info(id, aux),
             Layer::Requant(requant) => requant.step_info(id, aux),
+            Layer::Rescale(rescale) => rescale.step_info(id, aux),
             Layer::Pooling(pooling) => pooling.step_info(id, aux),
             Layer::Flatten(reshape) => reshape.step_info(id, aux),
+            Layer::Reshape(reshape) => reshape.step_info(id, aux),
+            Layer::Transpose(transpose) => transpose.step_info(id, aux),
+            Layer::Concat(concat) => concat.step_info(id, aux),
+            Layer::Split(split) => split.step_info(id, aux),
+            Layer::Add(add) => add.step_info(id, aux),
+            Layer::MatMul(matmul) => matmul.step_info(id, aux),
+            Layer::Softmax(softmax) => softmax.step_info(id, aux),
+            Layer::TopK(topk) => topk.step_info(id, aux),
+            Layer::Normalization(norm) => norm.step_info(id, aux),
+            Layer::Affine(affine) => affine.step_info(id, aux),
+            Layer::Embedding(embedding) => embedding.step_info(id, aux),
         }
     }
 
@@ -340,10 +555,23 @@ where
             Layer::Dense(dense) => dense.commit_info(id),
             Layer::Convolution(convolution) => convolution.commit_info(id),
             Layer::SchoolBookConvolution(school_book_conv) => school_book_conv.commit_info(id),
+            Layer::GroupedConvolution(convolution) => convolution.commit_info(id),
             Layer::Activation(activation) => activation.commit_info(id),
             Layer::Requant(requant) => requant.commit_info(id),
+            Layer::Rescale(rescale) => rescale.commit_info(id),
             Layer::Pooling(pooling) => pooling.commit_info(id),
             Layer::Flatten(reshape) => reshape.commit_info(id),
+            Layer::Reshape(reshape) => reshape.commit_info(id),
+            Layer::Transpose(transpose) => transpose.commit_info(id),
+            Layer::Concat(concat) => concat.commit_info(id),
+            Layer::Split(split) => split.commit_info(id),
+            Layer::Add(add) => add.commit_info(id),
+            Layer::MatMul(matmul) => matmul.commit_info(id),
+            Layer::Softmax(softmax) => softmax.commit_info(id),
+            Layer::TopK(topk) => topk.commit_info(id),
+            Layer::Normalization(norm) => norm.commit_info(id),
+            Layer::Affine(affine) => affine.commit_info(id),
+            Layer::Embedding(embedding) => embedding.commit_info(id),
         }
     }
 }
@@ -353,16 +581,35 @@ impl PadOp for Layer<Element> {
     where
         Self: Sized,
     {
+        // only a dense layer multiplies the non zero padded entries of a sigmoid output by zeros
+        if !matches!(self, Layer::Dense(_) | Layer::Flatten(_)) {
+            si.ensure_zero_padding(&self.describe())?;
+        }
         Ok(match self {
             Layer::Dense(dense) => Layer::Dense(dense.pad_node(si)?),
             Layer::Convolution(convolution) => Layer::Convolution(convolution.pad_node(si)?),
             Layer::SchoolBookConvolution(school_book_conv) => {
                 Layer::SchoolBookConvolution(school_book_conv.pad_node(si)?)
             }
+            Layer::GroupedConvolution(convolution) => {
+                Layer::GroupedConvolution(convolution.pad_node(si)?)
+            }
             Layer::Activation(activation) => Layer::Activation(activation.pad_node(si)?),
             Layer::Requant(requant) => Layer::Requant(requant.pad_node(si)?),
+            Layer::Rescale(rescale) => Layer::Rescale(rescale.pad_node(si)?),
             Layer::Pooling(pooling) => Layer::Pooling(pooling.pad_node(si)?),
             Layer::Flatten(flatten) => Layer::Flatten(flatten.pad_node(si)?),
+            Layer::Reshape(reshape) => Layer::Reshape(reshape.pad_node(si)?),
+            Layer::Transpose(transpose) => Layer::Transpose(transpose.pad_node(si)?),
+            Layer::Concat(concat) => Layer::Concat(concat.pad_node(si)?),
+            Layer::Split(split) => Layer::Split(split.pad_node(si)?),
+            Layer::Add(add) => Layer::Add(add.pad_node(si)?),
+            Layer::MatMul(matmul) => Layer::MatMul(matmul.pad_node(si)?),
+            Layer::Softmax(softmax) => Layer::Softmax(softmax.pad_node(si)?),
+            Layer::TopK(topk) => Layer::TopK(topk.pad_node(si)?),
+            Layer::Normalization(norm) => Layer::Normalization(norm.pad_node(si)?),
+            Layer::Affine(affine) => Layer::Affine(affine.pad_node(si)?),
+            Layer::Embedding(embedding) => Layer::Embedding(embedding.pad_node(si)?),
         })
     }
 }
@@ -400,6 +647,13 @@ where
             Layer::SchoolBookConvolution(_) => {
                 unreachable!("prove cannot be called for school book convolution")
             }
+            Layer::GroupedConvolution(convolution) => {
+                if let LayerCtx::Convolution(info) = ctx {
+                    convolution.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No convolution ctx found when proving grouped convolution layer")
+                }
+            }
             Layer::Activation(activation) => {
                 if let LayerCtx::Activation(info) = ctx {
                     activation.prove(node_id, info, last_claims, step_data, prover)
@@ -414,6 +668,13 @@ where
                     bail!("No requant ctx found when proving requant layer")
                 }
             }
+            Layer::Rescale(rescale) => {
+                if let LayerCtx::Rescale(info) = ctx {
+                    rescale.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No rescale ctx found when proving rescale layer")
+                }
+            }
             Layer::Pooling(pooling) => {
                 if let LayerCtx::Pooling(info) = ctx {
                     pooling.prove(node_id, info, last_claims, step_data, prover)
@@ -421,7 +682,78 @@ where
                     bail!("No pooling ctx found when proving pooling layer")
                 }
             }
-            Layer::Flatten(_) => unreachable!("prove cannot be called for reshape"),
+            Layer::Add(add) => {
+                if let LayerCtx::Add(info) = ctx {
+                    add.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No add ctx found when proving add layer")
+                }
+            }
+            Layer::MatMul(matmul) => {
+                if let LayerCtx::MatMul(info) = ctx {
+                    matmul.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No matmul ctx found when proving matmul layer")
+                }
+            }
+            Layer::Softmax(softmax) => {
+                if let LayerCtx::Softmax(info) = ctx {
+                    softmax.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No softmax ctx found when proving softmax layer")
+                }
+            }
+            Layer::TopK(topk) => {
+                if let LayerCtx::TopK(info) = ctx {
+                    topk.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No top-k ctx found when proving top-k layer")
+                }
+            }
+            Layer::Normalization(norm) => {
+                if let LayerCtx::Normalization(info) = ctx {
+                    norm.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No normalization ctx found when proving normalization layer")
+                }
+            }
+            Layer::Affine(affine) => {
+                if let LayerCtx::Affine(info) = ctx {
+                    affine.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No affine ctx found when proving affine layer")
+                }
+            }
+            Layer::Embedding(embedding) => {
+                if let LayerCtx::Embedding(info) = ctx {
+                    embedding.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No embedding ctx found when proving embedding layer")
+                }
+            }
+            Layer::Transpose(transpose) => {
+                if let LayerCtx::Transpose(info) = ctx {
+                    transpose.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No transpose ctx found when proving transpose layer")
+                }
+            }
+            Layer::Concat(concat) => {
+                if let LayerCtx::Concat(info) = ctx {
+                    concat.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No concat ctx found when proving concat layer")
+                }
+            }
+            Layer::Split(split) => {
+                if let LayerCtx::Split(info) = ctx {
+                    split.prove(node_id, info, last_claims, step_data, prover)
+                } else {
+                    bail!("No split ctx found when proving split layer")
+                }
+            }
+            Layer::Flatten(_) => unreachable!("prove cannot be called for flatten"),
+            Layer::Reshape(_) => unreachable!("prove cannot be called for reshape"),
         }
     }
 
@@ -439,14 +771,71 @@ where
                 assert!(!school_book_conv.is_provable());
                 Ok(())
             }
+            Layer::GroupedConvolution(convolution) => {
+                convolution.gen_lookup_witness(id, gen, step_data)
+            }
             Layer::Activation(activation) => activation.gen_lookup_witness(id, gen, step_data),
             Layer::Requant(requant) => requant.gen_lookup_witness(id, gen, step_data),
+            Layer::Rescale(rescale) => rescale.gen_lookup_witness(id, gen, step_data),
             Layer::Pooling(pooling) => pooling.gen_lookup_witness(id, gen, step_data),
+            Layer::Add(add) => add.gen_lookup_witness(id, gen, step_data),
+            Layer::MatMul(matmul) => matmul.gen_lookup_witness(id, gen, step_data),
+            Layer::Softmax(softmax) => softmax.gen_lookup_witness(id, gen, step_data),
+            Layer::TopK(topk) => topk.gen_lookup_witness(id, gen, step_data),
+            Layer::Normalization(norm) => norm.gen_lookup_witness(id, gen, step_data),
+            Layer::Affine(affine) => affine.gen_lookup_witness(id, gen, step_data),
+            Layer::Embedding(embedding) => embedding.gen_lookup_witness(id, gen, step_data),
+            Layer::Transpose(transpose) => transpose.gen_lookup_witness(id, gen, step_data),
+            Layer::Concat(concat) => concat.gen_lookup_witness(id, gen, step_data),
+            Layer::Split(split) => split.gen_lookup_witness(id, gen, step_data),
             Layer::Flatten(reshape) => {
                 // check that the layer is not provable, so we don't need to call the method
                 assert!(!reshape.is_provable());
                 Ok(())
             }
+            Layer::Reshape(reshape) => {
+                // check that the layer is not provable, so we don't need to call the method
+                assert!(!reshape.is_provable());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Layer<f32> {
+    /// Returns how the layer deals with the zero point of the scaling factors of its inputs
+    pub(crate) fn zero_point_support(&self) -> ZeroPointSupport {
+        match self {
+            Layer::Dense(_) => ZeroPointSupport::Absorbed,
+            // the zero padding of the input would have to be padded with the zero point instead
+            Layer::Convolution(conv) | Layer::GroupedConvolution(GroupedConv(conv))
+                if !conv.pads_input() =>
+            {
+                ZeroPointSupport::Absorbed
+            }
+            Layer::Activation(activation) => activation.zero_point_support(),
+            // max pooling selects inputs, while average pooling sums them
+            Layer::Pooling(pooling) if pooling.window_size().is_none() => {
+                ZeroPointSupport::Forwarded
+            }
+            Layer::Flatten(_) | Layer::Reshape(_) | Layer::Transpose(_) | Layer::Split(_) => {
+                ZeroPointSupport::Forwarded
+            }
+            // the indices of the biggest entries are the same whatever the zero point
+            Layer::TopK(_) => ZeroPointSupport::Absorbed,
+            Layer::Convolution(_)
+            | Layer::GroupedConvolution(_)
+            | Layer::SchoolBookConvolution(_)
+            | Layer::Requant(_)
+            | Layer::Rescale(_)
+            | Layer::Pooling(_)
+            | Layer::Concat(_)
+            | Layer::Add(_)
+            | Layer::MatMul(_)
+            | Layer::Softmax(_)
+            | Layer::Normalization(_)
+            | Layer::Affine(_)
+            | Layer::Embedding(_) => ZeroPointSupport::Unsupported,
         }
     }
 }
@@ -460,6 +849,12 @@ impl QuantizeOp for Layer<f32> {
         node_id: provable::NodeId,
         input_scaling: &[ScalingFactor],
     ) -> anyhow::Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            self.zero_point_support() != ZeroPointSupport::Unsupported
+                || input_scaling.iter().all(ScalingFactor::is_symmetric),
+            "Layer {} does not support inputs quantized with a zero point",
+            self.describe()
+        );
         Ok(match self {
             Layer::Dense(dense) => {
                 let output = dense.quantize_op::<S>(data, node_id, input_scaling)?;
@@ -467,6 +862,7 @@ impl QuantizeOp for Layer<f32> {
                     quanzited_op: Layer::Dense(output.quanzited_op),
                     output_scalings: output.output_scalings,
                     requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
                 }
             }
             Layer::Convolution(convolution) => {
@@ -475,6 +871,7 @@ impl QuantizeOp for Layer<f32> {
                     quanzited_op: Layer::Convolution(output.quanzited_op),
                     output_scalings: output.output_scalings,
                     requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
                 }
             }
             Layer::SchoolBookConvolution(school_book_conv) => {
@@ -483,28 +880,145 @@ impl QuantizeOp for Layer<f32> {
                     quanzited_op: Layer::SchoolBookConvolution(output.quanzited_op),
                     output_scalings: output.output_scalings,
                     requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::GroupedConvolution(convolution) => {
+                let output = convolution.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::GroupedConvolution(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::Activation(activation) => {
+                let output = activation.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Activation(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
                 }
             }
-            Layer::Activation(activation) => QuantizeOutput {
-                quanzited_op: Layer::Activation(activation),
-                output_scalings: input_scaling.to_vec(),
-                requant_layer: None,
-            },
             Layer::Requant(requant) => QuantizeOutput {
                 quanzited_op: Layer::Requant(requant),
                 output_scalings: input_scaling.to_vec(),
                 requant_layer: None,
+                rescale_layer: None,
             },
-            Layer::Pooling(pooling) => QuantizeOutput {
-                quanzited_op: Layer::Pooling(pooling),
+            Layer::Rescale(rescale) => QuantizeOutput {
+                quanzited_op: Layer::Rescale(rescale),
                 output_scalings: input_scaling.to_vec(),
                 requant_layer: None,
+                rescale_layer: None,
             },
+            Layer::Pooling(pooling) => {
+                let output = pooling.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Pooling(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
             Layer::Flatten(flatten) => QuantizeOutput {
                 quanzited_op: Layer::Flatten(flatten),
                 output_scalings: input_scaling.to_vec(),
                 requant_layer: None,
+                rescale_layer: None,
             },
+            Layer::Reshape(reshape) => QuantizeOutput {
+                quanzited_op: Layer::Reshape(reshape),
+                output_scalings: input_scaling.to_vec(),
+                requant_layer: None,
+                rescale_layer: None,
+            },
+            Layer::Transpose(transpose) => QuantizeOutput {
+                quanzited_op: Layer::Transpose(transpose),
+                output_scalings: input_scaling.to_vec(),
+                requant_layer: None,
+                rescale_layer: None,
+            },
+            Layer::Concat(concat) => {
+                let output = concat.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Concat(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            // all the outputs are slices of the input, so they share its scaling factor
+            Layer::Split(split) => QuantizeOutput {
+                output_scalings: vec![input_scaling[0]; split.num_outputs(input_scaling.len())],
+                quanzited_op: Layer::Split(split),
+                requant_layer: None,
+                rescale_layer: None,
+            },
+            Layer::Add(add) => {
+                let output = add.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Add(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::MatMul(matmul) => {
+                let output = matmul.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::MatMul(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::Softmax(softmax) => {
+                let output = softmax.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Softmax(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::TopK(topk) => {
+                let output = topk.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::TopK(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::Normalization(norm) => {
+                let output = norm.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Normalization(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::Affine(affine) => {
+                let output = affine.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Affine(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
+            Layer::Embedding(embedding) => {
+                let output = embedding.quantize_op::<S>(data, node_id, input_scaling)?;
+                QuantizeOutput {
+                    quanzited_op: Layer::Embedding(output.quanzited_op),
+                    output_scalings: output.output_scalings,
+                    requant_layer: output.requant_layer,
+                    rescale_layer: output.rescale_layer,
+                }
+            }
         })
     }
 }
@@ -519,7 +1033,17 @@ where
             Self::Convolution(_) => "Convolution".to_string(),
             Self::Activation(_) => "Activation".to_string(),
             Self::Requant(_) => "Requant".to_string(),
+            Self::Rescale(_) => "Rescale".to_string(),
             Self::Pooling(_) => "Pooling".to_string(),
+            Self::Add(_) => "Add".to_string(),
+            Self::Concat(_) => "Concat".to_string(),
+            Self::Split(_) => "Split".to_string(),
+            Self::MatMul(_) => "MatMul".to_string(),
+            Self::Softmax(_) => "Softmax".to_string(),
+            Self::TopK(_) => "TopK".to_string(),
+            Self::Normalization(_) => "Normalization".to_string(),
+            Self::Affine(_) => "Affine".to_string(),
+            Self::Embedding(_) => "Embedding".to_string(),
             Self::Dummy => "Dummy".to_string(),
         }
     }
@@ -528,10 +1052,44 @@ where
         match self {
             LayerProof::Dense(..) => None,
             LayerProof::Convolution(..) => None,
+            LayerProof::Add(..) => None,
+            LayerProof::Concat(..) => None,
+            LayerProof::Split(..) => None,
+            LayerProof::MatMul(..) => None,
+            LayerProof::Affine(..) => None,
+            LayerProof::Rescale(..) => None,
             LayerProof::Dummy => None,
             LayerProof::Activation(ActivationProof { lookup, .. })
             | LayerProof::Requant(RequantProof { lookup, .. })
-            | LayerProof::Pooling(PoolingProof { lookup, .. }) => Some(lookup.fractional_outputs()),
+            | LayerProof::Pooling(PoolingProof::Max(MaxPoolingProof { lookup, .. })) => {
+                Some(lookup.fractional_outputs())
+            }
+            LayerProof::Pooling(PoolingProof::Avg(..)) => None,
+            LayerProof::Softmax(SoftmaxProof {
+                exp_lookup,
+                range_lookup,
+                ..
+            }) => {
+                let (mut numerators, mut denominators) = exp_lookup.fractional_outputs();
+                let (range_numerators, range_denominators) = range_lookup.fractional_outputs();
+                numerators.extend(range_numerators);
+                denominators.extend(range_denominators);
+                Some((numerators, denominators))
+            }
+            LayerProof::TopK(TopKProof { lookup, .. }) => Some(lookup.fractional_outputs()),
+            LayerProof::Normalization(NormalizationProof {
+                inv_sqrt_lookup,
+                range_lookup,
+                ..
+            }) => {
+                let (mut numerators, mut denominators) = inv_sqrt_lookup.fractional_outputs();
+                let (range_numerators, range_denominators) = range_lookup.fractional_outputs();
+                numerators.extend(range_numerators);
+                denominators.extend(range_denominators);
+                Some((numerators, denominators))
+            }
+            // the lookups of each embedding layer are checked against its own table by the layer
+            LayerProof::Embedding(..) => None,
         }
     }
 }
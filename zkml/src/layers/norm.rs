@@ -0,0 +1,1279 @@
+//! Normalization layers of transformers, LayerNorm and RMSNorm, which normalize each row of their
+//! input, e.g. each token of a sequence, before scaling it with learned weights.
+use std::collections::HashMap;
+
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{
+        compute_betas_eval, identity_eval,
+        poly_id::{NORM_BETA_POLY_ID, NORM_INV_SQRT_POLY_ID, NORM_VARIANCE_POLY_ID},
+        precommit::PolyID,
+        same_poly,
+    },
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{
+        LayerCtx, LayerProof,
+        requant::Requant,
+        softmax::{limbs, recombine_limbs, sum_points},
+        topk::prefix_eq_eval,
+    },
+    lookup::{
+        context::{COLUMN_SEPARATOR, LookupWitnessGen, TableType},
+        logup_gkr::{
+            prover::batch_prove as logup_batch_prove, structs::LogUpProof,
+            verifier::verify_logup_proof,
+        },
+    },
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo, normalization},
+    quantization::{self, Fieldizer},
+    tensor::Number,
+};
+use anyhow::{Result, anyhow, bail, ensure};
+use ff::Field;
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::Itertools;
+use multilinear_extensions::{
+    mle::{IntoMLE, MultilinearExtension},
+    virtual_poly::{ArcMultilinearExtension, VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+/// Number of bits of precision of the inverse square roots computed by the lookup table.
+pub const INV_SQRT_BITS: usize = 16;
+
+const IS_PROVABLE: bool = true;
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormKind {
+    /// `gamma * (x - mean) / sqrt(var + eps) + beta`
+    Layer,
+    /// `gamma * x / sqrt(mean(x^2) + eps)`
+    Rms,
+}
+
+/// Normalization over the last dimension of the input, i.e. over each row of a matrix.
+///
+/// Once quantized, the `n` entries of a row are first centered as `c = n * x - sum(x)` (no centering
+/// for RMSNorm), so that `V = sum(c^2)` is `n^3` times the variance of the row. `V` is shifted right
+/// to fit in the bit length of the input and mapped to `t = 2^INV_SQRT_BITS / sqrt(var + eps)` with a
+/// lookup table, where the bits dropped by the shift are range checked. The output is then
+/// `c * t * gamma + beta`, requantized by the following layer.
+/// The variance is looked up with as many bits of precision as the input over its full range, so the
+/// normalization is less accurate over rows with a variance much smaller than the square of the
+/// biggest quantized value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Normalization<T> {
+    kind: NormKind,
+    /// scale applied to each entry of the normalized rows
+    gamma: Tensor<T>,
+    /// shift added to each entry of the normalized rows, zero for RMSNorm
+    beta: Tensor<T>,
+    epsilon: f32,
+    /// Number of entries of each row, i.e. of the unpadded last dimension of the input
+    num_entries: usize,
+    /// Lookup table of the inverse square roots, `None` before quantization
+    table: Option<InvSqrtTable>,
+}
+
+/// Info about the normalization layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NormalizationCtx {
+    pub kind: NormKind,
+    pub table: InvSqrtTable,
+    pub gamma_poly_id: PolyID,
+    pub beta_poly_id: PolyID,
+    pub inv_sqrt_poly_id: PolyID,
+    pub variance_poly_id: PolyID,
+    /// Number of variables of the output
+    pub num_vars: usize,
+    /// Number of variables of the rows of the output, 0 if the input is a vector
+    pub row_num_vars: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NormalizationProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// the lookup proof for the inverse square roots of the variances
+    pub(crate) inv_sqrt_lookup: LogUpProof<E>,
+    /// the lookup proof for the range check of the bits dropped from the variances
+    pub(crate) range_lookup: LogUpProof<E>,
+    /// Evaluation of the inputs of the inverse square root table at the point of the range check
+    pub(crate) variance_eval: E,
+    /// Evaluation of `beta` at the columns of the output claim, `None` for RMSNorm
+    pub(crate) beta_eval: Option<E>,
+    /// Sumcheck batching the output claim with the sum of the squares of the centered rows
+    pub(crate) sumcheck: IOPProof<E>,
+    /// Evaluations of the centered input, of the inverse square roots and of `gamma` at the point
+    /// of the sumcheck
+    pub(crate) individual_evals: Vec<E>,
+    /// Evaluation of the input at the point of the sumcheck
+    pub(crate) input_eval: E,
+    /// Evaluations of the input over the points whose sum is the sum of the rows at the point of
+    /// the sumcheck, see [`sum_points`], empty for RMSNorm
+    pub(crate) sum_evals: Vec<E>,
+    /// proof for the accumulation of the claims about the input, `None` for RMSNorm
+    pub(crate) input_accumulation: Option<same_poly::Proof<E>>,
+    /// proof for the accumulation of the claims about the inverse square roots
+    pub(crate) inv_sqrt_accumulation: same_poly::Proof<E>,
+    /// proof for the accumulation of the claims about the inputs of the lookup table
+    pub(crate) variance_accumulation: same_poly::Proof<E>,
+}
+
+/// Values derived from a row of the quantized input, see [`Normalization`]
+struct NormRow {
+    /// `n * x - sum(x)` for LayerNorm, `n * x` for RMSNorm
+    centered: Vec<Element>,
+    /// Input of the lookup table, i.e. the shifted sum of the squares of the centered entries
+    variance: Element,
+    /// Output of the lookup table
+    inv_sqrt: Element,
+    /// Bits dropped from the sum of the squares by the shift
+    remainder: Element,
+}
+
+impl<T: Number> Normalization<T> {
+    /// LayerNorm over rows of `gamma.len()` entries
+    pub fn layer_norm(gamma: Tensor<T>, beta: Tensor<T>, epsilon: f32) -> Self {
+        assert_eq!(gamma.get_shape().len(), 1, "gamma must be a vector");
+        assert_eq!(
+            gamma.get_shape(),
+            beta.get_shape(),
+            "gamma and beta must have the same shape"
+        );
+        let num_entries = gamma.get_shape()[0];
+        Self {
+            kind: NormKind::Layer,
+            gamma,
+            beta,
+            epsilon,
+            num_entries,
+            table: None,
+        }
+    }
+
+    /// RMSNorm over rows of `gamma.len()` entries
+    pub fn rms_norm(gamma: Tensor<T>, epsilon: f32) -> Self {
+        assert_eq!(gamma.get_shape().len(), 1, "gamma must be a vector");
+        let num_entries = gamma.get_shape()[0];
+        Self {
+            kind: NormKind::Rms,
+            beta: Tensor::zeros(gamma.get_shape()),
+            gamma,
+            epsilon,
+            num_entries,
+            table: None,
+        }
+    }
+
+    pub fn kind(&self) -> NormKind {
+        self.kind
+    }
+
+    /// Pads `gamma` and `beta` with zeros to the padded length of the rows, so that the padded
+    /// entries of the output are zero.
+    pub(crate) fn padded(mut self, row_len: usize) -> Result<Self> {
+        ensure!(
+            row_len >= self.num_entries,
+            "Padded rows of {} entries are shorter than the {} entries of normalization layer",
+            row_len,
+            self.num_entries
+        );
+        self.gamma = self.gamma.pad_1d(row_len);
+        self.beta = self.beta.pad_1d(row_len);
+        Ok(self)
+    }
+
+    fn check_input_shape(&self, input_shape: &[usize]) -> Result<()> {
+        ensure!(
+            input_shape.len() == 1 || input_shape.len() == 2,
+            "Normalization is only supported over vectors and rows of matrices, found input shape {:?}",
+            input_shape
+        );
+        ensure!(
+            *input_shape.last().unwrap() == self.gamma.get_shape()[0],
+            "Rows of {} entries don't match the {} weights of normalization layer",
+            input_shape.last().unwrap(),
+            self.gamma.get_shape()[0]
+        );
+        Ok(())
+    }
+}
+
+impl Normalization<f32> {
+    /// Evaluates the normalization over each row of `input`
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        let n = self.num_entries as f32;
+        let output = input
+            .get_data()
+            .chunks(self.num_entries)
+            .flat_map(|row| {
+                let mean = match self.kind {
+                    NormKind::Layer => row.iter().sum::<f32>() / n,
+                    NormKind::Rms => 0.0,
+                };
+                let var = row.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / n;
+                let inv_sqrt = 1.0 / (var + self.epsilon).sqrt();
+                row.iter()
+                    .zip(self.gamma.get_data().iter().zip(self.beta.get_data()))
+                    .map(move |(x, (g, b))| (x - mean) * inv_sqrt * g + b)
+            })
+            .collect_vec();
+        Tensor::new(input.get_shape(), output)
+    }
+
+    /// Quantizes the layer given the scaling factors of its input and output. The output is
+    /// requantized by the fixed-point approximation of the ratio between the scale of the normalized
+    /// entries and the output scale.
+    pub(crate) fn quantize_from_scalings(
+        self,
+        input_scaling: &ScalingFactor,
+        output_scaling: ScalingFactor,
+    ) -> Result<QuantizeOutput<Normalization<Element>>> {
+        let table = InvSqrtTable::new(self.num_entries, input_scaling, self.epsilon);
+        let unit = (self.num_entries << INV_SQRT_BITS) as f32;
+        let gamma_scaling =
+            ScalingFactor::from_tensor(&self.gamma, Some(quantization::domain(table.bit_len())));
+        // the normalized entries have scale `s_gamma / (n * 2^INV_SQRT_BITS)`, and are brought to
+        // the scale of the output by the fixed-point multiplier of the requant layer
+        let product_scale = gamma_scaling.scale() / unit;
+        let m = product_scale / output_scaling.scale();
+        ensure!(
+            m < 1.0,
+            "Output scale {} of normalization layer is too small",
+            output_scaling.scale()
+        );
+        // bound on the absolute value of the normalized entries before the shift by beta
+        let max_product = (self.num_entries << table.bit_len()) as Element
+            * table.max_output()
+            * quantization::max(table.bit_len());
+        let beta_scaling =
+            ScalingFactor::from_scale(product_scale, Some((-max_product, max_product)));
+        let quantized = Normalization {
+            kind: self.kind,
+            gamma: self.gamma.quantize(&gamma_scaling),
+            beta: self.beta.quantize(&beta_scaling),
+            epsilon: self.epsilon,
+            num_entries: self.num_entries,
+            table: Some(table),
+        };
+        let max_output = max_product + quantized.beta.max_abs_output();
+        Ok(QuantizeOutput {
+            quanzited_op: quantized,
+            output_scalings: vec![output_scaling],
+            requant_layer: Some(Requant::from_multiplier(
+                max_output as usize,
+                m as f64,
+                output_scaling.bit_len(),
+            )),
+            rescale_layer: None,
+        })
+    }
+}
+
+impl Normalization<Element> {
+    fn table(&self) -> Result<InvSqrtTable> {
+        self.table.ok_or(anyhow!(
+            "Normalization must be quantized to be evaluated over integers"
+        ))
+    }
+
+    /// Derives the centered entries of a row, and the lookup of its inverse square root
+    fn row(&self, table: &InvSqrtTable, row: &[Element]) -> NormRow {
+        let n = self.num_entries as Element;
+        let sum = match self.kind {
+            NormKind::Layer => row.iter().take(self.num_entries).sum::<Element>(),
+            NormKind::Rms => 0,
+        };
+        let centered = row.iter().map(|x| n * x - sum).collect_vec();
+        let sum_squares = centered
+            .iter()
+            .take(self.num_entries)
+            .map(|c| c * c)
+            .sum::<Element>();
+        let variance = table.input(sum_squares);
+        NormRow {
+            inv_sqrt: table.apply(variance),
+            remainder: sum_squares & ((1 << table.shift()) - 1),
+            centered,
+            variance,
+        }
+    }
+
+    /// Evaluates the quantized normalization over each row of `input`
+    pub fn quantized_op(&self, input: &Tensor<Element>) -> Result<Tensor<Element>> {
+        let table = self.table()?;
+        let output = input
+            .get_data()
+            .chunks(self.gamma.get_shape()[0])
+            .flat_map(|row| {
+                let row = self.row(&table, row);
+                row.centered
+                    .into_iter()
+                    .zip(self.gamma.get_data().iter().zip(self.beta.get_data()))
+                    .map(move |(c, (g, b))| c * row.inv_sqrt * g + b)
+            })
+            .collect_vec();
+        Ok(Tensor::new(input.get_shape(), output))
+    }
+}
+
+impl<N: Number> OpInfo for Normalization<N> {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        let name = match self.kind {
+            NormKind::Layer => "LayerNorm",
+            NormKind::Rms => "RMSNorm",
+        };
+        format!(
+            "{}: {} entries, epsilon {}",
+            name, self.num_entries, self.epsilon
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for Normalization<f32> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found {} inputs for normalization layer, expected 1",
+            inputs.len()
+        );
+        self.check_input_shape(&inputs[0].get_shape())?;
+        self.check_input_shape(&unpadded_input_shapes[0])?;
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])]))
+    }
+}
+
+impl Evaluate<Element> for Normalization<Element> {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found {} inputs for normalization layer, expected 1",
+            inputs.len()
+        );
+        self.check_input_shape(&inputs[0].get_shape())?;
+        Ok(LayerOut::from_vec(vec![self.quantized_op(inputs[0])?]))
+    }
+}
+
+impl QuantizeOp for Normalization<f32> {
+    type QuantizedOp = Normalization<Element>;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        data: &S::AuxData,
+        node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == 1,
+            "Found {} input scaling factors for normalization layer, expected 1",
+            input_scaling.len()
+        );
+        let mut output_scalings =
+            S::scaling_factors_for_node(data, node_id, self.num_outputs(input_scaling.len()));
+        ensure!(
+            output_scalings.len() == 1,
+            "Output scaling for normalization layer different from 1"
+        );
+        self.quantize_from_scalings(&input_scaling[0], output_scalings.pop().unwrap())
+    }
+}
+
+impl<E> ProveInfo<E> for Normalization<Element>
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        let table = self.table()?;
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for normalization layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let shape = &aux.last_output_shape[0];
+        self.check_input_shape(shape)?;
+        let num_vars = ceil_log2(shape.iter().product());
+        // the lookups are performed over the whole output, which needs at least 2 variables
+        ensure!(
+            num_vars >= 2,
+            "Normalization layer needs an input with at least 4 entries, found shape {:?}",
+            shape
+        );
+        let row_num_vars = num_vars - ceil_log2(*shape.last().unwrap());
+        aux.tables.insert(TableType::InvSqrt(table));
+        aux.tables.insert(TableType::Range(table.bit_len()));
+        Ok((
+            LayerCtx::Normalization(NormalizationCtx {
+                kind: self.kind,
+                table,
+                gamma_poly_id: id,
+                beta_poly_id: NORM_BETA_POLY_ID + id,
+                inv_sqrt_poly_id: NORM_INV_SQRT_POLY_ID + id,
+                variance_poly_id: NORM_VARIANCE_POLY_ID + id,
+                num_vars,
+                row_num_vars,
+            }),
+            aux,
+        ))
+    }
+
+    fn commit_info(&self, id: NodeId) -> Vec<Option<(PolyID, Vec<E>)>> {
+        let id = id as PolyID;
+        let gamma = Some((id, self.gamma.evals_flat()));
+        match self.kind {
+            NormKind::Layer => vec![
+                gamma,
+                Some((NORM_BETA_POLY_ID + id, self.beta.evals_flat())),
+            ],
+            NormKind::Rms => vec![gamma],
+        }
+    }
+}
+
+impl PadOp for Normalization<Element> {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        normalization(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Normalization<Element>
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = NormalizationCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for normalization layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of normalization layer, expected 1",
+            step_data.inputs.len()
+        );
+        Ok(vec![ctx.prove_step(
+            prover,
+            last_claims[0],
+            step_data.inputs[0].get_data(),
+            self,
+            node_id,
+        )?])
+    }
+
+    fn gen_lookup_witness(
+        &self,
+        id: NodeId,
+        gen: &mut LookupWitnessGen<E>,
+        step_data: &StepData<Element, E>,
+    ) -> Result<()> {
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found more than 1 input in inference step of normalization layer"
+        );
+        let table = self.table()?;
+        let input = step_data.inputs[0].get_data();
+        let row_len = self.gamma.get_shape()[0];
+        // the values derived from each row are repeated over all its entries, so that the
+        // lookups are performed over as many entries as the output
+        let rows = input
+            .chunks(row_len)
+            .map(|row| self.row(&table, row))
+            .collect_vec();
+        let broadcast = |f: fn(&NormRow) -> Element| {
+            rows.iter()
+                .flat_map(|row| std::iter::repeat(f(row)).take(row_len))
+                .collect_vec()
+        };
+        let to_base_field = |column: &[Element]| {
+            column
+                .iter()
+                .map(|v| Fieldizer::<E>::to_field(v).as_bases()[0])
+                .collect_vec()
+        };
+        let variances = broadcast(|row| row.variance);
+        let inv_sqrts = broadcast(|row| row.inv_sqrt);
+        let remainders = broadcast(|row| row.remainder);
+
+        // 1. lookup of the inverse square roots of the variances
+        let table_type = TableType::InvSqrt(table);
+        gen.tables.insert(table_type);
+        let inv_sqrt_lookups = gen
+            .lookups
+            .entry(table_type)
+            .or_insert_with(HashMap::default);
+        variances.iter().zip(&inv_sqrts).for_each(|(v, t)| {
+            *inv_sqrt_lookups
+                .entry(v + COLUMN_SEPARATOR * t)
+                .or_insert(0u64) += 1
+        });
+        let inv_sqrt_columns = vec![to_base_field(&variances), to_base_field(&inv_sqrts)];
+
+        // 2. range check of the bits dropped from the sums of squares, and of their distance to
+        // the upper bound
+        let complements = remainders
+            .iter()
+            .map(|r| (1 << table.shift()) - 1 - r)
+            .collect_vec();
+        let num_limbs = table.remainder_limbs();
+        let range_columns = [
+            limbs(&remainders, num_limbs, table.bit_len()),
+            limbs(&complements, num_limbs, table.bit_len()),
+        ]
+        .concat();
+        let range_table = TableType::Range(table.bit_len());
+        gen.tables.insert(range_table);
+        let range_lookups = gen
+            .lookups
+            .entry(range_table)
+            .or_insert_with(HashMap::default);
+        range_columns
+            .iter()
+            .flatten()
+            .for_each(|limb| *range_lookups.entry(*limb).or_insert(0u64) += 1);
+        let range_columns = range_columns
+            .iter()
+            .map(|column| to_base_field(column))
+            .collect_vec();
+
+        gen.polys_with_id.push((
+            NORM_INV_SQRT_POLY_ID + id,
+            inv_sqrts.iter().map(Fieldizer::<E>::to_field).collect(),
+        ));
+        gen.polys_with_id.push((
+            NORM_VARIANCE_POLY_ID + id,
+            variances.iter().map(Fieldizer::<E>::to_field).collect(),
+        ));
+        let lookups = gen.lookups_no_challenges.entry(id).or_default();
+        lookups.push((inv_sqrt_columns, 2, table_type));
+        lookups.push((range_columns, 1, range_table));
+
+        Ok(())
+    }
+}
+
+impl NormalizationCtx {
+    /// Number of variables of the columns of the output, i.e. of the entries of each row
+    fn col_num_vars(&self) -> usize {
+        self.num_vars - self.row_num_vars
+    }
+
+    /// Evaluation of the sums of squares of the centered rows at the row variables of the point of
+    /// the range check, from the evaluations of the table inputs and of the remainders
+    fn sum_squares_eval<E: ExtensionField>(&self, variance_eval: E, remainder_eval: E) -> E {
+        let offset = E::from(1u64 << (self.table.bit_len() - 1));
+        (variance_eval + offset) * E::from(1u64 << self.table.shift()) + remainder_eval
+    }
+
+    /// Repeats `evals`, defined over the rows, over all the columns of the output
+    fn broadcast_rows<E: ExtensionField>(&self, evals: &[E]) -> Vec<E> {
+        evals
+            .iter()
+            .flat_map(|e| std::iter::repeat(*e).take(1 << self.col_num_vars()))
+            .collect()
+    }
+
+    /// Repeats `evals`, defined over the columns, over all the rows of the output
+    fn broadcast_cols<E: ExtensionField>(&self, evals: &[E]) -> Vec<E> {
+        (0..1 << self.row_num_vars)
+            .flat_map(|_| evals.iter().copied())
+            .collect()
+    }
+
+    /// Points over which the input is evaluated to derive the sum of the rows at `row_point`
+    fn row_sum_points<E: ExtensionField>(points: &[(E, Vec<E>)], row_point: &[E]) -> Vec<Vec<E>> {
+        points
+            .iter()
+            .map(|(_, point)| [point.as_slice(), row_point].concat())
+            .collect()
+    }
+
+    /// Evaluation of the centered input derived from the evaluation of the input and of the
+    /// input over the points returned by [`sum_points`]
+    fn centered_eval<E: ExtensionField>(
+        num_entries: usize,
+        input_eval: E,
+        points: &[(E, Vec<E>)],
+        sum_evals: &[E],
+    ) -> E {
+        points
+            .iter()
+            .zip(sum_evals)
+            .fold(E::from(num_entries as u64) * input_eval, |acc, ((weight, _), eval)| {
+                acc - *weight * *eval
+            })
+    }
+
+    #[timed::timed_instrument(name = "Prover::prove_normalization_step")]
+    pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        prover: &mut Prover<E, T>,
+        last_claim: &Claim<E>,
+        input: &[E],
+        layer: &Normalization<Element>,
+        node_id: NodeId,
+    ) -> Result<Claim<E>>
+    where
+        E: ExtensionField + Serialize + DeserializeOwned,
+        E::BaseField: Serialize + DeserializeOwned,
+    {
+        let num_entries = layer.num_entries;
+        let mut witnesses = prover.lookup_witnesses(node_id)?;
+        ensure!(
+            witnesses.len() == 2,
+            "Found {} lookup witnesses for normalization layer, expected 2",
+            witnesses.len()
+        );
+        let range_witness = witnesses.pop().unwrap();
+        let inv_sqrt_witness = witnesses.pop().unwrap();
+        let [variances, inv_sqrts] = [0, 1].map(|i| {
+            inv_sqrt_witness.column_evals()[i]
+                .iter()
+                .map(|e| E::from(*e))
+                .collect_vec()
+                .into_mle()
+        });
+
+        // 1. Run the lookup protocols
+        let inv_sqrt_lookup = logup_batch_prove(&inv_sqrt_witness, prover.transcript)?;
+        let range_lookup = logup_batch_prove(&range_witness, prover.transcript)?;
+        let range_point = range_lookup.output_claims()[0].point.clone();
+        let (_, range_row_point) = range_point.split_at(self.col_num_vars());
+
+        // 2. The sum of the squares of the centered rows at the point of the range check is derived
+        // from the inputs of the table and the remainders
+        let variance_eval = variances.evaluate(&range_point);
+        prover.transcript.append_field_element_ext(&variance_eval);
+        let sum_squares_eval = self.sum_squares_eval(
+            variance_eval,
+            recombine_limbs(
+                &range_lookup
+                    .output_claims()
+                    .iter()
+                    .take(self.table.remainder_limbs())
+                    .map(|claim| claim.eval)
+                    .collect_vec(),
+                self.table.bit_len(),
+            ),
+        );
+        let (output_col_point, _) = last_claim.point.split_at(self.col_num_vars());
+        let beta_eval = match self.kind {
+            NormKind::Layer => {
+                let eval = layer.beta.evals_flat().into_mle().evaluate(output_col_point);
+                prover.transcript.append_field_element_ext(&eval);
+                Some(eval)
+            }
+            NormKind::Rms => None,
+        };
+
+        // 3. Batch the output claim, without beta, with the sum of squares in a single sumcheck:
+        // sum_i eq(p, i) * c(i) * t(i) * gamma(i) + l * eq(z, r) * mask(j) * c(i)^2
+        let n = E::from(num_entries as u64);
+        let row_len = 1 << self.col_num_vars();
+        let row_sums = input
+            .chunks(row_len)
+            .map(|row| match self.kind {
+                NormKind::Layer => row.iter().take(num_entries).fold(E::ZERO, |acc, x| acc + *x),
+                NormKind::Rms => E::ZERO,
+            })
+            .collect_vec();
+        let centered = input
+            .iter()
+            .enumerate()
+            .map(|(i, x)| n * *x - row_sums[i / row_len])
+            .collect_vec();
+        let challenge = prover
+            .transcript
+            .get_and_append_challenge(b"normalization_batching")
+            .elements;
+        let mask = (0..row_len)
+            .map(|j| if j < num_entries { E::ONE } else { E::ZERO })
+            .collect_vec();
+        let centered_mle: ArcMultilinearExtension<E> = centered.into_mle().into();
+        let mut vp = VirtualPolynomial::<E>::new(self.num_vars);
+        vp.add_mle_list(
+            vec![
+                compute_betas_eval(&last_claim.point).into_mle().into(),
+                centered_mle.clone(),
+                inv_sqrts.clone().into(),
+                self.broadcast_cols(&layer.gamma.evals_flat()).into_mle().into(),
+            ],
+            E::ONE,
+        );
+        vp.add_mle_list(
+            vec![
+                self.broadcast_rows(&compute_betas_eval(range_row_point))
+                    .into_mle()
+                    .into(),
+                self.broadcast_cols(&mask).into_mle().into(),
+                centered_mle.clone(),
+                centered_mle,
+            ],
+            challenge,
+        );
+        #[allow(deprecated)]
+        let (sumcheck, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        let evals = state.get_mle_final_evaluations();
+        // the evaluations follow the order in which the polynomials are added
+        let individual_evals = vec![evals[1], evals[2], evals[3]];
+        debug_assert_eq!(
+            sumcheck.extract_sum(),
+            last_claim.eval - beta_eval.unwrap_or(E::ZERO) + challenge * sum_squares_eval,
+            "Inconsistent sum of squares for normalization layer"
+        );
+        let point = sumcheck.point.clone();
+        let (col_point, row_point) = point.split_at(self.col_num_vars());
+
+        // 4. The centered input is derived from the input and the sums of its rows
+        let input_mle = input.to_vec().into_mle();
+        let input_eval = input_mle.evaluate(&point);
+        let (sum_eval_points, sum_evals) = match self.kind {
+            NormKind::Layer => {
+                let points = Self::row_sum_points(
+                    &sum_points::<E>(self.col_num_vars(), num_entries),
+                    row_point,
+                );
+                let evals = points.iter().map(|p| input_mle.evaluate(p)).collect_vec();
+                (points, evals)
+            }
+            NormKind::Rms => (vec![], vec![]),
+        };
+        prover.transcript.append_field_element_ext(&input_eval);
+        prover.transcript.append_field_element_exts(&sum_evals);
+
+        // 5. Claims about the weights
+        prover.commit_prover.add_claim(
+            self.gamma_poly_id,
+            Claim::new(col_point.to_vec(), individual_evals[2]),
+        )?;
+        if let Some(beta_eval) = beta_eval {
+            prover.commit_prover.add_claim(
+                self.beta_poly_id,
+                Claim::new(output_col_point.to_vec(), beta_eval),
+            )?;
+        }
+
+        // 6. Accumulate the claims about the input, the inverse square roots and the table inputs
+        let same_poly_ctx = same_poly::Context::<E>::new(self.num_vars);
+        let (input_claim, input_accumulation) = match self.kind {
+            NormKind::Layer => {
+                let mut input_prover = same_poly::Prover::<E>::new(input_mle);
+                input_prover.add_claim(Claim::new(point.clone(), input_eval))?;
+                sum_eval_points
+                    .into_iter()
+                    .zip(&sum_evals)
+                    .try_for_each(|(point, eval)| input_prover.add_claim(Claim::new(point, *eval)))?;
+                let proof = input_prover.prove(&same_poly_ctx, prover.transcript)?;
+                (proof.extract_claim(), Some(proof))
+            }
+            NormKind::Rms => (Claim::new(point.clone(), input_eval), None),
+        };
+
+        let mut inv_sqrt_prover = same_poly::Prover::<E>::new(inv_sqrts);
+        inv_sqrt_prover.add_claim(inv_sqrt_lookup.output_claims()[1].clone())?;
+        inv_sqrt_prover.add_claim(Claim::new(point, individual_evals[1]))?;
+        let inv_sqrt_accumulation = inv_sqrt_prover.prove(&same_poly_ctx, prover.transcript)?;
+        prover
+            .witness_prover
+            .add_claim(self.inv_sqrt_poly_id, inv_sqrt_accumulation.extract_claim())?;
+
+        let mut variance_prover = same_poly::Prover::<E>::new(variances);
+        variance_prover.add_claim(inv_sqrt_lookup.output_claims()[0].clone())?;
+        variance_prover.add_claim(Claim::new(range_point, variance_eval))?;
+        let variance_accumulation = variance_prover.prove(&same_poly_ctx, prover.transcript)?;
+        prover
+            .witness_prover
+            .add_claim(self.variance_poly_id, variance_accumulation.extract_claim())?;
+
+        prover.push_proof(
+            node_id,
+            LayerProof::Normalization(NormalizationProof {
+                inv_sqrt_lookup,
+                range_lookup,
+                variance_eval,
+                beta_eval,
+                sumcheck,
+                individual_evals,
+                input_eval,
+                sum_evals,
+                input_accumulation,
+                inv_sqrt_accumulation,
+                variance_accumulation,
+            }),
+        );
+        Ok(input_claim)
+    }
+}
+
+impl OpInfo for NormalizationCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Normalization ctx: {:?}, 2^{} rows of {} entries",
+            self.kind,
+            self.row_num_vars,
+            self.table.num_entries()
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for NormalizationCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = NormalizationProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for normalization layer, expected 1",
+            last_claims.len()
+        );
+        let last_claim = last_claims[0];
+        let num_entries = self.table.num_entries();
+        let challenges = |table_type: TableType| {
+            verifier
+                .challenge_storage
+                .as_ref()
+                .unwrap()
+                .get_challenges_by_name(&table_type.name())
+                .ok_or(anyhow!(
+                    "Couldn't get challenges for LookupType: {}",
+                    table_type.name()
+                ))
+        };
+        let (inv_sqrt_constant_challenge, inv_sqrt_column_challenge) =
+            challenges(TableType::InvSqrt(self.table))?;
+        let (range_constant_challenge, range_column_challenge) =
+            challenges(TableType::Range(self.table.bit_len()))?;
+
+        // 1. Verify the lookup proofs
+        let inv_sqrt_claims = verify_logup_proof(
+            &proof.inv_sqrt_lookup,
+            1,
+            inv_sqrt_constant_challenge,
+            inv_sqrt_column_challenge,
+            verifier.transcript,
+        )?;
+        let num_limbs = self.table.remainder_limbs();
+        let range_claims = verify_logup_proof(
+            &proof.range_lookup,
+            2 * num_limbs,
+            range_constant_challenge,
+            range_column_challenge,
+            verifier.transcript,
+        )?;
+        ensure!(
+            inv_sqrt_claims.claims().len() == 2 && range_claims.claims().len() == 2 * num_limbs,
+            "Invalid number of lookup claims in normalization proof"
+        );
+        let range_point = range_claims.claims()[0].point.clone();
+        let (_, range_row_point) = range_point.split_at(self.col_num_vars());
+
+        // 2. Check the remainders lie in [0, 2^shift) and derive the sum of squares
+        let limb_evals = range_claims
+            .claims()
+            .iter()
+            .map(|claim| claim.eval)
+            .collect_vec();
+        let (remainder_evals, complement_evals) = limb_evals.split_at(num_limbs);
+        let remainder_eval = recombine_limbs(remainder_evals, self.table.bit_len());
+        ensure!(
+            recombine_limbs(complement_evals, self.table.bit_len())
+                == E::from(1u64 << self.table.shift()) - E::ONE - remainder_eval,
+            "Normalization remainders are not smaller than the shift of the variances"
+        );
+        verifier
+            .transcript
+            .append_field_element_ext(&proof.variance_eval);
+        let sum_squares_eval = self.sum_squares_eval(proof.variance_eval, remainder_eval);
+        let (output_col_point, _) = last_claim.point.split_at(self.col_num_vars());
+        match (self.kind, proof.beta_eval) {
+            (NormKind::Layer, Some(beta_eval)) => {
+                verifier.transcript.append_field_element_ext(&beta_eval)
+            }
+            (NormKind::Rms, None) => {}
+            _ => bail!("Normalization proof doesn't match the kind of the layer"),
+        }
+
+        // 3. Verify the sumcheck batching the output claim with the sum of squares
+        let challenge = verifier
+            .transcript
+            .get_and_append_challenge(b"normalization_batching")
+            .elements;
+        let claimed_sum = last_claim.eval - proof.beta_eval.unwrap_or(E::ZERO)
+            + challenge * sum_squares_eval;
+        let subclaim = IOPVerifierState::<E>::verify(
+            claimed_sum,
+            &proof.sumcheck,
+            &VPAuxInfo::from_mle_list_dimensions(&[
+                vec![self.num_vars; 4],
+                vec![self.num_vars; 4],
+            ]),
+            verifier.transcript,
+        );
+        let point = subclaim.point_flat();
+        let (col_point, row_point) = point.split_at(self.col_num_vars());
+        ensure!(
+            proof.individual_evals.len() == 3,
+            "Found {} individual evaluations in normalization proof, expected 3",
+            proof.individual_evals.len()
+        );
+        let (centered_eval, inv_sqrt_eval, gamma_eval) = (
+            proof.individual_evals[0],
+            proof.individual_evals[1],
+            proof.individual_evals[2],
+        );
+        ensure!(
+            identity_eval(&last_claim.point, &point) * centered_eval * inv_sqrt_eval * gamma_eval
+                + challenge
+                    * identity_eval(range_row_point, row_point)
+                    * prefix_eq_eval(&[col_point], num_entries)
+                    * centered_eval
+                    * centered_eval
+                == subclaim.expected_evaluation,
+            "Normalization sumcheck claim failed"
+        );
+
+        // 4. Check the centered input against the input and the sums of its rows
+        let points = match self.kind {
+            NormKind::Layer => sum_points::<E>(self.col_num_vars(), num_entries),
+            NormKind::Rms => vec![],
+        };
+        ensure!(
+            proof.sum_evals.len() == points.len(),
+            "Found {} evaluations of the input in normalization proof, expected {}",
+            proof.sum_evals.len(),
+            points.len()
+        );
+        verifier
+            .transcript
+            .append_field_element_ext(&proof.input_eval);
+        verifier
+            .transcript
+            .append_field_element_exts(&proof.sum_evals);
+        ensure!(
+            Self::centered_eval(num_entries, proof.input_eval, &points, &proof.sum_evals)
+                == centered_eval,
+            "Normalization centered input doesn't match the input"
+        );
+
+        // 5. Claims about the weights
+        verifier.commit_verifier.add_claim(
+            self.gamma_poly_id,
+            Claim::new(col_point.to_vec(), gamma_eval),
+        )?;
+        if let Some(beta_eval) = proof.beta_eval {
+            verifier.commit_verifier.add_claim(
+                self.beta_poly_id,
+                Claim::new(output_col_point.to_vec(), beta_eval),
+            )?;
+        }
+
+        // 6. Verify the accumulation of the claims about the input, the inverse square roots and
+        // the table inputs
+        let sp_ctx = same_poly::Context::<E>::new(self.num_vars);
+        let input_claim = match &proof.input_accumulation {
+            Some(accumulation) => {
+                let mut input_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+                input_verifier.add_claim(Claim::new(point.clone(), proof.input_eval))?;
+                Self::row_sum_points(&points, row_point)
+                    .into_iter()
+                    .zip(&proof.sum_evals)
+                    .try_for_each(|(point, eval)| {
+                        input_verifier.add_claim(Claim::new(point, *eval))
+                    })?;
+                input_verifier.verify(accumulation, verifier.transcript)?
+            }
+            None if self.kind == NormKind::Rms => Claim::new(point.clone(), proof.input_eval),
+            None => bail!("Missing accumulation of the input claims in LayerNorm proof"),
+        };
+
+        let mut inv_sqrt_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        inv_sqrt_verifier.add_claim(inv_sqrt_claims.claims()[1].clone())?;
+        inv_sqrt_verifier.add_claim(Claim::new(point, inv_sqrt_eval))?;
+        let inv_sqrt_claim =
+            inv_sqrt_verifier.verify(&proof.inv_sqrt_accumulation, verifier.transcript)?;
+        verifier
+            .witness_verifier
+            .add_claim(self.inv_sqrt_poly_id, inv_sqrt_claim)?;
+
+        let mut variance_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        variance_verifier.add_claim(inv_sqrt_claims.claims()[0].clone())?;
+        variance_verifier.add_claim(Claim::new(range_point, proof.variance_eval))?;
+        let variance_claim =
+            variance_verifier.verify(&proof.variance_accumulation, verifier.transcript)?;
+        verifier
+            .witness_verifier
+            .add_claim(self.variance_poly_id, variance_claim)?;
+
+        // 7. return the input claim to be proven at subsequent step
+        Ok(vec![input_claim])
+    }
+}
+
+/// Lookup table of the inverse square roots of the variances of the rows normalized by a
+/// [`Normalization`] layer. The inputs are the sums of the squares of the centered rows, shifted
+/// right to fit in the bit length of the input and offset to start at the smallest quantized value.
+/// The output column of the table is committed.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct InvSqrtTable {
+    num_entries: usize,
+    /// Bit representation of epsilon in the squared quantized units of the input, so that the table
+    /// can be used as a key
+    epsilon: u32,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl InvSqrtTable {
+    pub fn new(num_entries: usize, input_scaling: &ScalingFactor, epsilon: f32) -> Self {
+        Self {
+            num_entries,
+            epsilon: (epsilon / (input_scaling.scale() * input_scaling.scale())).to_bits(),
+            bit_len: input_scaling.bit_len(),
+        }
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn num_entries(&self) -> usize {
+        self.num_entries
+    }
+
+    pub fn epsilon(&self) -> f32 {
+        f32::from_bits(self.epsilon)
+    }
+
+    /// Right shift applied to the sums of squares of the centered rows: these are `n^3` times the
+    /// variances, which are smaller than `2^(2 * bit_len - 2)`
+    pub fn shift(&self) -> usize {
+        ceil_log2(self.num_entries.pow(3)) + self.bit_len - 2
+    }
+
+    /// Number of `bit_len` bits limbs needed to range check the bits dropped by the shift
+    fn remainder_limbs(&self) -> usize {
+        self.shift().div_ceil(self.bit_len)
+    }
+
+    /// Maps the sum of the squares of a centered row to the input of the table
+    #[inline(always)]
+    pub fn input(&self, sum_squares: Element) -> Element {
+        (sum_squares >> self.shift()) - (1 << (self.bit_len - 1))
+    }
+
+    /// Maps an input of the table to `2^INV_SQRT_BITS / sqrt(var + eps)`, where `var` is the middle
+    /// of the range of variances mapped to the input
+    #[inline(always)]
+    pub fn apply(&self, input: Element) -> Element {
+        let shifted = (input + (1 << (self.bit_len - 1))) as f64 + 0.5;
+        let variance = shifted * (1u64 << self.shift()) as f64 / self.num_entries.pow(3) as f64;
+        ((1u64 << INV_SQRT_BITS) as f64 / (variance + self.epsilon() as f64).sqrt()).round()
+            as Element
+    }
+
+    /// Biggest output of the table, reached for the smallest variance
+    pub fn max_output(&self) -> Element {
+        self.apply(quantization::min(self.bit_len) - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    fn quantized_norm(
+        norm: Normalization<f32>,
+        input_scaling: &ScalingFactor,
+    ) -> (Normalization<Element>, Requant) {
+        let output = norm
+            .quantize_from_scalings(input_scaling, ScalingFactor::from_absolute_max(4.0, None))
+            .unwrap();
+        (output.quanzited_op, output.requant_layer.unwrap())
+    }
+
+    #[test]
+    fn test_normalization_quantized_output() {
+        let (num_rows, num_entries) = (3, 10);
+        let input_scaling = ScalingFactor::from_absolute_max(2.0, None);
+        let float_input = Tensor::<f32>::random(&vec![num_rows, num_entries]).scalar_mul(&2.0);
+        let gamma = Tensor::<f32>::random(&vec![num_entries]);
+        for norm in [
+            Normalization::layer_norm(
+                gamma.clone(),
+                Tensor::<f32>::random(&vec![num_entries]),
+                1e-5,
+            ),
+            Normalization::rms_norm(gamma.clone(), 1e-5),
+        ] {
+            let float_output = norm.op(&float_input);
+            let (quantized, requant) = quantized_norm(norm, &input_scaling);
+            let input = float_input.clone().quantize(&input_scaling);
+            let output = requant
+                .op(&quantized.quantized_op(&input).unwrap())
+                .unwrap();
+            let output_scaling = ScalingFactor::from_absolute_max(4.0, None);
+            let max = float_output.max_abs_output();
+            for (q, f) in output.get_data().iter().zip(float_output.get_data()) {
+                let dequantized = output_scaling.dequantize(q);
+                assert!(
+                    (dequantized - f).abs() <= 0.05 * max,
+                    "dequantized {dequantized} too far from {f}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalization_padded_rows() {
+        let (num_rows, num_entries) = (3, 5);
+        let input_scaling = ScalingFactor::from_absolute_max(1.0, None);
+        let norm = Normalization::layer_norm(
+            Tensor::<f32>::random(&vec![num_entries]),
+            Tensor::<f32>::random(&vec![num_entries]),
+            1e-5,
+        );
+        let (quantized, _) = quantized_norm(norm, &input_scaling);
+        let input = Tensor::<f32>::random(&vec![num_rows, num_entries]).quantize(&input_scaling);
+        let output = quantized.quantized_op(&input).unwrap();
+        let padded = quantized.padded(8).unwrap();
+        let padded_output = padded
+            .quantized_op(&input.pad_next_power_of_two())
+            .unwrap();
+        assert_eq!(padded_output.get_shape(), vec![4, 8]);
+        for row in 0..num_rows {
+            let padded_row = &padded_output.get_data()[row * 8..(row + 1) * 8];
+            assert_eq!(
+                &padded_row[..num_entries],
+                &output.get_data()[row * num_entries..(row + 1) * num_entries]
+            );
+            // the padded entries of each row are zero
+            assert!(padded_row[num_entries..].iter().all(|z| *z == 0));
+        }
+    }
+
+    #[test]
+    fn test_normalization_table_inputs() {
+        let num_entries = 13;
+        let input_scaling = ScalingFactor::from_absolute_max(1.0, None);
+        for norm in [
+            Normalization::layer_norm(
+                Tensor::<f32>::random(&vec![num_entries]),
+                Tensor::<f32>::random(&vec![num_entries]),
+                1e-5,
+            ),
+            Normalization::rms_norm(Tensor::<f32>::random(&vec![num_entries]), 1e-5),
+        ] {
+            let (quantized, _) = quantized_norm(norm, &input_scaling);
+            let table = quantized.table.unwrap();
+            // rows with the biggest and the smallest variances
+            let rows = [
+                (0..num_entries)
+                    .map(|i| {
+                        if i % 2 == 0 {
+                            quantization::MIN
+                        } else {
+                            quantization::MAX
+                        }
+                    })
+                    .collect_vec(),
+                vec![quantization::MIN; num_entries],
+                vec![0; num_entries],
+            ];
+            for row in rows {
+                let row = quantized.row(&table, &row);
+                assert!(row.variance >= quantization::MIN - 1 && row.variance <= quantization::MAX);
+                assert!(row.remainder >= 0 && row.remainder >> table.shift() == 0);
+                let evals = limbs(&[row.remainder], table.remainder_limbs(), table.bit_len())
+                    .into_iter()
+                    .map(|limb| Fieldizer::<F>::to_field(&limb[0]))
+                    .collect_vec();
+                assert_eq!(
+                    recombine_limbs(&evals, table.bit_len()),
+                    Fieldizer::<F>::to_field(&row.remainder)
+                );
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     Claim, Element, Prover,
     commit::{compute_betas_eval, identity_eval, precommit::PolyID, same_poly},
     iop::{context::ShapeStep, verifier::Verifier},
-    layers::{ContextAux, LayerProof},
+    layers::{ContextAux, LayerProof, requant::Requant},
     lookup::{
         context::{LookupWitnessGen, TableType},
         logup_gkr::{
@@ -14,10 +14,10 @@ use crate::{
     },
     model::StepData,
     padding::{PaddingMode, ShapeInfo, pooling},
-    quantization::{Fieldizer, IntoElement},
+    quantization::{self, Fieldizer, IntoElement, ScalingFactor, ScalingStrategy},
     tensor::{Number, Tensor},
 };
-use anyhow::{Context, Result, anyhow, ensure};
+use anyhow::{Context, Result, anyhow, bail, ensure};
 use ff_ext::ExtensionField;
 use gkr::util::ceil_log2;
 use itertools::{Itertools, izip};
@@ -35,26 +35,42 @@ use sumcheck::structs::IOPProverState;
 
 use super::{
     LayerCtx,
-    provable::{Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx},
+    provable::{
+        Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp,
+        QuantizeOutput, VerifiableCtx,
+    },
 };
 
 pub const MAXPOOL2D_KERNEL_SIZE: usize = 2;
+pub const AVGPOOL2D_KERNEL_SIZE: usize = 2;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Copy)]
 pub enum Pooling {
     Maxpool2D(Maxpool2D),
+    AvgPool2D(AvgPool2D),
+    GlobalAveragePool(GlobalAveragePool),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PoolingCtx {
-    pub poolinfo: Maxpool2D,
+    pub poolinfo: Pooling,
     pub poly_id: PolyID,
     pub num_vars: usize,
 }
 
 /// Contains proof material related to one step of the inference
 #[derive(Clone, Serialize, Deserialize)]
-pub struct PoolingProof<E: ExtensionField>
+pub enum PoolingProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    Max(MaxPoolingProof<E>),
+    Avg(AvgPoolingProof<E>),
+}
+
+/// Proof material for a max pooling step
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MaxPoolingProof<E: ExtensionField>
 where
     E::BaseField: Serialize + DeserializeOwned,
 {
@@ -68,8 +84,23 @@ where
     pub(crate) output_claims: Vec<Claim<E>>,
     /// The output evaluations of the diff polys produced by the zerocheck
     pub(crate) zerocheck_evals: Vec<E>,
-    /// This tells the verifier how far apart the variables get fixed on the input MLE
-    pub(crate) variable_gap: usize,
+    /// the sumcheck proof reducing the claims about the inputs in each window to a single claim about the input
+    pub(crate) input_sumcheck: IOPProof<E>,
+    /// the evaluation of the input poly at the point of the input sumcheck
+    pub(crate) input_eval: E,
+}
+
+/// Proof material for an average pooling step. The layer only sums the inputs in each window,
+/// the division being performed by the requant layer following it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AvgPoolingProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// the sumcheck proof showing that each output is the sum of the inputs in its window
+    pub(crate) sumcheck: IOPProof<E>,
+    /// the evaluation of the input poly at the point of the sumcheck
+    pub(crate) input_eval: E,
 }
 
 const IS_PROVABLE: bool = true;
@@ -78,14 +109,18 @@ impl OpInfo for Pooling {
     fn output_shapes(
         &self,
         input_shapes: &[Vec<usize>],
-        _padding_mode: PaddingMode,
+        padding_mode: PaddingMode,
     ) -> Vec<Vec<usize>> {
-        match self {
-            Pooling::Maxpool2D(maxpool2_d) => input_shapes
-                .into_iter()
-                .map(|shape| maxpool2_d.output_shape(shape))
-                .collect(),
-        }
+        input_shapes
+            .into_iter()
+            .map(|shape| match (self, padding_mode) {
+                // the unpadded output shape of a padded maxpool layer is the one without padding
+                (Pooling::Maxpool2D(maxpool), PaddingMode::NoPadding) => {
+                    maxpool.unpadded().output_shape(shape)
+                }
+                _ => self.output_shape(shape),
+            })
+            .collect()
     }
 
     fn num_outputs(&self, num_inputs: usize) -> usize {
@@ -98,6 +133,14 @@ impl OpInfo for Pooling {
                 "MaxPool2D{{ kernel size: {}, stride: {} }}",
                 maxpool2d.kernel_size, maxpool2d.stride
             ),
+            Pooling::AvgPool2D(avgpool2d) => format!(
+                "AvgPool2D{{ kernel size: {}, stride: {} }}",
+                avgpool2d.kernel_size, avgpool2d.stride
+            ),
+            Pooling::GlobalAveragePool(global) => format!(
+                "GlobalAveragePool{{ height: {}, width: {} }}",
+                global.height, global.width
+            ),
         }
     }
 
@@ -106,58 +149,114 @@ impl OpInfo for Pooling {
     }
 }
 
-impl<N: Number> Evaluate<N> for Pooling {
+impl Evaluate<f32> for Pooling {
     fn evaluate<E: ExtensionField>(
         &self,
-        inputs: &[&Tensor<N>],
+        inputs: &[&Tensor<f32>],
         _unpadded_input_shapes: Vec<Vec<usize>>,
-    ) -> Result<LayerOut<N, E>> {
+    ) -> Result<LayerOut<f32, E>> {
         ensure!(
             inputs.len() == 1,
             "Found more than 1 input when evaluating pooling layer"
         );
-        let input = inputs[0];
-        let output = match self {
-            Pooling::Maxpool2D(maxpool2d) => maxpool2d.op(input),
+        let output = self.op(inputs[0]);
+        // over floats, the average is computed directly
+        let output = match self.window_size() {
+            Some(window_size) => output.scalar_mul(&(1.0 / window_size as f32)),
+            None => output,
         };
         Ok(LayerOut::from_vec(vec![output]))
     }
 }
 
+impl Evaluate<Element> for Pooling {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found more than 1 input when evaluating pooling layer"
+        );
+        // over quantized values, the division of average pooling is done by the following requant layer
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])]))
+    }
+}
+
+impl QuantizeOp for Pooling {
+    type QuantizedOp = Pooling;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        _data: &S::AuxData,
+        _node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        let Some(window_size) = self.window_size() else {
+            // max pooling range checks the differences with the max over the bit length of the input
+            let quantized = match self {
+                Pooling::Maxpool2D(info) => {
+                    Pooling::Maxpool2D(info.with_bit_len(input_scaling[0].bit_len()))
+                }
+                _ => self,
+            };
+            return Ok(QuantizeOutput {
+                quanzited_op: quantized,
+                output_scalings: input_scaling.to_vec(),
+                requant_layer: None,
+                rescale_layer: None,
+            });
+        };
+        // The layer outputs the sum `s` of the `n` inputs in each window, while we need `s/n`.
+        // The requant layer multiplies `s` by the fixed-point approximation of `1/n`, so the output
+        // keeps the scaling factor of the input.
+        let min_output = window_size as Element * input_scaling[0].quantized_domain().0.abs();
+        Ok(QuantizeOutput {
+            quanzited_op: self,
+            output_scalings: input_scaling.to_vec(),
+            requant_layer: Some(Requant::from_multiplier(
+                min_output as usize,
+                1.0 / window_size as f64,
+                input_scaling[0].bit_len(),
+            )),
+            rescale_layer: None,
+        })
+    }
+}
+
 impl<E> ProveInfo<E> for Pooling
 where
     E: ExtensionField + DeserializeOwned,
     E::BaseField: Serialize + DeserializeOwned,
 {
     fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
-        let info = match self {
-            Pooling::Maxpool2D(info) => {
-                aux.tables.insert(TableType::Range);
-                let num_vars = aux.last_output_shape.iter_mut().fold(Ok(None), |expected_num_vars, shape| {
-                    // Pooling only affects the last two dimensions
-                    let total_number_dims = shape.len();
-
-                    shape.iter_mut()
-                        .skip(total_number_dims - 2)
-                        .for_each(|dim| *dim = (*dim - info.kernel_size) / info.stride + 1);
-
-                    let num_vars = shape.iter()
-                        .map(|dim| ceil_log2(*dim))
-                        .sum::<usize>();
-                    if let Some(vars) = expected_num_vars? {
-                        ensure!(vars == num_vars,
-                        "All input shapes for convolution must have the same number of variables");
-                    }
-                    Ok(Some(num_vars))
-                })?.expect("No input shape found for convolution layer?");
-
-                LayerCtx::Pooling(PoolingCtx {
-                    poolinfo: *info,
-                    poly_id: id,
-                    num_vars,
-                })
-            }
-        };
+        if let Pooling::Maxpool2D(info) = self {
+            aux.tables.insert(TableType::Range(info.bit_len));
+        }
+        let num_vars = aux
+            .last_output_shape
+            .iter_mut()
+            .fold(Ok(None), |expected_num_vars, shape| {
+                // Pooling only affects the last two dimensions
+                *shape = self.output_shape(shape);
+
+                let num_vars = shape.iter().map(|dim| ceil_log2(*dim)).sum::<usize>();
+                if let Some(vars) = expected_num_vars? {
+                    ensure!(
+                        vars == num_vars,
+                        "All input shapes for pooling must have the same number of variables"
+                    );
+                }
+                Ok(Some(num_vars))
+            })?
+            .expect("No input shape found for pooling layer?");
+
+        let info = LayerCtx::Pooling(PoolingCtx {
+            poolinfo: *self,
+            poly_id: id,
+            num_vars,
+        });
         Ok((info, aux))
     }
 }
@@ -187,14 +286,20 @@ where
         step_data: &StepData<E, E>,
         prover: &mut Prover<E, T>,
     ) -> Result<Vec<Claim<E>>> {
-        Ok(vec![self.prove_pooling(
-            prover,
-            last_claims[0],
-            &step_data.inputs[0],
-            &step_data.outputs.outputs()[0],
-            ctx,
-            id,
-        )?])
+        let claim = match self {
+            Pooling::Maxpool2D(_) => self.prove_pooling(
+                prover,
+                last_claims[0],
+                &step_data.inputs[0],
+                &step_data.outputs.outputs()[0],
+                ctx,
+                id,
+            )?,
+            Pooling::AvgPool2D(_) | Pooling::GlobalAveragePool(_) => {
+                self.prove_avg_pooling(prover, last_claims[0], &step_data.inputs[0], ctx, id)?
+            }
+        };
+        Ok(vec![claim])
     }
 
     fn gen_lookup_witness(
@@ -203,6 +308,10 @@ where
         gen: &mut LookupWitnessGen<E>,
         step_data: &StepData<Element, E>,
     ) -> Result<()> {
+        let Pooling::Maxpool2D(info) = self else {
+            // average pooling doesn't employ any lookup table
+            return Ok(());
+        };
         ensure!(
             step_data.inputs.len() == 1,
             "Found more than 1 input in inference step of pooling layer"
@@ -212,10 +321,11 @@ where
             "Found more than 1 output in inference step of pooling layer"
         );
 
-        gen.tables.insert(TableType::Range);
+        let table_type = TableType::Range(info.bit_len);
+        gen.tables.insert(table_type);
         let table_lookup_map = gen
             .lookups
-            .entry(TableType::Range)
+            .entry(table_type)
             .or_insert_with(|| HashMap::default());
 
         let (merged_lookups, column_evals) = self.lookup_witness::<E>(&step_data.inputs[0]);
@@ -233,7 +343,7 @@ where
                 .collect(),
         ));
         gen.lookups_no_challenges
-            .insert(id, (column_evals, 1, TableType::Range));
+            .insert(id, vec![(column_evals, 1, table_type)]);
 
         Ok(())
     }
@@ -243,12 +353,9 @@ impl OpInfo for PoolingCtx {
     fn output_shapes(
         &self,
         input_shapes: &[Vec<usize>],
-        _padding_mode: PaddingMode,
+        padding_mode: PaddingMode,
     ) -> Vec<Vec<usize>> {
-        input_shapes
-            .into_iter()
-            .map(|shape| self.poolinfo.output_shape(&shape))
-            .collect()
+        self.poolinfo.output_shapes(input_shapes, padding_mode)
     }
 
     fn num_outputs(&self, num_inputs: usize) -> usize {
@@ -256,10 +363,7 @@ impl OpInfo for PoolingCtx {
     }
 
     fn describe(&self) -> String {
-        format!(
-            "MaxPool2D ctx{{ kernel size: {}, stride: {} }}",
-            self.poolinfo.kernel_size, self.poolinfo.stride
-        )
+        format!("{} ctx", self.poolinfo.describe())
     }
 
     fn is_provable(&self) -> bool {
@@ -280,21 +384,38 @@ where
         proof: &Self::Proof,
         last_claims: &[&Claim<E>],
         verifier: &mut Verifier<E, T>,
-        _shape_step: &ShapeStep,
+        shape_step: &ShapeStep,
     ) -> Result<Vec<Claim<E>>> {
+        let (info, proof) = match (&self.poolinfo, proof) {
+            (Pooling::Maxpool2D(info), PoolingProof::Max(proof)) => (info, proof),
+            (Pooling::AvgPool2D(_) | Pooling::GlobalAveragePool(_), PoolingProof::Avg(proof)) => {
+                return Ok(vec![self.verify_avg_pooling(
+                    verifier,
+                    last_claims[0],
+                    proof,
+                    &shape_step.padded_input_shape[0],
+                )?]);
+            }
+            _ => bail!(
+                "Proof type doesn't match pooling layer {}",
+                self.poolinfo.describe()
+            ),
+        };
+        let table_type = TableType::Range(info.bit_len);
         let (constant_challenge, column_separation_challenge) = verifier
             .challenge_storage
             .as_ref()
             .unwrap()
-            .get_challenges_by_name(&TableType::Range.name())
+            .get_challenges_by_name(&table_type.name())
             .ok_or(anyhow!(
                 "Couldn't get challenges for LookupType: {}",
-                TableType::Range.name()
+                table_type.name()
             ))?;
         Ok(vec![self.verify_pooling(
             verifier,
             last_claims[0],
             proof,
+            &shape_step.padded_input_shape[0],
             constant_challenge,
             column_separation_challenge,
         )?])
@@ -306,12 +427,140 @@ impl Pooling {
         num_inputs
     }
 
+    /// Evaluates the pooling layer over `input`. For average pooling layers, it only computes the
+    /// sum of the inputs in each window, without the final division
     pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
         match self {
             Pooling::Maxpool2D(maxpool2d) => maxpool2d.op(input),
+            Pooling::AvgPool2D(avgpool2d) => avgpool2d.op(input),
+            Pooling::GlobalAveragePool(global) => global.op(input),
+        }
+    }
+
+    pub fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
+        match self {
+            Pooling::Maxpool2D(maxpool2d) => maxpool2d.output_shape(input_shape),
+            Pooling::AvgPool2D(avgpool2d) => avgpool2d.output_shape(input_shape),
+            Pooling::GlobalAveragePool(global) => global.output_shape(input_shape),
         }
     }
 
+    /// Number of inputs averaged in each window, `None` for max pooling
+    pub fn window_size(&self) -> Option<usize> {
+        match self {
+            Pooling::Maxpool2D(_) => None,
+            Pooling::AvgPool2D(avgpool2d) => Some(avgpool2d.kernel_size * avgpool2d.kernel_size),
+            Pooling::GlobalAveragePool(global) => Some(global.height * global.width),
+        }
+    }
+
+    /// Returns, for an average pooling layer with a 3D padded input of shape `input_shape`, the number of
+    /// variables of the row and column indices of the input which are summed over by the layer
+    fn summed_vars(&self, input_shape: &[usize]) -> (usize, usize) {
+        match self {
+            Pooling::AvgPool2D(avgpool2d) => {
+                let kernel_vars = ceil_log2(avgpool2d.kernel_size);
+                (kernel_vars, kernel_vars)
+            }
+            Pooling::GlobalAveragePool(_) => (ceil_log2(input_shape[1]), ceil_log2(input_shape[2])),
+            Pooling::Maxpool2D(_) => unreachable!("max pooling doesn't sum over the input"),
+        }
+    }
+
+    /// Computes the evaluations of the polynomial `S` such that `output(r) = sum_x input(x)*S(x)`,
+    /// where `r` is `output_point`. `S(x)` is the equality polynomial between `r` and the output
+    /// entry the input entry `x` contributes to, or zero if `x` is not summed by the layer.
+    fn selector_evals<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_shape: &[usize],
+    ) -> Vec<E> {
+        let (row_vars, col_vars) = self.summed_vars(input_shape);
+        let (height, width) = (input_shape[1], input_shape[2]);
+        let (out_col_vars, out_row_vars) =
+            (ceil_log2(width) - col_vars, ceil_log2(height) - row_vars);
+        let betas_col = compute_betas_eval(&output_point[..out_col_vars]);
+        let betas_row =
+            compute_betas_eval(&output_point[out_col_vars..out_col_vars + out_row_vars]);
+        let betas_channel = compute_betas_eval(&output_point[out_col_vars + out_row_vars..]);
+        // only the unpadded part of the input is summed by global average pooling, and only the
+        // windows of the real outputs by average pooling
+        let (summed_height, summed_width) = match self {
+            Pooling::GlobalAveragePool(global) => (global.height, global.width),
+            Pooling::AvgPool2D(AvgPool2D {
+                kernel_size,
+                real_output: Some((real_height, real_width)),
+                ..
+            }) => (real_height * kernel_size, real_width * kernel_size),
+            _ => (height, width),
+        };
+        (0..input_shape.iter().product::<usize>())
+            .into_par_iter()
+            .map(|i| {
+                let (c, row, col) = (i / (height * width), (i / width) % height, i % width);
+                if row < summed_height && col < summed_width {
+                    betas_channel[c] * betas_row[row >> row_vars] * betas_col[col >> col_vars]
+                } else {
+                    E::ZERO
+                }
+            })
+            .collect()
+    }
+
+    /// Evaluates the selector polynomial of [`Self::selector_evals`] at `input_point`, without
+    /// iterating over the whole input domain
+    fn selector_eval<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_point: &[E],
+        input_shape: &[usize],
+    ) -> Result<E> {
+        let (row_vars, col_vars) = self.summed_vars(input_shape);
+        let (height_vars, width_vars) = (ceil_log2(input_shape[1]), ceil_log2(input_shape[2]));
+        ensure!(
+            input_point.len() == input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>(),
+            "Invalid input point length for average pooling layer"
+        );
+        ensure!(
+            output_point.len() + row_vars + col_vars == input_point.len(),
+            "Invalid output point length for average pooling layer"
+        );
+        let (input_cols, rest) = input_point.split_at(width_vars);
+        let (input_rows, input_channels) = rest.split_at(height_vars);
+        let (output_cols, rest) = output_point.split_at(width_vars - col_vars);
+        let (output_rows, output_channels) = rest.split_at(height_vars - row_vars);
+        let (row_eval, col_eval) = match self {
+            // only the output rows and columns of the real outputs are selected
+            Pooling::AvgPool2D(AvgPool2D {
+                real_output: Some((real_height, real_width)),
+                ..
+            }) => (
+                prefix_identity_eval(output_rows, &input_rows[row_vars..], *real_height),
+                prefix_identity_eval(output_cols, &input_cols[col_vars..], *real_width),
+            ),
+            _ => (
+                identity_eval(output_rows, &input_rows[row_vars..]),
+                identity_eval(output_cols, &input_cols[col_vars..]),
+            ),
+        };
+        let eval = col_eval * row_eval * identity_eval(output_channels, input_channels);
+        Ok(match self {
+            // the polynomial selecting the rows and columns which are summed is the sum of the
+            // equality polynomials over all the selected rows and columns
+            Pooling::GlobalAveragePool(global) => {
+                eval * compute_betas_eval(input_rows)[..global.height]
+                    .iter()
+                    .copied()
+                    .sum::<E>()
+                    * compute_betas_eval(input_cols)[..global.width]
+                        .iter()
+                        .copied()
+                        .sum::<E>()
+            }
+            _ => eval,
+        })
+    }
+
     pub fn lookup_witness<E: ExtensionField>(
         &self,
         input: &Tensor<Element>,
@@ -332,6 +581,8 @@ impl Pooling {
 
                 (merged_lookups, field_vecs)
             }
+            // average pooling doesn't employ any lookup table
+            Pooling::AvgPool2D(_) | Pooling::GlobalAveragePool(_) => (vec![], vec![]),
         }
     }
     #[timed::timed_instrument(name = "Prover::prove_pooling_step")]
@@ -351,7 +602,10 @@ impl Pooling {
         E::BaseField: Serialize + DeserializeOwned,
         E: Serialize + DeserializeOwned,
     {
-        assert_eq!(input.get_shape().len(), 3, "Maxpool needs 3D inputs.");
+        let Pooling::Maxpool2D(maxpool) = self else {
+            bail!("prove_pooling called on {}", self.describe());
+        };
+        ensure!(input.get_shape().len() == 3, "Maxpool needs 3D inputs.");
         // Create the range check proof for the diff
         let prover_info = prover.lookup_witness(id)?;
 
@@ -365,6 +619,13 @@ impl Pooling {
                 DenseMultilinearExtension::<E>::from_evaluations_slice(info.num_vars, diff).into()
             })
             .collect::<Vec<ArcMultilinearExtension<E>>>();
+        let num_diffs = maxpool.kernel_size * maxpool.kernel_size;
+        ensure!(
+            diff_polys.len() == num_diffs,
+            "Expected {} diff polys for maxpool, found {}",
+            num_diffs,
+            diff_polys.len()
+        );
 
         // Run the Zerocheck that checks enforces that output does contain the maximum value for the kernel
         let mut vp = VirtualPolynomial::<E>::new(info.num_vars);
@@ -429,65 +690,101 @@ impl Pooling {
             .witness_prover
             .add_claim(info.poly_id, output_claim)
             .context("unable to add claim")?;
-        // Now we must do the samething accumulating evals for the input poly as we fix variables on the input poly.
-        // The point length is 2 longer because for now we only support MaxPool2D.
-
-        let padded_input_shape = input.get_shape();
-        let padded_input_row_length_log = ceil_log2(padded_input_shape[2]);
-        // We can batch all of the claims for the input poly with 00, 10, 01, 11 fixed into one with random challenges
-        let [r1, r2] = [prover
-            .transcript
-            .get_and_append_challenge(b"input_batching")
-            .elements; 2];
-
-        let one_minus_r1 = E::ONE - r1;
-        let one_minus_r2 = E::ONE - r2;
-        // To the input claims we add evaluations at both the zerocheck point and lookup point
-        // in the order 00, 01, 10, 11. These will be used in conjunction with r1 and r2 by the verifier to link the claims output by the sumcheck and lookup GKR
-        // proofs with the claims fed to the same poly verifier.
-
-        let multiplicands = [
-            one_minus_r1 * one_minus_r2,
-            one_minus_r1 * r2,
-            r1 * one_minus_r2,
-            r1 * r2,
-        ];
-
-        let zc_in_claim = izip!(
-            multiplicands.iter(),
-            sumcheck_state.get_mle_final_evaluations().iter(),
-        )
-        .fold(E::ZERO, |zc_acc, (m, zc)| {
-            zc_acc + *m * (output_zerocheck_eval - *zc)
-        });
+        // Now we must reduce the claims about the inputs in each window, `W_ab(z) = output(z) - diff_ab(z)`,
+        // to a single claim about the input poly. Since windows can overlap, we batch them with random
+        // challenges and run a sumcheck over `sum_x input(x) * S(x)`, see `Maxpool2D::input_selector_evals`.
+        let challenges = (
+            prover
+                .transcript
+                .get_and_append_challenge(b"input_batching_rows")
+                .elements,
+            prover
+                .transcript
+                .get_and_append_challenge(b"input_batching_cols")
+                .elements,
+        );
+        let input_shape = input.get_shape();
+        let input_num_vars = input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        let input_mle: ArcMultilinearExtension<E> = input.get_data().to_vec().into_mle().into();
+        let selector_mle: ArcMultilinearExtension<E> =
+            DenseMultilinearExtension::<E>::from_evaluations_ext_vec(
+                input_num_vars,
+                maxpool.input_selector_evals(zerocheck_point, &input_shape, challenges)?,
+            )
+            .into();
+        let mut vp = VirtualPolynomial::<E>::new(input_num_vars);
+        vp.add_mle_list(vec![input_mle, selector_mle], E::ONE);
 
-        let point_1 = [
-            &[r1],
-            &zerocheck_point[..padded_input_row_length_log - 1],
-            &[r2],
-            &zerocheck_point[padded_input_row_length_log - 1..],
-        ]
-        .concat();
-
-        let next_claim = Claim {
-            point: point_1,
-            eval: zc_in_claim,
-        };
+        #[allow(deprecated)]
+        let (input_sumcheck, input_state) =
+            IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        let input_eval = input_state.get_mle_final_evaluations()[0];
+        let next_claim = Claim::new(input_sumcheck.point.clone(), input_eval);
 
         // We don't need the last eval of the the sumcheck state as it is the beta poly
-
-        let zerocheck_evals = sumcheck_state.get_mle_final_evaluations()[..4].to_vec();
+        let zerocheck_evals = sumcheck_state.get_mle_final_evaluations()[..num_diffs].to_vec();
         // Push the step proof to the list
         prover.push_proof(
             id,
-            LayerProof::Pooling(PoolingProof {
+            LayerProof::Pooling(PoolingProof::Max(MaxPoolingProof {
                 sumcheck: proof,
                 lookup: logup_proof,
                 io_accumulation: claim_acc_proof,
                 output_claims,
                 zerocheck_evals,
-                variable_gap: padded_input_row_length_log - 1,
-            }),
+                input_sumcheck,
+                input_eval,
+            })),
+        );
+        Ok(next_claim)
+    }
+
+    #[timed::timed_instrument(name = "Prover::prove_avg_pooling_step")]
+    pub fn prove_avg_pooling<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        prover: &mut Prover<E, T>,
+        // last random claim made
+        last_claim: &Claim<E>,
+        // input to the pooling layer
+        input: &Tensor<E>,
+        info: &PoolingCtx,
+        id: NodeId,
+    ) -> anyhow::Result<Claim<E>>
+    where
+        E::BaseField: Serialize + DeserializeOwned,
+        E: Serialize + DeserializeOwned,
+    {
+        let input_shape = input.get_shape();
+        ensure!(input_shape.len() == 3, "Average pooling needs 3D inputs.");
+        ensure!(
+            last_claim.point.len() == info.num_vars,
+            "Invalid output claim for average pooling layer: expected {} variables, found {}",
+            info.num_vars,
+            last_claim.point.len()
+        );
+        let num_vars = input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        // Sumcheck over the input showing that output(r) = sum_x input(x) * selector(r, x)
+        let input_mle: ArcMultilinearExtension<E> = input.get_data().to_vec().into_mle().into();
+        let selector_mle: ArcMultilinearExtension<E> =
+            DenseMultilinearExtension::<E>::from_evaluations_ext_vec(
+                num_vars,
+                self.selector_evals(&last_claim.point, &input_shape),
+            )
+            .into();
+        let mut vp = VirtualPolynomial::<E>::new(num_vars);
+        vp.add_mle_list(vec![input_mle, selector_mle], E::ONE);
+
+        #[allow(deprecated)]
+        let (proof, sumcheck_state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+
+        let input_eval = sumcheck_state.get_mle_final_evaluations()[0];
+        let next_claim = Claim::new(proof.point.clone(), input_eval);
+        prover.push_proof(
+            id,
+            LayerProof::Pooling(PoolingProof::Avg(AvgPoolingProof {
+                sumcheck: proof,
+                input_eval,
+            })),
         );
         Ok(next_claim)
     }
@@ -495,13 +792,61 @@ impl Pooling {
 
 impl PoolingCtx {
     pub fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
-        maxpool2d_shape(input_shape)
+        self.poolinfo.output_shape(input_shape)
+    }
+
+    pub(crate) fn verify_avg_pooling<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        verifier: &mut Verifier<E, T>,
+        last_claim: &Claim<E>,
+        proof: &AvgPoolingProof<E>,
+        padded_input_shape: &[usize],
+    ) -> anyhow::Result<Claim<E>>
+    where
+        E::BaseField: Serialize + DeserializeOwned,
+        E: Serialize + DeserializeOwned,
+    {
+        ensure!(
+            padded_input_shape.len() == 3,
+            "Average pooling needs 3D inputs."
+        );
+        ensure!(
+            last_claim.point.len() == self.num_vars,
+            "Invalid output claim for average pooling layer: expected {} variables, found {}",
+            self.num_vars,
+            last_claim.point.len()
+        );
+        let num_vars = padded_input_shape
+            .iter()
+            .map(|d| ceil_log2(*d))
+            .sum::<usize>();
+        let poly_aux = VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![num_vars; 2]]);
+        let subclaim = IOPVerifierState::<E>::verify(
+            last_claim.eval,
+            &proof.sumcheck,
+            &poly_aux,
+            verifier.transcript,
+        );
+        let input_point = subclaim
+            .point
+            .iter()
+            .map(|chal| chal.elements)
+            .collect::<Vec<E>>();
+        let selector_eval =
+            self.poolinfo
+                .selector_eval(&last_claim.point, &input_point, padded_input_shape)?;
+        ensure!(
+            proof.input_eval * selector_eval == subclaim.expected_evaluation,
+            "Average pooling sumcheck claim failed"
+        );
+        Ok(Claim::new(input_point, proof.input_eval))
     }
     pub(crate) fn verify_pooling<E: ExtensionField, T: Transcript<E>>(
         &self,
         verifier: &mut Verifier<E, T>,
         last_claim: &Claim<E>,
-        proof: &PoolingProof<E>,
+        proof: &MaxPoolingProof<E>,
+        padded_input_shape: &[usize],
         constant_challenge: E,
         column_separation_challenge: E,
     ) -> anyhow::Result<Claim<E>>
@@ -509,17 +854,28 @@ impl PoolingCtx {
         E::BaseField: Serialize + DeserializeOwned,
         E: Serialize + DeserializeOwned,
     {
+        let Pooling::Maxpool2D(maxpool) = self.poolinfo else {
+            bail!("verify_pooling called on {}", self.poolinfo.describe());
+        };
+        let num_diffs = maxpool.kernel_size * maxpool.kernel_size;
+        ensure!(
+            proof.zerocheck_evals.len() == num_diffs,
+            "Expected {} zerocheck evaluations for maxpool, found {}",
+            num_diffs,
+            proof.zerocheck_evals.len()
+        );
         // 1. Verify the lookup proof
         let verifier_claims = verify_logup_proof(
             &proof.lookup,
-            4,
+            num_diffs,
             constant_challenge,
             column_separation_challenge,
             verifier.transcript,
         )?;
 
         // 2. Verify the sumcheck proof
-        let poly_aux = VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![self.num_vars; 5]]);
+        let poly_aux =
+            VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![self.num_vars; num_diffs + 1]]);
         let batching_challenge = verifier
             .transcript
             .get_and_append_challenge(b"batch_pooling")
@@ -558,42 +914,60 @@ impl PoolingCtx {
             .witness_verifier
             .add_claim(self.poly_id, commit_claim)?;
 
-        // Challenegs used to batch input poly claims together and link them with zerocheck and lookup verification output
-        let [r1, r2] = [verifier
-            .transcript
-            .get_and_append_challenge(b"input_batching")
-            .elements; 2];
-        let one_minus_r1 = E::ONE - r1;
-        let one_minus_r2 = E::ONE - r2;
-
-        let eval_multiplicands = [
-            one_minus_r1 * one_minus_r2,
-            one_minus_r1 * r2,
-            r1 * one_minus_r2,
-            r1 * r2,
-        ];
+        // Challenges used to batch the claims about the inputs in each window together
+        let challenges = (
+            verifier
+                .transcript
+                .get_and_append_challenge(b"input_batching_rows")
+                .elements,
+            verifier
+                .transcript
+                .get_and_append_challenge(b"input_batching_cols")
+                .elements,
+        );
+        let (row_challenge, col_challenge) = challenges;
         let zc_point = subclaim
             .point
             .iter()
             .map(|chal| chal.elements)
             .collect::<Vec<E>>();
-        let zerocheck_point = [
-            &[r1],
-            &zc_point[..proof.variable_gap],
-            &[r2],
-            &zc_point[proof.variable_gap..],
-        ]
-        .concat();
-
-        let zerocheck_input_eval = izip!(proof.zerocheck_evals.iter(), eval_multiplicands.iter())
-            .fold(E::ZERO, |zerocheck_acc, (&ze, &me)| {
-                zerocheck_acc + (output_claims[0].eval - ze) * me
-            });
+        ensure!(
+            output_claims.len() == 1 && output_claims[0].point == zc_point,
+            "Maxpool output claim is not made at the zerocheck point"
+        );
 
-        let out_claim = Claim {
-            point: zerocheck_point,
-            eval: zerocheck_input_eval,
-        };
+        // W_ab(z) = output(z) - diff_ab(z) for the window offset (a, b), batched with the challenges
+        let row_powers = powers(row_challenge, maxpool.kernel_size);
+        let col_powers = powers(col_challenge, maxpool.kernel_size);
+        let windows_eval = row_powers
+            .iter()
+            .cartesian_product(col_powers.iter())
+            .zip(proof.zerocheck_evals.iter())
+            .fold(E::ZERO, |acc, ((row_power, col_power), &ze)| {
+                acc + (output_claims[0].eval - ze) * *row_power * *col_power
+            });
+        let input_num_vars = padded_input_shape
+            .iter()
+            .map(|d| ceil_log2(*d))
+            .sum::<usize>();
+        let input_aux = VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![input_num_vars; 2]]);
+        let input_subclaim = IOPVerifierState::<E>::verify(
+            windows_eval,
+            &proof.input_sumcheck,
+            &input_aux,
+            verifier.transcript,
+        );
+        let input_point = input_subclaim
+            .point
+            .iter()
+            .map(|chal| chal.elements)
+            .collect::<Vec<E>>();
+        let selector_eval =
+            maxpool.input_selector_eval(&zc_point, &input_point, padded_input_shape, challenges)?;
+        ensure!(
+            proof.input_eval * selector_eval == input_subclaim.expected_evaluation,
+            "Maxpool input sumcheck claim failed"
+        );
 
         // Now we check consistency between the lookup/sumcheck proof claims and the claims passed to the same poly verifiers.
         let beta_eval = identity_eval(&output_claims[0].point, &verifier_claims.claims()[0].point);
@@ -616,15 +990,28 @@ impl PoolingCtx {
             "Computed zerocheck claim did not line up with output of sumcheck verification"
         );
 
-        Ok(out_claim)
+        Ok(Claim::new(input_point, proof.input_eval))
     }
 }
 
-/// Information about a maxpool2d step
+/// Information about a maxpool2d step over square windows
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
 pub struct Maxpool2D {
     pub kernel_size: usize,
     pub stride: usize,
+    /// Whether the last two dimensions of the output are padded to the next power of two. This is
+    /// set when padding the model, since the output size of generic kernel and stride is not
+    /// necessarily a power of two even when the input is.
+    #[serde(default)]
+    pub pad_output: bool,
+    /// Height and width of the output over the unpadded input of a padded layer. The padded outputs
+    /// beyond them are zero, even when their windows straddle the last rows or columns of the input.
+    #[serde(default)]
+    pub real_output: Option<(usize, usize)>,
+    /// Bit length of the quantized input, over which the differences with the max of each window
+    /// are range checked
+    #[serde(default = "quantization::default_bit_len")]
+    pub bit_len: usize,
 }
 
 impl Default for Maxpool2D {
@@ -632,129 +1019,400 @@ impl Default for Maxpool2D {
         Maxpool2D {
             kernel_size: MAXPOOL2D_KERNEL_SIZE,
             stride: MAXPOOL2D_KERNEL_SIZE,
+            pad_output: false,
+            real_output: None,
+            bit_len: quantization::default_bit_len(),
         }
     }
 }
 
 impl Maxpool2D {
-    pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
-        assert!(
-            self.kernel_size == MAXPOOL2D_KERNEL_SIZE,
-            "Maxpool2D works only for kernel size {}",
-            MAXPOOL2D_KERNEL_SIZE
-        );
-        assert!(
-            self.stride == MAXPOOL2D_KERNEL_SIZE,
-            "Maxpool2D works only for stride size {}",
-            MAXPOOL2D_KERNEL_SIZE
+    pub fn new(kernel_size: usize, stride: usize) -> Result<Self> {
+        ensure!(
+            kernel_size > 0 && stride > 0,
+            "Maxpool2D requires positive kernel size and stride, found {} and {}",
+            kernel_size,
+            stride
         );
-        input.maxpool2d(self.kernel_size, self.stride)
+        Ok(Self {
+            kernel_size,
+            stride,
+            pad_output: false,
+            real_output: None,
+            bit_len: quantization::default_bit_len(),
+        })
+    }
+
+    /// Returns the same layer, range checking the differences over `bit_len` bits
+    pub fn with_bit_len(self, bit_len: usize) -> Self {
+        Self { bit_len, ..self }
+    }
+
+    /// Returns the same layer, outputting tensors padded to the next power of two
+    pub fn padded(self) -> Self {
+        Self {
+            pad_output: true,
+            ..self
+        }
+    }
+
+    /// Returns the same layer, zeroing the padded outputs beyond the `height x width` outputs computed
+    /// over the unpadded input
+    pub fn with_real_output(self, height: usize, width: usize) -> Self {
+        Self {
+            real_output: Some((height, width)),
+            ..self
+        }
+    }
+
+    /// Returns the same layer, outputting tensors of the natural size
+    pub fn unpadded(self) -> Self {
+        Self {
+            pad_output: false,
+            real_output: None,
+            ..self
+        }
+    }
+
+    /// Returns whether the output at row `i` and column `j` is computed over the unpadded input
+    fn is_real_output(&self, i: usize, j: usize) -> bool {
+        self.real_output
+            .map_or(true, |(height, width)| i < height && j < width)
     }
 
+    pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
+        if !self.pad_output {
+            return input.maxpool2d(self.kernel_size, self.stride);
+        }
+        // The input is extended with zeros so that the windows of all the padded outputs are
+        // within its bounds. The outputs computed over the real input are not affected.
+        let input_shape = input.get_shape();
+        let output_shape = self.output_shape(&input_shape);
+        let dims = input_shape.len();
+        let mut extended_shape = input_shape.clone();
+        extended_shape[dims - 2..]
+            .iter_mut()
+            .zip(&output_shape[dims - 2..])
+            .for_each(|(dim, out_dim)| {
+                *dim = (*dim).max((out_dim - 1) * self.stride + self.kernel_size)
+            });
+        let mut extended = input.clone();
+        extended.pad_to_shape(extended_shape);
+        let output = extended.maxpool2d(self.kernel_size, self.stride);
+        let (out_height, out_width) = (output_shape[dims - 2], output_shape[dims - 1]);
+        let data = output
+            .get_data()
+            .iter()
+            .enumerate()
+            .map(|(idx, out)| {
+                if self.is_real_output((idx / out_width) % out_height, idx % out_width) {
+                    *out
+                } else {
+                    T::default()
+                }
+            })
+            .collect();
+        Tensor::new(output_shape, data)
+    }
+
+    /// Assumes padding=0, and dilation=1
+    /// https://pytorch.org/docs/stable/generated/torch.nn.MaxPool2d.html
     pub fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
-        maxpool2d_shape(input_shape)
+        let dims = input_shape.len();
+        let mut output_shape = input_shape.to_vec();
+        output_shape[dims - 2..].iter_mut().for_each(|dim| {
+            *dim = (*dim - self.kernel_size) / self.stride + 1;
+            if self.pad_output {
+                *dim = dim.next_power_of_two();
+            }
+        });
+        output_shape
     }
 
     /// Computes MLE evaluations related to proving Maxpool function.
-    /// The outputs of this function are the four polynomials corresponding to the input to the Maxpool, each with two variables fixed
-    /// so that PROD (Output - poly_i) == 0 at every evaluation point.
+    /// The outputs of this function are the `kernel_size^2` polynomials `Output - W_ab`, where
+    /// `W_ab(c, i, j) = Input(c, i * stride + a, j * stride + b)` is the input entry at offset `(a, b)`
+    /// in the window of each output, so that PROD_ab (Output - W_ab) == 0 at every evaluation point.
+    /// Entries out of the bounds of the input are zero, as when evaluating the padded layer, and so are the
+    /// windows of the padded outputs beyond [`Self::real_output`].
+    /// The polynomial for offset `(a, b)` is at index `a * kernel_size + b`.
     pub fn compute_polys<E: ExtensionField>(
         &self,
         input: &Tensor<Element>,
     ) -> Vec<Vec<E::BaseField>> {
-        let padded_input = input.pad_next_power_of_two();
+        let output = self.op(input);
+        let input_shape = input.get_shape();
+        let output_shape = output.get_shape();
+        let dims = input_shape.len();
+        let (height, width) = (input_shape[dims - 2], input_shape[dims - 1]);
+        let (out_height, out_width) = (output_shape[dims - 2], output_shape[dims - 1]);
+
+        (0..self.kernel_size)
+            .cartesian_product(0..self.kernel_size)
+            .map(|(a, b)| {
+                output
+                    .get_data()
+                    .par_iter()
+                    .enumerate()
+                    .map(|(idx, out)| {
+                        let n = idx / (out_height * out_width);
+                        let (i, j) = ((idx / out_width) % out_height, idx % out_width);
+                        let (row, col) = (i * self.stride + a, j * self.stride + b);
+                        let value = if row < height && col < width && self.is_real_output(i, j) {
+                            input.get_data()[n * height * width + row * width + col]
+                        } else {
+                            0
+                        };
+                        let diff: E = (out - value).to_field();
+                        diff.as_bases()[0]
+                    })
+                    .collect::<Vec<E::BaseField>>()
+            })
+            .collect()
+    }
 
-        let padded_output = self.op(&input).pad_next_power_of_two();
-        let padded_input_shape = padded_input.get_shape();
+    /// Returns the vector `v` of size `input_dim` such that `v[x]` is the sum of `challenge^a * betas[i]`
+    /// over the first `num_outputs` output indices `i` and window offsets `a` with `i * stride + a = x`.
+    fn window_selector<E: ExtensionField>(
+        &self,
+        betas: &[E],
+        num_outputs: usize,
+        input_dim: usize,
+        challenge: E,
+    ) -> Vec<E> {
+        let powers = powers(challenge, self.kernel_size);
+        let mut selector = vec![E::ZERO; input_dim];
+        betas
+            .iter()
+            .take(num_outputs)
+            .enumerate()
+            .for_each(|(i, beta)| {
+                powers.iter().enumerate().for_each(|(a, power)| {
+                    if let Some(entry) = selector.get_mut(i * self.stride + a) {
+                        *entry += *beta * *power;
+                    }
+                })
+            });
+        selector
+    }
+
+    /// Splits an output point of the layer and returns the row and column selectors of [`Self::window_selector`],
+    /// together with the channel part of the point.
+    fn split_selectors<'a, E: ExtensionField>(
+        &self,
+        output_point: &'a [E],
+        input_shape: &[usize],
+        (row_challenge, col_challenge): (E, E),
+    ) -> Result<(Vec<E>, Vec<E>, &'a [E])> {
+        ensure!(input_shape.len() == 3, "Maxpool needs 3D inputs.");
+        let output_shape = self.output_shape(input_shape);
+        ensure!(
+            output_shape.iter().all(|d| d.is_power_of_two()),
+            "Maxpool output shape {:?} is not padded",
+            output_shape
+        );
+        ensure!(
+            output_point.len() == output_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>(),
+            "Invalid output point length for maxpool layer"
+        );
+        let (output_cols, rest) = output_point.split_at(ceil_log2(output_shape[2]));
+        let (output_rows, output_channels) = rest.split_at(ceil_log2(output_shape[1]));
+        // the windows of the zeroed outputs are not selected
+        let (real_rows, real_cols) = self
+            .real_output
+            .unwrap_or((output_shape[1], output_shape[2]));
+        let row_selector = self.window_selector(
+            &compute_betas_eval(output_rows),
+            real_rows,
+            input_shape[1],
+            row_challenge,
+        );
+        let col_selector = self.window_selector(
+            &compute_betas_eval(output_cols),
+            real_cols,
+            input_shape[2],
+            col_challenge,
+        );
+        Ok((row_selector, col_selector, output_channels))
+    }
 
-        let new_fixed = (0..padded_input_shape[2] << 1)
+    /// Computes the evaluations of the polynomial `S` such that
+    /// `sum_ab row_challenge^a * col_challenge^b * W_ab(r) = sum_x input(x) * S(x)`, where `r` is `output_point`
+    /// and `W_ab` are the polynomials of [`Self::compute_polys`].
+    fn input_selector_evals<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_shape: &[usize],
+        challenges: (E, E),
+    ) -> Result<Vec<E>> {
+        let (row_selector, col_selector, output_channels) =
+            self.split_selectors(output_point, input_shape, challenges)?;
+        let betas_channel = compute_betas_eval(output_channels);
+        let (height, width) = (input_shape[1], input_shape[2]);
+        Ok((0..input_shape.iter().product::<usize>())
             .into_par_iter()
             .map(|i| {
-                padded_input
-                    .get_data()
-                    .iter()
-                    .skip(i)
-                    .step_by(padded_input_shape[2] << 1)
-                    .copied()
-                    .collect::<Vec<Element>>()
+                let (c, row, col) = (i / (height * width), (i / width) % height, i % width);
+                betas_channel[c] * row_selector[row] * col_selector[col]
             })
-            .collect::<Vec<Vec<Element>>>();
+            .collect())
+    }
 
-        let new_even = new_fixed
-            .iter()
-            .step_by(2)
-            .cloned()
-            .collect::<Vec<Vec<Element>>>();
+    /// Evaluates the selector polynomial of [`Self::input_selector_evals`] at `input_point`, without
+    /// iterating over the whole input domain
+    fn input_selector_eval<E: ExtensionField>(
+        &self,
+        output_point: &[E],
+        input_point: &[E],
+        input_shape: &[usize],
+        challenges: (E, E),
+    ) -> Result<E> {
+        let (row_selector, col_selector, output_channels) =
+            self.split_selectors(output_point, input_shape, challenges)?;
+        ensure!(
+            input_point.len() == input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>(),
+            "Invalid input point length for maxpool layer"
+        );
+        let (input_cols, rest) = input_point.split_at(ceil_log2(input_shape[2]));
+        let (input_rows, input_channels) = rest.split_at(ceil_log2(input_shape[1]));
+        let row_eval = izip!(&row_selector, compute_betas_eval(input_rows))
+            .fold(E::ZERO, |acc, (s, beta)| acc + *s * beta);
+        let col_eval = izip!(&col_selector, compute_betas_eval(input_cols))
+            .fold(E::ZERO, |acc, (s, beta)| acc + *s * beta);
+        Ok(identity_eval(output_channels, input_channels) * row_eval * col_eval)
+    }
+}
+
+/// Information about an average pooling step over square windows
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
+pub struct AvgPool2D {
+    pub kernel_size: usize,
+    pub stride: usize,
+    /// Height and width of the output over the unpadded input of a padded layer. The padded outputs
+    /// beyond them are zero, even when their windows contain the last rows or columns of the input.
+    #[serde(default)]
+    pub real_output: Option<(usize, usize)>,
+}
+
+impl Default for AvgPool2D {
+    fn default() -> Self {
+        AvgPool2D {
+            kernel_size: AVGPOOL2D_KERNEL_SIZE,
+            stride: AVGPOOL2D_KERNEL_SIZE,
+            real_output: None,
+        }
+    }
+}
+
+impl AvgPool2D {
+    pub fn new(kernel_size: usize, stride: usize) -> Result<Self> {
+        // non overlapping windows of power of two size keep the padded input and output shapes aligned
+        ensure!(
+            kernel_size == stride,
+            "AvgPool2D works only for kernel size equal to stride, found {} and {}",
+            kernel_size,
+            stride
+        );
+        ensure!(
+            kernel_size.is_power_of_two(),
+            "AvgPool2D works only for power of two kernel size, found {}",
+            kernel_size
+        );
+        Ok(Self {
+            kernel_size,
+            stride,
+            real_output: None,
+        })
+    }
 
-        let new_odd = new_fixed
+    /// Returns the same layer, zeroing the padded outputs beyond the `height x width` outputs computed
+    /// over the unpadded input
+    pub fn with_real_output(self, height: usize, width: usize) -> Self {
+        Self {
+            real_output: Some((height, width)),
+            ..self
+        }
+    }
+
+    /// Sums the inputs of each window, the division is left to the caller
+    pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
+        let output = input.sum_pool2d(self.kernel_size, self.stride);
+        let Some((height, width)) = self.real_output else {
+            return output;
+        };
+        let output_shape = output.get_shape();
+        let dims = output_shape.len();
+        let (out_height, out_width) = (output_shape[dims - 2], output_shape[dims - 1]);
+        let data = output
+            .get_data()
             .iter()
-            .skip(1)
-            .step_by(2)
-            .cloned()
-            .collect::<Vec<Vec<Element>>>();
-
-        let (even_diff, odd_diff): (Vec<Vec<E::BaseField>>, Vec<Vec<E::BaseField>>) = new_even
-            .par_chunks(padded_input_shape[2] >> 1)
-            .zip(new_odd.par_chunks(padded_input_shape[2] >> 1))
-            .map(|(even_chunk, odd_chunk)| {
-                let mut even_merged = even_chunk.to_vec();
-                let mut odd_merged = odd_chunk.to_vec();
-                for i in (0..ceil_log2(padded_input_shape[2]) - 1).rev() {
-                    let mid_point = 1 << i;
-                    let (even_low, even_high) = even_merged.split_at(mid_point);
-                    let (odd_low, odd_high) = odd_merged.split_at(mid_point);
-                    even_merged = even_low
-                        .iter()
-                        .zip(even_high.iter())
-                        .map(|(l, h)| {
-                            l.iter()
-                                .interleave(h.iter())
-                                .copied()
-                                .collect::<Vec<Element>>()
-                        })
-                        .collect::<Vec<Vec<Element>>>();
-                    odd_merged = odd_low
-                        .iter()
-                        .zip(odd_high.iter())
-                        .map(|(l, h)| {
-                            l.iter()
-                                .interleave(h.iter())
-                                .copied()
-                                .collect::<Vec<Element>>()
-                        })
-                        .collect::<Vec<Vec<Element>>>();
+            .enumerate()
+            .map(|(idx, out)| {
+                if (idx / out_width) % out_height < height && idx % out_width < width {
+                    *out
+                } else {
+                    T::default()
                 }
-
-                izip!(
-                    even_merged[0].iter(),
-                    odd_merged[0].iter(),
-                    padded_output.get_data()
-                )
-                .map(|(e, o, data)| {
-                    let e_field: E = (data - e).to_field();
-                    let o_field: E = (data - o).to_field();
-                    (e_field.as_bases()[0], o_field.as_bases()[0])
-                })
-                .unzip::<_, _, Vec<E::BaseField>, Vec<E::BaseField>>()
             })
-            .unzip();
+            .collect();
+        Tensor::new(output_shape, data)
+    }
 
-        [even_diff, odd_diff].concat()
+    pub fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
+        let dims = input_shape.len();
+        let mut output_shape = input_shape.to_vec();
+        output_shape[dims - 2..]
+            .iter_mut()
+            .for_each(|dim| *dim = (*dim - self.kernel_size) / self.stride + 1);
+        output_shape
     }
 }
 
-/// Assumes kernel=2, stride=2, padding=0, and dilation=1
-/// https://pytorch.org/docs/stable/generated/torch.nn.MaxPool2d.html
-pub fn maxpool2d_shape(input_shape: &[usize]) -> Vec<usize> {
-    let stride = 2usize;
-    let padding = 0usize;
-    let kernel = 2usize;
-    let dilation = 1usize;
+/// Information about a global average pooling step, which averages each channel of the input
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
+pub struct GlobalAveragePool {
+    /// Height of the (unpadded) input
+    pub height: usize,
+    /// Width of the (unpadded) input
+    pub width: usize,
+}
+
+impl GlobalAveragePool {
+    pub fn new(height: usize, width: usize) -> Self {
+        Self { height, width }
+    }
+
+    /// Sums the inputs of each channel, ignoring padded rows and columns. The division is left to the caller
+    pub fn op<T: Number>(&self, input: &Tensor<T>) -> Tensor<T> {
+        input.spatial_sum(self.height, self.width)
+    }
 
-    let d1 = input_shape[0];
-    let d2 = (input_shape[1] + 2 * padding - dilation * (kernel - 1) - 1) / stride + 1;
+    pub fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
+        let dims = input_shape.len();
+        let mut output_shape = input_shape.to_vec();
+        output_shape[dims - 2..].iter_mut().for_each(|dim| *dim = 1);
+        output_shape
+    }
+}
+
+/// Evaluates the equality polynomial between `a` and `b` restricted to the first `n` points of the
+/// hypercube, i.e. `sum_{x < n} eq(a, x) * eq(b, x)`
+fn prefix_identity_eval<E: ExtensionField>(a: &[E], b: &[E], n: usize) -> E {
+    izip!(compute_betas_eval(a), compute_betas_eval(b))
+        .take(n)
+        .fold(E::ZERO, |acc, (x, y)| acc + x * y)
+}
 
-    vec![d1, d2, d2]
+/// Returns the first `n` powers of `challenge`, starting from 1
+fn powers<E: ExtensionField>(challenge: E, n: usize) -> Vec<E> {
+    std::iter::successors(Some(E::ONE), |power| Some(*power * challenge))
+        .take(n)
+        .collect()
+}
+
+/// Output shape of the default maxpool layer, with kernel=2 and stride=2
+pub fn maxpool2d_shape(input_shape: &[usize]) -> Vec<usize> {
+    Maxpool2D::default().output_shape(input_shape)
 }
 #[cfg(test)]
 mod tests {
@@ -794,10 +1452,7 @@ mod tests {
                 .collect::<Vec<Element>>();
             let input = Tensor::<Element>::new(random_shape.clone(), data);
 
-            let info = Maxpool2D {
-                kernel_size: MAXPOOL2D_KERNEL_SIZE,
-                stride: MAXPOOL2D_KERNEL_SIZE,
-            };
+            let info = Maxpool2D::default();
 
             let output = info.op(&input);
 
@@ -1028,4 +1683,175 @@ mod tests {
             assert_eq!(mle_eval, maybe_eval);
         }
     }
+
+    /// Checks that each output of `maxpool` over `input` is the maximum of its window, and that the
+    /// batched window evaluations are the sum of the input weighted by the selector
+    fn assert_max_pool_polys(maxpool: &Maxpool2D, input: &Tensor<Element>) {
+        let mut rng = thread_rng();
+        let input_shape = input.get_shape();
+        let num_vars = input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        let input_evals = input
+            .get_data()
+            .iter()
+            .map(Fieldizer::<F>::to_field)
+            .collect_vec();
+        let kernel_size = maxpool.kernel_size;
+        let output = maxpool.op(input);
+        let output_shape = output.get_shape();
+
+        // each output is the maximum of its window
+        let diffs = maxpool.compute_polys::<F>(input);
+        assert_eq!(diffs.len(), kernel_size * kernel_size);
+        (0..output.get_data().len()).for_each(|i| {
+            let values = diffs
+                .iter()
+                .map(|diff| F::from(diff[i]).into_element())
+                .collect_vec();
+            assert!(values.iter().all(|v| *v >= 0));
+            assert!(values.contains(&0));
+        });
+
+        // the batched window evaluations are the sum of the input weighted by the selector
+        let output_num_vars = output_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        let output_point = (0..output_num_vars)
+            .map(|_| F::random(&mut rng))
+            .collect_vec();
+        let challenges = (F::random(&mut rng), F::random(&mut rng));
+        let output_eval = output
+            .get_data()
+            .iter()
+            .map(Fieldizer::<F>::to_field)
+            .collect_vec()
+            .into_mle()
+            .evaluate(&output_point);
+        let row_powers = powers(challenges.0, kernel_size);
+        let col_powers = powers(challenges.1, kernel_size);
+        let windows_eval = row_powers
+            .iter()
+            .cartesian_product(col_powers.iter())
+            .zip(diffs)
+            .fold(F::ZERO, |acc, ((row_power, col_power), diff)| {
+                let diff_eval =
+                    DenseMultilinearExtension::<F>::from_evaluations_vec(output_num_vars, diff)
+                        .evaluate(&output_point);
+                acc + (output_eval - diff_eval) * *row_power * *col_power
+            });
+        let selector = maxpool
+            .input_selector_evals(&output_point, &input_shape, challenges)
+            .unwrap();
+        let sum = izip!(&input_evals, &selector).fold(F::ZERO, |acc, (i, s)| acc + *i * *s);
+        assert_eq!(sum, windows_eval);
+
+        // the verifier evaluates the selector without iterating over the input domain
+        let input_point = (0..num_vars).map(|_| F::random(&mut rng)).collect_vec();
+        let expected = DenseMultilinearExtension::<F>::from_evaluations_ext_vec(num_vars, selector)
+            .evaluate(&input_point);
+        assert_eq!(
+            maxpool
+                .input_selector_eval(&output_point, &input_point, &input_shape, challenges)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_max_pool_overlapping_windows() {
+        let input_shape = vec![4usize, 16, 32];
+        let input = Tensor::<Element>::random(&input_shape);
+        for (kernel_size, stride) in [(2, 2), (3, 2), (3, 1), (3, 3)] {
+            let maxpool = Maxpool2D::new(kernel_size, stride).unwrap().padded();
+            let output = maxpool.op(&input);
+            let output_shape = output.get_shape();
+            assert_eq!(output_shape, maxpool.output_shape(&input_shape));
+            assert!(output_shape.iter().all(|d| d.is_power_of_two()));
+            // the padding doesn't change the outputs computed over the real input
+            let unpadded_output = maxpool.unpadded().op(&input);
+            let unpadded_shape = unpadded_output.get_shape();
+            let mut expected = unpadded_output.clone();
+            expected.pad_to_shape(output_shape.clone());
+            (0..output.get_data().len())
+                .filter(|i| {
+                    (i / output_shape[2]) % output_shape[1] < unpadded_shape[1]
+                        && i % output_shape[2] < unpadded_shape[2]
+                })
+                .for_each(|i| assert_eq!(output.get_data()[i], expected.get_data()[i]));
+
+            assert_max_pool_polys(&maxpool, &input);
+        }
+    }
+
+    #[test]
+    fn test_max_pool_zeroes_padded_outputs() {
+        // the 13x13 output of 3x3 windows with stride 2 over a 27x27 input is padded to 16x16, and
+        // the windows of the outputs in row and column 13 straddle the last row and column of the input
+        let real_input = Tensor::<Element>::random(&vec![4, 27, 27]);
+        let mut input = real_input.clone();
+        input.pad_to_shape(vec![4, 32, 32]);
+        let maxpool = Maxpool2D::new(3, 2).unwrap().padded();
+        let mut expected = maxpool.unpadded().op(&real_input);
+        assert_eq!(expected.get_shape(), vec![4, 13, 13]);
+        expected.pad_to_shape(vec![4, 16, 16]);
+        assert_ne!(maxpool.op(&input).get_data(), expected.get_data());
+
+        let maxpool = maxpool.with_real_output(13, 13);
+        let output = maxpool.op(&input);
+        assert_eq!(output.get_shape(), vec![4, 16, 16]);
+        assert_eq!(output.get_data(), expected.get_data());
+        assert_max_pool_polys(&maxpool, &input);
+    }
+
+    #[test]
+    fn test_avg_pool_selector() {
+        let mut rng = thread_rng();
+        let input_shape = vec![4usize, 16, 8];
+        let num_vars = input_shape.iter().map(|d| ceil_log2(*d)).sum::<usize>();
+        let input = Tensor::<Element>::random(&input_shape);
+        let input_evals = input
+            .get_data()
+            .iter()
+            .map(Fieldizer::<F>::to_field)
+            .collect_vec();
+        for pooling in [
+            Pooling::AvgPool2D(AvgPool2D::default()),
+            Pooling::AvgPool2D(AvgPool2D::new(4, 4).unwrap()),
+            // a 15 x 7 input leaves its last row and column out of the windows
+            Pooling::AvgPool2D(AvgPool2D::default().with_real_output(7, 3)),
+            Pooling::GlobalAveragePool(GlobalAveragePool::new(13, 7)),
+        ] {
+            let output = pooling.op(&input);
+            assert_eq!(output.get_shape(), pooling.output_shape(&input_shape));
+            let output_num_vars = output
+                .get_shape()
+                .iter()
+                .map(|d| ceil_log2(*d))
+                .sum::<usize>();
+            let output_point = (0..output_num_vars)
+                .map(|_| F::random(&mut rng))
+                .collect_vec();
+            let output_eval = output
+                .get_data()
+                .iter()
+                .map(Fieldizer::<F>::to_field)
+                .collect_vec()
+                .into_mle()
+                .evaluate(&output_point);
+
+            // the output evaluation is the sum of the input weighted by the selector
+            let selector = pooling.selector_evals(&output_point, &input_shape);
+            let sum = izip!(&input_evals, &selector).fold(F::ZERO, |acc, (i, s)| acc + *i * *s);
+            assert_eq!(sum, output_eval);
+
+            // the verifier evaluates the selector without iterating over the input domain
+            let input_point = (0..num_vars).map(|_| F::random(&mut rng)).collect_vec();
+            let expected =
+                DenseMultilinearExtension::<F>::from_evaluations_ext_vec(num_vars, selector)
+                    .evaluate(&input_point);
+            assert_eq!(
+                pooling
+                    .selector_eval(&output_point, &input_point, &input_shape)
+                    .unwrap(),
+                expected
+            );
+        }
+    }
 }
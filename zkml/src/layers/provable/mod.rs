@@ -20,7 +20,9 @@ use crate::{
     tensor::{ConvData, Number},
 };
 
-use super::{Layer, LayerCtx, LayerProof, flatten::Flatten, requant::Requant};
+use super::{
+    Layer, LayerCtx, LayerProof, flatten::Flatten, requant::Requant, rescale::Rescale,
+};
 
 pub(crate) type NodeId = usize;
 
@@ -155,60 +157,66 @@ where
     /// Get the claims corresponding to the output edges of a node.
     /// Requires the input claims for the nodes of the model using the
     /// outputs of the current node, and the claims of the output
-    /// tensors of the model
+    /// tensors of the model.
+    /// Returns, for each output wire of the node, the claims found for
+    /// all the edges of the wire: if an output is used as input in
+    /// different nodes, there will be more than one claim about the same
+    /// polynomial, which needs to be batched before proving the node
     pub(crate) fn claims_for_node<'a, 'b>(
         &self,
         claims_by_node: &'a HashMap<NodeId, Vec<Claim<E>>>,
         output_claims: &'b [Claim<E>],
-    ) -> Result<Vec<&'a Claim<E>>>
+    ) -> Result<Vec<Vec<&'a Claim<E>>>>
     where
         'b: 'a,
     {
         self.outputs.iter().map(|out| {
-            // For now, we support in proving only one edge per output wire,
-            // as if an output is used as input in different nodes, we need
-            // to batch claims about the same polynomial. ToDo: batch claims
-            assert_eq!(out.edges.len(), 1);
-            let edge = &out.edges[0];
-            Ok(if let Some(id) = &edge.node {
-                let claims_for_node = claims_by_node.get(id).ok_or(
-                    anyhow!("No claims found for layer {}", id)
-                )?;
-                ensure!(edge.index < claims_for_node.len(),
-                    "Not enough claims found for node {}: required claim for input {}, but {} claims found",
-                    id,
-                    edge.index,
-                    claims_for_node.len()
-                );
-                &claims_for_node[edge.index]
-            } else {
-                // it's an output node, so we use directly the claim for the corresponding output
-                ensure!(edge.index < output_claims.len(),
-                 "Required claim for output {} of the model, but only {} output claims found",
-                 edge.index,
-                 output_claims.len(),
-                );
-                &output_claims[edge.index]
-            })
+            ensure!(!out.edges.is_empty(), "Found output wire with no edges");
+            out.edges.iter().map(|edge| {
+                Ok(if let Some(id) = &edge.node {
+                    let claims_for_node = claims_by_node.get(id).ok_or(
+                        anyhow!("No claims found for layer {}", id)
+                    )?;
+                    ensure!(edge.index < claims_for_node.len(),
+                        "Not enough claims found for node {}: required claim for input {}, but {} claims found",
+                        id,
+                        edge.index,
+                        claims_for_node.len()
+                    );
+                    &claims_for_node[edge.index]
+                } else {
+                    // it's an output node, so we use directly the claim for the corresponding output
+                    ensure!(edge.index < output_claims.len(),
+                     "Required claim for output {} of the model, but only {} output claims found",
+                     edge.index,
+                     output_claims.len(),
+                    );
+                    &output_claims[edge.index]
+                })
+            }).collect()
         }).collect()
     }
 
     /// Get the claims corresponding to the input tensors of the model.
     /// Requires as inputs the contexts for all the nodes in the model
     /// and the set of claims for the input tensors of all the nodes of
-    /// the model
+    /// the model. Returns, for each input tensor of the model, the claims
+    /// produced by all the nodes using such input tensor
     pub(crate) fn input_claims<'a, I: Iterator<Item = (&'a NodeId, &'a Self)>>(
         nodes: I,
         claims_by_node: &HashMap<NodeId, Vec<Claim<E>>>,
-    ) -> Result<Vec<&Claim<E>>> {
-        let mut claims = BTreeMap::new();
+    ) -> Result<Vec<Vec<&Claim<E>>>> {
+        let mut claims: BTreeMap<usize, Vec<&Claim<E>>> = BTreeMap::new();
         for (node_id, ctx) in nodes {
             for (i, edge) in ctx.inputs.iter().enumerate() {
                 if edge.node.is_none() {
                     let claims_for_node = claims_by_node
                         .get(node_id)
                         .ok_or(anyhow!("Claim not found for node {}", node_id))?;
-                    claims.insert(edge.index, &claims_for_node[i]);
+                    claims
+                        .entry(edge.index)
+                        .or_default()
+                        .push(&claims_for_node[i]);
                 }
             }
         }
@@ -223,7 +231,7 @@ where
             "Not all input claims were found"
         );
 
-        Ok(claims.into_iter().map(|(_, claim)| claim).collect())
+        Ok(claims.into_iter().map(|(_, claims)| claims).collect())
     }
 }
 
@@ -289,6 +297,21 @@ pub struct QuantizeOutput<Op> {
     pub(crate) output_scalings: Vec<ScalingFactor>,
     /// The requant layer to be added to the model, if any
     pub(crate) requant_layer: Option<Requant>,
+    /// The layer rescaling each output channel, to be added to the model before the requant layer,
+    /// when the channels are quantized with different scaling factors
+    pub(crate) rescale_layer: Option<Rescale>,
+}
+
+/// How a layer deals with the zero point of the scaling factors of its inputs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ZeroPointSupport {
+    /// The inputs must be quantized symmetrically, i.e. with a zero point equal to zero
+    Unsupported,
+    /// The zero point is taken into account by the layer, e.g. in the bias of a dense layer, and
+    /// does not affect the outputs
+    Absorbed,
+    /// The outputs are quantized with the scaling factors of the inputs, zero point included
+    Forwarded,
 }
 
 pub trait QuantizeOp {
@@ -386,10 +409,24 @@ where
                 activation_ctx.output_shapes(input_shapes, padding_mode)
             }
             LayerCtx::Requant(requant_ctx) => requant_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Rescale(rescale_ctx) => rescale_ctx.output_shapes(input_shapes, padding_mode),
             LayerCtx::Pooling(pooling_ctx) => pooling_ctx.output_shapes(input_shapes, padding_mode),
             LayerCtx::Flatten => {
                 <Flatten as OpInfo>::output_shapes(&Flatten, input_shapes, padding_mode)
             }
+            LayerCtx::Reshape(reshape) => reshape.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Transpose(transpose) => transpose.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Concat(concat) => concat.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Split(split) => split.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Add(add_ctx) => add_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::MatMul(matmul_ctx) => matmul_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Softmax(softmax_ctx) => softmax_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::TopK(topk_ctx) => topk_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Normalization(norm_ctx) => norm_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Affine(affine_ctx) => affine_ctx.output_shapes(input_shapes, padding_mode),
+            LayerCtx::Embedding(embedding_ctx) => {
+                embedding_ctx.output_shapes(input_shapes, padding_mode)
+            }
             _ => unreachable!(),
         }
     }
@@ -400,8 +437,20 @@ where
             LayerCtx::Convolution(conv_ctx) => conv_ctx.num_outputs(num_inputs),
             LayerCtx::Activation(activation_ctx) => activation_ctx.num_outputs(num_inputs),
             LayerCtx::Requant(requant_ctx) => requant_ctx.num_outputs(num_inputs),
+            LayerCtx::Rescale(rescale_ctx) => rescale_ctx.num_outputs(num_inputs),
             LayerCtx::Pooling(pooling_ctx) => pooling_ctx.num_outputs(num_inputs),
             LayerCtx::Flatten => <Flatten as OpInfo>::num_outputs(&Flatten, num_inputs),
+            LayerCtx::Reshape(reshape) => reshape.num_outputs(num_inputs),
+            LayerCtx::Transpose(transpose) => transpose.num_outputs(num_inputs),
+            LayerCtx::Concat(concat) => concat.num_outputs(num_inputs),
+            LayerCtx::Split(split) => split.num_outputs(num_inputs),
+            LayerCtx::Add(add_ctx) => add_ctx.num_outputs(num_inputs),
+            LayerCtx::MatMul(matmul_ctx) => matmul_ctx.num_outputs(num_inputs),
+            LayerCtx::Softmax(softmax_ctx) => softmax_ctx.num_outputs(num_inputs),
+            LayerCtx::TopK(topk_ctx) => topk_ctx.num_outputs(num_inputs),
+            LayerCtx::Normalization(norm_ctx) => norm_ctx.num_outputs(num_inputs),
+            LayerCtx::Affine(affine_ctx) => affine_ctx.num_outputs(num_inputs),
+            LayerCtx::Embedding(embedding_ctx) => embedding_ctx.num_outputs(num_inputs),
             _ => unreachable!(),
         }
     }
@@ -412,8 +461,20 @@ where
             LayerCtx::Convolution(conv_ctx) => conv_ctx.describe(),
             LayerCtx::Activation(activation_ctx) => activation_ctx.describe(),
             LayerCtx::Requant(requant_ctx) => requant_ctx.describe(),
+            LayerCtx::Rescale(rescale_ctx) => rescale_ctx.describe(),
             LayerCtx::Pooling(pooling_ctx) => pooling_ctx.describe(),
             LayerCtx::Flatten => Flatten.describe(),
+            LayerCtx::Reshape(reshape) => reshape.describe(),
+            LayerCtx::Transpose(transpose) => transpose.describe(),
+            LayerCtx::Concat(concat) => concat.describe(),
+            LayerCtx::Split(split) => split.describe(),
+            LayerCtx::Add(add_ctx) => add_ctx.describe(),
+            LayerCtx::MatMul(matmul_ctx) => matmul_ctx.describe(),
+            LayerCtx::Softmax(softmax_ctx) => softmax_ctx.describe(),
+            LayerCtx::TopK(topk_ctx) => topk_ctx.describe(),
+            LayerCtx::Normalization(norm_ctx) => norm_ctx.describe(),
+            LayerCtx::Affine(affine_ctx) => affine_ctx.describe(),
+            LayerCtx::Embedding(embedding_ctx) => embedding_ctx.describe(),
             _ => unreachable!(),
         }
     }
@@ -424,8 +485,20 @@ where
             LayerCtx::Convolution(conv_ctx) => conv_ctx.is_provable(),
             LayerCtx::Activation(activation_ctx) => activation_ctx.is_provable(),
             LayerCtx::Requant(requant_ctx) => requant_ctx.is_provable(),
+            LayerCtx::Rescale(rescale_ctx) => rescale_ctx.is_provable(),
             LayerCtx::Pooling(pooling_ctx) => pooling_ctx.is_provable(),
             LayerCtx::Flatten => Flatten.is_provable(),
+            LayerCtx::Reshape(reshape) => reshape.is_provable(),
+            LayerCtx::Transpose(transpose) => transpose.is_provable(),
+            LayerCtx::Concat(concat) => concat.is_provable(),
+            LayerCtx::Split(split) => split.is_provable(),
+            LayerCtx::Add(add_ctx) => add_ctx.is_provable(),
+            LayerCtx::MatMul(matmul_ctx) => matmul_ctx.is_provable(),
+            LayerCtx::Softmax(softmax_ctx) => softmax_ctx.is_provable(),
+            LayerCtx::TopK(topk_ctx) => topk_ctx.is_provable(),
+            LayerCtx::Normalization(norm_ctx) => norm_ctx.is_provable(),
+            LayerCtx::Affine(affine_ctx) => affine_ctx.is_provable(),
+            LayerCtx::Embedding(embedding_ctx) => embedding_ctx.is_provable(),
             _ => unreachable!(),
         }
     }
@@ -474,6 +547,13 @@ where
                     bail!("requant proof not found when verifying requantization layer")
                 }
             }
+            LayerCtx::Rescale(rescale_ctx) => {
+                if let LayerProof::Rescale(proof) = proof {
+                    rescale_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("rescale proof not found when verifying rescale layer")
+                }
+            }
             LayerCtx::Pooling(pooling_ctx) => {
                 if let LayerProof::Pooling(proof) = proof {
                     pooling_ctx.verify(proof, last_claims, verifier, shape_step)
@@ -481,6 +561,77 @@ where
                     bail!("pooling proof not found when verifying pooling layer")
                 }
             }
+            LayerCtx::Add(add_ctx) => {
+                if let LayerProof::Add(proof) = proof {
+                    add_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("add proof not found when verifying add layer")
+                }
+            }
+            LayerCtx::Concat(concat) => {
+                if let LayerProof::Concat(proof) = proof {
+                    concat.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("concat proof not found when verifying concat layer")
+                }
+            }
+            LayerCtx::Split(split) => {
+                if let LayerProof::Split(proof) = proof {
+                    split.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("split proof not found when verifying split layer")
+                }
+            }
+            LayerCtx::MatMul(matmul_ctx) => {
+                if let LayerProof::MatMul(proof) = proof {
+                    matmul_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("matmul proof not found when verifying matmul layer")
+                }
+            }
+            LayerCtx::Softmax(softmax_ctx) => {
+                if let LayerProof::Softmax(proof) = proof {
+                    softmax_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("softmax proof not found when verifying softmax layer")
+                }
+            }
+            LayerCtx::TopK(topk_ctx) => {
+                if let LayerProof::TopK(proof) = proof {
+                    topk_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("top-k proof not found when verifying top-k layer")
+                }
+            }
+            LayerCtx::Normalization(norm_ctx) => {
+                if let LayerProof::Normalization(proof) = proof {
+                    norm_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("normalization proof not found when verifying normalization layer")
+                }
+            }
+            LayerCtx::Affine(affine_ctx) => {
+                if let LayerProof::Affine(proof) = proof {
+                    affine_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("affine proof not found when verifying affine layer")
+                }
+            }
+            LayerCtx::Embedding(embedding_ctx) => {
+                if let LayerProof::Embedding(proof) = proof {
+                    embedding_ctx.verify(proof, last_claims, verifier, shape_step)
+                } else {
+                    bail!("embedding proof not found when verifying embedding layer")
+                }
+            }
+            LayerCtx::Transpose(transpose) => {
+                // the output claim is only moved to the input, there is nothing else to verify
+                if let LayerProof::Dummy = proof {
+                    transpose.verify(&(), last_claims, verifier, shape_step)
+                } else {
+                    bail!("unexpected proof found when verifying transpose layer")
+                }
+            }
             _ => unreachable!("Trying to verify a non-provable layer"),
         }
     }
@@ -36,6 +36,7 @@ use crate::{
 use super::{
     LayerCtx,
     provable::{Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx},
+    rescale::MULTIPLIER_BITS,
 };
 
 enum RequantResult {
@@ -58,9 +59,14 @@ pub struct Requant {
     pub range: usize,
     /// The range we want the values to be in post requantizing
     pub after_range: usize,
-    /// TEST ONLY: this can be given to simulate a perfect requantization during inference. Note that it CAN NOT
-    /// be proven currently.
-    pub multiplier: Option<f32>,
+    /// Integer multiplier applied to the values before the shift, so that the values are requantized by
+    /// the fixed-point multiplier `multiplier / 2^right_shift`. It is 1 for a requantization by a
+    /// power of two.
+    #[serde(default = "default_multiplier")]
+    pub multiplier: Element,
+    /// Zero point of the scaling factor of the output, added to the values after the shift
+    #[serde(default)]
+    pub zero_point: Element,
 }
 
 /// Info related to the lookup protocol necessary to requantize
@@ -85,6 +91,10 @@ where
 
 const IS_PROVABLE: bool = true;
 
+fn default_multiplier() -> Element {
+    1
+}
+
 impl OpInfo for Requant {
     fn output_shapes(
         &self,
@@ -100,9 +110,11 @@ impl OpInfo for Requant {
 
     fn describe(&self) -> String {
         format!(
-            "Requant: shift: {}, offset: 2^{}",
+            "Requant: multiplier: {}, shift: {}, offset: 2^{}, zero point: {}",
+            self.multiplier,
             self.right_shift,
             (self.range << 1).ilog2() as usize,
+            self.zero_point,
         )
     }
 
@@ -132,7 +144,7 @@ where
     E::BaseField: Serialize + DeserializeOwned,
 {
     fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
-        aux.tables.insert(TableType::Range);
+        aux.tables.insert(self.table_type());
         let num_vars = aux
             .last_output_shape
             .iter_mut()
@@ -200,10 +212,11 @@ where
             "Found more than 1 output in inference step of requant layer"
         );
 
-        gen.tables.insert(TableType::Range);
+        let table_type = self.table_type();
+        gen.tables.insert(table_type);
         let table_lookup_map = gen
             .lookups
-            .entry(TableType::Range)
+            .entry(table_type)
             .or_insert_with(|| HashMap::default());
 
         let (merged_lookups, column_evals) =
@@ -222,7 +235,7 @@ where
         ));
 
         gen.lookups_no_challenges
-            .insert(id, (column_evals, 1, TableType::Range));
+            .insert(id, vec![(column_evals, 1, table_type)]);
 
         Ok(())
     }
@@ -243,9 +256,11 @@ impl OpInfo for RequantCtx {
 
     fn describe(&self) -> String {
         format!(
-            "Requant ctx: shift: {}, offset: 2^{}",
+            "Requant ctx: multiplier: {}, shift: {}, offset: 2^{}, zero point: {}",
+            self.requant.multiplier,
             self.requant.right_shift,
             (self.requant.range << 1).ilog2() as usize,
+            self.requant.zero_point,
         )
     }
 
@@ -269,14 +284,15 @@ where
         verifier: &mut Verifier<E, T>,
         _shape_step: &ShapeStep,
     ) -> Result<Vec<Claim<E>>> {
+        let table_type = self.requant.table_type();
         let (constant_challenge, column_separation_challenge) = verifier
             .challenge_storage
             .as_ref()
             .unwrap()
-            .get_challenges_by_name(&TableType::Range.name())
+            .get_challenges_by_name(&table_type.name())
             .ok_or(anyhow!(
                 "Couldn't get challenges for LookupType: {}",
-                TableType::Range.name()
+                table_type.name()
             ))?;
         Ok(vec![self.verify_requant(
             verifier,
@@ -293,16 +309,50 @@ impl Requant {
         num_inputs
     }
 
-    pub fn new(min_value: usize, right_shift: usize) -> Self {
+    /// Requantization by the shift `right_shift` of inputs whose absolute value is bounded by
+    /// `min_value`, to outputs quantized over `bit_len` bits
+    pub fn new(min_value: usize, right_shift: usize, bit_len: usize) -> Self {
         Self {
             right_shift,
             range: min_value,
-            after_range: *quantization::RANGE as usize,
-            multiplier: None,
+            after_range: quantization::range(bit_len) as usize,
+            multiplier: 1,
+            zero_point: 0,
         }
     }
-    pub fn set_test_multiplier(&mut self, multiplier: f32) {
-        self.multiplier = Some(multiplier);
+
+    /// Requantization by the real multiplier `m`, approximated as in gemmlowp by the fixed-point
+    /// multiplier `multiplier / 2^right_shift`, where the integer multiplier has `MULTIPLIER_BITS + 1`
+    /// bits. `min_value` is the bound on the absolute value of the inputs, before the multiplication,
+    /// and the outputs are quantized over `bit_len` bits.
+    pub fn from_multiplier(min_value: usize, m: f64, bit_len: usize) -> Self {
+        // the multiplier is in [2^MULTIPLIER_BITS, 2^(MULTIPLIER_BITS + 1))
+        let right_shift = ((-m.log2()).ceil() as isize + MULTIPLIER_BITS as isize).max(0) as usize;
+        let multiplier = ((m * (1u64 << right_shift) as f64).round() as Element).max(1);
+        Self {
+            right_shift,
+            range: min_value * multiplier as usize,
+            after_range: quantization::range(bit_len) as usize,
+            multiplier,
+            zero_point: 0,
+        }
+    }
+
+    /// Sets the zero point added to the outputs, for an output quantized asymmetrically
+    pub fn with_zero_point(mut self, zero_point: Element) -> Self {
+        self.zero_point = zero_point;
+        self
+    }
+
+    /// Number of bits of the outputs, which is also the number of bits of each chunk of the inputs
+    /// range checked by the lookup
+    pub fn bit_len(&self) -> usize {
+        ceil_log2(self.after_range)
+    }
+
+    /// Range table used to check the chunks of the inputs
+    pub(crate) fn table_type(&self) -> TableType {
+        TableType::Range(self.bit_len())
     }
     pub fn op(
         &self,
@@ -349,38 +399,29 @@ impl Requant {
     /// Applies requantization to a single element.
     ///
     /// This function performs the following steps:
-    /// 1. Adds a large offset (max_bit) to ensure all values are positive
+    /// 1. Multiplies the element by the integer multiplier, and adds a large offset (max_bit) to
+    ///    ensure all values are positive
     /// 2. Right-shifts by the specified amount to reduce the bit width
     /// 3. Subtracts the shifted offset to restore the correct value range
+    /// 4. Adds the zero point of the output
     ///
     /// The result is a value that has been scaled down to fit within the
     /// target bit width while preserving the relative magnitudes.
     #[inline(always)]
     fn apply(&self, e: &Element) -> RequantResult {
-        if let Some(_multiplier) = self.multiplier {
-            panic!("this is only for test - disable manually");
-            #[allow(unreachable_code)]
-            let _res = (*e as f64 * _multiplier as f64).round() as Element;
-            if !(_res >= *quantization::MIN && _res <= *quantization::MAX) {
-                return RequantResult::OutOfRange(
-                    _res.clamp(*quantization::MIN, *quantization::MAX),
-                );
-            } else {
-                return RequantResult::Ok(_res);
-            }
-        }
         let max_bit = (self.range << 1) as Element;
-        let tmp = e + max_bit;
+        let tmp = e * self.multiplier + max_bit;
         assert!(
             tmp >= 0,
-            "offset is too small: element {} + {} (self.range << 1) = {}",
+            "offset is too small: element {} * {} + {} (self.range << 1) = {}",
             e,
+            self.multiplier,
             self.range << 1,
             tmp
         );
         let tmp = tmp >> self.right_shift;
-        let res = tmp - (max_bit >> self.right_shift);
-        if !(res >= *quantization::MIN && res <= *quantization::MAX) {
+        let res = tmp - (max_bit >> self.right_shift) + self.zero_point;
+        if !(res >= quantization::min(self.bit_len()) && res <= quantization::max(self.bit_len())) {
             warn!("{} is NOT quantized correctly: res {}", e, res);
             RequantResult::OutOfRange(res)
         } else {
@@ -391,6 +432,9 @@ impl Requant {
     pub fn write_to_transcript<E: ExtensionField, T: Transcript<E>>(&self, t: &mut T) {
         t.append_field_element(&E::BaseField::from(self.right_shift as u64));
         t.append_field_element(&E::BaseField::from(self.range as u64));
+        t.append_field_element(&E::BaseField::from(self.after_range as u64));
+        t.append_field_element(&E::BaseField::from(self.multiplier as u64));
+        t.append_field_element(&Fieldizer::<E>::to_field(&self.zero_point).as_bases()[0]);
     }
 
     /// to_mle returns two polynomials:
@@ -431,9 +475,9 @@ impl Requant {
         let subtract = max_bit >> self.right_shift;
 
         input.iter().enumerate().for_each(|(index, val)| {
-            let pre_shift = val + max_bit as i128;
+            let pre_shift = val * self.multiplier + max_bit as i128;
             let tmp = pre_shift >> self.right_shift;
-            let input = tmp - subtract as i128;
+            let input = tmp - subtract as i128 + self.zero_point;
             let input_field: E = input.to_field();
 
             mle_evals[0][index] = input_field.as_bases()[0];
@@ -462,7 +506,7 @@ impl Requant {
                     .map(|col| E::from(col[i]))
                     .collect::<Vec<E>>();
 
-                let field_value: E = value.to_field();
+                let field_value: E = (value * self.multiplier).to_field();
                 acc & (self.recombine_claims(&calc_evals) == field_value)
             })
         });
@@ -493,9 +537,9 @@ impl Requant {
         let subtract = max_bit >> self.right_shift;
 
         input.iter().enumerate().for_each(|(index, val)| {
-            let pre_shift = val + max_bit as i128;
+            let pre_shift = val * self.multiplier + max_bit as i128;
             let tmp = pre_shift >> self.right_shift;
-            let input = tmp - subtract as i128 + (self.after_range as i128 >> 1);
+            let input = tmp - subtract as i128 + self.zero_point + (self.after_range as i128 >> 1);
             let in_field: E = input.to_field();
 
             lookups[0][index] = input;
@@ -527,7 +571,7 @@ impl Requant {
                     .map(|col| E::from(col[i]))
                     .collect::<Vec<E>>();
 
-                let field_value: E = value.to_field();
+                let field_value: E = (value * self.multiplier).to_field();
                 acc & (self.recombine_claims(&calc_evals) == field_value)
             })
         });
@@ -547,8 +591,16 @@ impl Requant {
 
         // There may be padding claims so we only take the first `num_columns` claims
 
+        // the zero point is added after the shift, so it is removed from the first column
+        let zero_point = if self.zero_point.is_negative() {
+            E::default() - E::from(self.zero_point.unsigned_abs() as u64)
+        } else {
+            E::from(self.zero_point as u64)
+        };
         let tmp_eval = E::from(1 << self.right_shift as u64)
-            * (eval_claims[0] + E::from(subtract as u64) - E::from(self.after_range as u64 >> 1))
+            * (eval_claims[0] + E::from(subtract as u64)
+                - E::from(self.after_range as u64 >> 1)
+                - zero_point)
             + eval_claims.iter().skip(1).rev().enumerate().fold(
                 E::default(),
                 |acc, (i, &claim)| {
@@ -558,6 +610,17 @@ impl Requant {
             );
         tmp_eval - E::from(max_bit as u64)
     }
+
+    /// Evaluation of the input of the layer from the evaluations of the looked up columns: the columns
+    /// are the decomposition of the inputs multiplied by the multiplier, which is divided out.
+    pub(crate) fn input_eval<E: ExtensionField>(&self, eval_claims: &[E]) -> E {
+        let product_eval = self.recombine_claims(eval_claims);
+        if self.multiplier == 1 {
+            product_eval
+        } else {
+            product_eval * E::from(self.multiplier as u64).invert().unwrap()
+        }
+    }
     #[timed::timed_instrument(name = "Prover::prove_requant")]
     pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
         &self,
@@ -587,7 +650,7 @@ impl Requant {
             .map(|claim| claim.eval)
             .collect::<Vec<E>>();
 
-        let combined_eval = requant_info.requant.recombine_claims(&eval_claims);
+        let combined_eval = requant_info.requant.input_eval(&eval_claims);
 
         // Pass the eval associated with the poly used in the activation step to the same poly prover
         let first_claim = logup_proof
@@ -598,7 +661,7 @@ impl Requant {
 
         let corrected_claim = Claim::<E> {
             point: point.clone(),
-            eval: first_claim.eval - E::from((*quantization::RANGE / 2) as u64),
+            eval: first_claim.eval - E::from(self.after_range as u64 >> 1),
         };
         // println!("correct claim eval: {:?}", corrected_claim.eval);
         // println!(
@@ -665,7 +728,7 @@ impl RequantCtx {
         // The first claim needs to be shifted down as we add a value to make sure that all its evals are in the range 0..1 << BIT_LEn
         let corrected_claim = Claim::<E>::new(
             point.clone(),
-            first_claim.eval - E::from((*quantization::RANGE / 2) as u64),
+            first_claim.eval - E::from(self.requant.after_range as u64 >> 1),
         );
         sp_verifier.add_claim(corrected_claim)?;
 
@@ -681,8 +744,69 @@ impl RequantCtx {
             .iter()
             .map(|claim| claim.eval)
             .collect::<Vec<E>>();
-        let eval = self.requant.recombine_claims(&eval_claims);
+        let eval = self.requant.input_eval(&eval_claims);
         // 4. return the input claim for to be proven at subsequent step
         Ok(Claim { point, eval })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tensor::Tensor;
+
+    #[test]
+    fn test_requant_fixed_point_multiplier() {
+        let m = 0.0123;
+        let requant = Requant::from_multiplier(1 << 12, m, quantization::DEFAULT_BIT_LEN);
+        assert!(requant.multiplier >> MULTIPLIER_BITS == 1);
+        let input = Tensor::<Element>::new(vec![6], vec![-4096, -1000, -1, 0, 999, 4096]);
+        let output = requant.op(&input).unwrap();
+        for (e, o) in input.get_data().iter().zip(output.get_data()) {
+            let expected = *e as f64 * m;
+            assert!(
+                (*o as f64 - expected).abs() <= 1.0,
+                "requantized {o} too far from {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_requant_fixed_point_accuracy() {
+        for m in [0.0123, 0.3, 0.0007, 0.6, 1.0 / 9.0] {
+            // all the inputs are requantized in the range of the output
+            let min_value = (quantization::MAX as f64 / m) as usize;
+            let input = Tensor::<Element>::new(
+                vec![2 * min_value + 1],
+                (-(min_value as Element)..=min_value as Element).collect(),
+            );
+            let max_error = |requant: &Requant, m: f64| {
+                let output = requant.op(&input).unwrap();
+                input
+                    .get_data()
+                    .iter()
+                    .zip(output.get_data())
+                    .map(|(e, o)| (*o as f64 - *e as f64 * m).abs())
+                    .fold(0.0, f64::max)
+            };
+            // the shift-only requantization approximates `m` by the power of two `2^-shift`
+            let shift = (-m.log2()).ceil() as usize;
+            let shift_error = max_error(
+                &Requant::new(min_value, shift, quantization::DEFAULT_BIT_LEN),
+                m,
+            );
+            let fixed_point_error = max_error(
+                &Requant::from_multiplier(min_value, m, quantization::DEFAULT_BIT_LEN),
+                m,
+            );
+            assert!(
+                fixed_point_error <= 1.0,
+                "fixed-point requantization by {m} off by {fixed_point_error}"
+            );
+            assert!(
+                fixed_point_error * 10.0 < shift_error,
+                "fixed-point error {fixed_point_error} not much smaller than shift error {shift_error} for {m}"
+            );
+        }
+    }
+}
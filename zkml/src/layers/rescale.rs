@@ -0,0 +1,503 @@
+//! Per-channel rescaling of the output of a layer quantized with one scaling factor per output row
+//! or channel, before the output is requantized with a single shift.
+use crate::{
+    Claim, Element, Prover, ScalingFactor, Tensor,
+    commit::{compute_betas_eval, identity_eval, precommit::PolyID},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof},
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    quantization::Fieldizer,
+};
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::Itertools;
+use multilinear_extensions::{
+    mle::IntoMLE,
+    virtual_poly::{VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx,
+};
+
+/// Number of bits of precision of the multipliers: the largest multiplier has `MULTIPLIER_BITS + 1`
+/// bits, so that the multipliers of channels with much smaller scaling factors keep enough precision.
+pub const MULTIPLIER_BITS: usize = 16;
+
+const IS_PROVABLE: bool = true;
+
+/// Multiplies each channel of its input by an integer multiplier. The shape of the multipliers
+/// broadcasts to the shape of the input, aligning the last dimensions: e.g. `[c]` for the rows of a
+/// dense layer, or `[c, 1, 1]` for the output channels of a convolution.
+///
+/// A layer whose rows `r` are quantized with their own scaling factor `s_r` is followed by this
+/// layer, with multipliers `m_r ~ 2^shift * s_in * s_r / s_out`, and by a requant layer with the
+/// common right shift `shift`.
+///
+/// The multipliers are committed, and the product of the input with the multipliers broadcast over
+/// the input is proven with a sumcheck, reducing the output claim to claims about the input and the
+/// multipliers at the point of the sumcheck.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rescale {
+    multipliers: Tensor<Element>,
+}
+
+/// Info about the rescale layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RescaleCtx {
+    pub poly_id: PolyID,
+    /// Number of variables of the input
+    pub num_vars: usize,
+    /// Padded shape of the input
+    pub input_shape: Vec<usize>,
+    /// Padded shape of the multipliers
+    pub multipliers_shape: Vec<usize>,
+}
+
+/// Proof for the rescale layer: the sumcheck for the product of the input with the multipliers, and
+/// the evaluations of the input and of the multipliers at the point of the sumcheck.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RescaleProof<E: ExtensionField> {
+    sumcheck: IOPProof<E>,
+    input_eval: E,
+    multiplier_eval: E,
+}
+
+impl Rescale {
+    pub fn new(multipliers: Tensor<Element>) -> Self {
+        Self { multipliers }
+    }
+
+    /// Derives the multipliers of the channels quantized with the scaling factors
+    /// `channel_scalings`, for an input quantized with `input_scaling` and an output quantized with
+    /// `output_scaling`. The multipliers have `num_dims` dimensions, the first one being the
+    /// channels. Returns the layer along with the right shift of the requant layer which follows it.
+    pub(crate) fn from_scalings(
+        input_scaling: &ScalingFactor,
+        channel_scalings: &[ScalingFactor],
+        output_scaling: &ScalingFactor,
+        num_dims: usize,
+    ) -> (Self, usize) {
+        let ms = channel_scalings
+            .iter()
+            .map(|s| input_scaling.m(s, output_scaling) as f64)
+            .collect_vec();
+        let max_m = ms.iter().copied().fold(f64::MIN_POSITIVE, f64::max);
+        // the largest multiplier is in [2^MULTIPLIER_BITS, 2^(MULTIPLIER_BITS + 1))
+        let shift = ((-max_m.log2()).ceil() as isize + MULTIPLIER_BITS as isize).max(0) as usize;
+        let multipliers = ms
+            .iter()
+            .map(|m| (m * (1u64 << shift) as f64).round() as Element)
+            .collect_vec();
+        let mut shape = vec![1; num_dims.max(1)];
+        shape[0] = multipliers.len();
+        (Self::new(Tensor::new(shape, multipliers)), shift)
+    }
+
+    pub fn multipliers(&self) -> &Tensor<Element> {
+        &self.multipliers
+    }
+
+    /// Largest multiplier, which bounds the growth of the range of the input
+    pub fn max_multiplier(&self) -> Element {
+        self.multipliers.max_abs_output()
+    }
+
+    /// Pads the multipliers with zeros, so that the padded channels of the output are zero
+    pub(crate) fn padded(self) -> Self {
+        Self::new(self.multipliers.pad_next_power_of_two())
+    }
+
+    pub(crate) fn check_input_shape(&self, input_shape: &[usize]) -> Result<()> {
+        let shape = self.multipliers.get_shape();
+        ensure!(
+            shape.len() <= input_shape.len()
+                && shape
+                    .iter()
+                    .rev()
+                    .zip(input_shape.iter().rev())
+                    .all(|(m, d)| *m == 1 || m == d),
+            "Multipliers of shape {:?} can't be broadcast to the input shape {:?} of rescale layer",
+            shape,
+            input_shape
+        );
+        Ok(())
+    }
+
+    /// Repeats the multipliers over an input of shape `input_shape`
+    fn broadcast(&self, input_shape: &[usize]) -> Vec<Element> {
+        let shape = self.multipliers.get_shape();
+        let data = self.multipliers.get_data();
+        (0..input_shape.iter().product::<usize>())
+            .map(|i| {
+                // the last dimension varies the fastest
+                let (index, _, _) = input_shape.iter().rev().zip(shape.iter().rev()).fold(
+                    (0, 1, i),
+                    |(index, stride, i), (dim, m)| {
+                        let index = if *m == 1 {
+                            index
+                        } else {
+                            index + (i % dim) * stride
+                        };
+                        (index, stride * m, i / dim)
+                    },
+                );
+                data[index]
+            })
+            .collect()
+    }
+
+    pub fn op(&self, input: &Tensor<Element>) -> Result<Tensor<Element>> {
+        let shape = input.get_shape();
+        self.check_input_shape(&shape)?;
+        let output = input
+            .get_data()
+            .iter()
+            .zip(self.broadcast(&shape))
+            .map(|(x, m)| x * m)
+            .collect_vec();
+        Ok(Tensor::new(shape, output))
+    }
+}
+
+/// Extracts from a point over an input of padded shape `input_shape` the point over the multipliers
+/// of padded shape `multipliers_shape`, i.e. the variables of the dimensions which are not broadcast.
+fn multipliers_point<E: Clone>(
+    point: &[E],
+    input_shape: &[usize],
+    multipliers_shape: &[usize],
+) -> Vec<E> {
+    let mut offset = 0;
+    // the variables of the last dimension come first
+    input_shape
+        .iter()
+        .rev()
+        .zip(multipliers_shape.iter().rev())
+        .flat_map(|(dim, m)| {
+            let vars = &point[offset..offset + ceil_log2(*dim)];
+            offset += vars.len();
+            if *m == 1 { &vars[..0] } else { vars }
+        })
+        .cloned()
+        .collect()
+}
+
+impl OpInfo for Rescale {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Rescale: multipliers {:?}, max {}",
+            self.multipliers.get_shape(),
+            self.max_multiplier()
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<Element> for Rescale {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found {} inputs for rescale layer, expected 1",
+            inputs.len()
+        );
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])?]))
+    }
+}
+
+impl<E> ProveInfo<E> for Rescale
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for rescale layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let input_shape = aux.last_output_shape[0].clone();
+        self.check_input_shape(&input_shape)?;
+        Ok((
+            LayerCtx::Rescale(RescaleCtx {
+                poly_id: id,
+                num_vars: input_shape.iter().map(|d| ceil_log2(*d)).sum(),
+                input_shape,
+                multipliers_shape: self.multipliers.get_shape(),
+            }),
+            aux,
+        ))
+    }
+
+    fn commit_info(&self, id: NodeId) -> Vec<Option<(PolyID, Vec<E>)>> {
+        vec![Some((id as PolyID, self.multipliers.evals_flat()))]
+    }
+}
+
+impl PadOp for Rescale {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::rescale(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Rescale
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = RescaleCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for rescale layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of rescale layer, expected 1",
+            step_data.inputs.len()
+        );
+        let last_claim = last_claims[0];
+        let multipliers: Vec<E> = self
+            .broadcast(&ctx.input_shape)
+            .into_iter()
+            .map(|m| m.to_field())
+            .collect();
+        let mut vp = VirtualPolynomial::<E>::new(ctx.num_vars);
+        vp.add_mle_list(
+            vec![
+                compute_betas_eval(&last_claim.point).into_mle().into(),
+                step_data.inputs[0].get_data().to_vec().into_mle().into(),
+                multipliers.into_mle().into(),
+            ],
+            E::ONE,
+        );
+        #[allow(deprecated)]
+        let (sumcheck, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        debug_assert_eq!(
+            sumcheck.extract_sum(),
+            last_claim.eval,
+            "Inconsistent output claim for rescale layer"
+        );
+        let evals = state.get_mle_final_evaluations();
+        let (input_eval, multiplier_eval) = (evals[1], evals[2]);
+        let point = sumcheck.point.clone();
+        prover
+            .transcript
+            .append_field_element_exts(&[input_eval, multiplier_eval]);
+        prover.commit_prover.add_claim(
+            ctx.poly_id,
+            Claim::new(
+                multipliers_point(&point, &ctx.input_shape, &ctx.multipliers_shape),
+                multiplier_eval,
+            ),
+        )?;
+        prover.push_proof(
+            node_id,
+            LayerProof::Rescale(RescaleProof {
+                sumcheck,
+                input_eval,
+                multiplier_eval,
+            }),
+        );
+        Ok(vec![Claim::new(point, input_eval)])
+    }
+}
+
+impl OpInfo for RescaleCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Rescale ctx: multipliers {:?} over input {:?}",
+            self.multipliers_shape, self.input_shape
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for RescaleCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = RescaleProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for rescale layer, expected 1",
+            last_claims.len()
+        );
+        let last_claim = last_claims[0];
+        ensure!(
+            last_claim.point.len() == self.num_vars,
+            "Invalid output claim for rescale layer: expected {} variables, found {}",
+            self.num_vars,
+            last_claim.point.len()
+        );
+        let subclaim = IOPVerifierState::<E>::verify(
+            last_claim.eval,
+            &proof.sumcheck,
+            &VPAuxInfo::from_mle_list_dimensions(&[vec![self.num_vars; 3]]),
+            verifier.transcript,
+        );
+        let point = subclaim.point_flat();
+        ensure!(
+            identity_eval(&last_claim.point, &point) * proof.input_eval * proof.multiplier_eval
+                == subclaim.expected_evaluation,
+            "Rescale sumcheck claim failed"
+        );
+        verifier
+            .transcript
+            .append_field_element_exts(&[proof.input_eval, proof.multiplier_eval]);
+        verifier.commit_verifier.add_claim(
+            self.poly_id,
+            Claim::new(
+                multipliers_point(&point, &self.input_shape, &self.multipliers_shape),
+                proof.multiplier_eval,
+            ),
+        )?;
+        Ok(vec![Claim::new(point, proof.input_eval)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+    use multilinear_extensions::mle::MultilinearExtension;
+
+    use crate::{quantization::TensorFielder, testing::random_field_vector};
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_rescale_broadcast() {
+        let rescale = Rescale::new(Tensor::new(vec![2, 1, 1], vec![3, -2]));
+        let input = Tensor::<Element>::random(&[2, 2, 4]);
+        let output = rescale.op(&input).unwrap();
+        for (i, (x, y)) in input.get_data().iter().zip(output.get_data()).enumerate() {
+            let m = if i < 8 { 3 } else { -2 };
+            assert_eq!(*y, x * m);
+        }
+        // the rows of a dense layer applied to each row of its input are its last dimension
+        let rescale = Rescale::new(Tensor::new(vec![4], vec![1, 2, 3, 4]));
+        let input = Tensor::<Element>::random(&[3, 4]);
+        let output = rescale.op(&input).unwrap();
+        for (i, (x, y)) in input.get_data().iter().zip(output.get_data()).enumerate() {
+            assert_eq!(*y, x * (i % 4 + 1) as Element);
+        }
+        assert!(rescale.op(&Tensor::<Element>::random(&[4, 3])).is_err());
+    }
+
+    #[test]
+    fn test_rescale_multipliers_point() {
+        let input_shape = vec![4, 2, 8];
+        let rescale = Rescale::new(Tensor::<Element>::random(&[4, 1, 1]));
+        let broadcast: Tensor<F> =
+            Tensor::new(input_shape.clone(), rescale.broadcast(&input_shape)).to_fields();
+        let point = random_field_vector::<F>(6);
+        let multipliers: Tensor<F> = rescale.multipliers().clone().to_fields();
+        assert_eq!(
+            broadcast.get_data().to_vec().into_mle().evaluate(&point),
+            multipliers
+                .get_data()
+                .to_vec()
+                .into_mle()
+                .evaluate(&multipliers_point(
+                    &point,
+                    &input_shape,
+                    &rescale.multipliers().get_shape()
+                ))
+        );
+    }
+
+    #[test]
+    fn test_rescale_from_scalings() {
+        let input_scaling = ScalingFactor::from_absolute_max(1.0, None);
+        let output_scaling = ScalingFactor::from_absolute_max(4.0, None);
+        let channel_scalings = [0.5, 0.1, 0.02]
+            .iter()
+            .map(|m| ScalingFactor::from_absolute_max(*m, None))
+            .collect_vec();
+        let (rescale, shift) =
+            Rescale::from_scalings(&input_scaling, &channel_scalings, &output_scaling, 3);
+        assert_eq!(rescale.multipliers().get_shape(), vec![3, 1, 1]);
+        assert!(rescale.max_multiplier() < 1 << (MULTIPLIER_BITS + 1));
+        assert!(rescale.max_multiplier() >= 1 << MULTIPLIER_BITS);
+        // multiplying then shifting approximates the floating point rescaling
+        for (m, s) in rescale
+            .multipliers()
+            .get_data()
+            .iter()
+            .zip(&channel_scalings)
+        {
+            let expected = input_scaling.m(s, &output_scaling);
+            let approx = *m as f32 / (1u64 << shift) as f32;
+            assert!(
+                (approx - expected).abs() <= 1.0 / (1u64 << shift) as f32,
+                "multiplier {approx} too far from {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Reshape layer, splitting or merging dimensions of its input without moving any entry.
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    NextPowerOfTwo, Tensor,
+    commit::precommit::PolyID,
+    iop::context::ContextAux,
+    layers::LayerCtx,
+    padding::{PaddingMode, ShapeInfo},
+    tensor::Number,
+};
+
+use super::provable::{Evaluate, LayerOut, OpInfo, PadOp, ProveInfo};
+
+/// Reshapes its input to `new_shape`, keeping the entries in the same row-major order.
+///
+/// Once padded, each dimension of a tensor has a power of two size, so the variables of its MLE are the
+/// bits of the indices of each dimension. The layer is supported only when the padded input and the
+/// padded output share the same layout: the output MLE is then the input MLE, with its variables just
+/// regrouped in different dimensions, so a claim about the output is a claim about the input and there
+/// is nothing to prove. This holds when, in each group of consecutive dimensions being merged or split,
+/// all the dimensions but the outermost one have a power of two size; other reshapes of a tensor into a
+/// vector are handled by the `Flatten` layer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Reshape {
+    new_shape: Vec<usize>,
+    /// Shape of the output of the padded layer, known once the layer is padded
+    padded_shape: Option<Vec<usize>>,
+}
+
+impl Reshape {
+    pub fn new(new_shape: Vec<usize>) -> Self {
+        Self {
+            new_shape,
+            padded_shape: None,
+        }
+    }
+
+    pub fn new_shape(&self) -> &[usize] {
+        &self.new_shape
+    }
+
+    /// Returns the layer to be proven over an input of shape `input_shape` once padded
+    pub fn padded(self, input_shape: &[usize]) -> Result<Self> {
+        ensure!(
+            input_shape.iter().product::<usize>() == self.new_shape.iter().product::<usize>(),
+            "Can't reshape a tensor of shape {:?} into shape {:?}",
+            input_shape,
+            self.new_shape
+        );
+        ensure!(
+            regroups_padded_dims(input_shape, &self.new_shape),
+            "Reshape from {:?} to {:?} moves entries of the padded input",
+            input_shape,
+            self.new_shape
+        );
+        let padded_shape = self.new_shape.next_power_of_two();
+        Ok(Self {
+            new_shape: self.new_shape,
+            padded_shape: Some(padded_shape),
+        })
+    }
+
+    fn output_shape(&self, padding_mode: PaddingMode) -> Vec<usize> {
+        match padding_mode {
+            PaddingMode::NoPadding => self.new_shape.clone(),
+            PaddingMode::Padding => self.new_shape.next_power_of_two(),
+        }
+    }
+}
+
+/// Returns whether reshaping a tensor of shape `input_shape` into `output_shape` keeps all its entries at
+/// the same position once both shapes are padded to the next power of two. Dimensions of size 1 don't
+/// change the layout, so they are ignored. The other dimensions are split into the smallest groups of
+/// consecutive dimensions with the same number of entries in input and output: the padding of the
+/// outermost dimension of a group only appends entries at the end of the group, while the padding of
+/// any other dimension would insert entries in the middle of it.
+pub(crate) fn regroups_padded_dims(input_shape: &[usize], output_shape: &[usize]) -> bool {
+    let input_dims = input_shape.iter().filter(|&&d| d != 1).collect::<Vec<_>>();
+    let output_dims = output_shape.iter().filter(|&&d| d != 1).collect::<Vec<_>>();
+    if input_dims.iter().copied().product::<usize>()
+        != output_dims.iter().copied().product::<usize>()
+    {
+        return false;
+    }
+    let (mut i, mut j) = (0, 0);
+    // the products of the remaining dimensions are equal, so both sides run out of dimensions together
+    while i < input_dims.len() && j < output_dims.len() {
+        let (mut input_size, mut output_size) = (*input_dims[i], *output_dims[j]);
+        i += 1;
+        j += 1;
+        while input_size != output_size {
+            let dim = if input_size < output_size {
+                let dim = *input_dims[i];
+                input_size *= dim;
+                i += 1;
+                dim
+            } else {
+                let dim = *output_dims[j];
+                output_size *= dim;
+                j += 1;
+                dim
+            };
+            if !dim.is_power_of_two() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+impl OpInfo for Reshape {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes
+            .iter()
+            .map(|_| self.output_shape(padding_mode))
+            .collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!("Reshape: {:?}", self.new_shape)
+    }
+
+    fn is_provable(&self) -> bool {
+        false
+    }
+}
+
+impl<N: Number> Evaluate<N> for Reshape {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<N>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<N, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found more than 1 input when evaluating reshape layer"
+        );
+        let input = inputs[0];
+        let new_shape = self.padded_shape.as_ref().unwrap_or(&self.new_shape);
+        ensure!(
+            input.get_data().len() == new_shape.iter().product::<usize>(),
+            "Can't reshape a tensor of shape {:?} into shape {:?}",
+            input.get_shape(),
+            new_shape
+        );
+        Ok(LayerOut::from_vec(vec![
+            input.clone().reshape(new_shape.clone()),
+        ]))
+    }
+}
+
+impl<E> ProveInfo<E> for Reshape
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        aux.last_output_shape = self.output_shapes(&aux.last_output_shape, PaddingMode::Padding);
+        Ok((LayerCtx::Reshape(self.clone()), aux))
+    }
+}
+
+impl PadOp for Reshape {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::reshape(self, si)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use crate::{Element, layers::provable::evaluate_layer};
+
+    use super::*;
+
+    #[test]
+    fn test_regroups_padded_dims() {
+        // merging dimensions whose inner dimensions are powers of two
+        assert!(regroups_padded_dims(&[3, 4], &[12]));
+        assert!(regroups_padded_dims(&[3, 2, 4], &[6, 4]));
+        assert!(regroups_padded_dims(&[6, 4], &[3, 8]));
+        // dimensions of size 1 are ignored
+        assert!(regroups_padded_dims(&[5, 3], &[1, 5, 1, 3]));
+        // the padding of the inner dimension would be in the middle of the merged dimension
+        assert!(!regroups_padded_dims(&[4, 3], &[12]));
+        assert!(!regroups_padded_dims(&[12], &[2, 6]));
+        // different number of entries
+        assert!(!regroups_padded_dims(&[4, 3], &[4, 4]));
+    }
+
+    #[test]
+    fn test_reshape_padded_layout() {
+        let input_shape = vec![6, 4];
+        let new_shape = vec![3, 8];
+        let input = Tensor::<Element>::random(&input_shape);
+        let reshape = Reshape::new(new_shape.clone());
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(&reshape, &[&input], None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_shape(), new_shape);
+
+        let padded_reshape = reshape.padded(&input_shape).unwrap();
+        let padded_output = evaluate_layer::<GoldilocksExt2, _, _>(
+            &padded_reshape,
+            &[&input.pad_next_power_of_two()],
+            None,
+        )
+        .unwrap()
+        .outputs()[0]
+            .clone();
+        // the padded output is the padding of the output
+        assert_eq!(padded_output, output.pad_next_power_of_two());
+
+        assert!(Reshape::new(vec![2, 12]).padded(&input_shape).is_err());
+    }
+}
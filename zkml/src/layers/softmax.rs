@@ -0,0 +1,1184 @@
+//! Softmax layer, turning the logits at the output of a model into a probability vector, or each
+//! row of a matrix into a probability vector, e.g. the attention scores of a transformer.
+use std::collections::HashMap;
+
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{
+        compute_betas_eval, identity_eval, poly_id::SOFTMAX_EXP_POLY_ID, precommit::PolyID,
+        same_poly,
+    },
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof},
+    lookup::{
+        context::{COLUMN_SEPARATOR, LookupWitnessGen, TableType},
+        logup_gkr::{
+            prover::batch_prove as logup_batch_prove, structs::LogUpProof,
+            verifier::verify_logup_proof,
+        },
+    },
+    model::StepData,
+    padding::PaddingMode,
+    quantization::{self, Fieldizer},
+};
+use anyhow::{Result, anyhow, bail, ensure};
+use ff::Field;
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::Itertools;
+use multilinear_extensions::{
+    mle::{IntoMLE, MultilinearExtension},
+    virtual_poly::{VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+/// Number of bits of precision of the exponentials computed by the softmax lookup table.
+pub const SOFTMAX_EXP_BITS: usize = 16;
+
+const IS_PROVABLE: bool = true;
+
+/// Softmax over the entries of a vector, e.g. the logits of a classifier, or over each row of a
+/// matrix.
+///
+/// Once quantized, the layer first maps each input `x` to `e = 2^SOFTMAX_EXP_BITS * exp(s * (x - MAX))`
+/// with a lookup table, whose output column is committed, where `s` is the scale of the inputs:
+/// subtracting the biggest quantized value doesn't change the softmax, and bounds the exponentials.
+/// These are then normalized to `round(MAX * e / sum)`, i.e. the probabilities quantized with
+/// [`Softmax::output_scaling`]. The rounding of the division is proven by range checking its
+/// remainder.
+/// Only the unpadded entries of the unpadded rows are normalized, the padded entries of the output
+/// are zero, so that the output can be revealed.
+#[derive(Clone, Debug, Copy, Default, Serialize, Deserialize)]
+pub struct Softmax {
+    /// Lookup table of the exponentials, `None` before quantization
+    table: Option<SoftmaxTable>,
+}
+
+/// Info about the softmax layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SoftmaxCtx {
+    pub table: SoftmaxTable,
+    pub poly_id: PolyID,
+    pub exp_poly_id: PolyID,
+    /// Number of variables of the output
+    pub num_vars: usize,
+    /// Number of variables of the rows of the output, 0 if the input is a vector
+    pub row_num_vars: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SoftmaxProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// Evaluations of the exponentials over the points whose sum is the normalization factor of
+    /// the rows at the point of the range check of the remainders, see [`sum_points`]
+    pub(crate) sum_evals: Vec<E>,
+    /// Proof for the sum over the rows of the products between the normalization factors and the
+    /// output, `None` if the input is a vector
+    pub(crate) row_sum: Option<RowSumProof<E>>,
+    /// Evaluation of the output at the columns of the point of the range check of the remainders,
+    /// and at the rows of the point of the row sumcheck, if any
+    pub(crate) output_eval: E,
+    /// Evaluations of the exponentials over the points whose weighted sum is the evaluation of the
+    /// unpadded exponentials at the point of the range check of the remainders, see
+    /// [`SoftmaxCtx::unpadded_points`]
+    pub(crate) unpadded_evals: Vec<E>,
+    /// the lookup proof for the exponentials
+    pub(crate) exp_lookup: LogUpProof<E>,
+    /// the lookup proof for the range check of the remainders of the normalization
+    pub(crate) range_lookup: LogUpProof<E>,
+    /// proof for the accumulation of the claims about the output
+    pub(crate) output_accumulation: same_poly::Proof<E>,
+    /// proof for the accumulation of the claims about the exponentials
+    pub(crate) exp_accumulation: same_poly::Proof<E>,
+}
+
+/// Proof that `sum_r eq(z, r) * sum(r) * output(x, r)` is equal to the claimed value, where `sum(r)`
+/// is the normalization factor of the row `r`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RowSumProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    pub(crate) sumcheck: IOPProof<E>,
+    /// Evaluations of the exponentials over the points whose sum is the normalization factor of
+    /// the rows at the point of the sumcheck
+    pub(crate) sum_evals: Vec<E>,
+}
+
+impl Softmax {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scaling factor of the probabilities quantized over `bit_len` bits
+    pub fn output_scaling(bit_len: usize) -> ScalingFactor {
+        ScalingFactor::from_absolute_max(1.0, Some(quantization::domain(bit_len)))
+    }
+
+    /// Evaluates the softmax over each row of `input`
+    pub fn op(&self, input: &Tensor<f32>) -> Tensor<f32> {
+        let output = input
+            .get_data()
+            .chunks(row_len(&input.get_shape()))
+            .flat_map(|row| {
+                let max = row.iter().fold(f32::NEG_INFINITY, |acc, x| acc.max(*x));
+                let exps = row.iter().map(|x| (x - max).exp()).collect_vec();
+                let sum = exps.iter().sum::<f32>();
+                exps.into_iter().map(move |e| e / sum)
+            })
+            .collect_vec();
+        Tensor::new(input.get_shape(), output)
+    }
+
+    /// Evaluates the quantized softmax over each of the first `num_rows` rows of `input`, whose
+    /// first `num_entries` entries are normalized. The other entries of the output are zero.
+    pub fn quantized_op(
+        &self,
+        input: &Tensor<Element>,
+        num_rows: usize,
+        num_entries: usize,
+    ) -> Result<Tensor<Element>> {
+        let table = self.table.ok_or(anyhow!(
+            "Softmax must be quantized to be evaluated over integers"
+        ))?;
+        let exps = input
+            .get_data()
+            .iter()
+            .map(|x| table.apply(*x))
+            .collect_vec();
+        let row_len = row_len(&input.get_shape());
+        let output = exps
+            .chunks(row_len)
+            .zip(unpadded_exps(&exps, row_len, num_rows, num_entries).chunks(row_len))
+            .flat_map(|(row, unpadded_row)| {
+                let sum = row.iter().take(num_entries).sum::<Element>();
+                unpadded_row
+                    .iter()
+                    .map(move |e| normalize(*e, sum, table.bit_len()))
+            })
+            .collect_vec();
+        Ok(Tensor::new(input.get_shape(), output))
+    }
+}
+
+/// Returns the exponentials with zeros over the padded entries, i.e. outside of the first
+/// `num_entries` entries of the first `num_rows` rows: the padded entries are normalized as null
+/// exponentials, i.e. to zero, while the normalization factor of each row is still the sum of its
+/// first `num_entries` exponentials, which is never zero.
+fn unpadded_exps(
+    exps: &[Element],
+    row_len: usize,
+    num_rows: usize,
+    num_entries: usize,
+) -> Vec<Element> {
+    exps.iter()
+        .enumerate()
+        .map(|(i, e)| {
+            if i / row_len < num_rows && i % row_len < num_entries {
+                *e
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Number of rows and number of entries of each row normalized by a softmax with the given
+/// unpadded input shapes
+fn unpadded_dims(unpadded_input_shapes: &[Vec<usize>]) -> Result<(usize, usize)> {
+    ensure!(
+        unpadded_input_shapes.len() == 1,
+        "Found {} inputs for softmax layer, expected 1",
+        unpadded_input_shapes.len()
+    );
+    ensure!(
+        unpadded_input_shapes[0].len() == 1 || unpadded_input_shapes[0].len() == 2,
+        "Softmax is only supported over vectors and rows of matrices, found input shape {:?}",
+        unpadded_input_shapes[0]
+    );
+    let shape = &unpadded_input_shapes[0];
+    Ok((
+        if shape.len() == 2 { shape[0] } else { 1 },
+        *shape.last().unwrap(),
+    ))
+}
+
+/// Length of the rows normalized by a softmax over an input with the given shape
+fn row_len(input_shape: &[usize]) -> usize {
+    *input_shape.last().unwrap()
+}
+
+/// Probability of an exponential `e` quantized over `bit_len` bits, i.e. `round(MAX * e / sum)`
+#[inline(always)]
+fn normalize(e: Element, sum: Element, bit_len: usize) -> Element {
+    (2 * quantization::max(bit_len) * e + sum) / (2 * sum)
+}
+
+/// Remainder of the rounded division computing the probability `p` of the exponential `e`, which
+/// lies in `[0, 2 * sum)` iff `p = round(MAX * e / sum)`
+#[inline(always)]
+fn remainder(e: Element, sum: Element, p: Element, bit_len: usize) -> Element {
+    2 * quantization::max(bit_len) * e + sum - 2 * sum * p
+}
+
+/// Number of `bit_len` bits limbs needed to range check the remainders of the normalization of
+/// rows with `2^num_vars` entries: the remainders are smaller than twice the sum of the exponentials.
+fn remainder_limbs(num_vars: usize, bit_len: usize) -> usize {
+    (SOFTMAX_EXP_BITS + 1 + num_vars).div_ceil(bit_len)
+}
+
+/// Returns the points whose weighted evaluations of a multilinear polynomial `P` give the evaluation
+/// at `point` of `P` restricted to its first `num_entries` entries, i.e.
+/// `sum_{x < num_entries} eq(point, x) * P(x)`. The entries are split in blocks as in [`sum_points`]:
+/// the sum over a block of `2^k` entries is the evaluation of `P` at the `k` least significant
+/// coordinates of `point` and at the bits of the block, weighted by the equality polynomial between
+/// the other coordinates of `point` and the bits of the block.
+pub(crate) fn prefix_points<E: ExtensionField>(
+    point: &[E],
+    num_entries: usize,
+) -> Vec<(E, Vec<E>)> {
+    let num_vars = point.len();
+    (0..=num_vars)
+        .rev()
+        .filter(|k| num_entries & (1 << k) != 0)
+        .map(|k| {
+            let block_start = (num_entries >> (k + 1)) << (k + 1);
+            let block_bits = (k..num_vars)
+                .map(|i| E::from(((block_start >> i) & 1) as u64))
+                .collect_vec();
+            (
+                identity_eval(&point[k..], &block_bits),
+                [&point[..k], block_bits.as_slice()].concat(),
+            )
+        })
+        .collect()
+}
+
+/// Returns the points whose weighted evaluations of a multilinear polynomial give the sum of its
+/// first `num_entries` entries, e.g. the normalization factor of the exponentials. The entries are split in blocks of `2^k` consecutive entries sharing their most significant bits,
+/// whose sum is `2^k` times the evaluation at the point with the `k` least significant coordinates
+/// set to `1/2` and the other ones set to the bits of the block.
+pub(crate) fn sum_points<E: ExtensionField>(
+    num_vars: usize,
+    num_entries: usize,
+) -> Vec<(E, Vec<E>)> {
+    let half = E::from(2u64).invert().unwrap();
+    (0..=num_vars)
+        .rev()
+        .filter(|k| num_entries & (1 << k) != 0)
+        .map(|k| {
+            let block_start = (num_entries >> (k + 1)) << (k + 1);
+            let point = (0..num_vars)
+                .map(|i| {
+                    if i < k {
+                        half
+                    } else {
+                        E::from(((block_start >> i) & 1) as u64)
+                    }
+                })
+                .collect_vec();
+            (E::from(1u64 << k), point)
+        })
+        .collect()
+}
+
+/// Decomposes each value in `bit_len` bits limbs, starting from the least significant one
+pub(crate) fn limbs(values: &[Element], num_limbs: usize, bit_len: usize) -> Vec<Vec<Element>> {
+    let mask = (1 << bit_len) - 1;
+    (0..num_limbs)
+        .map(|i| {
+            values
+                .iter()
+                .map(|v| (v >> (i * bit_len)) & mask)
+                .collect_vec()
+        })
+        .collect()
+}
+
+/// Recombines the evaluations of the `bit_len` bits limbs of some values into the evaluation of
+/// the values
+pub(crate) fn recombine_limbs<E: ExtensionField>(evals: &[E], bit_len: usize) -> E {
+    evals
+        .iter()
+        .rev()
+        .fold(E::ZERO, |acc, eval| acc * E::from(1u64 << bit_len) + *eval)
+}
+
+impl OpInfo for Softmax {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        match self.table {
+            Some(table) => format!("Softmax: input scale {}", table.input_scale()),
+            None => "Softmax".to_string(),
+        }
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for Softmax {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        unpadded_dims(&unpadded_input_shapes)?;
+        Ok(LayerOut::from_vec(vec![self.op(inputs[0])]))
+    }
+}
+
+impl Evaluate<Element> for Softmax {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        let (num_rows, num_entries) = unpadded_dims(&unpadded_input_shapes)?;
+        Ok(LayerOut::from_vec(vec![self.quantized_op(
+            inputs[0],
+            num_rows,
+            num_entries,
+        )?]))
+    }
+}
+
+impl QuantizeOp for Softmax {
+    type QuantizedOp = Softmax;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        _data: &S::AuxData,
+        _node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == 1,
+            "Found {} input scaling factors for softmax layer, expected 1",
+            input_scaling.len()
+        );
+        // the normalization outputs the probabilities quantized over the bit length of the inputs,
+        // so there is no need to requantize
+        Ok(QuantizeOutput {
+            quanzited_op: Softmax {
+                table: Some(SoftmaxTable::new(&input_scaling[0])),
+            },
+            output_scalings: vec![Self::output_scaling(input_scaling[0].bit_len())],
+            requant_layer: None,
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for Softmax
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        let table = self
+            .table
+            .ok_or(anyhow!("Softmax must be quantized to be proven"))?;
+        unpadded_dims(&aux.last_output_shape)?;
+        let shape = &aux.last_output_shape[0];
+        let num_vars = ceil_log2(shape.iter().product());
+        let row_num_vars = num_vars - ceil_log2(row_len(shape));
+        aux.tables.insert(TableType::Softmax(table));
+        aux.tables.insert(TableType::Range(table.bit_len()));
+        Ok((
+            LayerCtx::Softmax(SoftmaxCtx {
+                table,
+                poly_id: id,
+                exp_poly_id: SOFTMAX_EXP_POLY_ID + id,
+                num_vars,
+                row_num_vars,
+            }),
+            aux,
+        ))
+    }
+}
+
+impl PadOp for Softmax {}
+
+impl<E> ProvableOp<E> for Softmax
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = SoftmaxCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for softmax layer, expected 1",
+            last_claims.len()
+        );
+        let (num_rows, num_entries) = unpadded_dims(&step_data.unpadded_output_shapes)?;
+        Ok(vec![ctx.prove_step(
+            prover,
+            last_claims[0],
+            step_data.outputs.outputs()[0].get_data(),
+            num_rows,
+            num_entries,
+            node_id,
+        )?])
+    }
+
+    fn gen_lookup_witness(
+        &self,
+        id: NodeId,
+        gen: &mut LookupWitnessGen<E>,
+        step_data: &StepData<Element, E>,
+    ) -> Result<()> {
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found more than 1 input in inference step of softmax layer"
+        );
+        ensure!(
+            step_data.outputs.outputs().len() == 1,
+            "Found more than 1 output in inference step of softmax layer"
+        );
+        let table = self
+            .table
+            .ok_or(anyhow!("Softmax must be quantized to be proven"))?;
+        let input = step_data.inputs[0].get_data();
+        let output = step_data.outputs.outputs()[0].get_data();
+        let (num_rows, num_entries) = unpadded_dims(&step_data.unpadded_output_shapes)?;
+        let row_len = row_len(&step_data.inputs[0].get_shape());
+
+        // 1. lookup of the exponentials of the inputs
+        let exps = input.iter().map(|x| table.apply(*x)).collect_vec();
+        let table_type = TableType::Softmax(table);
+        gen.tables.insert(table_type);
+        let exp_lookups = gen
+            .lookups
+            .entry(table_type)
+            .or_insert_with(HashMap::default);
+        input
+            .iter()
+            .zip(&exps)
+            .for_each(|(x, e)| *exp_lookups.entry(x + COLUMN_SEPARATOR * e).or_insert(0u64) += 1);
+        let exp_columns = [input, exps.as_slice()]
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|v| Fieldizer::<E>::to_field(v).as_bases()[0])
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        // 2. range check of the remainders of the normalization of each row, and of their distance
+        // to the upper bound
+        let unpadded_exps = unpadded_exps(&exps, row_len, num_rows, num_entries);
+        let (remainders, complements): (Vec<_>, Vec<_>) = exps
+            .chunks(row_len)
+            .zip(unpadded_exps.chunks(row_len))
+            .zip(output.chunks(row_len))
+            .flat_map(|((row_exps, unpadded_row), row_output)| {
+                let sum = row_exps.iter().take(num_entries).sum::<Element>();
+                unpadded_row.iter().zip(row_output).map(move |(e, p)| {
+                    let r = remainder(*e, sum, *p, table.bit_len());
+                    (r, 2 * sum - 1 - r)
+                })
+            })
+            .unzip();
+        let bit_len = table.bit_len();
+        let num_limbs = remainder_limbs(ceil_log2(row_len), bit_len);
+        let range_columns = [
+            limbs(&remainders, num_limbs, bit_len),
+            limbs(&complements, num_limbs, bit_len),
+        ]
+        .concat();
+        debug_assert!(
+            remainders
+                .iter()
+                .chain(&complements)
+                .all(|r| *r >= 0 && *r >> (num_limbs * bit_len) == 0),
+            "Softmax remainders out of range"
+        );
+        let range_table = TableType::Range(bit_len);
+        gen.tables.insert(range_table);
+        let range_lookups = gen
+            .lookups
+            .entry(range_table)
+            .or_insert_with(HashMap::default);
+        range_columns
+            .iter()
+            .flatten()
+            .for_each(|limb| *range_lookups.entry(*limb).or_insert(0u64) += 1);
+        let range_columns = range_columns
+            .into_iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|v| Fieldizer::<E>::to_field(v).as_bases()[0])
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        gen.polys_with_id.push((
+            id as PolyID,
+            output.iter().map(Fieldizer::<E>::to_field).collect(),
+        ));
+        gen.polys_with_id.push((
+            SOFTMAX_EXP_POLY_ID + id,
+            exps.iter().map(Fieldizer::<E>::to_field).collect(),
+        ));
+        let lookups = gen.lookups_no_challenges.entry(id).or_default();
+        lookups.push((exp_columns, 2, table_type));
+        lookups.push((range_columns, 1, range_table));
+
+        Ok(())
+    }
+}
+
+impl SoftmaxCtx {
+    /// Number of variables of the columns of the output, i.e. of the entries of each row
+    fn col_num_vars(&self) -> usize {
+        self.num_vars - self.row_num_vars
+    }
+
+    /// Normalization factor derived from the evaluations of the exponentials over the points
+    /// returned by [`sum_points`]
+    fn sum<E: ExtensionField>(points: &[(E, Vec<E>)], sum_evals: &[E]) -> E {
+        points
+            .iter()
+            .zip(sum_evals)
+            .fold(E::ZERO, |acc, ((weight, _), eval)| acc + *weight * *eval)
+    }
+
+    /// Points over which the exponentials are evaluated to derive the normalization factor of the
+    /// rows at `row_point`, from the points over the columns returned by [`sum_points`]
+    fn row_sum_points<E: ExtensionField>(points: &[(E, Vec<E>)], row_point: &[E]) -> Vec<Vec<E>> {
+        points
+            .iter()
+            .map(|(_, point)| [point.as_slice(), row_point].concat())
+            .collect()
+    }
+
+    /// Points over which the exponentials are evaluated to derive the evaluation at `point` of the
+    /// exponentials with zeros over the padded entries, from the points returned by
+    /// [`prefix_points`] over the columns and the rows
+    fn unpadded_points<E: ExtensionField>(
+        &self,
+        point: &[E],
+        num_rows: usize,
+        num_entries: usize,
+    ) -> Vec<(E, Vec<E>)> {
+        let (col_point, row_point) = point.split_at(self.col_num_vars());
+        let row_points = prefix_points(row_point, num_rows);
+        prefix_points(col_point, num_entries)
+            .into_iter()
+            .flat_map(|(col_weight, col_point)| {
+                row_points.iter().map(move |(row_weight, row_point)| {
+                    (
+                        col_weight * row_weight,
+                        [col_point.as_slice(), row_point.as_slice()].concat(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Evaluation of the exponentials with zeros over the padded entries derived from the
+    /// evaluations of the remainders, of the normalization factor and of its product with the
+    /// output over the same point, as `2 * MAX * e = r - sum + 2 * sum * p`
+    fn exp_eval<E: ExtensionField>(&self, sum: E, remainder_eval: E, sum_output_eval: E) -> E {
+        let double_max: E = (2 * quantization::max(self.table.bit_len())).to_field();
+        (remainder_eval - sum + sum_output_eval.double()) * double_max.invert().unwrap()
+    }
+
+    #[timed::timed_instrument(name = "Prover::prove_softmax_step")]
+    pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        prover: &mut Prover<E, T>,
+        last_claim: &Claim<E>,
+        output: &[E],
+        num_rows: usize,
+        num_entries: usize,
+        node_id: NodeId,
+    ) -> Result<Claim<E>>
+    where
+        E: ExtensionField + Serialize + DeserializeOwned,
+        E::BaseField: Serialize + DeserializeOwned,
+    {
+        let mut witnesses = prover.lookup_witnesses(node_id)?;
+        ensure!(
+            witnesses.len() == 2,
+            "Found {} lookup witnesses for softmax layer, expected 2",
+            witnesses.len()
+        );
+        let range_witness = witnesses.pop().unwrap();
+        let exp_witness = witnesses.pop().unwrap();
+        let exp_evals = exp_witness.column_evals()[1]
+            .iter()
+            .map(|e| E::from(*e))
+            .collect_vec();
+        let exps = exp_evals.clone().into_mle();
+
+        // 1. Run the lookup protocols
+        let exp_lookup = logup_batch_prove(&exp_witness, prover.transcript)?;
+        let range_lookup = logup_batch_prove(&range_witness, prover.transcript)?;
+        let point = range_lookup.output_claims()[0].point.clone();
+        let (col_point, row_point) = point.split_at(self.col_num_vars());
+
+        // 2. The normalization factor of the rows at the point of the range check is derived from
+        // evaluations of the exponentials
+        let points = sum_points::<E>(self.col_num_vars(), num_entries);
+        let sum_eval_points = Self::row_sum_points(&points, row_point);
+        let sum_evals = sum_eval_points
+            .iter()
+            .map(|point| exps.evaluate(point))
+            .collect_vec();
+        prover.transcript.append_field_element_exts(&sum_evals);
+        let sum = Self::sum(&points, &sum_evals);
+
+        // 3. Each entry of the output is multiplied by the normalization factor of its row: over a
+        // matrix, the evaluation of this product is a sum over the rows
+        let output_mle = output.to_vec().into_mle();
+        let (row_sum, row_sum_points, output_point) = if self.row_num_vars == 0 {
+            (None, vec![], point.clone())
+        } else {
+            let row_sums = exp_evals
+                .chunks(1 << self.col_num_vars())
+                .map(|row| {
+                    row.iter()
+                        .take(num_entries)
+                        .fold(E::ZERO, |acc, e| acc + *e)
+                })
+                .collect_vec();
+            let mut vp = VirtualPolynomial::<E>::new(self.row_num_vars);
+            vp.add_mle_list(
+                vec![
+                    compute_betas_eval(row_point).into_mle().into(),
+                    row_sums.into_mle().into(),
+                    output_mle.fix_variables(col_point).into(),
+                ],
+                E::ONE,
+            );
+            #[allow(deprecated)]
+            let (sumcheck, _) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+            let row_sum_points = Self::row_sum_points(&points, &sumcheck.point);
+            let sum_evals = row_sum_points
+                .iter()
+                .map(|point| exps.evaluate(point))
+                .collect_vec();
+            prover.transcript.append_field_element_exts(&sum_evals);
+            let output_point = [col_point, sumcheck.point.as_slice()].concat();
+            (
+                Some(RowSumProof {
+                    sumcheck,
+                    sum_evals,
+                }),
+                row_sum_points,
+                output_point,
+            )
+        };
+        let output_eval = output_mle.evaluate(&output_point);
+        prover.transcript.append_field_element_ext(&output_eval);
+        let sum_output_eval = match &row_sum {
+            Some(row_sum) => row_sum.sumcheck.extract_sum(),
+            None => sum * output_eval,
+        };
+
+        // 4. Link the remainders to the output and the exponentials, whose evaluation with zeros
+        // over the padded entries is derived from evaluations of the exponentials
+        let unpadded_points = self.unpadded_points(&point, num_rows, num_entries);
+        let unpadded_evals = unpadded_points
+            .iter()
+            .map(|(_, point)| exps.evaluate(point))
+            .collect_vec();
+        prover.transcript.append_field_element_exts(&unpadded_evals);
+        debug_assert_eq!(
+            self.exp_eval(
+                sum,
+                recombine_limbs(
+                    &range_lookup
+                        .output_claims()
+                        .iter()
+                        .take(remainder_limbs(self.col_num_vars(), self.table.bit_len()))
+                        .map(|claim| claim.eval)
+                        .collect_vec(),
+                    self.table.bit_len()
+                ),
+                sum_output_eval
+            ),
+            Self::sum(&unpadded_points, &unpadded_evals),
+            "Inconsistent remainders for softmax layer"
+        );
+
+        // 5. Accumulate the claims about the output and the exponentials
+        let same_poly_ctx = same_poly::Context::<E>::new(self.num_vars);
+        let mut output_prover = same_poly::Prover::<E>::new(output_mle);
+        output_prover.add_claim(last_claim.clone())?;
+        output_prover.add_claim(Claim::new(output_point, output_eval))?;
+        let output_accumulation = output_prover.prove(&same_poly_ctx, prover.transcript)?;
+        prover
+            .witness_prover
+            .add_claim(self.poly_id, output_accumulation.extract_claim())?;
+
+        let mut exp_prover = same_poly::Prover::<E>::new(exps);
+        exp_prover.add_claim(exp_lookup.output_claims()[1].clone())?;
+        let row_sum_evals = row_sum
+            .as_ref()
+            .map(|row_sum| row_sum.sum_evals.as_slice())
+            .unwrap_or_default();
+        unpadded_points
+            .into_iter()
+            .map(|(_, point)| point)
+            .zip(&unpadded_evals)
+            .chain(sum_eval_points.into_iter().zip(&sum_evals))
+            .chain(row_sum_points.into_iter().zip(row_sum_evals))
+            .try_for_each(|(point, eval)| exp_prover.add_claim(Claim::new(point, *eval)))?;
+        let exp_accumulation = exp_prover.prove(&same_poly_ctx, prover.transcript)?;
+        prover
+            .witness_prover
+            .add_claim(self.exp_poly_id, exp_accumulation.extract_claim())?;
+
+        let input_claim = exp_lookup.output_claims()[0].clone();
+        prover.push_proof(
+            node_id,
+            LayerProof::Softmax(SoftmaxProof {
+                sum_evals,
+                row_sum,
+                output_eval,
+                unpadded_evals,
+                exp_lookup,
+                range_lookup,
+                output_accumulation,
+                exp_accumulation,
+            }),
+        );
+        Ok(input_claim)
+    }
+}
+
+impl OpInfo for SoftmaxCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.to_vec()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "Softmax ctx: input scale {}, 2^{} rows of 2^{} entries",
+            self.table.input_scale(),
+            self.row_num_vars,
+            self.col_num_vars()
+        )
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for SoftmaxCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = SoftmaxProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for softmax layer, expected 1",
+            last_claims.len()
+        );
+        let challenges = |table_type: TableType| {
+            verifier
+                .challenge_storage
+                .as_ref()
+                .unwrap()
+                .get_challenges_by_name(&table_type.name())
+                .ok_or(anyhow!(
+                    "Couldn't get challenges for LookupType: {}",
+                    table_type.name()
+                ))
+        };
+        let (exp_constant_challenge, exp_column_challenge) =
+            challenges(TableType::Softmax(self.table))?;
+        let (range_constant_challenge, range_column_challenge) =
+            challenges(TableType::Range(self.table.bit_len()))?;
+
+        // 1. Verify the lookup proofs
+        let exp_claims = verify_logup_proof(
+            &proof.exp_lookup,
+            1,
+            exp_constant_challenge,
+            exp_column_challenge,
+            verifier.transcript,
+        )?;
+        let num_limbs = remainder_limbs(self.col_num_vars(), self.table.bit_len());
+        let range_claims = verify_logup_proof(
+            &proof.range_lookup,
+            2 * num_limbs,
+            range_constant_challenge,
+            range_column_challenge,
+            verifier.transcript,
+        )?;
+        ensure!(
+            exp_claims.claims().len() == 2 && range_claims.claims().len() == 2 * num_limbs,
+            "Invalid number of lookup claims in softmax proof"
+        );
+        let point = range_claims.claims()[0].point.clone();
+        let (col_point, row_point) = point.split_at(self.col_num_vars());
+
+        // 2. Derive the normalization factor of the rows at the point of the range check
+        let (num_rows, num_entries) = unpadded_dims(&shape_step.unpadded_input_shape)?;
+        let points = sum_points::<E>(self.col_num_vars(), num_entries);
+        ensure!(
+            proof.sum_evals.len() == points.len(),
+            "Found {} evaluations of the exponentials in softmax proof, expected {}",
+            proof.sum_evals.len(),
+            points.len()
+        );
+        verifier
+            .transcript
+            .append_field_element_exts(&proof.sum_evals);
+        let sum = Self::sum(&points, &proof.sum_evals);
+
+        // 3. Verify the sum over the rows of the normalization factors times the output, if any
+        let (sum_output_eval, row_sum_claims, output_point) = match (
+            &proof.row_sum,
+            self.row_num_vars,
+        ) {
+            (None, 0) => {
+                verifier
+                    .transcript
+                    .append_field_element_ext(&proof.output_eval);
+                (sum * proof.output_eval, vec![], point.clone())
+            }
+            (Some(row_sum), row_num_vars) if row_num_vars > 0 => {
+                ensure!(
+                    row_sum.sum_evals.len() == points.len(),
+                    "Found {} evaluations of the exponentials in softmax row sum proof, expected {}",
+                    row_sum.sum_evals.len(),
+                    points.len()
+                );
+                let claimed_sum = row_sum.sumcheck.extract_sum();
+                let subclaim = IOPVerifierState::<E>::verify(
+                    claimed_sum,
+                    &row_sum.sumcheck,
+                    &VPAuxInfo::from_mle_list_dimensions(&[vec![row_num_vars; 3]]),
+                    verifier.transcript,
+                );
+                let sumcheck_point = subclaim.point_flat();
+                verifier
+                    .transcript
+                    .append_field_element_exts(&row_sum.sum_evals);
+                verifier
+                    .transcript
+                    .append_field_element_ext(&proof.output_eval);
+                ensure!(
+                    identity_eval(row_point, &sumcheck_point)
+                        * Self::sum(&points, &row_sum.sum_evals)
+                        * proof.output_eval
+                        == subclaim.expected_evaluation,
+                    "Softmax row sumcheck claim failed"
+                );
+                let row_sum_claims = Self::row_sum_points(&points, &sumcheck_point)
+                    .into_iter()
+                    .zip(&row_sum.sum_evals)
+                    .map(|(point, eval)| Claim::new(point, *eval))
+                    .collect_vec();
+                (
+                    claimed_sum,
+                    row_sum_claims,
+                    [col_point, sumcheck_point.as_slice()].concat(),
+                )
+            }
+            _ => bail!("Softmax proof doesn't match the shape of the input"),
+        };
+
+        // 4. Check the remainders lie in [0, 2 * sum) and derive the evaluation of the exponentials
+        let limb_evals = range_claims
+            .claims()
+            .iter()
+            .map(|claim| claim.eval)
+            .collect_vec();
+        let (remainder_evals, complement_evals) = limb_evals.split_at(num_limbs);
+        let remainder_eval = recombine_limbs(remainder_evals, self.table.bit_len());
+        ensure!(
+            recombine_limbs(complement_evals, self.table.bit_len())
+                == sum.double() - E::ONE - remainder_eval,
+            "Softmax remainders are not smaller than twice the normalization factor"
+        );
+        let exp_eval = self.exp_eval(sum, remainder_eval, sum_output_eval);
+        let unpadded_points = self.unpadded_points(&point, num_rows, num_entries);
+        ensure!(
+            proof.unpadded_evals.len() == unpadded_points.len(),
+            "Found {} evaluations of the unpadded exponentials in softmax proof, expected {}",
+            proof.unpadded_evals.len(),
+            unpadded_points.len()
+        );
+        verifier
+            .transcript
+            .append_field_element_exts(&proof.unpadded_evals);
+        ensure!(
+            Self::sum(&unpadded_points, &proof.unpadded_evals) == exp_eval,
+            "Softmax exponentials don't match the normalization of the unpadded entries"
+        );
+
+        // 5. Verify the accumulation of the claims about the output and the exponentials
+        let sp_ctx = same_poly::Context::<E>::new(self.num_vars);
+        let mut output_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        output_verifier.add_claim(last_claims[0].clone())?;
+        output_verifier.add_claim(Claim::new(output_point, proof.output_eval))?;
+        let output_claim =
+            output_verifier.verify(&proof.output_accumulation, verifier.transcript)?;
+        verifier
+            .witness_verifier
+            .add_claim(self.poly_id, output_claim)?;
+
+        let mut exp_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        exp_verifier.add_claim(exp_claims.claims()[1].clone())?;
+        unpadded_points
+            .into_iter()
+            .map(|(_, point)| point)
+            .zip(&proof.unpadded_evals)
+            .chain(
+                Self::row_sum_points(&points, row_point)
+                    .into_iter()
+                    .zip(&proof.sum_evals),
+            )
+            .map(|(point, eval)| Claim::new(point, *eval))
+            .chain(row_sum_claims)
+            .try_for_each(|claim| exp_verifier.add_claim(claim))?;
+        let exp_claim = exp_verifier.verify(&proof.exp_accumulation, verifier.transcript)?;
+        verifier
+            .witness_verifier
+            .add_claim(self.exp_poly_id, exp_claim)?;
+
+        // 6. return the input claim to be proven at subsequent step
+        Ok(vec![exp_claims.claims()[0].clone()])
+    }
+}
+
+/// Lookup table of the exponentials of a softmax, see [`Softmax`]. The inputs are quantized with
+/// the scaling factor of the input of the layer, and the probabilities over the same number of bits.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SoftmaxTable {
+    /// Bit representation of the scale of the inputs, so that the table can be used as a key
+    input_scale: u32,
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+impl SoftmaxTable {
+    pub fn new(input_scaling: &ScalingFactor) -> Self {
+        Self {
+            input_scale: input_scaling.scale().to_bits(),
+            bit_len: input_scaling.bit_len(),
+        }
+    }
+
+    pub fn input_scale(&self) -> f32 {
+        f32::from_bits(self.input_scale)
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Maps a quantized input to its exponential. The exponentials are at least 1, so that the
+    /// normalization factor is never zero, even when all the inputs are far below `MAX`.
+    #[inline(always)]
+    pub fn apply(&self, e: Element) -> Element {
+        let exp = ((e - quantization::max(self.bit_len)) as f32 * self.input_scale()).exp();
+        ((exp * (1u64 << SOFTMAX_EXP_BITS) as f32).round() as Element).max(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use crate::testing::random_field_vector;
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_softmax_quantized_probabilities() {
+        let input_scaling = ScalingFactor::from_absolute_max(4.0, None);
+        let output_scaling = Softmax::output_scaling(input_scaling.bit_len());
+        let softmax = Softmax {
+            table: Some(SoftmaxTable::new(&input_scaling)),
+        };
+        // the last entries are padding, which are not normalized
+        let num_entries = 13;
+        let float_input = Tensor::<f32>::random(&vec![num_entries]).scalar_mul(&4.0);
+        let float_output = softmax.op(&float_input);
+        let input = float_input.quantize(&input_scaling).pad_next_power_of_two();
+        let output = softmax.quantized_op(&input, 1, num_entries).unwrap();
+        assert!(output.get_data()[num_entries..].iter().all(|p| *p == 0));
+        for (q, f) in output.get_data().iter().zip(float_output.get_data()) {
+            let dequantized = output_scaling.dequantize(q);
+            assert!(
+                (dequantized - f).abs() <= 3.0 * output_scaling.scale(),
+                "dequantized {dequantized} too far from {f}"
+            );
+        }
+
+        // the remainders of the normalization, padding included, fit in the range checked limbs
+        let table = softmax.table.unwrap();
+        let exps = input
+            .get_data()
+            .iter()
+            .map(|x| table.apply(*x))
+            .collect_vec();
+        let sum = exps.iter().take(num_entries).sum::<Element>();
+        let bit_len = table.bit_len();
+        let num_limbs = remainder_limbs(ceil_log2(input.get_data().len()), bit_len);
+        let unpadded_exps = unpadded_exps(&exps, exps.len(), 1, num_entries);
+        for (e, p) in unpadded_exps.iter().zip(output.get_data()) {
+            let r = remainder(*e, sum, *p, bit_len);
+            assert!(r >= 0 && r < 2 * sum);
+            let evals = limbs(&[r], num_limbs, bit_len)
+                .into_iter()
+                .map(|limb| Fieldizer::<F>::to_field(&limb[0]))
+                .collect_vec();
+            assert_eq!(
+                recombine_limbs(&evals, bit_len),
+                Fieldizer::<F>::to_field(&r)
+            );
+        }
+    }
+
+    #[test]
+    fn test_softmax_rows() {
+        let input_scaling = ScalingFactor::from_absolute_max(4.0, None);
+        let softmax = Softmax {
+            table: Some(SoftmaxTable::new(&input_scaling)),
+        };
+        let (num_rows, num_entries) = (3, 5);
+        let float_input = Tensor::<f32>::random(&vec![num_rows, num_entries]).scalar_mul(&4.0);
+        let float_output = softmax.op(&float_input);
+        let input = float_input.quantize(&input_scaling).pad_next_power_of_two();
+        let output = softmax.quantized_op(&input, num_rows, num_entries).unwrap();
+        assert_eq!(output.get_shape(), vec![4, 8]);
+        // the padded rows and the padded entries of each row are zero
+        assert!(
+            output
+                .get_data()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| i / 8 >= num_rows || i % 8 >= num_entries)
+                .all(|(_, p)| *p == 0)
+        );
+        // each row is normalized independently, as a vector
+        for row in 0..num_rows {
+            let float_row = Tensor::new(
+                vec![num_entries],
+                float_input.get_data()[row * num_entries..(row + 1) * num_entries].to_vec(),
+            );
+            assert_eq!(
+                softmax.op(&float_row).get_data(),
+                &float_output.get_data()[row * num_entries..(row + 1) * num_entries]
+            );
+            let row_input = Tensor::new(vec![8], input.get_data()[row * 8..(row + 1) * 8].to_vec());
+            assert_eq!(
+                softmax
+                    .quantized_op(&row_input, 1, num_entries)
+                    .unwrap()
+                    .get_data(),
+                &output.get_data()[row * 8..(row + 1) * 8]
+            );
+        }
+    }
+
+    #[test]
+    fn test_softmax_sum_points() {
+        let num_vars = 5;
+        let evals = random_field_vector::<F>(1 << num_vars);
+        let mle = evals.clone().into_mle();
+        for num_entries in [1, 13, 16, 31, 32] {
+            let points = sum_points::<F>(num_vars, num_entries);
+            let sum = points
+                .iter()
+                .map(|(_, point)| mle.evaluate(point))
+                .collect_vec();
+            assert_eq!(
+                SoftmaxCtx::sum(&points, &sum),
+                evals
+                    .iter()
+                    .take(num_entries)
+                    .fold(F::ZERO, |acc, eval| acc + *eval)
+            );
+        }
+    }
+
+    #[test]
+    fn test_softmax_prefix_points() {
+        let num_vars = 5;
+        let evals = random_field_vector::<F>(1 << num_vars);
+        let mle = evals.clone().into_mle();
+        let point = random_field_vector::<F>(num_vars);
+        let betas = compute_betas_eval(&point);
+        for num_entries in [1, 13, 16, 31, 32] {
+            let points = prefix_points(&point, num_entries);
+            let prefix_evals = points
+                .iter()
+                .map(|(_, point)| mle.evaluate(point))
+                .collect_vec();
+            assert_eq!(
+                SoftmaxCtx::sum(&points, &prefix_evals),
+                evals
+                    .iter()
+                    .zip(&betas)
+                    .take(num_entries)
+                    .fold(F::ZERO, |acc, (eval, beta)| acc + *eval * beta)
+            );
+        }
+    }
+}
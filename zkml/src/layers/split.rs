@@ -0,0 +1,403 @@
+//! Split of a tensor into several tensors along one dimension, e.g. to separate the heads of an attention layer.
+use anyhow::{Result, anyhow, ensure};
+use ff_ext::ExtensionField;
+use itertools::Itertools;
+use multilinear_extensions::mle::IntoMLE;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use transcript::Transcript;
+
+use crate::{
+    Claim, Prover, Tensor,
+    commit::{precommit::PolyID, same_poly},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{
+        LayerCtx, LayerProof,
+        concat::{selector_num_vars, selector_offset, selector_point},
+    },
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    tensor::Number,
+};
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Splits its input along dimension `axis` into tensors of sizes `sizes` along this dimension.
+///
+/// Once padded, all the outputs but the last one must not be padded along `axis`, and the last one must
+/// have the same padded size: the padded outputs are then consecutive slices of the padded input, and
+/// the variables of the input MLE are the variables of the outputs, with the bits of the index of the
+/// output inserted as selector variables right after the variables of `axis`. A claim about an output is
+/// then a claim about the input, over the output point with the selector variables fixed to the index
+/// of the output, and the claims for all the outputs are batched into a single claim about the input.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Split {
+    axis: usize,
+    sizes: Vec<usize>,
+    /// Whether the layer is applied to a padded input, in which case the outputs are padded along `axis`
+    padded: bool,
+}
+
+/// Proof for the split layer: the proof batching the claims about the input derived from each output claim.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SplitProof<E: ExtensionField> {
+    batching: same_poly::Proof<E>,
+}
+
+impl Split {
+    pub fn new(axis: usize, sizes: Vec<usize>) -> Result<Self> {
+        ensure!(
+            !sizes.is_empty() && sizes.iter().all(|&s| s > 0),
+            "Invalid sizes {:?} for split layer",
+            sizes
+        );
+        Ok(Self {
+            axis,
+            sizes,
+            padded: false,
+        })
+    }
+
+    pub fn axis(&self) -> usize {
+        self.axis
+    }
+
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// Size along `axis` of each padded output
+    pub(crate) fn padded_size(&self) -> usize {
+        self.sizes.last().unwrap().next_power_of_two()
+    }
+
+    /// Returns the layer to be proven over a padded input
+    pub(crate) fn padded(self) -> Result<Self> {
+        let padded_size = self.padded_size();
+        ensure!(
+            self.sizes[..self.sizes.len() - 1]
+                .iter()
+                .all(|&s| s == padded_size),
+            "Split into sizes {:?} moves entries of the padded input: all the sizes but the last one must \
+            be the next power of two of the last one",
+            self.sizes
+        );
+        Ok(Self {
+            padded: true,
+            ..self
+        })
+    }
+
+    /// Shapes of the outputs for an input of shape `input_shape`
+    fn part_shapes(&self, input_shape: &[usize], padding_mode: PaddingMode) -> Vec<Vec<usize>> {
+        let sizes = match padding_mode {
+            PaddingMode::NoPadding => self.sizes.clone(),
+            PaddingMode::Padding => vec![self.padded_size(); self.sizes.len()],
+        };
+        sizes
+            .into_iter()
+            .map(|size| {
+                let mut shape = input_shape.to_vec();
+                shape[self.axis] = size;
+                shape
+            })
+            .collect()
+    }
+
+    /// Computes the points of the claims about the input corresponding to the points `output_points` of
+    /// the claims about each output, for an input of padded shape `padded_input_shape`
+    fn input_points<E: ExtensionField>(
+        &self,
+        output_points: &[&[E]],
+        padded_input_shape: &[usize],
+    ) -> Result<Vec<Vec<E>>> {
+        ensure!(
+            output_points.len() == self.sizes.len(),
+            "Found {} output claims for split layer, expected {}",
+            output_points.len(),
+            self.sizes.len()
+        );
+        ensure!(
+            self.axis < padded_input_shape.len(),
+            "Invalid axis {} for split layer applied to an input of shape {:?}",
+            self.axis,
+            padded_input_shape
+        );
+        let mut part_shape = padded_input_shape.to_vec();
+        part_shape[self.axis] = self.padded_size();
+        let offset = selector_offset(&part_shape, self.axis)?;
+        let num_selector_vars = selector_num_vars(self.sizes.len());
+        ensure!(
+            padded_input_shape[self.axis] == self.padded_size() << num_selector_vars,
+            "Padded input shape {:?} doesn't match the sizes {:?} of split layer",
+            padded_input_shape,
+            self.sizes
+        );
+        let num_output_vars = part_shape.iter().product::<usize>().ilog2() as usize;
+        output_points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                ensure!(
+                    point.len() == num_output_vars,
+                    "Invalid claim for output {i} of split layer: expected {} variables, found {}",
+                    num_output_vars,
+                    point.len()
+                );
+                Ok(point[..offset]
+                    .iter()
+                    .cloned()
+                    .chain(selector_point::<E>(i, num_selector_vars))
+                    .chain(point[offset..].iter().cloned())
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+impl OpInfo for Split {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes
+            .iter()
+            .flat_map(|s| self.part_shapes(s, padding_mode))
+            .collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        assert_eq!(num_inputs, 1);
+        self.sizes.len()
+    }
+
+    fn describe(&self) -> String {
+        format!("Split: axis {}, sizes {:?}", self.axis, self.sizes)
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<N: Number> Evaluate<N> for Split {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<N>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<N, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found more than 1 input when evaluating split layer"
+        );
+        let input = inputs[0];
+        let shape = input.get_shape();
+        ensure!(
+            self.axis < shape.len(),
+            "Invalid axis {} for split layer applied to an input of shape {:?}",
+            self.axis,
+            shape
+        );
+        let outputs = if self.padded {
+            // the padded input ends with the padding of the outputs to a power of two number of outputs
+            let mut sizes = vec![self.padded_size(); self.sizes.len()];
+            let rest = shape[self.axis]
+                .checked_sub(sizes.iter().sum())
+                .ok_or(anyhow!(
+                    "Padded input of shape {:?} is too small for split layer",
+                    shape
+                ))?;
+            sizes.push(rest);
+            let mut outputs = input.split_axis(self.axis, &sizes);
+            outputs.truncate(self.sizes.len());
+            outputs
+        } else {
+            ensure!(
+                shape[self.axis] == self.sizes.iter().sum::<usize>(),
+                "Can't split dimension {} of shape {:?} into sizes {:?}",
+                self.axis,
+                shape,
+                self.sizes
+            );
+            input.split_axis(self.axis, &self.sizes)
+        };
+        Ok(LayerOut::from_vec(outputs))
+    }
+}
+
+impl<E> ProveInfo<E> for Split
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} input shapes for split layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        aux.last_output_shape = self.output_shapes(&aux.last_output_shape, PaddingMode::Padding);
+        Ok((LayerCtx::Split(self.clone()), aux))
+    }
+}
+
+impl PadOp for Split {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::split(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Split
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = Split;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of split layer, expected 1",
+            step_data.inputs.len()
+        );
+        let input = &step_data.inputs[0];
+        let output_points = last_claims
+            .iter()
+            .map(|claim| claim.point.as_slice())
+            .collect_vec();
+        let input_points = ctx.input_points(&output_points, &input.get_shape())?;
+        let input_mle = input.get_data().to_vec().into_mle();
+        let sp_ctx = same_poly::Context::<E>::new(input_points[0].len());
+        let mut sp_prover = same_poly::Prover::<E>::new(input_mle);
+        for (point, claim) in input_points.into_iter().zip(last_claims) {
+            sp_prover.add_claim(Claim::new(point, claim.eval))?;
+        }
+        let batching = sp_prover.prove(&sp_ctx, prover.transcript)?;
+        let claim = batching.extract_claim();
+        prover.push_proof(node_id, LayerProof::Split(SplitProof { batching }));
+        Ok(vec![claim])
+    }
+}
+
+impl<E> VerifiableCtx<E> for Split
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = SplitProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            shape_step.padded_input_shape.len() == 1,
+            "Found {} input shapes for split layer, expected 1",
+            shape_step.padded_input_shape.len()
+        );
+        let output_points = last_claims
+            .iter()
+            .map(|claim| claim.point.as_slice())
+            .collect_vec();
+        let input_points = self.input_points(&output_points, &shape_step.padded_input_shape[0])?;
+        let sp_ctx = same_poly::Context::<E>::new(input_points[0].len());
+        let mut sp_verifier = same_poly::Verifier::<E>::new(&sp_ctx);
+        for (point, claim) in input_points.into_iter().zip(last_claims) {
+            sp_verifier.add_claim(Claim::new(point, claim.eval))?;
+        }
+        let claim = sp_verifier.verify(&proof.batching, verifier.transcript)?;
+        Ok(vec![claim])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+    use multilinear_extensions::mle::MultilinearExtension;
+
+    use crate::{
+        Element, layers::provable::evaluate_layer, quantization::TensorFielder,
+        testing::random_field_vector,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_split_input_points() {
+        let input_shape = vec![3, 12];
+        let input = Tensor::<Element>::random(&input_shape);
+        let split = Split::new(1, vec![4, 4, 4]).unwrap();
+        let outputs = evaluate_layer::<GoldilocksExt2, _, _>(&split, &[&input], None)
+            .unwrap()
+            .outputs()
+            .into_iter()
+            .cloned()
+            .collect_vec();
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs.iter().all(|o| o.get_shape() == vec![3, 4]));
+
+        let split = split.padded().unwrap();
+        let padded_input = input.pad_next_power_of_two();
+        let padded_outputs = evaluate_layer::<GoldilocksExt2, _, _>(&split, &[&padded_input], None)
+            .unwrap()
+            .outputs()
+            .into_iter()
+            .cloned()
+            .collect_vec();
+        // the padded outputs are the padding of the outputs
+        for (padded_output, output) in padded_outputs.iter().zip(&outputs) {
+            assert_eq!(*padded_output, output.pad_next_power_of_two());
+        }
+
+        let output_points = (0..3)
+            .map(|_| random_field_vector::<GoldilocksExt2>(4))
+            .collect_vec();
+        let input_points = split
+            .input_points(
+                &output_points.iter().map(|p| p.as_slice()).collect_vec(),
+                &padded_input.get_shape(),
+            )
+            .unwrap();
+        let padded_input: Tensor<GoldilocksExt2> = padded_input.to_fields();
+        let input_mle = padded_input.get_data().to_vec().into_mle();
+        for ((output, output_point), input_point) in padded_outputs
+            .into_iter()
+            .zip(&output_points)
+            .zip(&input_points)
+        {
+            let output: Tensor<GoldilocksExt2> = output.to_fields();
+            let output_eval = output.get_data().to_vec().into_mle().evaluate(output_point);
+            assert_eq!(input_mle.evaluate(input_point), output_eval);
+        }
+    }
+
+    #[test]
+    fn test_split_padded_sizes() {
+        assert!(Split::new(0, vec![4, 3]).unwrap().padded().is_ok());
+        assert!(Split::new(0, vec![3, 4]).unwrap().padded().is_err());
+        assert!(Split::new(0, vec![4, 2]).unwrap().padded().is_err());
+        assert!(Split::new(0, vec![]).is_err());
+    }
+}
@@ -0,0 +1,771 @@
+//! Top-k layer, revealing the indices of the biggest entries of its input, e.g. the label predicted
+//! by a classifier, rather than the logits themselves.
+use std::collections::HashMap;
+
+use crate::{
+    Claim, Element, Prover, ScalingFactor, ScalingStrategy, Tensor,
+    commit::{compute_betas_eval, identity_eval, precommit::PolyID},
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::{LayerCtx, LayerProof, softmax::sum_points},
+    lookup::{
+        context::{LookupWitnessGen, TableType},
+        logup_gkr::{
+            prover::batch_prove as logup_batch_prove, structs::LogUpProof,
+            verifier::verify_logup_proof,
+        },
+    },
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo, topk},
+    quantization::{self, Fieldizer, IntoElement},
+    tensor::Number,
+};
+use anyhow::{Result, anyhow, ensure};
+use ff_ext::ExtensionField;
+use gkr::util::ceil_log2;
+use itertools::{Itertools, izip};
+use multilinear_extensions::{
+    mle::{DenseMultilinearExtension, IntoMLE, MultilinearExtension},
+    virtual_poly::{ArcMultilinearExtension, VPAuxInfo, VirtualPolynomial},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sumcheck::structs::{IOPProof, IOPProverState, IOPVerifierState};
+use transcript::Transcript;
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, QuantizeOp, QuantizeOutput,
+    VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Layer returning the indices of the `k` biggest entries of a vector, sorted by decreasing entry. Ties are
+/// broken in favour of the smallest index, so `k = 1` gives the argmax.
+///
+/// Once quantized, the layer proves its output by range checking the difference between the `k`-th
+/// biggest entry and every other entry, like max pooling does, and the differences between consecutive
+/// selected entries. Only the indices are revealed to the verifier, never the entries of the input.
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct TopK {
+    /// Number of indices returned by the layer
+    k: usize,
+    /// Number of entries of the unpadded input vector, known once the layer is padded
+    num_entries: Option<usize>,
+    /// Bit length of the quantized input, over which the differences are range checked
+    #[serde(default = "quantization::default_bit_len")]
+    bit_len: usize,
+}
+
+/// Info about the top-k layer derived during the setup phase
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TopKCtx {
+    pub(crate) k: usize,
+    pub(crate) num_entries: usize,
+    pub(crate) num_vars: usize,
+    pub(crate) bit_len: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TopKProof<E: ExtensionField>
+where
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    /// Indices of the `k` biggest entries of the input, i.e. the output of the layer
+    pub(crate) indices: Vec<usize>,
+    /// the lookup proof for the range check of the differences, see [`differences`]
+    pub(crate) lookup: LogUpProof<E>,
+    /// sumcheck proof reducing the claim about the differences to a claim about the input
+    pub(crate) sumcheck: IOPProof<E>,
+    /// Evaluation of the input at the point of the sumcheck
+    pub(crate) input_eval: E,
+}
+
+impl TopK {
+    pub fn new(k: usize) -> Result<Self> {
+        ensure!(k > 0, "TopK must return at least one index");
+        Ok(Self {
+            k,
+            num_entries: None,
+            bit_len: quantization::default_bit_len(),
+        })
+    }
+
+    pub fn argmax() -> Self {
+        Self {
+            k: 1,
+            num_entries: None,
+            bit_len: quantization::default_bit_len(),
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the layer to be proven over an input vector with `num_entries` unpadded entries
+    pub fn padded(self, num_entries: usize) -> Result<Self> {
+        ensure!(
+            self.k <= num_entries,
+            "TopK can't return {} indices out of {} entries",
+            self.k,
+            num_entries
+        );
+        Ok(Self {
+            num_entries: Some(num_entries),
+            ..self
+        })
+    }
+
+    /// Returns the indices of the `k` biggest entries among the first `num_entries` entries of `input`.
+    /// The padded layer pads the output to the next power of two.
+    pub fn op<T: Number>(&self, input: &Tensor<T>, num_entries: usize) -> Result<Vec<usize>> {
+        ensure!(
+            self.k <= num_entries && num_entries <= input.get_data().len(),
+            "TopK can't return {} indices out of {} entries",
+            self.k,
+            num_entries
+        );
+        let mut indices = top_k_indices(&input.get_data()[..num_entries], self.k);
+        if self.num_entries.is_some() {
+            indices.resize(self.k.next_power_of_two(), 0);
+        }
+        Ok(indices)
+    }
+}
+
+/// Indices of the `k` biggest values, sorted by decreasing value and then by increasing index
+fn top_k_indices<T: Number>(values: &[T], k: usize) -> Vec<usize> {
+    // the sort is stable, so equal values keep the order of their indices
+    (0..values.len())
+        .sorted_by(|a, b| values[*b].compare(&values[*a]))
+        .take(k)
+        .collect()
+}
+
+/// Number of entries of the input vector of a top-k layer with the given unpadded input shapes
+fn num_entries(unpadded_input_shapes: &[Vec<usize>]) -> Result<usize> {
+    ensure!(
+        unpadded_input_shapes.len() == 1,
+        "Found {} inputs for top-k layer, expected 1",
+        unpadded_input_shapes.len()
+    );
+    ensure!(
+        unpadded_input_shapes[0].len() == 1,
+        "TopK is only supported over vectors, found input shape {:?}",
+        unpadded_input_shapes[0]
+    );
+    Ok(unpadded_input_shapes[0][0])
+}
+
+/// Differences range checked to prove the output of the layer, which are all non negative iff the
+/// selected entries are the biggest ones, sorted by decreasing entry and then by increasing index:
+/// - for a non selected entry `x_j`, the difference between the `k`-th biggest entry `v` and `x_j`,
+///   minus one if `x_j` precedes `v`
+/// - for all the selected entries but the last one, the difference with the next selected entry,
+///   minus one if the next entry precedes it
+///
+/// The differences are zero for the `k`-th selected entry and the padded entries.
+fn differences(input: &[Element], indices: &[usize], num_entries: usize) -> Vec<Element> {
+    let last = *indices.last().unwrap();
+    let mut diffs = input
+        .iter()
+        .enumerate()
+        .map(|(j, x)| {
+            if j < num_entries && !indices.contains(&j) {
+                input[last] - x - (j < last) as Element
+            } else {
+                0
+            }
+        })
+        .collect_vec();
+    indices.iter().tuple_windows().for_each(|(i, next)| {
+        diffs[*i] = input[*i] - input[*next] - (next < i) as Element;
+    });
+    diffs
+}
+
+/// Binary representation of `index` as a point over `num_vars` variables
+fn index_point<E: ExtensionField>(index: usize, num_vars: usize) -> Vec<E> {
+    (0..num_vars)
+        .map(|i| E::from(((index >> i) & 1) as u64))
+        .collect()
+}
+
+/// Computes `sum_{j < len} prod_p eq(p, j)` for the given points without iterating over the first
+/// `len` entries, see [`sum_points`]
+pub(crate) fn prefix_eq_eval<E: ExtensionField>(points: &[&[E]], len: usize) -> E {
+    let num_vars = points[0].len();
+    sum_points::<E>(num_vars, len)
+        .into_iter()
+        .fold(E::ZERO, |acc, (weight, block)| {
+            let eval = block.iter().enumerate().fold(E::ONE, |eval, (i, b)| {
+                let (ones, zeros) = points.iter().fold((E::ONE, E::ONE), |(ones, zeros), p| {
+                    (ones * p[i], zeros * (E::ONE - p[i]))
+                });
+                eval * (ones * *b + zeros * (E::ONE - *b))
+            });
+            acc + weight * eval
+        })
+}
+
+impl OpInfo for TopK {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        let output_len = match padding_mode {
+            PaddingMode::NoPadding => self.k,
+            PaddingMode::Padding => self.k.next_power_of_two(),
+        };
+        input_shapes.iter().map(|_| vec![output_len]).collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        if self.k == 1 {
+            "ArgMax".to_string()
+        } else {
+            format!("TopK: k = {}", self.k)
+        }
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl Evaluate<f32> for TopK {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<f32>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<f32, E>> {
+        let indices = self.op(inputs[0], num_entries(&unpadded_input_shapes)?)?;
+        Ok(LayerOut::from_vec(vec![Tensor::new(
+            vec![indices.len()],
+            indices.into_iter().map(|i| i as f32).collect(),
+        )]))
+    }
+}
+
+impl Evaluate<Element> for TopK {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<Element>],
+        unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<Element, E>> {
+        let indices = self.op(inputs[0], num_entries(&unpadded_input_shapes)?)?;
+        Ok(LayerOut::from_vec(vec![Tensor::new(
+            vec![indices.len()],
+            indices.into_iter().map(|i| i as Element).collect(),
+        )]))
+    }
+}
+
+impl QuantizeOp for TopK {
+    type QuantizedOp = TopK;
+
+    fn quantize_op<S: ScalingStrategy>(
+        self,
+        _data: &S::AuxData,
+        _node_id: NodeId,
+        input_scaling: &[ScalingFactor],
+    ) -> Result<QuantizeOutput<Self::QuantizedOp>> {
+        ensure!(
+            input_scaling.len() == 1,
+            "Found {} input scaling factors for top-k layer, expected 1",
+            input_scaling.len()
+        );
+        // the indices are not affected by the scale of the input, so they are output as they are
+        Ok(QuantizeOutput {
+            quanzited_op: Self {
+                bit_len: input_scaling[0].bit_len(),
+                ..self
+            },
+            output_scalings: vec![ScalingFactor::from_scale(1.0, None)],
+            requant_layer: None,
+            rescale_layer: None,
+        })
+    }
+}
+
+impl<E> ProveInfo<E> for TopK
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        let num_entries = self
+            .num_entries
+            .ok_or(anyhow!("TopK must be padded to be proven"))?;
+        ensure!(
+            aux.last_output_shape.len() == 1,
+            "Found {} inputs for top-k layer, expected 1",
+            aux.last_output_shape.len()
+        );
+        let num_vars = aux.last_output_shape[0]
+            .iter()
+            .map(|dim| ceil_log2(*dim))
+            .sum::<usize>();
+        aux.tables.insert(TableType::Range(self.bit_len));
+        aux.last_output_shape = vec![vec![self.k.next_power_of_two()]];
+        Ok((
+            LayerCtx::TopK(TopKCtx {
+                k: self.k,
+                num_entries,
+                num_vars,
+                bit_len: self.bit_len,
+            }),
+            aux,
+        ))
+    }
+}
+
+impl PadOp for TopK {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        topk(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for TopK
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = TopKCtx;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        node_id: NodeId,
+        ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for top-k layer, expected 1",
+            last_claims.len()
+        );
+        Ok(vec![ctx.prove_step(
+            prover,
+            step_data.inputs[0].get_data(),
+            step_data.outputs.outputs()[0].get_data(),
+            node_id,
+        )?])
+    }
+
+    fn gen_lookup_witness(
+        &self,
+        id: NodeId,
+        gen: &mut LookupWitnessGen<E>,
+        step_data: &StepData<Element, E>,
+    ) -> Result<()> {
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found more than 1 input in inference step of top-k layer"
+        );
+        let num_entries = self
+            .num_entries
+            .ok_or(anyhow!("TopK must be padded to be proven"))?;
+        let input = step_data.inputs[0].get_data();
+        let indices = top_k_indices(&input[..num_entries], self.k);
+        let diffs = differences(input, &indices, num_entries);
+        ensure!(
+            diffs.iter().all(|d| *d >= 0 && *d >> self.bit_len == 0),
+            "Input of top-k layer is out of the quantized range"
+        );
+
+        let table_type = TableType::Range(self.bit_len);
+        gen.tables.insert(table_type);
+        let table_lookup_map = gen
+            .lookups
+            .entry(table_type)
+            .or_insert_with(HashMap::default);
+        diffs
+            .iter()
+            .for_each(|d| *table_lookup_map.entry(*d).or_insert(0u64) += 1);
+        let column = diffs
+            .iter()
+            .map(|d| Fieldizer::<E>::to_field(d).as_bases()[0])
+            .collect_vec();
+        gen.lookups_no_challenges
+            .insert(id, vec![(vec![column], 1, table_type)]);
+
+        Ok(())
+    }
+}
+
+impl TopKCtx {
+    /// Coefficients of the selected entries in the selector of [`Self::selector_evals`], given
+    /// `eq(r, i)` for each selected index `i` and the sum of `eq(r, j)` over the non selected entries
+    fn selected_coeffs<E: ExtensionField>(&self, selected_eqs: &[E], not_selected: E) -> Vec<E> {
+        (0..selected_eqs.len())
+            .map(|l| {
+                let own = if l + 1 < selected_eqs.len() {
+                    selected_eqs[l]
+                } else {
+                    not_selected
+                };
+                let previous = if l > 0 { selected_eqs[l - 1] } else { E::ZERO };
+                own - previous
+            })
+            .collect()
+    }
+
+    /// Evaluations over the input domain of the selector `S` such that `sum_j S(j) * x_j` is the
+    /// evaluation at `r` of the differences of [`differences`], up to the constant returned by
+    /// [`Self::selected_sum`]. It is `S(j) = -eq(r, j)` for the unpadded and non selected entries, while
+    /// the coefficients of the selected entries are given by [`Self::selected_coeffs`].
+    fn selector_evals<E: ExtensionField>(&self, lookup_point: &[E], indices: &[usize]) -> Vec<E> {
+        let betas = compute_betas_eval(lookup_point);
+        let mut selector = betas
+            .iter()
+            .enumerate()
+            .map(|(j, beta)| {
+                if j < self.num_entries && !indices.contains(&j) {
+                    -*beta
+                } else {
+                    E::ZERO
+                }
+            })
+            .collect_vec();
+        let not_selected = -selector.iter().copied().sum::<E>();
+        let selected_eqs = indices.iter().map(|i| betas[*i]).collect_vec();
+        izip!(indices, self.selected_coeffs(&selected_eqs, not_selected))
+            .for_each(|(i, coeff)| selector[*i] = coeff);
+        selector
+    }
+
+    /// Returns `eq(r, i)` for each selected index `i`, together with the sum of `eq(r, j)` over the
+    /// unpadded and non selected entries
+    fn selected_eqs<E: ExtensionField>(
+        &self,
+        lookup_point: &[E],
+        indices: &[usize],
+    ) -> (Vec<E>, E) {
+        let selected_eqs = indices
+            .iter()
+            .map(|i| identity_eval(lookup_point, &index_point::<E>(*i, self.num_vars)))
+            .collect_vec();
+        let not_selected = prefix_eq_eval(&[lookup_point], self.num_entries)
+            - selected_eqs.iter().copied().sum::<E>();
+        (selected_eqs, not_selected)
+    }
+
+    /// Evaluation of the selector returned by [`Self::selector_evals`] at `input_point`
+    fn selector_eval<E: ExtensionField>(
+        &self,
+        lookup_point: &[E],
+        input_point: &[E],
+        indices: &[usize],
+    ) -> E {
+        let (selected_eqs, not_selected) = self.selected_eqs(lookup_point, indices);
+        let coeffs = self.selected_coeffs(&selected_eqs, not_selected);
+        izip!(indices, selected_eqs, coeffs).fold(
+            -prefix_eq_eval(&[lookup_point, input_point], self.num_entries),
+            |acc, (i, eq, coeff)| {
+                let input_eq = identity_eval(input_point, &index_point::<E>(*i, self.num_vars));
+                acc + (eq + coeff) * input_eq
+            },
+        )
+    }
+
+    /// Sum over the input domain of the input times the selector returned by [`Self::selector_evals`],
+    /// derived from the evaluation of the differences at the point of the lookup
+    fn selected_sum<E: ExtensionField>(
+        &self,
+        lookup_point: &[E],
+        indices: &[usize],
+        diff_eval: E,
+    ) -> E {
+        let (selected_eqs, _) = self.selected_eqs(lookup_point, indices);
+        let last = *indices.last().unwrap();
+        // the differences subtract one for the non selected entries preceding the last selected one
+        let preceding = izip!(indices, &selected_eqs)
+            .filter(|(i, _)| **i < last)
+            .fold(prefix_eq_eval(&[lookup_point], last), |acc, (_, eq)| {
+                acc - *eq
+            });
+        // and for the selected entries followed by a preceding one
+        izip!(indices, &selected_eqs)
+            .tuple_windows()
+            .filter(|((i, _), (next, _))| next < i)
+            .fold(diff_eval + preceding, |acc, ((_, eq), _)| acc + *eq)
+    }
+
+    #[timed::timed_instrument(name = "Prover::prove_topk_step")]
+    pub(crate) fn prove_step<E: ExtensionField, T: Transcript<E>>(
+        &self,
+        prover: &mut Prover<E, T>,
+        input: &[E],
+        output: &[E],
+        node_id: NodeId,
+    ) -> Result<Claim<E>>
+    where
+        E: ExtensionField + Serialize + DeserializeOwned,
+        E::BaseField: Serialize + DeserializeOwned,
+    {
+        let indices = output[..self.k]
+            .iter()
+            .map(|i| i.into_element() as usize)
+            .collect_vec();
+        prover
+            .transcript
+            .append_field_element_exts(&indices.iter().map(|i| E::from(*i as u64)).collect_vec());
+
+        // 1. Range check the differences
+        let witness = prover.lookup_witness(node_id)?;
+        let lookup = logup_batch_prove(&witness, prover.transcript)?;
+        let lookup_point = &lookup.output_claims()[0].point;
+
+        // 2. Reduce the claim about the differences, which are linear in the input, to a claim about
+        // the input
+        let input_mle: ArcMultilinearExtension<E> = input.to_vec().into_mle().into();
+        let selector_mle: ArcMultilinearExtension<E> =
+            DenseMultilinearExtension::<E>::from_evaluations_ext_vec(
+                self.num_vars,
+                self.selector_evals(lookup_point, &indices),
+            )
+            .into();
+        let mut vp = VirtualPolynomial::<E>::new(self.num_vars);
+        vp.add_mle_list(vec![input_mle, selector_mle], E::ONE);
+        #[allow(deprecated)]
+        let (sumcheck, state) = IOPProverState::<E>::prove_parallel(vp, prover.transcript);
+        let input_eval = state.get_mle_final_evaluations()[0];
+        let input_claim = Claim::new(sumcheck.point.clone(), input_eval);
+
+        prover.push_proof(
+            node_id,
+            LayerProof::TopK(TopKProof {
+                indices,
+                lookup,
+                sumcheck,
+                input_eval,
+            }),
+        );
+        Ok(input_claim)
+    }
+}
+
+impl OpInfo for TopKCtx {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        let output_len = match padding_mode {
+            PaddingMode::NoPadding => self.k,
+            PaddingMode::Padding => self.k.next_power_of_two(),
+        };
+        input_shapes.iter().map(|_| vec![output_len]).collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!("TopK ctx: k = {}, {} entries", self.k, self.num_entries)
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<E> VerifiableCtx<E> for TopKCtx
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = TopKProof<E>;
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        verifier: &mut Verifier<E, T>,
+        _shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for top-k layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            proof.indices.len() == self.k,
+            "Expected {} indices in top-k proof, found {}",
+            self.k,
+            proof.indices.len()
+        );
+        ensure!(
+            proof.indices.iter().all(|i| *i < self.num_entries),
+            "TopK index out of the {} entries of the input",
+            self.num_entries
+        );
+        // the order of the selected entries is range checked, but it can't tell a repeated index
+        ensure!(
+            proof.indices.iter().all_unique(),
+            "TopK indices are not distinct"
+        );
+
+        // 1. Check the indices are the output of the layer
+        let last_claim = last_claims[0];
+        let mut output = proof
+            .indices
+            .iter()
+            .map(|i| E::from(*i as u64))
+            .collect_vec();
+        ensure!(
+            output.len() <= 1 << last_claim.point.len(),
+            "TopK output claim is made over {} variables, but there are {} indices",
+            last_claim.point.len(),
+            output.len()
+        );
+        verifier.transcript.append_field_element_exts(&output);
+        output.resize(1 << last_claim.point.len(), E::ZERO);
+        ensure!(
+            output.into_mle().evaluate(&last_claim.point) == last_claim.eval,
+            "TopK output claim doesn't match the indices"
+        );
+
+        // 2. Verify the range check of the differences
+        let table_type = TableType::Range(self.bit_len);
+        let (constant_challenge, column_separation_challenge) = verifier
+            .challenge_storage
+            .as_ref()
+            .unwrap()
+            .get_challenges_by_name(&table_type.name())
+            .ok_or(anyhow!(
+                "Couldn't get challenges for LookupType: {}",
+                table_type.name()
+            ))?;
+        let lookup_claims = verify_logup_proof(
+            &proof.lookup,
+            1,
+            constant_challenge,
+            column_separation_challenge,
+            verifier.transcript,
+        )?;
+        ensure!(
+            lookup_claims.claims().len() == 1,
+            "Invalid number of lookup claims in top-k proof"
+        );
+        let diff_claim = &lookup_claims.claims()[0];
+
+        // 3. Verify the reduction of the claim about the differences to a claim about the input
+        let claimed_sum = self.selected_sum(&diff_claim.point, &proof.indices, diff_claim.eval);
+        let aux = VPAuxInfo::<E>::from_mle_list_dimensions(&[vec![self.num_vars; 2]]);
+        let subclaim =
+            IOPVerifierState::<E>::verify(claimed_sum, &proof.sumcheck, &aux, verifier.transcript);
+        let input_point = subclaim
+            .point
+            .iter()
+            .map(|chal| chal.elements)
+            .collect_vec();
+        let selector_eval = self.selector_eval(&diff_claim.point, &input_point, &proof.indices);
+        ensure!(
+            proof.input_eval * selector_eval == subclaim.expected_evaluation,
+            "TopK input sumcheck claim failed"
+        );
+
+        Ok(vec![Claim::new(input_point, proof.input_eval)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use goldilocks::GoldilocksExt2;
+
+    use crate::testing::random_field_vector;
+
+    use super::*;
+
+    type F = GoldilocksExt2;
+
+    #[test]
+    fn test_topk_indices() {
+        let input = Tensor::<Element>::new(vec![8], vec![3, -7, 12, 5, 12, 0, 3, 99]);
+        // the last entry is padding, so it is ignored, and ties are broken by the smallest index
+        let topk = TopK::new(3).unwrap();
+        assert_eq!(topk.op(&input, 7).unwrap(), vec![2, 4, 3]);
+        assert_eq!(TopK::argmax().op(&input, 7).unwrap(), vec![2]);
+        // the padded layer pads the indices to a power of two
+        let padded = topk.padded(7).unwrap();
+        assert_eq!(padded.op(&input, 7).unwrap(), vec![2, 4, 3, 0]);
+        assert!(TopK::new(8).unwrap().padded(7).is_err());
+
+        // the differences are zero for the last selected and the padded entries, the entries preceding
+        // the k-th biggest one must be strictly smaller, and the selected entries are compared with the
+        // next one
+        assert_eq!(differences(input.get_data(), &[2, 4, 3], 7), vec![
+            1, 11, 0, 0, 6, 5, 2, 0
+        ]);
+        // an entry equal to the k-th biggest one but preceding it gives a negative difference
+        assert_eq!(differences(input.get_data(), &[2, 6], 7)[0], -1);
+        // and so do equal selected entries out of the order of their indices
+        assert_eq!(differences(input.get_data(), &[4, 2, 3], 7)[4], -1);
+        assert!(differences(input.get_data(), &[3, 2, 4], 7)[3] < 0);
+    }
+
+    #[test]
+    fn test_topk_selector() {
+        let num_vars = 5;
+        let ctx = TopKCtx {
+            k: 3,
+            num_entries: 27,
+            num_vars,
+            bit_len: quantization::DEFAULT_BIT_LEN,
+        };
+        let indices = vec![20, 3, 11];
+        let lookup_point = random_field_vector::<F>(num_vars);
+        let input_point = random_field_vector::<F>(num_vars);
+
+        // the verifier evaluates the selector without iterating over the input domain
+        let selector = ctx.selector_evals(&lookup_point, &indices);
+        assert_eq!(
+            DenseMultilinearExtension::<F>::from_evaluations_ext_vec(num_vars, selector.clone())
+                .evaluate(&input_point),
+            ctx.selector_eval(&lookup_point, &input_point, &indices)
+        );
+
+        // the sum of the input times the selector is derived from the evaluation of the differences
+        let input = (0..1 << num_vars)
+            .map(|j| (j * 37 % 101) as Element - 50)
+            .collect_vec();
+        let input = Tensor::new(vec![1 << num_vars], input);
+        let indices = TopK::new(3).unwrap().op(&input, ctx.num_entries).unwrap();
+        let selector = ctx.selector_evals(&lookup_point, &indices);
+        let fields = input
+            .get_data()
+            .iter()
+            .map(Fieldizer::<F>::to_field)
+            .collect_vec();
+        let diff_eval = differences(input.get_data(), &indices, ctx.num_entries)
+            .iter()
+            .map(Fieldizer::<F>::to_field)
+            .collect_vec()
+            .into_mle()
+            .evaluate(&lookup_point);
+        assert_eq!(
+            fields
+                .iter()
+                .zip(&selector)
+                .fold(F::ZERO, |acc, (x, s)| acc + *x * *s),
+            ctx.selected_sum(&lookup_point, &indices, diff_eval)
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Transpose layer, permuting the dimensions of its input.
+use anyhow::{Result, ensure};
+use ff_ext::ExtensionField;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use transcript::Transcript;
+
+use crate::{
+    Claim, Prover, Tensor,
+    commit::precommit::PolyID,
+    iop::{
+        context::{ContextAux, ShapeStep},
+        verifier::Verifier,
+    },
+    layers::LayerCtx,
+    model::StepData,
+    padding::{PaddingMode, ShapeInfo},
+    tensor::Number,
+};
+
+use super::provable::{
+    Evaluate, LayerOut, NodeId, OpInfo, PadOp, ProvableOp, ProveInfo, VerifiableCtx,
+};
+
+const IS_PROVABLE: bool = true;
+
+/// Permutes the dimensions of its input: dimension `i` of the output is dimension `perm[i]` of the input.
+///
+/// Once padded, each dimension of the input has a power of two size, so the variables of the input MLE
+/// are the bits of the indices of each dimension, and the output MLE is the input MLE with the groups of
+/// variables of each dimension permuted. A claim about the output is then turned into a claim about the
+/// input by permuting the variables of its point, without any sumcheck: the layer has no proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transpose {
+    perm: Vec<usize>,
+}
+
+impl Transpose {
+    pub fn new(perm: Vec<usize>) -> Result<Self> {
+        ensure!(
+            perm.iter().copied().sorted().eq(0..perm.len()),
+            "{:?} is not a permutation of the dimensions",
+            perm
+        );
+        Ok(Self { perm })
+    }
+
+    pub fn perm(&self) -> &[usize] {
+        &self.perm
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Vec<usize> {
+        self.perm.iter().map(|&p| input_shape[p]).collect()
+    }
+
+    /// Computes the point of the input claim corresponding to the point `output_point` of an output claim,
+    /// for an input of padded shape `padded_input_shape`
+    pub(crate) fn input_point<E: Clone>(
+        &self,
+        output_point: &[E],
+        padded_input_shape: &[usize],
+    ) -> Result<Vec<E>> {
+        ensure!(
+            padded_input_shape.len() == self.perm.len(),
+            "Transpose of {} dimensions applied to an input of shape {:?}",
+            self.perm.len(),
+            padded_input_shape
+        );
+        ensure!(
+            padded_input_shape.iter().all(|d| d.is_power_of_two()),
+            "Input shape {:?} of transpose layer is not padded",
+            padded_input_shape
+        );
+        let num_vars = padded_input_shape
+            .iter()
+            .map(|d| d.ilog2() as usize)
+            .collect_vec();
+        ensure!(
+            output_point.len() == num_vars.iter().sum::<usize>(),
+            "Invalid output claim for transpose layer: expected {} variables, found {}",
+            num_vars.iter().sum::<usize>(),
+            output_point.len()
+        );
+        // the variables are in little endian order, so the last dimension is addressed by the first ones
+        let mut input_dim_vars = vec![&output_point[..0]; self.perm.len()];
+        let mut offset = 0;
+        for &p in self.perm.iter().rev() {
+            input_dim_vars[p] = &output_point[offset..offset + num_vars[p]];
+            offset += num_vars[p];
+        }
+        Ok(input_dim_vars
+            .into_iter()
+            .rev()
+            .flatten()
+            .cloned()
+            .collect())
+    }
+}
+
+impl OpInfo for Transpose {
+    fn output_shapes(
+        &self,
+        input_shapes: &[Vec<usize>],
+        _padding_mode: PaddingMode,
+    ) -> Vec<Vec<usize>> {
+        input_shapes.iter().map(|s| self.output_shape(s)).collect()
+    }
+
+    fn num_outputs(&self, num_inputs: usize) -> usize {
+        num_inputs
+    }
+
+    fn describe(&self) -> String {
+        format!("Transpose: {:?}", self.perm)
+    }
+
+    fn is_provable(&self) -> bool {
+        IS_PROVABLE
+    }
+}
+
+impl<N: Number> Evaluate<N> for Transpose {
+    fn evaluate<E: ExtensionField>(
+        &self,
+        inputs: &[&Tensor<N>],
+        _unpadded_input_shapes: Vec<Vec<usize>>,
+    ) -> Result<LayerOut<N, E>> {
+        ensure!(
+            inputs.len() == 1,
+            "Found more than 1 input when evaluating transpose layer"
+        );
+        let input = inputs[0];
+        ensure!(
+            input.get_shape().len() == self.perm.len(),
+            "Transpose of {} dimensions applied to an input of shape {:?}",
+            self.perm.len(),
+            input.get_shape()
+        );
+        Ok(LayerOut::from_vec(vec![input.permute_axes(&self.perm)]))
+    }
+}
+
+impl<E> ProveInfo<E> for Transpose
+where
+    E: ExtensionField + DeserializeOwned,
+    E::BaseField: Serialize + DeserializeOwned,
+{
+    fn step_info(&self, _id: PolyID, mut aux: ContextAux) -> Result<(LayerCtx<E>, ContextAux)> {
+        aux.last_output_shape = self.output_shapes(&aux.last_output_shape, PaddingMode::Padding);
+        Ok((LayerCtx::Transpose(self.clone()), aux))
+    }
+}
+
+impl PadOp for Transpose {
+    fn pad_node(self, si: &mut ShapeInfo) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        crate::padding::transpose(self, si)
+    }
+}
+
+impl<E> ProvableOp<E> for Transpose
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Ctx = Transpose;
+
+    fn prove<T: Transcript<E>>(
+        &self,
+        _node_id: NodeId,
+        _ctx: &Self::Ctx,
+        last_claims: Vec<&Claim<E>>,
+        step_data: &StepData<E, E>,
+        _prover: &mut Prover<E, T>,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for transpose layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            step_data.inputs.len() == 1,
+            "Found {} inputs in inference step of transpose layer, expected 1",
+            step_data.inputs.len()
+        );
+        let last_claim = last_claims[0];
+        let point = self.input_point(&last_claim.point, &step_data.inputs[0].get_shape())?;
+        Ok(vec![Claim::new(point, last_claim.eval)])
+    }
+}
+
+impl<E> VerifiableCtx<E> for Transpose
+where
+    E: ExtensionField,
+    E::BaseField: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    type Proof = ();
+
+    fn verify<T: Transcript<E>>(
+        &self,
+        _proof: &Self::Proof,
+        last_claims: &[&Claim<E>],
+        _verifier: &mut Verifier<E, T>,
+        shape_step: &ShapeStep,
+    ) -> Result<Vec<Claim<E>>> {
+        ensure!(
+            last_claims.len() == 1,
+            "Found {} output claims for transpose layer, expected 1",
+            last_claims.len()
+        );
+        ensure!(
+            shape_step.padded_input_shape.len() == 1,
+            "Found {} input shapes for transpose layer, expected 1",
+            shape_step.padded_input_shape.len()
+        );
+        let last_claim = last_claims[0];
+        let point = self.input_point(&last_claim.point, &shape_step.padded_input_shape[0])?;
+        Ok(vec![Claim::new(point, last_claim.eval)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+    use multilinear_extensions::mle::{IntoMLE, MultilinearExtension};
+
+    use crate::{
+        Element, layers::provable::evaluate_layer, quantization::TensorFielder,
+        testing::random_field_vector,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_transpose_input_point() {
+        let shape = vec![4, 2, 8];
+        let input = Tensor::<Element>::random(&shape);
+        let transpose = Transpose::new(vec![2, 0, 1]).unwrap();
+        let output = evaluate_layer::<GoldilocksExt2, _, _>(&transpose, &[&input], None)
+            .unwrap()
+            .outputs()[0]
+            .clone();
+        assert_eq!(output.get_shape(), vec![8, 4, 2]);
+
+        let output_point = random_field_vector::<GoldilocksExt2>(6);
+        let input_point = transpose.input_point(&output_point, &shape).unwrap();
+        let output: Tensor<GoldilocksExt2> = output.to_fields();
+        let input: Tensor<GoldilocksExt2> = input.to_fields();
+        let output_eval = output
+            .get_data()
+            .to_vec()
+            .into_mle()
+            .evaluate(&output_point);
+        let input_eval = input.get_data().to_vec().into_mle().evaluate(&input_point);
+        assert_eq!(input_eval, output_eval);
+
+        // the input must be padded
+        assert!(transpose.input_point(&output_point, &[3, 2, 8]).is_err());
+    }
+
+    #[test]
+    fn test_transpose_invalid_permutation() {
+        assert!(Transpose::new(vec![0, 2]).is_err());
+        assert!(Transpose::new(vec![1, 1, 0]).is_err());
+    }
+}
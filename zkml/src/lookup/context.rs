@@ -10,11 +10,20 @@ use transcript::Transcript;
 
 use crate::{
     Element,
-    commit::precommit::Context,
+    commit::{
+        compute_betas_eval,
+        poly_id::{COMMITTED_TABLE_POLY_ID, TABLE_POLY_ID_OFFSET},
+        precommit::{Context, PolyID},
+    },
     iop::ChallengeStorage,
     layers::{
-        activation::Relu,
+        activation::{
+            ClipTable, CommittedTable, LEAKY_RELU_ALPHA_BITS, LeakyReluTable, ReluTable,
+            SigmoidTable,
+        },
+        norm::InvSqrtTable,
         provable::{NodeId, ProvableOp},
+        softmax::SoftmaxTable,
     },
     lookup::logup_gkr::structs::LogUpInput,
     model::{InferenceTrace, ModelCtx, ToIterator},
@@ -22,40 +31,185 @@ use crate::{
 };
 
 use super::logup_gkr::error::LogUpError;
-pub const TABLE_POLY_ID_OFFSET: usize = 666;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TableType {
-    Relu,
-    Range,
+    Relu(ReluTable),
+    /// Range check of values over the given number of bits
+    Range(usize),
+    Sigmoid(SigmoidTable),
+    Committed(CommittedTable),
+    LeakyRelu(LeakyReluTable),
+    Clip(ClipTable),
+    Softmax(SoftmaxTable),
+    InvSqrt(InvSqrtTable),
+    /// Rows of the embedding tables, which are specific to each embedding layer: the table side
+    /// of the lookup argument is proven by the layers, this type only keys their challenges.
+    Embedding,
 }
 
 impl TableType {
+    /// Builds a table with two columns, the quantized inputs over `bit_len` bits and the outputs of
+    /// `f` over them.
+    fn two_column_table<E: ExtensionField>(
+        bit_len: usize,
+        column_separator: Element,
+        f: impl Fn(Element) -> Element,
+    ) -> (Vec<Element>, Vec<Vec<E::BaseField>>) {
+        let (comb, field): (Vec<Element>, Vec<(E::BaseField, E::BaseField)>) =
+            (quantization::min(bit_len) - 1..=quantization::max(bit_len))
+                .map(|i| {
+                    let out = f(i);
+                    let i_field: E = i.to_field();
+                    let out_field: E = out.to_field();
+                    (
+                        i + out * column_separator,
+                        (i_field.as_bases()[0], out_field.as_bases()[0]),
+                    )
+                })
+                .unzip();
+        let (col_one, col_two): (Vec<E::BaseField>, Vec<E::BaseField>) = field.into_iter().unzip();
+        (comb, vec![col_one, col_two])
+    }
+
+    /// Evaluation of the multilinear extension of the index `k` of the entries, i.e. `sum_i p_i 2^i`
+    fn index_eval<E: ExtensionField>(point: &[E]) -> E {
+        point
+            .iter()
+            .enumerate()
+            .fold(E::ZERO, |acc, (index, p)| acc + *p * E::from(1u64 << index))
+    }
+
+    /// Evaluation of the input column of a two column table, which contains all the quantized values
+    /// over `n` bits in increasing order, starting from `-2^(n-1)`, where `n` is the size of `point`.
+    fn input_column_eval<E: ExtensionField>(point: &[E]) -> E {
+        Self::index_eval(point) - E::from(1u64 << (point.len() - 1))
+    }
+
+    /// Returns `(sum_{k < c} eq(k, point), sum_{k < c} k * eq(k, point))`, i.e. the evaluations of
+    /// the multilinear extensions of the indicator of the first `c` entries and of their indices.
+    /// The entries below `c` are split by the highest bit where their index differs from `c`: for
+    /// each set bit `i` of `c`, the indices sharing the bits of `c` above `i` and unset at `i` sum to
+    /// the equality polynomial over the bits above `i`, whatever their lower bits.
+    fn prefix_evals<E: ExtensionField>(point: &[E], c: usize) -> (E, E) {
+        if c >> point.len() != 0 {
+            return (E::ONE, Self::index_eval(point));
+        }
+        let (mut count, mut sum, mut eq_prefix) = (E::ZERO, E::ZERO, E::ONE);
+        for (i, p) in point.iter().enumerate().rev() {
+            if (c >> i) & 1 == 1 {
+                let weight = eq_prefix * (E::ONE - p);
+                let high = E::from(((c >> (i + 1)) << (i + 1)) as u64);
+                count += weight;
+                sum += weight * (high + Self::index_eval(&point[..i]));
+                eq_prefix *= p;
+            } else {
+                eq_prefix *= E::ONE - p;
+            }
+        }
+        (count, sum)
+    }
+
+    /// Evaluation of the output column of a two column table clamping its inputs between `min` and
+    /// `max`: the outputs are `min` over the first entries, the inputs up to the entry of `max`,
+    /// and `max` over the last entries.
+    fn clamp_column_eval<E: ExtensionField>(point: &[E], min: Element, max: Element) -> E {
+        let offset = quantization::min(point.len()) - 1;
+        let index = |e: Element| (e - offset).clamp(0, 1 << point.len()) as usize;
+        let (count_min, sum_min) = Self::prefix_evals(point, index(min));
+        let (count_max, sum_max) = Self::prefix_evals(point, index(max + 1));
+        Fieldizer::<E>::to_field(&min) * count_min + sum_max - sum_min
+            + Fieldizer::<E>::to_field(&offset) * (count_max - count_min)
+            + Fieldizer::<E>::to_field(&max) * (E::ONE - count_max)
+    }
+
+    /// Evaluation of the output column of a leaky relu table with quantized slope `alpha`. The
+    /// output of a negative input `e` is `(alpha * e + 2^(B-1) - R(e)) / 2^B`, where `B` is
+    /// [`LEAKY_RELU_ALPHA_BITS`] and the remainder `R(e)` only depends on the lowest `B` bits of `e`.
+    /// These bits are split in two halves: `R` is the sum of the remainders of both halves, minus
+    /// `2^B` when they wrap around, which is counted from the sorted remainders of the lower half.
+    fn leaky_relu_column_eval<E: ExtensionField>(point: &[E], alpha: Element) -> E {
+        let (magnitude, sign) = point.split_at(point.len() - 1);
+        let magnitude_eval = Self::index_eval(magnitude);
+        let modulus: Element = 1 << LEAKY_RELU_ALPHA_BITS;
+        let half = modulus / 2;
+        // a negative input is `e = x - 2^(n-1)`, where `x` is given by the lower bits
+        let shift: Element = 1 << magnitude.len();
+        let remainder_offset = half - alpha * shift;
+        let remainder_vars = magnitude.len().min(LEAKY_RELU_ALPHA_BITS);
+        let (low_point, high_point) = magnitude[..remainder_vars].split_at(remainder_vars / 2);
+        let mut low_remainders = compute_betas_eval(low_point)
+            .into_iter()
+            .enumerate()
+            .map(|(x, beta)| {
+                let remainder = (remainder_offset + alpha * x as Element).rem_euclid(modulus);
+                (remainder, beta)
+            })
+            .collect::<Vec<_>>();
+        let high_remainders = compute_betas_eval(high_point)
+            .into_iter()
+            .enumerate()
+            .map(|(x, beta)| {
+                let remainder = (alpha * ((x as Element) << low_point.len())).rem_euclid(modulus);
+                (remainder, beta)
+            })
+            .collect::<Vec<_>>();
+        let weighted_sum = |remainders: &[(Element, E)]| {
+            remainders.iter().fold(E::ZERO, |acc, (remainder, beta)| {
+                acc + Fieldizer::<E>::to_field(remainder) * beta
+            })
+        };
+        let mut remainder_eval = weighted_sum(&low_remainders) + weighted_sum(&high_remainders);
+        low_remainders.sort_by_key(|(remainder, _)| *remainder);
+        // sums of the equality polynomials of the lower halves from each position of the sorted list
+        let mut suffix_sums = low_remainders
+            .iter()
+            .rev()
+            .scan(E::ZERO, |acc, (_, beta)| {
+                *acc += beta;
+                Some(*acc)
+            })
+            .collect::<Vec<_>>();
+        suffix_sums.reverse();
+        suffix_sums.push(E::ZERO);
+        let wrapped = high_remainders
+            .iter()
+            .fold(E::ZERO, |acc, (high_remainder, beta)| {
+                let start = low_remainders
+                    .partition_point(|(low_remainder, _)| low_remainder + high_remainder < modulus);
+                acc + suffix_sums[start] * beta
+            });
+        remainder_eval -= wrapped * Fieldizer::<E>::to_field(&modulus);
+        let negative_output = (Fieldizer::<E>::to_field(&alpha)
+            * (magnitude_eval - Fieldizer::<E>::to_field(&shift))
+            + Fieldizer::<E>::to_field(&half)
+            - remainder_eval)
+            * Fieldizer::<E>::to_field(&modulus).invert().unwrap();
+        sign[0] * magnitude_eval + (E::ONE - sign[0]) * negative_output
+    }
+
+    /// Maps an input of a two column table to the corresponding output.
+    fn output(&self, input: Element) -> Element {
+        match self {
+            TableType::Relu(table) => table.apply(input),
+            TableType::Range(_) => unreachable!("Range table has a single column"),
+            TableType::Sigmoid(table) => table.apply(input),
+            TableType::Committed(table) => table.apply(input),
+            TableType::LeakyRelu(table) => table.apply(input),
+            TableType::Clip(table) => table.apply(input),
+            TableType::Softmax(table) => table.apply(input),
+            TableType::InvSqrt(table) => table.apply(input),
+            TableType::Embedding => unreachable!("Embedding tables are proven by the layers"),
+        }
+    }
+
     fn get_merged_table_column<E: ExtensionField>(
         &self,
         column_separator: Element,
     ) -> (Vec<Element>, Vec<Vec<E::BaseField>>) {
         match self {
-            TableType::Relu => {
-                let (comb, field): (Vec<Element>, Vec<(E::BaseField, E::BaseField)>) =
-                    (*quantization::MIN - 1..=*quantization::MAX)
-                        .map(|i| {
-                            let out = Relu::apply(i);
-                            let i_field: E = i.to_field();
-                            let out_field: E = out.to_field();
-                            (
-                                i + out * column_separator,
-                                (i_field.as_bases()[0], out_field.as_bases()[0]),
-                            )
-                        })
-                        .unzip();
-                let (col_one, col_two): (Vec<E::BaseField>, Vec<E::BaseField>) =
-                    field.into_iter().unzip();
-                (comb, vec![col_one, col_two])
-            }
-            TableType::Range => {
-                let (element_out, field): (Vec<Element>, Vec<E::BaseField>) = (0..1
-                    << *quantization::BIT_LEN)
+            TableType::Range(bit_len) => {
+                let (element_out, field): (Vec<Element>, Vec<E::BaseField>) = (0..1 << *bit_len)
                     .map(|i| {
                         let i_field: E = i.to_field();
                         (i, i_field.as_bases()[0])
@@ -63,27 +217,89 @@ impl TableType {
                     .unzip();
                 (element_out, vec![field])
             }
+            _ => Self::two_column_table::<E>(self.bit_len(), column_separator, |i| self.output(i)),
         }
     }
 
-    pub fn name(&self) -> String {
+    /// Number of bits of the values looked up into the table, i.e. the number of variables of its
+    /// columns. Tables over different numbers of bits are distinct tables.
+    pub fn bit_len(&self) -> usize {
         match self {
-            TableType::Relu => "Relu".to_string(),
-            TableType::Range => "Range".to_string(),
+            TableType::Relu(table) => table.bit_len(),
+            TableType::Range(bit_len) => *bit_len,
+            TableType::Sigmoid(table) => table.bit_len(),
+            TableType::Committed(table) => table.bit_len(),
+            TableType::LeakyRelu(table) => table.bit_len(),
+            TableType::Clip(table) => table.bit_len(),
+            TableType::Softmax(table) => table.bit_len(),
+            TableType::InvSqrt(table) => table.bit_len(),
+            TableType::Embedding => unreachable!("Embedding tables are proven by the layers"),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        let name = match self {
+            TableType::Relu(table) => format!("Relu_{}", table.zero_point()),
+            TableType::Range(_) => "Range".to_string(),
+            TableType::Sigmoid(table) => format!("Sigmoid_{}", table.input_scale()),
+            TableType::Committed(table) => format!(
+                "{}_{}_{}",
+                table.function().name(),
+                table.input_scale(),
+                table.output_scaling().scale()
+            ),
+            TableType::LeakyRelu(table) => format!("LeakyRelu_{}", table.alpha()),
+            TableType::Clip(table) => format!("Clip_{}_{}", table.min(), table.max()),
+            TableType::Softmax(table) => format!("Softmax_{}", table.input_scale()),
+            TableType::InvSqrt(table) => {
+                format!("InvSqrt_{}_{}", table.num_entries(), table.epsilon())
+            }
+            TableType::Embedding => return "Embedding".to_string(),
+        };
+        format!("{name}_{}bits", self.bit_len())
+    }
+
+    /// Returns true if the output column of the table is committed at setup time, rather than
+    /// evaluated by the verifier.
+    pub fn is_committed(&self) -> bool {
+        matches!(
+            self,
+            TableType::Sigmoid(_)
+                | TableType::Committed(_)
+                | TableType::Softmax(_)
+                | TableType::InvSqrt(_)
+        )
+    }
+
+    /// Returns true if the table is proven by the layer looking up into it rather than along with
+    /// the other tables, as its content is specific to the layer.
+    pub fn is_local(&self) -> bool {
+        matches!(self, TableType::Embedding)
+    }
+
+    /// Returns the evaluations of the committed column of the table, if any
+    pub(crate) fn committed_column<E: ExtensionField>(&self) -> Option<Vec<E>> {
+        if !self.is_committed() {
+            return None;
         }
+        let (_, mut columns) = self.get_merged_table_column::<E>(COLUMN_SEPARATOR);
+        Some(columns.pop()?.into_iter().map(E::from).collect())
     }
 
+    /// Evaluates the columns of the table having a closed form multilinear extension at `point`.
+    /// The output column of a committed table is not evaluated: its claim is instead checked with an
+    /// opening of the commitment.
     pub fn evaluate_table_columns<E: ExtensionField>(
         &self,
         point: &[E],
     ) -> Result<Vec<E>, LogUpError> {
         match self {
-            TableType::Range => {
-                if point.len() != *quantization::BIT_LEN {
+            TableType::Range(bit_len) => {
+                if point.len() != *bit_len {
                     return Err(LogUpError::VerifierError(format!(
                         "Point was not the correct size to produce a range table evaluation, point size: {}, expected: {}",
                         point.len(),
-                        *quantization::BIT_LEN
+                        self.bit_len()
                     )));
                 }
 
@@ -94,20 +310,17 @@ impl TableType {
                         .fold(E::ZERO, |acc, (index, p)| acc + *p * E::from(1u64 << index)),
                 ])
             }
-            TableType::Relu => {
-                if point.len() != *quantization::BIT_LEN {
+            // with a zero point equal to zero, the output column is the input column over its upper half
+            TableType::Relu(table) if table.zero_point() == 0 => {
+                if point.len() != self.bit_len() {
                     return Err(LogUpError::VerifierError(format!(
                         "Point was not the correct size to produce a relu table evaluation, point size: {}, expected: {}",
                         point.len(),
-                        *quantization::BIT_LEN
+                        self.bit_len()
                     )));
                 }
 
-                let first_column = point
-                    .iter()
-                    .enumerate()
-                    .fold(E::ZERO, |acc, (index, p)| acc + *p * E::from(1u64 << index))
-                    - E::from(1u64 << (*quantization::BIT_LEN - 1));
+                let first_column = Self::input_column_eval(point);
 
                 let second_column = point
                     .iter()
@@ -118,13 +331,87 @@ impl TableType {
                     });
                 Ok(vec![first_column, second_column])
             }
+            TableType::LeakyRelu(table) => {
+                if point.len() != self.bit_len() {
+                    return Err(LogUpError::VerifierError(format!(
+                        "Point was not the correct size to produce a leaky relu table evaluation, point size: {}, expected: {}",
+                        point.len(),
+                        self.bit_len()
+                    )));
+                }
+
+                Ok(vec![
+                    Self::input_column_eval(point),
+                    Self::leaky_relu_column_eval(point, table.alpha()),
+                ])
+            }
+            TableType::Clip(table) => {
+                if point.len() != self.bit_len() {
+                    return Err(LogUpError::VerifierError(format!(
+                        "Point was not the correct size to produce a clip table evaluation, point size: {}, expected: {}",
+                        point.len(),
+                        self.bit_len()
+                    )));
+                }
+
+                Ok(vec![
+                    Self::input_column_eval(point),
+                    Self::clamp_column_eval(point, table.min(), table.max()),
+                ])
+            }
+            // with a nonzero zero point, the relu clamps its inputs between the zero point and the
+            // largest quantized value
+            TableType::Relu(table) => {
+                if point.len() != self.bit_len() {
+                    return Err(LogUpError::VerifierError(format!(
+                        "Point was not the correct size to produce a {} table evaluation, point size: {}, expected: {}",
+                        self.name(),
+                        point.len(),
+                        self.bit_len()
+                    )));
+                }
+
+                Ok(vec![
+                    Self::input_column_eval(point),
+                    Self::clamp_column_eval(
+                        point,
+                        table.zero_point(),
+                        quantization::max(point.len()),
+                    ),
+                ])
+            }
+            TableType::Sigmoid(_)
+            | TableType::Committed(_)
+            | TableType::Softmax(_)
+            | TableType::InvSqrt(_) => {
+                if point.len() != self.bit_len() {
+                    return Err(LogUpError::VerifierError(format!(
+                        "Point was not the correct size to produce a {} table evaluation, point size: {}, expected: {}",
+                        self.name(),
+                        point.len(),
+                        self.bit_len()
+                    )));
+                }
+
+                Ok(vec![Self::input_column_eval(point)])
+            }
+            TableType::Embedding => Err(LogUpError::VerifierError(
+                "Embedding tables are verified by the embedding layers".to_string(),
+            )),
         }
     }
 
     pub fn generate_challenge<E: ExtensionField, T: Transcript<E>>(&self, transcript: &mut T) -> E {
         match self {
-            TableType::Relu => transcript.get_and_append_challenge(b"Relu").elements,
-            TableType::Range => {
+            TableType::Relu(_) => transcript.get_and_append_challenge(b"Relu").elements,
+            TableType::Sigmoid(_) => transcript.get_and_append_challenge(b"Sigmoid").elements,
+            TableType::Committed(_) => transcript.get_and_append_challenge(b"Committed").elements,
+            TableType::LeakyRelu(_) => transcript.get_and_append_challenge(b"LeakyRelu").elements,
+            TableType::Clip(_) => transcript.get_and_append_challenge(b"Clip").elements,
+            TableType::Softmax(_) => transcript.get_and_append_challenge(b"Softmax").elements,
+            TableType::InvSqrt(_) => transcript.get_and_append_challenge(b"InvSqrt").elements,
+            TableType::Embedding => transcript.get_and_append_challenge(b"Embedding").elements,
+            TableType::Range(_) => {
                 // Theres only one column for a range check so we don't need to generate a challenge
                 E::ONE
             }
@@ -147,13 +434,31 @@ impl LookupContext {
     pub fn iter(&self) -> impl Iterator<Item = &TableType> {
         self.tables.iter()
     }
+
+    /// Returns the committed columns of the tables, with their poly ids
+    pub fn committed_polys<E: ExtensionField>(&self) -> Vec<(PolyID, Vec<E>)> {
+        self.tables
+            .iter()
+            .enumerate()
+            .filter_map(|(i, table)| {
+                table
+                    .committed_column()
+                    .map(|column| (COMMITTED_TABLE_POLY_ID + i, column))
+            })
+            .collect()
+    }
 }
 
 pub struct LookupWitnessGen<E: ExtensionField> {
     pub(crate) tables: BTreeSet<TableType>,
     pub(crate) lookups: HashMap<TableType, HashMap<Element, u64>>,
     pub(crate) polys_with_id: Vec<(usize, Vec<E>)>,
-    pub(crate) lookups_no_challenges: HashMap<NodeId, (Vec<Vec<E::BaseField>>, usize, TableType)>,
+    /// Lookups performed by each node, a node can look up into several tables
+    pub(crate) lookups_no_challenges:
+        HashMap<NodeId, Vec<(Vec<Vec<E::BaseField>>, usize, TableType)>>,
+    /// Columns and multiplicities of the local tables of each node, see [`TableType::is_local`]
+    pub(crate) node_tables_no_challenges:
+        HashMap<NodeId, Vec<(Vec<Vec<E::BaseField>>, Vec<E::BaseField>, TableType)>>,
 }
 
 impl<E: ExtensionField> LookupWitnessGen<E> {
@@ -163,6 +468,7 @@ impl<E: ExtensionField> LookupWitnessGen<E> {
             lookups: HashMap::new(),
             polys_with_id: Vec::new(),
             lookups_no_challenges: HashMap::new(),
+            node_tables_no_challenges: HashMap::new(),
         }
     }
 }
@@ -177,7 +483,7 @@ pub fn generate_lookup_witnesses<'a, E: ExtensionField, T: Transcript<E>>(
     (
         Option<Context<E>>,
         ChallengeStorage<E>,
-        HashMap<NodeId, LogUpInput<E>>,
+        HashMap<NodeId, Vec<LogUpInput<E>>>,
         Vec<LogUpInput<E>>,
     ),
     LogUpError,
@@ -221,7 +527,9 @@ where
 
     debug!("Lookup witness generation: generating table multiplicities...");
     // calculate the table multiplicities
-    let tables_no_challenges = witness_gen.tables.iter().enumerate().map(|(i,table_type)| {
+    // local tables are proven by the nodes, so they are skipped here while keeping the index of
+    // the other tables in the set
+    let tables_no_challenges = witness_gen.tables.iter().enumerate().filter(|(_, table_type)| !table_type.is_local()).map(|(i,table_type)| {
         let (table_column, column_evals) = table_type.get_merged_table_column::<E>(COLUMN_SEPARATOR);
 
         let table_lookup_data = witness_gen.lookups.get(table_type).ok_or(LogUpError::ParamterError(format!("Tried to retrieve lookups for a table of type: {:?}, but no table of that type exists", table_type)))?;
@@ -256,30 +564,50 @@ where
     debug!("Lookup witness generation: challenge storage...");
     let challenge_storage = initialise_from_table_set::<E, T>(&witness_gen.tables, transcript);
 
-    let lookup_inputs = witness_gen
+    let mut lookup_inputs = witness_gen
         .lookups_no_challenges
         .into_iter()
-        .map(
-            |(node_id, (column_evals, columns_per_instance, table_type))| {
-                let (constant_challenge, column_challenge) = challenge_storage
-                    .get_challenges_by_name(&table_type.name())
-                    .ok_or(LogUpError::ParamterError(format!(
-                        "No challegnes found for table type: {} when generating lookup witness",
-                        table_type.name()
-                    )))?;
-
-                Ok((
-                    node_id,
+        .map(|(node_id, lookups)| {
+            let inputs = lookups
+                .into_iter()
+                .map(|(column_evals, columns_per_instance, table_type)| {
+                    let (constant_challenge, column_challenge) = challenge_storage
+                        .get_challenges_by_name(&table_type.name())
+                        .ok_or(LogUpError::ParamterError(format!(
+                            "No challegnes found for table type: {} when generating lookup witness",
+                            table_type.name()
+                        )))?;
+
                     LogUpInput::<E>::new_lookup(
                         column_evals,
                         constant_challenge,
                         column_challenge,
                         columns_per_instance,
-                    )?,
-                ))
-            },
-        )
-        .collect::<Result<HashMap<NodeId, LogUpInput<E>>, LogUpError>>()?;
+                    )
+                })
+                .collect::<Result<Vec<_>, LogUpError>>()?;
+            Ok((node_id, inputs))
+        })
+        .collect::<Result<HashMap<NodeId, Vec<LogUpInput<E>>>, LogUpError>>()?;
+
+    // the inputs for the local tables of a node come after the ones of its lookups
+    for (node_id, tables) in witness_gen.node_tables_no_challenges {
+        let inputs = lookup_inputs.entry(node_id).or_default();
+        for (column_evals, multiplicities, table_type) in tables {
+            let (constant_challenge, column_challenge) = challenge_storage
+                .get_challenges_by_name(&table_type.name())
+                .ok_or(LogUpError::ParamterError(format!(
+                    "No challegnes found for table type: {} when generating node table witness",
+                    table_type.name()
+                )))?;
+            inputs.push(LogUpInput::<E>::new_table(
+                column_evals,
+                multiplicities,
+                constant_challenge,
+                column_challenge,
+            )?);
+        }
+    }
 
     let table_inputs = tables_no_challenges
         .into_iter()
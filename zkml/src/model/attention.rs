@@ -0,0 +1,259 @@
+//! Attention block of a transformer, built out of dense, matmul, softmax and add layers.
+use std::ops::Range;
+
+use anyhow::{Result, ensure};
+
+use crate::{
+    Tensor,
+    layers::{
+        Layer,
+        add::Add,
+        dense::Dense,
+        matmul::MatMul,
+        provable::{Edge, Node, NodeId},
+        softmax::Softmax,
+    },
+};
+
+use super::Model;
+
+/// Multi-head attention over a sequence of tokens, with the weights laid out as in PyTorch: the
+/// projections of the queries, keys and values map each token to the concatenation of
+/// `num_heads` heads, and the output projection maps the concatenation of the heads back to the
+/// dimension of the tokens.
+///
+/// Each head is added to the model as its own projections of the tokens, followed by the product
+/// of the queries and the keys scaled by `1/sqrt(head_dim)`, a softmax over each row of the scores
+/// and the product with the values. Multiplying the concatenation of the heads by the output
+/// projection is the same as multiplying each head by the corresponding columns of the output
+/// projection and summing the results, so no concatenation is needed.
+#[derive(Clone, Debug)]
+pub struct MultiHeadAttention {
+    num_heads: usize,
+    query: Dense<f32>,
+    key: Dense<f32>,
+    value: Dense<f32>,
+    output: Dense<f32>,
+}
+
+impl MultiHeadAttention {
+    pub fn new(
+        num_heads: usize,
+        query: Dense<f32>,
+        key: Dense<f32>,
+        value: Dense<f32>,
+        output: Dense<f32>,
+    ) -> Result<Self> {
+        let (inner_dim, model_dim) = (query.nrows(), query.ncols());
+        ensure!(
+            num_heads > 0 && inner_dim % num_heads == 0,
+            "Projections with {} rows can't be split in {} heads",
+            inner_dim,
+            num_heads
+        );
+        ensure!(
+            [&key, &value]
+                .iter()
+                .all(|dense| dense.nrows() == inner_dim && dense.ncols() == model_dim),
+            "Projections of the queries, keys and values must have the same shape"
+        );
+        ensure!(
+            output.nrows() == model_dim && output.ncols() == inner_dim,
+            "Output projection of shape [{}, {}] doesn't match the projections of shape [{}, {}]",
+            output.nrows(),
+            output.ncols(),
+            inner_dim,
+            model_dim
+        );
+        Ok(Self {
+            num_heads,
+            query,
+            key,
+            value,
+            output,
+        })
+    }
+
+    pub fn num_heads(&self) -> usize {
+        self.num_heads
+    }
+
+    pub fn head_dim(&self) -> usize {
+        self.query.nrows() / self.num_heads
+    }
+
+    /// Adds the attention over a `[seq_len, model_dim]` matrix, the input of the model or the
+    /// output of a node as given by `input`, to the model. Returns the id of the node outputting
+    /// the result of the attention.
+    pub fn add_to_model(
+        &self,
+        model: &mut Model<f32>,
+        input: Edge,
+        seq_len: usize,
+    ) -> Result<NodeId> {
+        // the dense layers are applied to each token only if there are several of them
+        ensure!(
+            seq_len > 1,
+            "Attention requires at least 2 tokens, found {}",
+            seq_len
+        );
+        let head_dim = self.head_dim();
+        let mut output_node = None;
+        for head in 0..self.num_heads {
+            let rows = head * head_dim..(head + 1) * head_dim;
+            let mut projection = |dense: &Dense<f32>| {
+                model.add_node(Node::new(
+                    vec![input.clone()],
+                    Layer::Dense(head_rows(dense, rows.clone())),
+                ))
+            };
+            let query = projection(&self.query)?;
+            let key = projection(&self.key)?;
+            let value = projection(&self.value)?;
+            let scores = model.add_node(Node::new(
+                vec![Edge::new(query, 0), Edge::new(key, 0)],
+                Layer::MatMul(
+                    MatMul::new_transposed(head_dim).with_scale(1.0 / (head_dim as f32).sqrt()),
+                ),
+            ))?;
+            let probabilities =
+                model.add_consecutive_layer(Layer::Softmax(Softmax::new()), Some(scores))?;
+            let attention = model.add_node(Node::new(
+                vec![Edge::new(probabilities, 0), Edge::new(value, 0)],
+                Layer::MatMul(MatMul::new(seq_len)),
+            ))?;
+            // the bias of the output projection is added only once
+            let projection = model.add_consecutive_layer(
+                Layer::Dense(head_columns(&self.output, rows, head == 0)),
+                Some(attention),
+            )?;
+            output_node = Some(match output_node {
+                None => projection,
+                Some(heads) => model.add_node(Node::new(
+                    vec![Edge::new(heads, 0), Edge::new(projection, 0)],
+                    Layer::Add(Add::new()),
+                ))?,
+            });
+        }
+        Ok(output_node.unwrap())
+    }
+}
+
+/// Dense layer with the given rows of the matrix and of the bias of `dense`
+fn head_rows(dense: &Dense<f32>, rows: Range<usize>) -> Dense<f32> {
+    let ncols = dense.ncols();
+    let matrix = dense.matrix.get_data()[rows.start * ncols..rows.end * ncols].to_vec();
+    let bias = dense.bias.get_data()[rows.clone()].to_vec();
+    Dense::new_from_weights(
+        Tensor::new(vec![rows.len(), ncols], matrix),
+        Tensor::new(vec![rows.len()], bias),
+    )
+}
+
+/// Dense layer with the given columns of the matrix of `dense`, and with its bias if `with_bias`
+fn head_columns(dense: &Dense<f32>, columns: Range<usize>, with_bias: bool) -> Dense<f32> {
+    let matrix = dense
+        .matrix
+        .get_data()
+        .chunks(dense.ncols())
+        .flat_map(|row| row[columns.clone()].iter().copied())
+        .collect();
+    let bias = if with_bias {
+        dense.bias.clone()
+    } else {
+        Tensor::zeros(dense.bias.get_shape())
+    };
+    Dense::new_from_weights(
+        Tensor::new(vec![dense.nrows(), columns.len()], matrix),
+        bias,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use goldilocks::GoldilocksExt2;
+
+    use crate::{model::Model, padding::PaddingMode};
+
+    use super::*;
+
+    impl MultiHeadAttention {
+        pub fn random(num_heads: usize, model_dim: usize, head_dim: usize) -> Self {
+            let inner_dim = num_heads * head_dim;
+            let projection = || Dense::random(vec![inner_dim, model_dim]);
+            Self::new(
+                num_heads,
+                projection(),
+                projection(),
+                projection(),
+                Dense::random(vec![model_dim, inner_dim]),
+            )
+            .unwrap()
+        }
+    }
+
+    /// Computes the attention over `input` as in PyTorch, i.e. with the heads concatenated
+    fn attention(mha: &MultiHeadAttention, input: &Tensor<f32>) -> Tensor<f32> {
+        let project = |dense: &Dense<f32>| {
+            let seq_len = input.get_shape()[0];
+            let bias = Tensor::new(
+                vec![seq_len, dense.nrows()],
+                dense.bias.get_data().repeat(seq_len),
+            );
+            input.matmul(&dense.matrix.transpose()).add(&bias)
+        };
+        let (query, key, value) = (project(&mha.query), project(&mha.key), project(&mha.value));
+        let head_dim = mha.head_dim();
+        let columns = |t: &Tensor<f32>, head: usize| {
+            let data = t
+                .get_data()
+                .chunks(t.get_shape()[1])
+                .flat_map(|row| row[head * head_dim..(head + 1) * head_dim].iter().copied())
+                .collect();
+            Tensor::new(vec![t.get_shape()[0], head_dim], data)
+        };
+        let heads = (0..mha.num_heads)
+            .map(|head| {
+                let scores = columns(&query, head)
+                    .matmul(&columns(&key, head).transpose())
+                    .scalar_mul(&(1.0 / (head_dim as f32).sqrt()));
+                Softmax::new().op(&scores).matmul(&columns(&value, head))
+            })
+            .collect::<Vec<_>>();
+        let seq_len = input.get_shape()[0];
+        let concat = (0..seq_len)
+            .flat_map(|row| {
+                heads.iter().flat_map(move |head| {
+                    head.get_data()[row * head_dim..(row + 1) * head_dim].to_vec()
+                })
+            })
+            .collect();
+        let concat = Tensor::new(vec![seq_len, mha.num_heads * head_dim], concat);
+        let bias = Tensor::new(
+            vec![seq_len, mha.output.nrows()],
+            mha.output.bias.get_data().repeat(seq_len),
+        );
+        concat.matmul(&mha.output.matrix.transpose()).add(&bias)
+    }
+
+    #[test]
+    fn test_attention_heads() {
+        let (seq_len, model_dim) = (5, 6);
+        let mha = MultiHeadAttention::random(3, model_dim, 2);
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![seq_len, model_dim]],
+            PaddingMode::NoPadding,
+        );
+        mha.add_to_model(&mut model, Edge::new_at_edge(0), seq_len)
+            .unwrap();
+        model.route_output(None).unwrap();
+        let input = Tensor::<f32>::random(&vec![seq_len, model_dim]);
+        let trace = model.run::<GoldilocksExt2>(&[input.clone()]).unwrap();
+        let output = trace.outputs().unwrap()[0].clone();
+        let expected = attention(&mha, &input);
+        assert_eq!(output.get_shape(), expected.get_shape());
+        for (x, y) in output.get_data().iter().zip(expected.get_data()) {
+            assert!((x - y).abs() < 1e-4, "{x} too far from {y}");
+        }
+    }
+}
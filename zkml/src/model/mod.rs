@@ -12,7 +12,6 @@ use crate::{
     layers::{
         Layer,
         provable::{Edge, Evaluate, Node, NodeCtx, NodeId, OpInfo},
-        requant::Requant,
     },
     padding::PaddingMode,
     quantization::InferenceTracker,
@@ -20,9 +19,11 @@ use crate::{
     try_unzip,
 };
 
+pub(crate) mod attention;
 pub(crate) mod iterator;
 pub(crate) mod trace;
 
+pub use attention::MultiHeadAttention;
 pub use iterator::ToIterator;
 pub use trace::{InferenceStep, InferenceTrace, StepData};
 
@@ -174,10 +175,11 @@ where
         }
     }
 
-    /// Add a re-quantization node to the model after the node with id `input_node_id`
+    /// Add a re-quantization node, i.e. a requant or a rescale layer, to the model after the node
+    /// with id `input_node_id`
     pub(crate) fn add_requant_node(
         &mut self,
-        requant: Requant,
+        layer: Layer<N>,
         input_node_id: NodeId,
     ) -> anyhow::Result<NodeId> {
         let input_node = self
@@ -192,7 +194,7 @@ where
                     index: i,
                 })
                 .collect(),
-            Layer::Requant(requant),
+            layer,
             input_node.outputs.clone(), // copy output wires of `input_node` to requant node
         );
         // remove edges from outputs of `input_node`
@@ -525,15 +527,30 @@ pub(crate) mod test {
         ScalingFactor, ScalingStrategy, init_test_logging,
         layers::{
             Layer,
-            activation::{Activation, Relu},
-            convolution::{Convolution, SchoolBookConv},
+            activation::{
+                Activation, Clip, CommittedActivation, LeakyRelu, Relu, Sigmoid, TableFunction,
+            },
+            add::Add,
+            affine::Affine,
+            concat::Concat,
+            convolution::{ConvPadding, Convolution, GroupedConv, SchoolBookConv},
             dense::Dense,
-            pooling::{MAXPOOL2D_KERNEL_SIZE, Maxpool2D, Pooling},
-            provable::{Edge, Node, OpInfo, evaluate_layer},
+            embedding::Embedding,
+            flatten::Flatten,
+            matmul::MatMul,
+            norm::Normalization,
+            pooling::{AvgPool2D, GlobalAveragePool, MAXPOOL2D_KERNEL_SIZE, Maxpool2D, Pooling},
+            provable::{Edge, Node, NodeId, OpInfo, evaluate_layer},
             requant::Requant,
+            reshape::Reshape,
+            softmax::Softmax,
+            split::Split,
+            topk::TopK,
+            transpose::Transpose,
         },
+        model::MultiHeadAttention,
         padding::{PaddingMode, pad_model},
-        quantization::{self, InferenceObserver},
+        quantization::{self, Calibration, InferenceObserver},
         tensor::Number,
         testing::{random_bool_vector, random_vector},
     };
@@ -583,19 +600,22 @@ pub(crate) mod test {
                         Dense::random(vec![nrows.next_power_of_two(), ncols.next_power_of_two()]);
                     // Figure out the requant information such that output is still within range
                     let (min_output_range, max_output_range) =
-                        dense.output_range(*quantization::MIN, *quantization::MAX);
+                        dense.output_range(quantization::MIN, quantization::MAX);
                     let output_scaling_factor = ScalingFactor::from_scale(
                         ((max_output_range - min_output_range) as f64
-                            / (*quantization::MAX - *quantization::MIN) as f64)
+                            / (quantization::MAX - quantization::MIN) as f64)
                             as f32,
                         None,
                     );
                     let input_scaling_factor = ScalingFactor::from_scale(1.0, None);
                     let max_model = dense.matrix.max_value().max(dense.bias.max_value()) as f32;
                     let model_scaling_factor = ScalingFactor::from_absolute_max(max_model, None);
-                    let shift =
-                        input_scaling_factor.shift(&model_scaling_factor, &output_scaling_factor);
-                    let requant = Requant::new(min_output_range as usize, shift);
+                    let m = input_scaling_factor.m(&model_scaling_factor, &output_scaling_factor);
+                    let requant = Requant::from_multiplier(
+                        min_output_range.unsigned_abs() as usize,
+                        m as f64,
+                        quantization::DEFAULT_BIT_LEN,
+                    );
                     last_node_id =
                         Some(model.add_consecutive_layer(Layer::Dense(dense), last_node_id)?);
                     last_node_id =
@@ -1089,6 +1109,97 @@ pub(crate) mod test {
         }
     }
 
+    #[test]
+    fn test_cnn_prover_zero_padding() {
+        let input_shape = vec![2, 16, 16];
+        for (padding, stride) in [
+            (ConvPadding::SameUpper, 1),
+            (ConvPadding::SameLower, 2),
+            (ConvPadding::Explicit([1, 0, 2, 3]), 1),
+        ] {
+            let conv1 = Tensor::random(&vec![4, 2, 3, 3]);
+            let conv2 = Tensor::random(&vec![4, 4, 3, 3]);
+            let mut model = Model::<Element>::new_from_input_shapes(
+                vec![input_shape.clone()],
+                PaddingMode::Padding,
+            );
+            let input = Tensor::random(&model.input_shapes()[0]);
+            // the first convolution leaves garbage rows and columns in the padded output, which must
+            // not end up in the zero padding of the second one
+            model
+                .add_consecutive_layer(
+                    Layer::Convolution(
+                        Convolution::new(conv1.clone(), Tensor::random(&vec![conv1.kw()]))
+                            .into_padded_and_ffted(&input_shape),
+                    ),
+                    None,
+                )
+                .unwrap();
+            model
+                .add_consecutive_layer(
+                    Layer::Convolution(
+                        Convolution::new(conv2.clone(), Tensor::random(&vec![conv2.kw()]))
+                            .with_stride(stride)
+                            .unwrap()
+                            .with_padding(padding)
+                            .into_padded_and_ffted(&vec![4, 14, 14]),
+                    ),
+                    None,
+                )
+                .unwrap();
+            model.route_output(None).unwrap();
+            model.describe();
+            let trace = model.run::<F>(&vec![input]).unwrap();
+            let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"m2vec");
+            let ctx = Context::<GoldilocksExt2>::generate(&model, None)
+                .expect("Unable to generate context");
+            let io = trace.to_verifier_io();
+            let prover: Prover<'_, GoldilocksExt2, BasicTranscript<GoldilocksExt2>> =
+                Prover::new(&ctx, &mut tr);
+            let proof = prover.prove(trace).expect("unable to generate proof");
+            let mut verifier_transcript: BasicTranscript<GoldilocksExt2> =
+                BasicTranscript::new(b"m2vec");
+            verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cnn_prover_strided() {
+        let input_shape = vec![2, 16, 16];
+        for stride in [2, 3] {
+            let conv1 = Tensor::random(&vec![4, 2, 3, 3]);
+            let mut model = Model::<Element>::new_from_input_shapes(
+                vec![input_shape.clone()],
+                PaddingMode::Padding,
+            );
+            let input = Tensor::random(&model.input_shapes()[0]);
+            model
+                .add_consecutive_layer(
+                    Layer::Convolution(
+                        Convolution::new(conv1.clone(), Tensor::random(&vec![conv1.kw()]))
+                            .with_stride(stride)
+                            .unwrap()
+                            .into_padded_and_ffted(&input_shape),
+                    ),
+                    None,
+                )
+                .unwrap();
+            model.route_output(None).unwrap();
+            model.describe();
+            let trace = model.run::<F>(&vec![input]).unwrap();
+            let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"m2vec");
+            let ctx = Context::<GoldilocksExt2>::generate(&model, None)
+                .expect("Unable to generate context");
+            let io = trace.to_verifier_io();
+            let prover: Prover<'_, GoldilocksExt2, BasicTranscript<GoldilocksExt2>> =
+                Prover::new(&ctx, &mut tr);
+            let proof = prover.prove(trace).expect("unable to generate proof");
+            let mut verifier_transcript: BasicTranscript<GoldilocksExt2> =
+                BasicTranscript::new(b"m2vec");
+            verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap();
+        }
+    }
+
     type E = GoldilocksExt2;
     type T = BasicTranscript<GoldilocksExt2>;
     type N = Element;
@@ -1170,10 +1281,18 @@ pub(crate) mod test {
             .map(|(tensor, s)| tensor.quantize(s).pad_next_power_of_two())
             .collect_vec();
 
-        let trace = model.run(&input_tensors)?;
+        prove_padded_model(&model, &input_tensors)
+    }
+
+    /// Proves and verifies the inference of a quantized and padded model over the given inputs
+    fn prove_padded_model(
+        model: &Model<Element>,
+        input_tensors: &[Tensor<Element>],
+    ) -> anyhow::Result<()> {
+        let trace = model.run(input_tensors)?;
         let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"model");
         let ctx =
-            Context::<GoldilocksExt2>::generate(&model, None).expect("Unable to generate context");
+            Context::<GoldilocksExt2>::generate(model, None).expect("Unable to generate context");
         let prover: Prover<'_, E, T> = Prover::new(&ctx, &mut tr);
         let io = trace.to_verifier_io();
         let proof = prover.prove(trace).expect("unable to generate proof");
@@ -1281,4 +1400,1085 @@ pub(crate) mod test {
 
         prove_model(model).unwrap();
     }
+
+    #[test]
+    fn test_model_fan_out() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let nrows = 42;
+        let dense = Dense::random(vec![nrows, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let relu = Activation::Relu(Relu::new());
+        let relu_node = model
+            .add_consecutive_layer(Layer::Activation(relu), Some(input_node))
+            .unwrap();
+        // the output of the relu node is used as input by two different dense nodes
+        let dense = Dense::random(vec![37, nrows]);
+        let first_output_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(relu_node))
+            .unwrap();
+        let dense = Dense::random(vec![23, nrows]);
+        let second_output_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(relu_node))
+            .unwrap();
+        model
+            .route_output(Some(vec![
+                Edge::new(first_output_node, 0),
+                Edge::new(second_output_node, 0),
+            ]))
+            .unwrap();
+
+        assert_eq!(model.nodes[&relu_node].outputs[0].edges.len(), 2);
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_avg_pooling() {
+        init_test_logging();
+        let input_shape = vec![4usize, 28, 28];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let avg_node = model
+            .add_consecutive_layer(
+                Layer::Pooling(Pooling::AvgPool2D(AvgPool2D::default())),
+                None,
+            )
+            .unwrap();
+        // average pooling halves the spatial dimensions of the input
+        let global_node = model
+            .add_consecutive_layer(
+                Layer::Pooling(Pooling::GlobalAveragePool(GlobalAveragePool::new(14, 14))),
+                Some(avg_node),
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(global_node))
+            .unwrap();
+        let dense = Dense::random(vec![8, input_shape[0]]);
+        model
+            .add_consecutive_layer(Layer::Dense(dense), Some(flatten_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_avg_pooling_odd_dims() {
+        init_test_logging();
+        // the last row of the input is not summed: it would land in the padded output row 3
+        let input_shape = vec![4usize, 7, 10];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let avg_node = model
+            .add_consecutive_layer(
+                Layer::Pooling(Pooling::AvgPool2D(AvgPool2D::default())),
+                None,
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(avg_node))
+            .unwrap();
+        let dense = Dense::random(vec![8, input_shape[0] * 3 * 5]);
+        model
+            .add_consecutive_layer(Layer::Dense(dense), Some(flatten_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_overlapping_maxpool() {
+        init_test_logging();
+        let input_shape = vec![4usize, 27, 27];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let maxpool = Maxpool2D::new(3, 2).unwrap();
+        let pool_node = model
+            .add_consecutive_layer(Layer::Pooling(Pooling::Maxpool2D(maxpool)), None)
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(pool_node))
+            .unwrap();
+        // overlapping 3x3 windows with stride 2 produce a 13x13 output
+        let dense = Dense::random(vec![8, input_shape[0] * 13 * 13]);
+        model
+            .add_consecutive_layer(Layer::Dense(dense), Some(flatten_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_rectangular_cnn() {
+        init_test_logging();
+        let input_shape = vec![2usize, 20, 13];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let filter1 = Tensor::random(&vec![4, 2, 3, 2]);
+        let conv1_node = model
+            .add_consecutive_layer(
+                Layer::Convolution(
+                    Convolution::new(filter1, Tensor::random(&vec![4]))
+                        .with_padding(ConvPadding::SameUpper),
+                ),
+                None,
+            )
+            .unwrap();
+        // the 20x13 output of the first convolution is pooled into a 10x6 map
+        let pool_node = model
+            .add_consecutive_layer(
+                Layer::Pooling(Pooling::Maxpool2D(Maxpool2D::default())),
+                Some(conv1_node),
+            )
+            .unwrap();
+        let filter2 = Tensor::random(&vec![3, 4, 2, 3]);
+        let conv2_node = model
+            .add_consecutive_layer(
+                Layer::Convolution(
+                    Convolution::new(filter2, Tensor::random(&vec![3]))
+                        .with_stride(2)
+                        .unwrap(),
+                ),
+                Some(pool_node),
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(conv2_node))
+            .unwrap();
+        // the second convolution outputs a 5x2 map
+        let dense = Dense::random(vec![8, 3 * 5 * 2]);
+        model
+            .add_consecutive_layer(Layer::Dense(dense), Some(flatten_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_depthwise_separable_cnn() {
+        init_test_logging();
+        let input_shape = vec![3usize, 16, 16];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        // depthwise convolution: each input channel is convolved with its own filter
+        let depthwise_node = model
+            .add_consecutive_layer(
+                Layer::GroupedConvolution(
+                    GroupedConv::new(
+                        Tensor::random(&vec![3, 1, 3, 3]),
+                        Tensor::random(&vec![3]),
+                        3,
+                    )
+                    .unwrap()
+                    .with_padding(ConvPadding::SameUpper),
+                ),
+                None,
+            )
+            .unwrap();
+        let pointwise_node = model
+            .add_consecutive_layer(
+                Layer::Convolution(
+                    Convolution::new(Tensor::random(&vec![4, 3, 2, 2]), Tensor::random(&vec![4]))
+                        .with_stride(2)
+                        .unwrap(),
+                ),
+                Some(depthwise_node),
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(pointwise_node))
+            .unwrap();
+        let dense = Dense::random(vec![8, 4 * 8 * 8]);
+        model
+            .add_consecutive_layer(Layer::Dense(dense), Some(flatten_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_sigmoid() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let dense = Dense::random(vec![27, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let sigmoid_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Sigmoid(Sigmoid::new())),
+                Some(input_node),
+            )
+            .unwrap();
+        // the padded outputs of the sigmoid are not zero, but they are multiplied by the
+        // zero padded columns of the next dense layer
+        let dense = Dense::random(vec![13, 27]);
+        let dense_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(sigmoid_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Sigmoid(Sigmoid::new())),
+                Some(dense_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_sigmoid_rejects_non_dense_consumer() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let dense = Dense::random(vec![27, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let sigmoid_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Sigmoid(Sigmoid::new())),
+                Some(input_node),
+            )
+            .unwrap();
+        // the relu would keep the non zero padded outputs of the sigmoid
+        model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(sigmoid_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        let err = prove_model(model).unwrap_err();
+        assert!(err.to_string().contains("sigmoid"), "{err}");
+    }
+
+    #[test]
+    fn test_model_committed_activations() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let mut last_node = None;
+        let mut last_size = INPUT_SIZE;
+        for (function, size) in [
+            (TableFunction::Gelu, 27),
+            (TableFunction::Tanh, 19),
+            (TableFunction::Silu, 13),
+        ] {
+            let dense = Dense::random(vec![size, last_size]);
+            let dense_node = model
+                .add_consecutive_layer(Layer::Dense(dense), last_node)
+                .unwrap();
+            let activation = Activation::Committed(CommittedActivation::new(function));
+            last_node = Some(
+                model
+                    .add_consecutive_layer(Layer::Activation(activation), Some(dense_node))
+                    .unwrap(),
+            );
+            last_size = size;
+        }
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_leaky_relu_and_clip() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let mut last_node = None;
+        let mut last_size = INPUT_SIZE;
+        for (activation, size) in [
+            (Activation::LeakyRelu(LeakyRelu::new(0.01)), 27),
+            (Activation::Clip(Clip::relu6()), 19),
+            (Activation::Clip(Clip::new(Some(-0.5), None)), 13),
+        ] {
+            let dense = Dense::random(vec![size, last_size]);
+            let dense_node = model
+                .add_consecutive_layer(Layer::Dense(dense), last_node)
+                .unwrap();
+            last_node = Some(
+                model
+                    .add_consecutive_layer(Layer::Activation(activation), Some(dense_node))
+                    .unwrap(),
+            );
+            last_size = size;
+        }
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_softmax() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let dense = Dense::random(vec![27, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(input_node),
+            )
+            .unwrap();
+        // the model outputs the probabilities of 10 classes, which are not a power of two
+        let dense = Dense::random(vec![10, 27]);
+        let logits_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(relu_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(Layer::Softmax(Softmax::new()), Some(logits_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_softmax_rows_zero_padding() {
+        init_test_logging();
+        // 3 rows of 5 entries are padded to 4 rows of 8 entries
+        let (num_rows, num_entries) = (3, 5);
+        let input_shape = vec![num_rows, num_entries];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        model
+            .add_consecutive_layer(Layer::Softmax(Softmax::new()), None)
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        let float_input = Tensor::<f32>::random(&input_shape);
+        let (quantized_model, md) = InferenceObserver::new_with_representative_input(vec![vec![
+            float_input.get_data().to_vec(),
+        ]])
+        .quantize(model)
+        .unwrap();
+        let model = pad_model(quantized_model).unwrap();
+        let input = float_input.quantize(&md.input[0]).pad_next_power_of_two();
+        // the output revealed to the verifier is zero over the padded entries
+        let trace = model.run::<E>(&[input.clone()]).unwrap();
+        let output = trace.outputs().unwrap()[0];
+        assert_eq!(output.get_shape(), vec![4, 8]);
+        assert!(
+            output
+                .get_data()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| i / 8 >= num_rows || i % 8 >= num_entries)
+                .all(|(_, p)| *p == 0)
+        );
+        prove_padded_model(&model, &[input]).unwrap();
+    }
+
+    /// Builds a classifier with 10 classes followed by the given top-k layer
+    fn build_topk_model(topk: TopK) -> Model<f32> {
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let dense = Dense::random(vec![27, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(input_node),
+            )
+            .unwrap();
+        let dense = Dense::random(vec![10, 27]);
+        let logits_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(relu_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(Layer::TopK(topk), Some(logits_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+        model
+    }
+
+    #[test]
+    fn test_model_argmax() {
+        init_test_logging();
+        let model = build_topk_model(TopK::argmax());
+        model.describe();
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_topk() {
+        init_test_logging();
+        // the 3 indices are padded to 4 in the output of the model
+        let model = build_topk_model(TopK::new(3).unwrap());
+        model.describe();
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_topk_hides_logits() {
+        init_test_logging();
+        let model = build_topk_model(TopK::new(3).unwrap());
+        let input_shape = model.input_shapes()[0].clone();
+        let (quantized_model, md) = InferenceObserver::new().quantize(model).unwrap();
+        let model = pad_model(quantized_model).unwrap();
+        let input = Tensor::random(&input_shape)
+            .quantize(&md.input[0])
+            .pad_next_power_of_two();
+        let trace = model.run(&[input]).unwrap();
+        let (topk_id, topk_step) = trace
+            .steps
+            .iter()
+            .find(|(_, step)| matches!(step.op, Layer::TopK(_)))
+            .unwrap();
+        let topk_id = *topk_id;
+        let logits = topk_step.step_data.inputs[0].get_data()[..10].to_vec();
+
+        let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"model");
+        let ctx = Context::<GoldilocksExt2>::generate(&model, None).unwrap();
+        let prover: Prover<'_, E, T> = Prover::new(&ctx, &mut tr);
+        let io = trace.to_verifier_io();
+        let proof = prover.prove(trace).unwrap();
+
+        // the proof of the layer only reveals the indices, not the logits
+        let topk_proof =
+            serde_json::to_value(&proof).unwrap()["steps"][topk_id.to_string()]["TopK"].clone();
+        let fields = topk_proof
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .sorted()
+            .collect_vec();
+        assert_eq!(fields, vec!["indices", "input_eval", "lookup", "sumcheck"]);
+        let serialized = topk_proof.to_string();
+        logits.iter().for_each(|logit| {
+            let field = quantization::Fieldizer::<GoldilocksExt2>::to_field(logit);
+            assert!(!serialized.contains(&serde_json::to_string(&field).unwrap()));
+        });
+
+        let mut verifier_transcript: BasicTranscript<GoldilocksExt2> =
+            BasicTranscript::new(b"model");
+        verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap();
+    }
+
+    #[test]
+    fn test_model_matmul() {
+        init_test_logging();
+        // none of the dimensions is a power of two, so both inputs and the output are padded
+        const INNER_DIM: usize = 11;
+        let input_shapes = vec![vec![6, INNER_DIM], vec![INNER_DIM, 5]];
+        let mut model = Model::<f32>::new_from_input_shapes(input_shapes, PaddingMode::NoPadding);
+        let matmul_node = model
+            .add_node(Node::new(
+                vec![Edge::new_at_edge(0), Edge::new_at_edge(1)],
+                Layer::MatMul(MatMul::new(INNER_DIM)),
+            ))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(matmul_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        assert_eq!(model.nodes[&matmul_node].inputs.len(), 2);
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_attention() {
+        init_test_logging();
+        // 6 tokens of dimension 12, none of the dimensions is a power of two
+        let (seq_len, model_dim) = (6, 12);
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![seq_len, model_dim]],
+            PaddingMode::NoPadding,
+        );
+        MultiHeadAttention::random(1, model_dim, model_dim)
+            .add_to_model(&mut model, Edge::new_at_edge(0), seq_len)
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_transformer_encoder() {
+        init_test_logging();
+        let (seq_len, model_dim, hidden_dim) = (6, 12, 20);
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![seq_len, model_dim]],
+            PaddingMode::NoPadding,
+        );
+        let attention_node = MultiHeadAttention::random(2, model_dim, model_dim / 2)
+            .add_to_model(&mut model, Edge::new_at_edge(0), seq_len)
+            .unwrap();
+        let attention_residual = model
+            .add_node(Node::new(
+                vec![Edge::new_at_edge(0), Edge::new(attention_node, 0)],
+                Layer::Add(Add::new()),
+            ))
+            .unwrap();
+        // the feed-forward network is applied to each token
+        let hidden_node = model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![hidden_dim, model_dim])),
+                Some(attention_residual),
+            )
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(hidden_node),
+            )
+            .unwrap();
+        let ffn_node = model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![model_dim, hidden_dim])),
+                Some(relu_node),
+            )
+            .unwrap();
+        model
+            .add_node(Node::new(
+                vec![Edge::new(attention_residual, 0), Edge::new(ffn_node, 0)],
+                Layer::Add(Add::new()),
+            ))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_normalization() {
+        init_test_logging();
+        let (seq_len, model_dim, hidden_dim) = (6, 12, 20);
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![seq_len, model_dim]],
+            PaddingMode::NoPadding,
+        );
+        let layer_norm_node = model
+            .add_consecutive_layer(
+                Layer::Normalization(Normalization::layer_norm(
+                    Tensor::random(&vec![model_dim]),
+                    Tensor::random(&vec![model_dim]),
+                    1e-5,
+                )),
+                None,
+            )
+            .unwrap();
+        // the normalization is applied to each token, before and after a feed-forward layer
+        let dense_node = model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![hidden_dim, model_dim])),
+                Some(layer_norm_node),
+            )
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Normalization(Normalization::rms_norm(
+                    Tensor::random(&vec![hidden_dim]),
+                    1e-6,
+                )),
+                Some(dense_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_affine() {
+        init_test_logging();
+        let (num_channels, height, width) = (3, 5, 6);
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![num_channels, height, width]],
+            PaddingMode::NoPadding,
+        );
+        // a batch normalization over the channels of the input, which can't be folded
+        let affine_node = model
+            .add_consecutive_layer(
+                Layer::Affine(Affine::new(
+                    Tensor::random(&vec![num_channels]),
+                    Tensor::random(&vec![num_channels]),
+                )),
+                None,
+            )
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(affine_node),
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(relu_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![10, num_channels * height * width])),
+                Some(flatten_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_embedding() {
+        init_test_logging();
+        let (num_tokens, num_embeddings, embedding_dim) = (5, 10, 6);
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![num_tokens]], PaddingMode::NoPadding);
+        let embedding_node = model
+            .add_consecutive_layer(
+                Layer::Embedding(Embedding::new(Tensor::random(&vec![
+                    num_embeddings,
+                    embedding_dim,
+                ]))),
+                None,
+            )
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(embedding_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![4, num_tokens * embedding_dim])),
+                Some(flatten_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        // an index is looked up several times
+        let indices: Vec<Element> = vec![3, 0, 9, 3, 7];
+        let float_input = indices.iter().map(|i| *i as f32).collect_vec();
+        let (quantized_model, _md) =
+            InferenceObserver::new_with_representative_input(vec![vec![float_input]])
+                .quantize(model)
+                .unwrap();
+        let model = pad_model(quantized_model).unwrap();
+        // the indices are given to the quantized model as they are
+        let input = Tensor::new(vec![num_tokens], indices).pad_next_power_of_two();
+        prove_padded_model(&model, &[input]).unwrap();
+    }
+
+    /// Model looking up each of its two inputs in its own embedding table
+    fn build_two_embeddings_model(
+        num_tokens: usize,
+        first_shape: Vec<usize>,
+        second_shape: Vec<usize>,
+    ) -> (Model<f32>, NodeId, NodeId) {
+        let mut model = Model::<f32>::new_from_input_shapes(
+            vec![vec![num_tokens], vec![num_tokens]],
+            PaddingMode::NoPadding,
+        );
+        let first_node = model
+            .add_node(Node::new(
+                vec![Edge::new_at_edge(0)],
+                Layer::Embedding(Embedding::new(Tensor::random(&first_shape))),
+            ))
+            .unwrap();
+        let second_node = model
+            .add_node(Node::new(
+                vec![Edge::new_at_edge(1)],
+                Layer::Embedding(Embedding::new(Tensor::random(&second_shape))),
+            ))
+            .unwrap();
+        (model, first_node, second_node)
+    }
+
+    #[test]
+    fn test_model_token_and_position_embeddings() {
+        init_test_logging();
+        let (num_tokens, vocab_size, embedding_dim) = (5, 10, 6);
+        let (mut model, token_node, position_node) =
+            build_two_embeddings_model(num_tokens, vec![vocab_size, embedding_dim], vec![
+                num_tokens,
+                embedding_dim,
+            ]);
+        model
+            .add_node(Node::new(
+                vec![Edge::new(token_node, 0), Edge::new(position_node, 0)],
+                Layer::Add(Add::new()),
+            ))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        let tokens: Vec<Element> = vec![3, 0, 9, 3, 4];
+        let positions: Vec<Element> = (0..num_tokens as Element).collect_vec();
+        let to_float = |indices: &[Element]| indices.iter().map(|i| *i as f32).collect_vec();
+        let (quantized_model, _md) = InferenceObserver::new_with_representative_input(vec![vec![
+            to_float(&tokens),
+            to_float(&positions),
+        ]])
+        .quantize(model)
+        .unwrap();
+        let model = pad_model(quantized_model).unwrap();
+        let inputs = [tokens, positions]
+            .map(|indices| Tensor::new(vec![num_tokens], indices).pad_next_power_of_two());
+        prove_padded_model(&model, &inputs).unwrap();
+    }
+
+    #[test]
+    fn test_model_embedding_rejects_rows_of_other_table() {
+        init_test_logging();
+        let (num_tokens, num_embeddings, embedding_dim) = (4, 8, 4);
+        let shape = vec![num_embeddings, embedding_dim];
+        let (mut model, first_node, second_node) =
+            build_two_embeddings_model(num_tokens, shape.clone(), shape);
+        model.route_output(None).unwrap();
+
+        // both layers look up the same rows
+        let indices: Vec<Element> = vec![5, 1, 5, 2];
+        let float_input = indices.iter().map(|i| *i as f32).collect_vec();
+        let (quantized_model, _md) = InferenceObserver::new_with_representative_input(vec![vec![
+            float_input.clone(),
+            float_input,
+        ]])
+        .quantize(model)
+        .unwrap();
+        let model = pad_model(quantized_model).unwrap();
+        let input = Tensor::new(vec![num_tokens], indices);
+        let mut trace = model.run(&[input.clone(), input]).unwrap();
+
+        // each layer outputs the rows of the other table: the lookups of both layers are still
+        // matched by the entries of both tables together, but not by the table of their own layer
+        let outputs = [first_node, second_node]
+            .map(|node_id| trace.get_step(&node_id).unwrap().outputs()[0].clone());
+        for (node_id, output) in [(first_node, &outputs[1]), (second_node, &outputs[0])] {
+            let step = trace.steps.get_mut(&node_id).unwrap();
+            step.step_data.outputs.outputs[0] = output.clone();
+        }
+        trace.output.iter_mut().for_each(|output| {
+            *output = if output.get_data() == outputs[0].get_data() {
+                outputs[1].clone()
+            } else {
+                outputs[0].clone()
+            }
+        });
+
+        let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"model");
+        let ctx = Context::<GoldilocksExt2>::generate(&model, None).unwrap();
+        let prover: Prover<'_, E, T> = Prover::new(&ctx, &mut tr);
+        let io = trace.to_verifier_io();
+        let proof = prover.prove(trace).unwrap();
+        let mut verifier_transcript: BasicTranscript<GoldilocksExt2> =
+            BasicTranscript::new(b"model");
+        let err = verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap_err();
+        assert!(format!("{err:?}").contains("not matched by its table"));
+    }
+
+    #[test]
+    fn test_model_transpose_reshape() {
+        init_test_logging();
+        let (height, width) = (4, 6);
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![height, width]], PaddingMode::NoPadding);
+        let transpose_node = model
+            .add_consecutive_layer(Layer::Transpose(Transpose::new(vec![1, 0]).unwrap()), None)
+            .unwrap();
+        // [6, 4] -> [3, 8] only regroups the variables of the padded tensor
+        let reshape_node = model
+            .add_consecutive_layer(
+                Layer::Reshape(Reshape::new(vec![3, 8])),
+                Some(transpose_node),
+            )
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(reshape_node),
+            )
+            .unwrap();
+        let vector_node = model
+            .add_consecutive_layer(Layer::Reshape(Reshape::new(vec![24])), Some(relu_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![5, height * width])),
+                Some(vector_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_split_concat() {
+        init_test_logging();
+        let (height, width) = (4, 7);
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![height, width]], PaddingMode::NoPadding);
+        // the first branch is not padded along the split axis, the second one is padded from 3 to 4
+        let split_node = model
+            .add_node(Node::new(
+                vec![Edge::new_at_edge(0)],
+                Layer::Split(Split::new(1, vec![4, 3]).unwrap()),
+            ))
+            .unwrap();
+        let relu_node = model
+            .add_node(Node::new(
+                vec![Edge::new(split_node, 0)],
+                Layer::Activation(Activation::Relu(Relu::new())),
+            ))
+            .unwrap();
+        // the dense branch gets its own scaling factor, so the branches are rescaled before the concat
+        let dense_node = model
+            .add_node(Node::new(
+                vec![Edge::new(split_node, 1)],
+                Layer::Dense(Dense::random(vec![3, 3])),
+            ))
+            .unwrap();
+        let concat_node = model
+            .add_node(Node::new(
+                vec![Edge::new(relu_node, 0), Edge::new(dense_node, 0)],
+                Layer::Concat(Concat::new(1)),
+            ))
+            .unwrap();
+        let flatten_node = model
+            .add_consecutive_layer(Layer::Flatten(Flatten), Some(concat_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Dense(Dense::random(vec![5, height * width])),
+                Some(flatten_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        assert_eq!(model.nodes[&split_node].outputs.len(), 2);
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_residual() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 45;
+        let input_shape = vec![INPUT_SIZE];
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![input_shape.clone()], PaddingMode::NoPadding);
+        let nrows = 42;
+        let dense = Dense::random(vec![nrows, INPUT_SIZE]);
+        let input_node = model
+            .add_consecutive_layer(Layer::Dense(dense), None)
+            .unwrap();
+        let relu = Activation::Relu(Relu::new());
+        let relu_node = model
+            .add_consecutive_layer(Layer::Activation(relu), Some(input_node))
+            .unwrap();
+        let dense = Dense::random(vec![nrows, nrows]);
+        let residual_node = model
+            .add_consecutive_layer(Layer::Dense(dense), Some(relu_node))
+            .unwrap();
+        // skip connection: the output of the relu node is added to the output of the residual branch
+        let add_node = model
+            .add_node(Node::new(
+                vec![Edge::new(relu_node, 0), Edge::new(residual_node, 0)],
+                Layer::Add(Add::new()),
+            ))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        assert_eq!(model.nodes[&relu_node].outputs[0].edges.len(), 2);
+        assert_eq!(model.nodes[&add_node].inputs.len(), 2);
+
+        model.describe();
+
+        prove_model(model).unwrap();
+    }
+
+    #[test]
+    fn test_model_asymmetric_quantization() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 23;
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![INPUT_SIZE]], PaddingMode::NoPadding);
+        let dense_node = model
+            .add_consecutive_layer(Layer::Dense(Dense::random(vec![17, INPUT_SIZE])), None)
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(dense_node),
+            )
+            .unwrap();
+        model
+            .add_consecutive_layer(Layer::Dense(Dense::random(vec![9, 17])), Some(relu_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        // non-negative inputs, e.g. pixels, use the whole quantized range with a zero point
+        let float_input = Tensor::<f32>::random(&[INPUT_SIZE]);
+        let float_input = Tensor::new(
+            vec![INPUT_SIZE],
+            float_input.get_data().iter().map(|x| x.abs()).collect_vec(),
+        );
+        let (quantized_model, md) = InferenceObserver::new_with_representative_input(vec![vec![
+            float_input.get_data().to_vec(),
+        ]])
+        .with_asymmetric_quantization()
+        .quantize(model)
+        .unwrap();
+        assert_eq!(md.input[0].zero_point(), quantization::MIN);
+        let model = pad_model(quantized_model).unwrap();
+        let input = float_input.quantize(&md.input[0]).pad_next_power_of_two();
+        prove_padded_model(&model, &[input]).unwrap();
+    }
+
+    #[test]
+    fn test_model_percentile_calibration() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 23;
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![INPUT_SIZE]], PaddingMode::NoPadding);
+        let dense_node = model
+            .add_consecutive_layer(Layer::Dense(Dense::random(vec![17, INPUT_SIZE])), None)
+            .unwrap();
+        model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(dense_node),
+            )
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        // a single outlier among the representative inputs
+        let mut inputs = (0..10)
+            .map(|_| vec![Tensor::<f32>::random(&[INPUT_SIZE]).get_data().to_vec()])
+            .collect_vec();
+        inputs[0][0][0] = 1000.0;
+        let (_, md) = InferenceObserver::new_with_representative_input(inputs.clone())
+            .quantize(model.clone())
+            .unwrap();
+        let (quantized_model, clipped_md) =
+            InferenceObserver::new_with_representative_input(inputs.clone())
+                .with_calibration(Calibration::Percentile(99.0))
+                .quantize(model)
+                .unwrap();
+        assert!(clipped_md.input[0].scale() < md.input[0].scale() / 10.0);
+        let model = pad_model(quantized_model).unwrap();
+        let input = Tensor::new(vec![INPUT_SIZE], inputs[1][0].clone())
+            .quantize(&clipped_md.input[0])
+            .pad_next_power_of_two();
+        prove_padded_model(&model, &[input]).unwrap();
+    }
+
+    #[test]
+    fn test_model_per_layer_bit_len() {
+        init_test_logging();
+        const INPUT_SIZE: usize = 23;
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![INPUT_SIZE]], PaddingMode::NoPadding);
+        let dense_node = model
+            .add_consecutive_layer(Layer::Dense(Dense::random(vec![17, INPUT_SIZE])), None)
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(Relu::new())),
+                Some(dense_node),
+            )
+            .unwrap();
+        let logits_node = model
+            .add_consecutive_layer(Layer::Dense(Dense::random(vec![9, 17])), Some(relu_node))
+            .unwrap();
+        model
+            .add_consecutive_layer(Layer::Softmax(Softmax::new()), Some(logits_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        model.describe();
+
+        // the outputs of the first dense layer, and thus the relu and the weights of the second
+        // dense layer, use 12 bits while the rest of the model uses the default bit length
+        let float_input = Tensor::<f32>::random(&[INPUT_SIZE]);
+        let (quantized_model, md) = InferenceObserver::new_with_representative_input(vec![vec![
+            float_input.get_data().to_vec(),
+        ]])
+        .with_node_bit_len(dense_node, 12)
+        .quantize(model)
+        .unwrap();
+        assert_eq!(md.layer_bit_len(dense_node), 12);
+        assert_eq!(md.layer_bit_len(relu_node), 12);
+        assert_eq!(md.layer_bit_len(logits_node), quantization::DEFAULT_BIT_LEN);
+        let model = pad_model(quantized_model).unwrap();
+        let input = float_input.quantize(&md.input[0]).pad_next_power_of_two();
+        prove_padded_model(&model, &[input]).unwrap();
+    }
 }
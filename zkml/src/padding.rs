@@ -3,13 +3,25 @@ use std::collections::HashMap;
 use anyhow::{Context, Result, anyhow, bail, ensure};
 
 use crate::{
-    Element,
+    Element, NextPowerOfTwo,
     layers::{
-        convolution::Convolution,
-        dense::Dense,
+        activation::Activation,
+        add::Add,
+        affine::Affine,
+        concat::Concat,
+        convolution::{Convolution, fft_input_shape, padded_strided_shape, zero_padded_shape},
+        dense::{Dense, is_row_wise},
+        embedding::Embedding,
         flatten::Flatten,
+        matmul::MatMul,
+        norm::Normalization,
         pooling::Pooling,
-        provable::{Node, NodeId},
+        provable::{Node, NodeId, OpInfo},
+        rescale::Rescale,
+        reshape::Reshape,
+        split::Split,
+        topk::TopK,
+        transpose::Transpose,
     },
     model::{Model, ToIterator},
     parser::{check_filter, safe_conv2d_shape, safe_maxpool2d_shape},
@@ -28,11 +40,25 @@ pub struct ShapeInfo {
     shapes: Vec<ShapeData>,
 }
 
+impl ShapeInfo {
+    /// Ensures the padded entries of the inputs of `layer` are zero, which is what all layers but
+    /// the dense one expect
+    pub(crate) fn ensure_zero_padding(&self, layer: &str) -> Result<()> {
+        ensure!(
+            self.shapes.iter().all(|sd| !sd.nonzero_pad),
+            "Layer {layer} can't be applied to the output of a sigmoid, whose padded entries are not zero"
+        );
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ShapeData {
     input_shape_padded: Shape,
     ignore_garbage_pad: GarbagePad,
     input_shape_og: Shape,
+    /// Whether the padded entries of the tensor are not zero, e.g. after a sigmoid
+    nonzero_pad: bool,
 }
 
 pub fn pad_model(mut model: Model<Element>) -> Result<Model<Element>> {
@@ -45,6 +71,7 @@ pub fn pad_model(mut model: Model<Element>) -> Result<Model<Element>> {
                 input_shape_padded: padded_shape,
                 ignore_garbage_pad: None,
                 input_shape_og: unpadded_shape,
+                nonzero_pad: false,
             })
             .collect(),
     };
@@ -89,24 +116,407 @@ pub fn pad_model(mut model: Model<Element>) -> Result<Model<Element>> {
     Ok(model)
 }
 
-pub(crate) fn reshape(si: &mut ShapeInfo) -> Result<Flatten> {
+pub(crate) fn flatten(si: &mut ShapeInfo) -> Result<Flatten> {
     si.shapes.iter_mut().for_each(|sd| {
         sd.ignore_garbage_pad = Some((sd.input_shape_og.clone(), sd.input_shape_padded.clone()))
     });
     Ok(Flatten)
 }
 
+pub(crate) fn activation(a: Activation, si: &mut ShapeInfo) -> Result<Activation> {
+    // the sigmoid of the zero padding is not zero
+    if let Activation::Sigmoid(_) = a {
+        si.shapes.iter_mut().for_each(|sd| sd.nonzero_pad = true);
+    }
+    Ok(a)
+}
+
+pub(crate) fn reshape(r: Reshape, si: &mut ShapeInfo) -> Result<Reshape> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding reshape layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first_mut().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Reshape layer can't be applied to a flattened input"
+    );
+    // the entries of the padded input are kept in place only if each dimension is padded to the next
+    // power of two
+    ensure!(
+        sd.input_shape_padded == sd.input_shape_og.next_power_of_two(),
+        "Input of reshape layer is padded to {:?}, expected {:?}",
+        sd.input_shape_padded,
+        sd.input_shape_og.next_power_of_two()
+    );
+    let r = r.padded(&sd.input_shape_og)?;
+    sd.input_shape_og = r.new_shape().to_vec();
+    sd.input_shape_padded = r.new_shape().to_vec().next_power_of_two();
+    Ok(r)
+}
+
+pub(crate) fn transpose(t: Transpose, si: &mut ShapeInfo) -> Result<Transpose> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding transpose layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first_mut().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Transpose layer can't be applied to a flattened input"
+    );
+    ensure!(
+        sd.input_shape_padded.len() == t.perm().len()
+            && sd.input_shape_padded.iter().all(|d| d.is_power_of_two()),
+        "Input shape {:?} of transpose layer {:?} is not padded",
+        sd.input_shape_padded,
+        t.perm()
+    );
+    sd.input_shape_og =
+        t.output_shapes(&[sd.input_shape_og.clone()], PaddingMode::NoPadding)[0].clone();
+    sd.input_shape_padded =
+        t.output_shapes(&[sd.input_shape_padded.clone()], PaddingMode::Padding)[0].clone();
+    Ok(t)
+}
+
+pub(crate) fn concat(c: Concat, si: &mut ShapeInfo) -> Result<Concat> {
+    ensure!(
+        !si.shapes.is_empty(),
+        "Found no input shape when padding concat layer"
+    );
+    ensure!(
+        si.shapes.iter().all(|sd| sd.ignore_garbage_pad.is_none()),
+        "Concat layer can't be applied to flattened inputs"
+    );
+    let axis = c.axis();
+    let first = &si.shapes[0];
+    ensure!(
+        axis < first.input_shape_og.len(),
+        "Invalid axis {} for concat layer applied to inputs of shape {:?}",
+        axis,
+        first.input_shape_og
+    );
+    ensure!(
+        si.shapes.iter().all(|sd| {
+            sd.input_shape_padded == first.input_shape_padded
+                && sd.input_shape_og.len() == first.input_shape_og.len()
+                && (0..sd.input_shape_og.len())
+                    .all(|i| i == axis || sd.input_shape_og[i] == first.input_shape_og[i])
+        }),
+        "Inputs of concat layer have incompatible shapes: {:?}",
+        si.shapes
+            .iter()
+            .map(|sd| &sd.input_shape_og)
+            .collect::<Vec<_>>()
+    );
+    // the padding of an input along the axis would end up in the middle of the padded output
+    let last = si.shapes.len() - 1;
+    ensure!(
+        si.shapes[..last]
+            .iter()
+            .all(|sd| sd.input_shape_og[axis] == sd.input_shape_padded[axis]),
+        "Concat layer requires all the inputs but the last one to be unpadded along axis {}: {:?}",
+        axis,
+        si.shapes
+            .iter()
+            .map(|sd| &sd.input_shape_og)
+            .collect::<Vec<_>>()
+    );
+    let input_shapes_og = si
+        .shapes
+        .iter()
+        .map(|sd| sd.input_shape_og.clone())
+        .collect::<Vec<_>>();
+    let input_shapes_padded = si
+        .shapes
+        .iter()
+        .map(|sd| sd.input_shape_padded.clone())
+        .collect::<Vec<_>>();
+    // the output is a single tensor
+    si.shapes.truncate(1);
+    let sd = si.shapes.first_mut().unwrap();
+    sd.input_shape_og = c.output_shapes(&input_shapes_og, PaddingMode::NoPadding)[0].clone();
+    sd.input_shape_padded = c.output_shapes(&input_shapes_padded, PaddingMode::Padding)[0].clone();
+    Ok(c.padded())
+}
+
+pub(crate) fn split(s: Split, si: &mut ShapeInfo) -> Result<Split> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding split layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Split layer can't be applied to a flattened input"
+    );
+    let axis = s.axis();
+    ensure!(
+        axis < sd.input_shape_og.len()
+            && sd.input_shape_og[axis] == s.sizes().iter().sum::<usize>(),
+        "Can't split dimension {} of shape {:?} into sizes {:?}",
+        axis,
+        sd.input_shape_og,
+        s.sizes()
+    );
+    let s = s.padded()?;
+    // one output for each size, with the same padding of the input on the other dimensions
+    let output_shapes_og = s.output_shapes(&[sd.input_shape_og.clone()], PaddingMode::NoPadding);
+    let output_shapes_padded =
+        s.output_shapes(&[sd.input_shape_padded.clone()], PaddingMode::Padding);
+    si.shapes = output_shapes_og
+        .into_iter()
+        .zip(output_shapes_padded)
+        .map(|(og, padded)| ShapeData {
+            input_shape_padded: padded,
+            ignore_garbage_pad: None,
+            input_shape_og: og,
+            nonzero_pad: false,
+        })
+        .collect();
+    Ok(s)
+}
+
 pub(crate) fn pooling(p: Pooling, si: &mut ShapeInfo) -> Result<Pooling> {
     for sd in si.shapes.iter_mut() {
         // Make sure that input shape is already padded and is well formed
         ensure!(
             sd.input_shape_padded.iter().all(|d| d.is_power_of_two()),
-            "Input shape for max pool is not padded"
+            "Input shape for pooling is not padded"
         );
-        sd.input_shape_og = safe_maxpool2d_shape(&sd.input_shape_og)?;
-        sd.input_shape_padded = safe_maxpool2d_shape(&sd.input_shape_padded)?;
+        match p {
+            Pooling::Maxpool2D(maxpool) => {
+                sd.input_shape_og = safe_maxpool2d_shape(&maxpool.unpadded(), &sd.input_shape_og)?;
+                sd.input_shape_padded =
+                    safe_maxpool2d_shape(&maxpool.padded(), &sd.input_shape_padded)?;
+            }
+            Pooling::AvgPool2D(avgpool) => {
+                ensure!(
+                    sd.input_shape_padded.len() == 3,
+                    "Input shape for average pooling is not 3D"
+                );
+                ensure!(
+                    avgpool.kernel_size == avgpool.stride && avgpool.kernel_size.is_power_of_two(),
+                    "Average pooling requires kernel size equal to stride and power of two"
+                );
+                sd.input_shape_og = p.output_shape(&sd.input_shape_og);
+                sd.input_shape_padded = p.output_shape(&sd.input_shape_padded);
+            }
+            Pooling::GlobalAveragePool(global) => {
+                ensure!(
+                    sd.input_shape_og.len() == 3,
+                    "Input shape for global average pooling is not 3D"
+                );
+                ensure!(
+                    sd.input_shape_og[1..] == [global.height, global.width],
+                    "Input shape {:?} for global average pooling doesn't match the averaged area {:?}",
+                    sd.input_shape_og,
+                    (global.height, global.width),
+                );
+                sd.input_shape_og = p.output_shape(&sd.input_shape_og);
+                sd.input_shape_padded = p.output_shape(&sd.input_shape_padded);
+            }
+        }
     }
-    Ok(p)
+    Ok(match p {
+        // the padded layer must output tensors whose dimensions are powers of two, with zeros beyond
+        // the outputs over the unpadded input
+        Pooling::Maxpool2D(maxpool) => {
+            ensure!(
+                si.shapes.len() == 1,
+                "Maxpool expects a single input, found {}",
+                si.shapes.len()
+            );
+            let real_output = &si.shapes[0].input_shape_og;
+            let dims = real_output.len();
+            Pooling::Maxpool2D(
+                maxpool
+                    .padded()
+                    .with_real_output(real_output[dims - 2], real_output[dims - 1]),
+            )
+        }
+        // the last rows or columns of an input whose dimensions are not multiples of the kernel
+        // would otherwise be summed into padded outputs
+        Pooling::AvgPool2D(avgpool) => {
+            ensure!(
+                si.shapes.len() == 1,
+                "Average pooling expects a single input, found {}",
+                si.shapes.len()
+            );
+            let real_output = &si.shapes[0].input_shape_og;
+            Pooling::AvgPool2D(avgpool.with_real_output(real_output[1], real_output[2]))
+        }
+        _ => p,
+    })
+}
+
+pub(crate) fn add(a: Add, si: &mut ShapeInfo) -> Result<Add> {
+    ensure!(
+        si.shapes.len() == 2,
+        "Found {} input shapes when padding add layer, expected 2",
+        si.shapes.len()
+    );
+    let (first, second) = (&si.shapes[0], &si.shapes[1]);
+    ensure!(
+        first.input_shape_og == second.input_shape_og
+            && first.input_shape_padded == second.input_shape_padded,
+        "Inputs of add layer have different shapes: {:?} and {:?}",
+        first.input_shape_og,
+        second.input_shape_og
+    );
+    // the output has the same shape of both inputs
+    si.shapes.truncate(1);
+    Ok(a)
+}
+
+pub(crate) fn matmul(m: MatMul, si: &mut ShapeInfo) -> Result<MatMul> {
+    ensure!(
+        si.shapes.len() == 2,
+        "Found {} input shapes when padding matmul layer, expected 2",
+        si.shapes.len()
+    );
+    let (left, right) = (&si.shapes[0], &si.shapes[1]);
+    ensure!(
+        left.ignore_garbage_pad.is_none() && right.ignore_garbage_pad.is_none(),
+        "MatMul inputs with garbage padding are not supported"
+    );
+    ensure!(
+        left.input_shape_og.len() == 2 && right.input_shape_og.len() == 2,
+        "Inputs of matmul layer must be matrices, found shapes {:?} and {:?}",
+        left.input_shape_og,
+        right.input_shape_og
+    );
+    // index of the shared dimension and of the output columns in the second input
+    let (right_inner, right_cols) = if m.is_right_transposed() {
+        (1, 0)
+    } else {
+        (0, 1)
+    };
+    ensure!(
+        left.input_shape_og[1] == m.inner_dim()
+            && right.input_shape_og[right_inner] == m.inner_dim(),
+        "Input shapes {:?} and {:?} don't match the shared dimension {} of matmul layer",
+        left.input_shape_og,
+        right.input_shape_og,
+        m.inner_dim()
+    );
+    ensure!(
+        left.input_shape_padded[1] == right.input_shape_padded[right_inner],
+        "Padded input shapes of matmul layer are incompatible: {:?} and {:?}",
+        left.input_shape_padded,
+        right.input_shape_padded
+    );
+    let input_shape_og = vec![left.input_shape_og[0], right.input_shape_og[right_cols]];
+    let input_shape_padded = vec![
+        left.input_shape_padded[0],
+        right.input_shape_padded[right_cols],
+    ];
+    // the output is a single matrix with the rows of the first input and the columns of the second one
+    si.shapes.truncate(1);
+    let sd = si.shapes.first_mut().unwrap();
+    sd.input_shape_og = input_shape_og;
+    sd.input_shape_padded = input_shape_padded;
+    Ok(m)
+}
+
+pub(crate) fn topk(t: TopK, si: &mut ShapeInfo) -> Result<TopK> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding top-k layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first_mut().unwrap();
+    ensure!(
+        sd.input_shape_og.len() == 1,
+        "Input of top-k layer must be a vector, found shape {:?}",
+        sd.input_shape_og
+    );
+    let t = t.padded(sd.input_shape_og[0])?;
+    // the output is made of the indices of the selected entries
+    sd.input_shape_og = vec![t.k()];
+    sd.input_shape_padded = vec![t.k().next_power_of_two()];
+    Ok(t)
+}
+
+pub(crate) fn normalization(
+    n: Normalization<Element>,
+    si: &mut ShapeInfo,
+) -> Result<Normalization<Element>> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding normalization layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Normalization layer does not support garbage padding"
+    );
+    ensure!(
+        sd.input_shape_padded.iter().all(|d| d.is_power_of_two()),
+        "Input shape for normalization is not padded"
+    );
+    // the output has the same shape of the input, only the weights are padded
+    n.padded(*sd.input_shape_padded.last().unwrap())
+}
+
+pub(crate) fn affine(a: Affine<Element>, si: &mut ShapeInfo) -> Result<Affine<Element>> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding affine layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Affine layer does not support garbage padding"
+    );
+    ensure!(
+        sd.input_shape_padded.iter().all(|d| d.is_power_of_two()),
+        "Input shape for affine layer is not padded"
+    );
+    ensure!(
+        sd.input_shape_padded.len() == sd.input_shape_og.len(),
+        "Padded and unpadded input shapes of affine layer have different lengths: {:?} and {:?}",
+        sd.input_shape_padded,
+        sd.input_shape_og
+    );
+    // the output has the same shape of the input, only the weights of the channels are padded
+    a.padded(sd.input_shape_padded[0], sd.input_shape_og[1..].to_vec())
+}
+
+pub(crate) fn rescale(r: Rescale, si: &mut ShapeInfo) -> Result<Rescale> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding rescale layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first().unwrap();
+    let r = r.padded();
+    // the channels of the multipliers must be padded like the channels of the input
+    r.check_input_shape(&sd.input_shape_padded)?;
+    // the output has the same shape of the input
+    Ok(r)
+}
+
+pub(crate) fn embedding(e: Embedding<Element>, si: &mut ShapeInfo) -> Result<Embedding<Element>> {
+    ensure!(
+        si.shapes.len() == 1,
+        "Found {} input shapes when padding embedding layer, expected 1",
+        si.shapes.len()
+    );
+    let sd = si.shapes.first_mut().unwrap();
+    ensure!(
+        sd.ignore_garbage_pad.is_none(),
+        "Embedding layer does not support garbage padding"
+    );
+    // the embedding dimension is appended to the shape of the indices
+    sd.input_shape_og = e.output_shape(&sd.input_shape_og, PaddingMode::NoPadding);
+    sd.input_shape_padded = e.output_shape(&sd.input_shape_padded, PaddingMode::Padding);
+    Ok(e.padded())
 }
 
 pub(crate) fn pad_conv(
@@ -119,7 +529,15 @@ pub(crate) fn pad_conv(
         "More than 1 input shape found when padding convolution layer"
     );
     let sd = si.shapes.first_mut().unwrap();
-    sd.input_shape_og = safe_conv2d_shape(&sd.input_shape_og, &c.filter.get_shape())?;
+    // the FFT filter is derived from the input shape, not from the (possibly strided) output shape
+    let unpadded_input_shape = sd.input_shape_og.clone();
+    // the zero padding of the convolution enlarges the input before the convolution itself
+    let pads = c.pads(&unpadded_input_shape);
+    sd.input_shape_og = safe_conv2d_shape(
+        &zero_padded_shape(&unpadded_input_shape, pads),
+        &c.filter.get_shape(),
+        c.stride,
+    )?;
     let weight_shape = c.filter.get_shape();
     // Perform basic sanity checks on the tensor dimensions
     check_filter(&weight_shape).context("filter shape test failed:")?;
@@ -140,19 +558,28 @@ pub(crate) fn pad_conv(
     // Since we are doing an FFT based conv, we need to pad the last two dimensions of the filter to match the input.
     let weight_shape = c.filter.pad_next_power_of_two().get_shape();
     let (filter_height, filter_width) = (weight_shape[2], weight_shape[3]);
-    let (input_height, input_width) = (sd.input_shape_padded[1], sd.input_shape_padded[2]);
+    let fft_input_shape = fft_input_shape(&sd.input_shape_padded, pads);
+    let (input_height, input_width) = (fft_input_shape[1], fft_input_shape[2]);
 
     ensure!(
         filter_height <= input_height && filter_width <= input_width,
         "Filter dimensions in convolution have to be smaller than input dimensions",
     );
 
-    let new_conv = new_conv_good.into_padded_and_ffted(&sd.input_shape_og);
-    let output_shape = safe_conv2d_shape(&sd.input_shape_padded, &weight_shape)?;
-    sd.input_shape_padded = output_shape
+    let new_conv = new_conv_good.into_padded_and_ffted(&unpadded_input_shape);
+    ensure!(
+        new_conv.input_channels() == sd.input_shape_padded[0],
+        "Input channels of convolution don't match the filter: {} vs {}",
+        sd.input_shape_padded[0],
+        new_conv.input_channels()
+    );
+    let output_shape = safe_conv2d_shape(&fft_input_shape, &weight_shape, 1)?;
+    let output_shape = output_shape
         .iter()
         .map(|i| i.next_power_of_two())
         .collect::<Vec<_>>();
+    // a strided convolution subsamples the output of the FFT convolution
+    sd.input_shape_padded = padded_strided_shape(&output_shape, c.stride);
     Ok(new_conv)
 }
 
@@ -164,7 +591,6 @@ pub(crate) fn pad_dense(mut d: Dense<Element>, si: &mut ShapeInfo) -> Result<Den
     );
     let sd = si.shapes.first_mut().unwrap();
     let nrows = d.matrix.get_shape()[0];
-    sd.input_shape_og = vec![nrows];
     ensure!(
         d.bias.get_data().len() == nrows,
         "Bias length {} does not match matrix width {}",
@@ -175,6 +601,30 @@ pub(crate) fn pad_dense(mut d: Dense<Element>, si: &mut ShapeInfo) -> Result<Den
         sd.input_shape_padded.iter().all(|d| d.is_power_of_two()),
         "Input shape for dense is not padded"
     );
+    if is_row_wise(&sd.input_shape_og, &d.unpadded_matrix_shape) {
+        // the matrix is applied to each row of the input, so only the columns of the input
+        // need to match the columns of the matrix
+        ensure!(
+            sd.ignore_garbage_pad.is_none() && !sd.nonzero_pad,
+            "Dense layer applied to each row of the input does not support garbage padding"
+        );
+        ensure!(
+            sd.input_shape_padded.len() == 2 && sd.input_shape_padded[1] >= d.matrix.ncols_2d(),
+            "Padded input shape {:?} does not fit dense matrix with {} columns",
+            sd.input_shape_padded,
+            d.matrix.ncols_2d()
+        );
+        let ncols = sd.input_shape_padded[1];
+        let nrows = pad_minimum(d.matrix.nrows_2d());
+        d.matrix.reshape_to_fit_inplace_2d(vec![nrows, ncols]);
+        d.bias = d.bias.pad_1d(nrows);
+        sd.input_shape_og = vec![sd.input_shape_og[0], d.unpadded_matrix_shape[0]];
+        sd.input_shape_padded = vec![sd.input_shape_padded[0], nrows];
+        return Ok(d);
+    }
+    sd.input_shape_og = vec![nrows];
+    // the padded entries of the input are multiplied by the zero padded columns of the matrix
+    sd.nonzero_pad = false;
     if sd.input_shape_padded.len() != 1 {
         sd.input_shape_padded = vec![sd.input_shape_padded.iter().product()];
         sd.input_shape_og = vec![sd.input_shape_og.iter().product()];
@@ -2,7 +2,7 @@ pub mod onnx;
 
 use crate::{
     Element,
-    layers::{convolution::conv2d_shape, pooling::maxpool2d_shape},
+    layers::{convolution::strided_conv2d_shape, pooling::Maxpool2D},
     model::Model,
     padding::pad_model,
     parser::onnx::from_path,
@@ -68,11 +68,28 @@ impl<S: ScalingStrategy> FloatOnnxLoader<S> {
     }
 }
 // Supported operators
-const ACTIVATION: [&str; 2] = ["Relu", "Sigmoid"];
+const ACTIVATION: [&str; 7] = [
+    "Relu",
+    "Sigmoid",
+    "Tanh",
+    "Gelu",
+    "LeakyRelu",
+    "Clip",
+    "Softmax",
+];
 const CONVOLUTION: [&str; 1] = ["Conv"];
-const DOWNSAMPLING: [&str; 1] = ["MaxPool"];
+const DOWNSAMPLING: [&str; 3] = ["MaxPool", "AveragePool", "GlobalAveragePool"];
 const LINEAR_ALG: [&str; 2] = ["Gemm", "MatMul"];
-const RESHAPE: [&str; 2] = ["Flatten", "Reshape"];
+const RESHAPE: [&str; 5] = ["Flatten", "Reshape", "Transpose", "Squeeze", "Unsqueeze"];
+// merge or separate the branches of a model, e.g. of an inception block
+const BRANCHING: [&str; 2] = ["Concat", "Split"];
+// Mul is only supported as the gating of a SiLU activation or as the scaling of a product
+const ARITHMETIC: [&str; 2] = ["Add", "Mul"];
+// only supported in attention layers: the division by a constant scales the product of the
+// queries and the keys
+const ATTENTION: [&str; 2] = ["Div", "Constant"];
+// layers revealing only part of the output of the model
+const OUTPUT: [&str; 1] = ["ArgMax"];
 
 fn is_mlp(filepath: &str) -> Result<bool> {
     let is_mlp = true;
@@ -94,6 +111,8 @@ fn is_mlp(filepath: &str) -> Result<bool> {
                 return Ok(false);
             }
             prev_was_gemm_or_matmul = false;
+        } else if OUTPUT.contains(&node.op_type.as_str()) {
+            prev_was_gemm_or_matmul = false;
         } else {
             return Err(Error::msg(format!(
                 "Operator '{}' unsupported, yet.",
@@ -125,6 +144,10 @@ fn is_cnn(filepath: &str) -> Result<bool> {
             && !ACTIVATION.contains(&op_type)
             && !LINEAR_ALG.contains(&op_type)
             && !RESHAPE.contains(&op_type)
+            && !BRANCHING.contains(&op_type)
+            && !ARITHMETIC.contains(&op_type)
+            && !ATTENTION.contains(&op_type)
+            && !OUTPUT.contains(&op_type)
         {
             return Err(Error::msg(format!(
                 "Operator '{}' unsupported, yet.",
@@ -157,33 +180,43 @@ fn is_cnn(filepath: &str) -> Result<bool> {
     Ok(is_cnn)
 }
 
-pub fn safe_conv2d_shape(input_shape: &[usize], filter_shape: &[usize]) -> Result<Vec<usize>> {
+pub fn safe_conv2d_shape(
+    input_shape: &[usize],
+    filter_shape: &[usize],
+    stride: usize,
+) -> Result<Vec<usize>> {
     let result = check_filter(filter_shape);
     assert!(result.is_ok(), "conv2d: Failed {:?}", result.unwrap_err());
 
     check_cnn_input(input_shape).context("conv2d: invalid input shape")?;
+    ensure!(stride > 0, "conv2d: stride must be positive");
 
-    Ok(conv2d_shape(input_shape, filter_shape))
+    Ok(strided_conv2d_shape(input_shape, filter_shape, stride))
 }
 
 pub fn check_filter(filter_shape: &[usize]) -> Result<()> {
     ensure!(filter_shape.len() == 4, "Filter should be 4D tensor.");
     ensure!(
-        filter_shape[2] == filter_shape[3],
-        "Filter should be square."
+        filter_shape[2] > 0 && filter_shape[3] > 0,
+        "Filter should have non-empty spatial dimensions."
     );
     Ok(())
 }
 
 pub fn check_cnn_input(input_shape: &[usize]) -> Result<()> {
     ensure!(input_shape.len() == 3, "input should be 3d tensor");
-    ensure!(input_shape[1] == input_shape[2], "input should be square");
     Ok(())
 }
 
-pub fn safe_maxpool2d_shape(input_shape: &[usize]) -> Result<Vec<usize>> {
+pub fn safe_maxpool2d_shape(maxpool: &Maxpool2D, input_shape: &[usize]) -> Result<Vec<usize>> {
     check_cnn_input(input_shape).context("maxpool2d: invalid input shape")?;
-    Ok(maxpool2d_shape(input_shape))
+    ensure!(
+        input_shape[1] >= maxpool.kernel_size && input_shape[2] >= maxpool.kernel_size,
+        "maxpool2d: kernel size {} is larger than input {:?}",
+        maxpool.kernel_size,
+        input_shape
+    );
+    Ok(maxpool.output_shape(input_shape))
 }
 
 /// Enum representing the different types of models that can be loaded
@@ -241,7 +274,8 @@ mod tests {
     use super::*;
 
     use crate::{
-        Context, Prover, ScalingFactor, init_test_logging, quantization::InferenceObserver, verify,
+        Context, Prover, ScalingFactor, init_test_logging, layers::Layer,
+        quantization::InferenceObserver, verify,
     };
     use goldilocks::GoldilocksExt2;
     use tracing::info;
@@ -377,6 +411,56 @@ mod tests {
         verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap();
     }
 
+    #[test]
+    fn test_load_attention() {
+        init_test_logging();
+        let filepath = "assets/scripts/transformer/encoder-01.onnx";
+        let (model, md) =
+            FloatOnnxLoader::new_with_scaling_strategy(&filepath, InferenceObserver::new())
+                .build()
+                .unwrap();
+        model.describe();
+        // the products of the queries with the keys and of the attention with the values
+        let matmuls = model
+            .nodes
+            .values()
+            .filter(|node| matches!(node.operation, Layer::MatMul(_)))
+            .count();
+        assert_eq!(matmuls, 2);
+        assert_eq!(model.unpadded_input_shapes(), vec![vec![6, 12]]);
+
+        let native_input = model
+            .unpadded_input_shapes()
+            .into_iter()
+            .zip(&md.input)
+            .map(|(shape, s)| crate::tensor::Tensor::<f32>::random(&shape).quantize(s))
+            .collect();
+        let input = model.prepare_inputs(native_input).unwrap();
+        let trace = model.run::<F>(&input).unwrap();
+
+        let mut tr: BasicTranscript<GoldilocksExt2> = BasicTranscript::new(b"m2vec");
+        let ctx =
+            Context::<GoldilocksExt2>::generate(&model, None).expect("Unable to generate context");
+        let prover: Prover<'_, GoldilocksExt2, BasicTranscript<GoldilocksExt2>> =
+            Prover::new(&ctx, &mut tr);
+        let io = trace.to_verifier_io();
+        let proof = prover.prove(trace).expect("unable to generate proof");
+        let mut verifier_transcript: BasicTranscript<GoldilocksExt2> =
+            BasicTranscript::new(b"m2vec");
+        verify::<_, _>(ctx, proof, io, &mut verifier_transcript).unwrap();
+    }
+
+    #[test]
+    fn test_load_multi_head_attention() {
+        // the heads are a batch dimension of the products, which is rejected rather than mis-parsed
+        let filepath = "assets/scripts/transformer/encoder-2heads-01.onnx";
+        let err = FloatOnnxLoader::new(&filepath).build().unwrap_err();
+        assert!(
+            format!("{err:?}").contains("multi-head attention"),
+            "unexpected error: {err:?}"
+        );
+    }
+
     #[test]
     fn test_tract() {
         let filepath = "assets/scripts/CNN/cnn-cifar-01.onnx";
@@ -2,15 +2,27 @@ use crate::{
     ModelType,
     layers::{
         Layer,
-        activation::Activation,
-        convolution::Convolution,
-        pooling::{MAXPOOL2D_KERNEL_SIZE, Maxpool2D, Pooling},
+        activation::{Activation, Clip, CommittedActivation, LeakyRelu, Sigmoid, TableFunction},
+        add::Add,
+        affine::Affine,
+        concat::Concat,
+        convolution::{ConvPadding, Convolution, GroupedConv},
+        embedding::Embedding,
+        flatten::Flatten,
+        matmul::MatMul,
+        pooling::{AvgPool2D, GlobalAveragePool, Maxpool2D, Pooling},
         provable::{Edge, Node as ProvableNode, NodeId, OpInfo},
+        reshape::Reshape,
+        softmax::Softmax,
+        split::Split,
+        topk::TopK,
+        transpose::Transpose,
     },
-    model::Model,
+    model::{Model, ToIterator},
     padding::PaddingMode,
 };
-use anyhow::{Context, Result, bail, ensure};
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use itertools::Itertools;
 use std::{collections::HashMap, iter::Peekable};
 use tracing::debug;
 use tract_onnx::{
@@ -18,9 +30,13 @@ use tract_onnx::{
     tract_core::{
         self,
         ops::{
+            array::{Gather, Slice, TypedConcat},
             binary::TypedBinOp,
-            cnn::{Conv, MaxPool},
+            change_axes::AxisOp,
+            cnn::{Conv, MaxPool, SumPool},
             einsum::EinSum,
+            element_wise::ElementWiseOp,
+            nn::{Reduce, Reducer},
             source::TypedSource,
         },
     },
@@ -95,19 +111,26 @@ pub fn from_path(path: &str) -> Result<Model<f32>> {
     let mut pmodel =
         Model::new_from_input_shapes(vec![input_shape.to_vec()], PaddingMode::NoPadding);
     let mut it = inference_order[1..].iter().peekable();
-    let mut first_node = true;
     let mut last_node_id = 0;
     let parser = ParserFactory::init();
+    // tract turns each output of a split into its own slice node, so the edges from these nodes are
+    // redirected to the corresponding output of the split layer
+    let mut split_outputs: HashMap<NodeId, Edge> = HashMap::new();
     while !it.is_empty() {
-        let (id, zkml_node) = parser
-            .parse_node(onnx_model, &mut it, first_node)
+        let (id, mut zkml_node) = parser
+            .parse_node(onnx_model, &mut it, inference_order[0])
             .context("Error parsing node")?;
-        // let (id, zkml_node) = parse_node(onnx_model, &mut it, first_node)?;
+        zkml_node.inputs = redirect_split_outputs(zkml_node.inputs, &split_outputs);
+        if let Layer::Split(_) = &zkml_node.operation {
+            let (_, slices) = split_slices(onnx_model, onnx_model.node(id))?;
+            for (i, (slice_id, _)) in slices.into_iter().enumerate() {
+                split_outputs.insert(slice_id, Edge::new(id, i));
+            }
+        }
         let desc = zkml_node.operation.describe();
         pmodel
             .add_node_with_id(id, zkml_node)
             .context(format!("adding node {}:", desc))?;
-        first_node = false;
         last_node_id = id;
     }
     let outputs = onnx_model
@@ -115,13 +138,138 @@ pub fn from_path(path: &str) -> Result<Model<f32>> {
         .iter()
         .map(|outlet| Edge::new(outlet.node, outlet.slot))
         .collect::<Vec<_>>();
+    let outputs = redirect_split_outputs(outputs, &split_outputs);
     assert!(
         outputs
             .iter()
             .any(|edge| edge.node.unwrap() == last_node_id)
     );
     pmodel.route_output(Some(outputs))?;
-    Ok(pmodel)
+    fold_affine_layers(pmodel)
+}
+
+/// Folds the per-channel affine layers, e.g. the batch normalizations of a model exported for
+/// inference, into the weights and bias of the dense or convolution layer they follow, so that they
+/// don't need to be proven on their own. This happens before quantization, so the folded weights
+/// get their own scaling factor. Affine layers which can't be folded, e.g. because the output of the
+/// previous layer is also used by other nodes, are kept as standalone layers.
+fn fold_affine_layers(mut model: Model<f32>) -> Result<Model<f32>> {
+    let model_input_shapes = model.unpadded_input_shapes();
+    let mut output_shapes: HashMap<NodeId, Vec<Vec<usize>>> = HashMap::new();
+    let mut foldable = vec![];
+    for (node_id, node) in model.to_forward_iterator() {
+        let input_shapes = node
+            .inputs
+            .iter()
+            .map(|edge| match edge.node {
+                Some(n) => output_shapes[&n][edge.index].clone(),
+                None => model_input_shapes[edge.index].clone(),
+            })
+            .collect_vec();
+        if let (
+            Layer::Affine(_),
+            [
+                Edge {
+                    node: Some(previous_id),
+                    index: 0,
+                },
+            ],
+        ) = (&node.operation, node.inputs.as_slice())
+        {
+            let previous = &model.nodes[previous_id];
+            let single_output = previous.outputs.len() == 1 && previous.outputs[0].edges.len() == 1;
+            // the channels of the affine layer must be the output channels of the previous layer,
+            // which is not the case for a dense layer applied to each row of a matrix
+            let same_channels = match &previous.operation {
+                Layer::Dense(_) => input_shapes[0].len() == 1,
+                Layer::Convolution(_) | Layer::GroupedConvolution(_) => true,
+                _ => false,
+            };
+            if single_output && same_channels {
+                foldable.push((node_id, *previous_id));
+            }
+        }
+        output_shapes.insert(
+            node_id,
+            node.operation
+                .output_shapes(&input_shapes, PaddingMode::NoPadding),
+        );
+    }
+    for (affine_id, previous_id) in foldable {
+        fold_affine_node(&mut model, affine_id, previous_id)?;
+    }
+    Ok(model)
+}
+
+/// Removes the affine node `affine_id` from the model by folding it into the node `previous_id`,
+/// whose output is only used by the affine node
+fn fold_affine_node(model: &mut Model<f32>, affine_id: NodeId, previous_id: NodeId) -> Result<()> {
+    let affine_node = model
+        .nodes
+        .remove(&affine_id)
+        .ok_or(anyhow!("Affine node {affine_id} not found in model"))?;
+    let Layer::Affine(affine) = affine_node.operation else {
+        bail!("Node {affine_id} is not an affine layer");
+    };
+    // the nodes using the output of the affine node now use the output of the previous node
+    for edge in &affine_node.outputs[0].edges {
+        if let Some(n) = edge.node {
+            let node = model.nodes.get_mut(&n).ok_or(anyhow!(
+                "Node {n}, using the output of node {affine_id}, not found"
+            ))?;
+            node.inputs[edge.index] = Edge::new(previous_id, 0);
+        }
+    }
+    let previous = model
+        .nodes
+        .get_mut(&previous_id)
+        .ok_or(anyhow!("Node {previous_id} not found in model"))?;
+    match previous.operation {
+        Layer::Dense(ref mut dense) => fold_affine(&mut dense.matrix, &mut dense.bias, &affine)?,
+        Layer::Convolution(ref mut conv) => fold_affine(&mut conv.filter, &mut conv.bias, &affine)?,
+        Layer::GroupedConvolution(ref mut conv) => {
+            fold_affine(&mut conv.0.filter, &mut conv.0.bias, &affine)?
+        }
+        ref other => bail!("Affine layer can't be folded into {}", other.describe()),
+    }
+    previous.outputs = affine_node.outputs;
+    Ok(())
+}
+
+/// Folds `scale[c] * (w * x + b)[c] + bias[c]` into the weights and the bias of each output channel
+/// `c`, where the weights of a channel are its slice along the first dimension of `weights`
+fn fold_affine(
+    weights: &mut crate::Tensor<f32>,
+    bias: &mut crate::Tensor<f32>,
+    affine: &Affine<f32>,
+) -> Result<()> {
+    let num_channels = affine.num_channels();
+    ensure_onnx!(
+        weights.get_shape()[0] == num_channels && bias.get_shape() == vec![num_channels],
+        "Affine layer with {} channels can't be folded into weights of shape {:?}",
+        num_channels,
+        weights.get_shape()
+    );
+    let channel_len = weights.get_data().len() / num_channels;
+    let scale = affine.scale().get_data();
+    *weights = crate::Tensor::new(
+        weights.get_shape(),
+        weights
+            .get_data()
+            .chunks(channel_len)
+            .zip(scale)
+            .flat_map(|(channel, s)| channel.iter().map(move |w| w * s))
+            .collect(),
+    );
+    *bias = crate::Tensor::new(
+        bias.get_shape(),
+        bias.get_data()
+            .iter()
+            .zip(scale.iter().zip(affine.bias().get_data()))
+            .map(|(b, (s, t))| b * s + t)
+            .collect(),
+    );
+    Ok(())
 }
 
 type LoadFn<'a, I> = fn(
@@ -151,11 +299,26 @@ impl<'a, I: Iterator<Item = &'a usize> + Sized> ParserFactory<'a, I> {
         let mut m = HashMap::new();
         m.insert("Conv", load_conv as LoadFn<'a, I>);
         m.insert("Gemm.ab", load_gemm as LoadFn<'a, I>);
-        m.insert("MatMul", load_gemm as LoadFn<'a, I>); //ToDo: currently MatMul is only used for dense layers without bias; 
-        // we would probably need an ad-hoc method when introducing general purpose matrix multiplication layer
+        m.insert("MatMul", load_matmul as LoadFn<'a, I>);
         m.insert("Relu", load_relu as LoadFn<'a, I>);
+        m.insert("Sigmoid", load_sigmoid as LoadFn<'a, I>);
+        m.insert("Tanh", load_tanh as LoadFn<'a, I>);
+        m.insert("Gelu", load_gelu as LoadFn<'a, I>);
+        m.insert("LeakyRelu", load_leaky_relu as LoadFn<'a, I>);
+        m.insert("Clip", load_clip as LoadFn<'a, I>);
         m.insert("Flatten", load_flatten as LoadFn<'a, I>);
-        m.insert("Pool", load_maxpool as LoadFn<'a, I>);
+        m.insert("Reshape", load_axis_op as LoadFn<'a, I>);
+        m.insert("Transpose", load_axis_op as LoadFn<'a, I>);
+        m.insert("Squeeze", load_axis_op as LoadFn<'a, I>);
+        m.insert("Unsqueeze", load_axis_op as LoadFn<'a, I>);
+        m.insert("Pool", load_pooling as LoadFn<'a, I>);
+        m.insert("Add", load_add as LoadFn<'a, I>);
+        m.insert("Softmax", load_softmax as LoadFn<'a, I>);
+        m.insert("ArgMax", load_argmax as LoadFn<'a, I>);
+        m.insert("BatchNormalization", load_batchnorm as LoadFn<'a, I>);
+        m.insert("Gather", load_gather as LoadFn<'a, I>);
+        m.insert("Concat", load_concat as LoadFn<'a, I>);
+        m.insert("Split", load_split as LoadFn<'a, I>);
         ParserFactory(m)
     }
 
@@ -163,7 +326,7 @@ impl<'a, I: Iterator<Item = &'a usize> + Sized> ParserFactory<'a, I> {
         &self,
         model: &OnnxModel,
         iter: &mut Peekable<I>,
-        first_node: bool,
+        input_node_id: usize,
     ) -> Result<(NodeId, CustomNode)> {
         let curr_node_id = iter.next().ok_or(anyhow::anyhow!("No nodes left"))?;
         let curr_node = model.node(*curr_node_id);
@@ -173,21 +336,28 @@ impl<'a, I: Iterator<Item = &'a usize> + Sized> ParserFactory<'a, I> {
         );
         #[allow(unused_variables)]
         let op_name = &curr_node.name;
+        // the longest matching name is the most specific one, e.g. `LeakyRelu` rather than `Relu`
         if let Some(layer_name) = self
             .0
             .keys()
-            .find(|&&layer_name| op_name.contains(layer_name))
+            .filter(|&&layer_name| op_name.contains(layer_name))
+            .max_by_key(|layer_name| layer_name.len())
         {
             let parser = self.0.get(layer_name).unwrap();
             let (node_id, mut node) = parser(model, *curr_node_id, curr_node, iter)?;
-            if first_node {
-                // if the node is the first one, we need to add the input edge as an input to the node
-                node.inputs = node
-                    .inputs
-                    .into_iter()
-                    .map(|x| Edge::new_at_edge(x.index))
-                    .collect();
-            }
+            // the input of the model can be used by several nodes, e.g. the projections of an
+            // attention layer and a skip connection, so all the edges from it are inputs of the model
+            node.inputs = node
+                .inputs
+                .into_iter()
+                .map(|x| {
+                    if x.node == Some(input_node_id) {
+                        Edge::new_at_edge(x.index)
+                    } else {
+                        x
+                    }
+                })
+                .collect();
             debug!(
                 "parsed node id: {:?} : {:?} <- inputs: {:?}",
                 curr_node_id,
@@ -214,39 +384,191 @@ fn load_flatten<'a, I: Iterator<Item = &'a usize> + Sized>(
     );
     let node = ProvableNode::new(
         vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
-        Layer::Flatten(crate::layers::flatten::Flatten),
+        Layer::Flatten(Flatten),
     );
     Ok((node_id, node))
 }
 
-fn load_maxpool<'a, I: Iterator<Item = &'a usize> + Sized>(
+/// Loads a node only changing the shape of its input: tract turns `Transpose` into moves of an axis,
+/// and `Reshape`, `Squeeze` and `Unsqueeze` into reshapes, additions or removals of axes. The batch
+/// dimension, which is removed from the input of the model, must be left untouched.
+fn load_axis_op<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(node.inputs.len() == 1, "{} must have 1 input", node.name);
+    let axis_op = downcast_to::<AxisOp>(node)?;
+    let input_link = node.inputs[0];
+    let input_shape = get_node_output_shape(model.node(input_link.node), input_link.slot)?;
+    let output_shape = get_node_output_shape(node, 0)?;
+    ensure_onnx!(
+        input_shape.first() == Some(&1) && output_shape.first() == Some(&1),
+        "{} must keep the batch dimension of size 1: {:?} -> {:?}",
+        node.name,
+        input_shape,
+        output_shape
+    );
+    let layer = match axis_op {
+        AxisOp::Move(from, to) => {
+            ensure_onnx!(
+                *from > 0 && *to > 0,
+                "{} can't move the batch dimension",
+                node.name
+            );
+            Layer::Transpose(Transpose::new(move_axis_permutation(
+                input_shape.len() - 1,
+                from - 1,
+                to - 1,
+            ))?)
+        }
+        // a reshape into a vector is a flatten, which also supports inputs with garbage in the padding
+        _ if output_shape.len() == 2 => Layer::Flatten(Flatten),
+        _ => Layer::Reshape(Reshape::new(output_shape[1..].to_vec())),
+    };
+    let provable_node = ProvableNode::new(vec![Edge::new(input_link.node, input_link.slot)], layer);
+    Ok((node_id, provable_node))
+}
+
+/// Loads a concatenation of the outputs of several nodes, e.g. of the branches of an inception block
+fn load_concat<'a, I: Iterator<Item = &'a usize> + Sized>(
     _model: &OnnxModel,
     node_id: NodeId,
     node: &OnnxNode,
     _iter: &mut Peekable<I>,
 ) -> Result<(NodeId, CustomNode)> {
+    let concat = downcast_to::<TypedConcat>(node)?;
     ensure_onnx!(
-        node.inputs.len() == 1,
-        "MaxPool {} must have 1 input",
+        concat.axis > 0,
+        "{} can't concatenate along the batch dimension",
         node.name
     );
-    let max_node = downcast_to::<MaxPool>(node)?;
-    let expected_value: usize = MAXPOOL2D_KERNEL_SIZE;
-    if let Some(ref strides) = max_node.pool_spec.strides {
+    let inputs = node
+        .inputs
+        .iter()
+        .map(|input| Edge::new(input.node, input.slot))
+        .collect();
+    let provable_node = ProvableNode::new(inputs, Layer::Concat(Concat::new(concat.axis - 1)));
+    Ok((node_id, provable_node))
+}
+
+/// Loads a `Split` node: tract turns it into one `Slice` node for each output, all reading the same
+/// input, which are loaded together as a single split layer.
+fn load_split<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let (axis, slices) = split_slices(model, node)?;
+    ensure_onnx!(axis > 0, "{} can't split the batch dimension", node.name);
+    // the other slices are part of the same layer, so they must follow this one
+    for _ in 1..slices.len() {
         ensure_onnx!(
-            strides.iter().all(|&x| x == expected_value),
-            "Strides must be {}",
-            expected_value
+            iter.next_if(|id| slices.iter().any(|(slice_id, _)| slice_id == *id))
+                .is_some(),
+            "Outputs of {} are not evaluated consecutively",
+            node.name
         );
     }
-    match max_node.pool_spec.padding {
-        PaddingSpec::Explicit(ref pad0, ref pad1) => {
+    let input_link = node.inputs[0];
+    let split = Split::new(axis - 1, slices.iter().map(|(_, size)| *size).collect())?;
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(input_link.node, input_link.slot)],
+        Layer::Split(split),
+    );
+    Ok((node_id, provable_node))
+}
+
+/// Returns the axis of the split which `node` is a slice of, together with the id and the size of each
+/// slice of the split, sorted by position along the axis
+fn split_slices(model: &OnnxModel, node: &OnnxNode) -> Result<(usize, Vec<(NodeId, usize)>)> {
+    ensure_onnx!(node.inputs.len() == 1, "{} must have 1 input", node.name);
+    let axis = downcast_to::<Slice>(node)?.axis;
+    let input_link = node.inputs[0];
+    let input_node = model.node(input_link.node);
+    let mut slices = input_node.outputs[input_link.slot]
+        .successors
+        .iter()
+        .filter_map(|inlet| {
+            let successor = model.node(inlet.node);
+            successor
+                .op_as::<Slice>()
+                .filter(|slice| slice.axis == axis && successor.name.contains("Split"))
+                .map(|slice| -> Result<(NodeId, usize, usize)> {
+                    Ok((inlet.node, slice.start.to_usize()?, slice.end.to_usize()?))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    slices.sort_by_key(|(_, start, _)| *start);
+    let input_shape = get_node_output_shape(input_node, input_link.slot)?;
+    ensure_onnx!(
+        slices.first().map(|(_, start, _)| *start) == Some(0)
+            && slices.iter().tuple_windows().all(|(a, b)| a.2 == b.1)
+            && slices.last().map(|(_, _, end)| *end) == input_shape.get(axis).copied(),
+        "Slices of split {} don't cover its input of shape {:?}",
+        node.name,
+        input_shape
+    );
+    Ok((
+        axis,
+        slices
+            .into_iter()
+            .map(|(id, start, end)| (id, end - start))
+            .collect(),
+    ))
+}
+
+/// Redirects the edges from the slice nodes of a split to the corresponding output of the split layer
+fn redirect_split_outputs(edges: Vec<Edge>, split_outputs: &HashMap<NodeId, Edge>) -> Vec<Edge> {
+    edges
+        .into_iter()
+        .map(|edge| {
+            edge.node
+                .and_then(|n| split_outputs.get(&n))
+                .cloned()
+                .unwrap_or(edge)
+        })
+        .collect()
+}
+
+/// Permutation of the dimensions moving dimension `from` to position `to`, shifting the dimensions in between
+fn move_axis_permutation(num_dims: usize, from: usize, to: usize) -> Vec<usize> {
+    let mut perm = (0..num_dims).collect_vec();
+    let axis = perm.remove(from);
+    perm.insert(to, axis);
+    perm
+}
+
+/// Loads any kind of pooling node: tract exposes max pooling as `MaxPool`, average pooling as a normalized
+/// `SumPool` and global average pooling as a sum `Reduce` over the spatial axes followed by a division.
+fn load_pooling<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    if downcast_to::<MaxPool>(node).is_ok() {
+        load_maxpool(model, node_id, node, iter)
+    } else if downcast_to::<SumPool>(node).is_ok() {
+        load_avgpool(model, node_id, node, iter)
+    } else if downcast_to::<Reduce>(node).is_ok() {
+        load_global_avgpool(model, node_id, node, iter)
+    } else {
+        err(format!("Unsupported pooling node {}", node.name))
+    }
+}
+
+fn check_pool_padding(node: &OnnxNode, padding: &PaddingSpec) -> Result<()> {
+    match padding {
+        PaddingSpec::Explicit(pad0, pad1) => {
             ensure_onnx!(
                 pad0.iter().all(|&x| x == 0) && pad1.iter().all(|&x| x == 0),
                 "Padding must be 0s"
             );
         }
-        PaddingSpec::ExplicitOnnxPool(ref pad0, ref pad1, _) => {
+        PaddingSpec::ExplicitOnnxPool(pad0, pad1, _) => {
             ensure_onnx!(
                 pad0.iter().all(|&x| x == 0) && pad1.iter().all(|&x| x == 0),
                 "Padding must be 0s"
@@ -256,23 +578,193 @@ fn load_maxpool<'a, I: Iterator<Item = &'a usize> + Sized>(
         _ => {
             return err(format!(
                 "Padding for {} must have valid padding {:?}",
-                node.name, max_node.pool_spec.padding
+                node.name, padding
             ));
         }
     }
+    Ok(())
+}
+
+fn load_avgpool<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
     ensure_onnx!(
-        max_node
-            .pool_spec
-            .kernel_shape
-            .iter()
-            .all(|&x| x == expected_value),
-        "Kernel shape must be square with size {}",
-        expected_value
+        node.inputs.len() == 1,
+        "AveragePool {} must have 1 input",
+        node.name
+    );
+    let avg_node = downcast_to::<SumPool>(node)?;
+    ensure_onnx!(
+        avg_node.normalize,
+        "SumPool {} is not an average pool",
+        node.name
+    );
+    let kernel_shape = &avg_node.pool_spec.kernel_shape;
+    ensure_onnx!(
+        kernel_shape.len() == 2 && kernel_shape[0] == kernel_shape[1],
+        "Kernel shape for {} must be square: {:?}",
+        node.name,
+        kernel_shape
+    );
+    let kernel_size = kernel_shape[0];
+    let stride = match avg_node.pool_spec.strides {
+        Some(ref strides) => {
+            ensure_onnx!(
+                strides.iter().all(|&x| x == strides[0]),
+                "Strides for {} must be the same on all dimensions: {:?}",
+                node.name,
+                strides
+            );
+            strides[0]
+        }
+        None => 1,
+    };
+    check_pool_padding(node, &avg_node.pool_spec.padding)?;
+    if let Some(ref dil) = avg_node.pool_spec.dilations {
+        ensure_onnx!(dil.iter().all(|&x| x == 1), "Dilations must be 1");
+    }
+    let avgpool = AvgPool2D::new(kernel_size, stride)
+        .context(format!("unsupported average pool {}", node.name))?;
+    let node = ProvableNode::new(
+        vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
+        Layer::Pooling(Pooling::AvgPool2D(avgpool)),
     );
+    Ok((node_id, node))
+}
+
+fn load_global_avgpool<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "GlobalAveragePool {} must have 1 input",
+        node.name
+    );
+    let reduce = downcast_to::<Reduce>(node)?;
+    ensure_onnx!(
+        matches!(reduce.reducer, Reducer::Sum),
+        "Reduce {} must be a sum to be part of a global average pool",
+        node.name
+    );
+    let input_link = node.inputs[0];
+    let input_shape = get_node_output_shape(model.node(input_link.node), input_link.slot)?;
+    ensure_onnx!(
+        input_shape.len() == 4 && input_shape[0] == 1,
+        "Input of GlobalAveragePool {} must be a 4D tensor with batch size 1: {:?}",
+        node.name,
+        input_shape
+    );
+    ensure_onnx!(
+        reduce.axes.iter().copied().sorted().collect_vec() == vec![2, 3],
+        "GlobalAveragePool {} must average over the spatial dimensions, found axes {:?}",
+        node.name,
+        reduce.axes
+    );
+    let (height, width) = (input_shape[2], input_shape[3]);
+    // the sum must be followed by the normalization, i.e. a division (or multiplication by the inverse)
+    // by the number of averaged elements, which is performed by our layer
+    let Some(&&next_node_id) = iter.peek() else {
+        return err(format!(
+            "GlobalAveragePool {} has no normalization",
+            node.name
+        ));
+    };
+    let next_node = model.node(next_node_id);
+    let binop = downcast_to::<TypedBinOp>(next_node)?;
+    let Some(norm_link) = next_node
+        .inputs
+        .iter()
+        .find(|&x| is_const(model.node(x.node)))
+    else {
+        return err(format!(
+            "GlobalAveragePool {} normalization has no constant input",
+            node.name
+        ));
+    };
+    ensure_onnx!(
+        next_node.inputs.len() == 2 && next_node.inputs.iter().any(|x| x.node == node_id),
+        "GlobalAveragePool {} is not followed by its normalization",
+        node.name
+    );
+    let norm = extract_const_tensor(model.node(norm_link.node))?;
+    ensure_onnx!(
+        norm.get_data().len() == 1,
+        "Normalization of GlobalAveragePool {} must be a scalar",
+        node.name
+    );
+    let window_size = (height * width) as f32;
+    let expected_norm = if binop.0.is::<tract_core::ops::math::Div>() {
+        window_size
+    } else if binop.0.is::<tract_core::ops::math::Mul>() {
+        1.0 / window_size
+    } else {
+        return err(format!(
+            "GlobalAveragePool {} is followed by an unsupported normalization",
+            node.name
+        ));
+    };
+    ensure_onnx!(
+        (norm.get_data()[0] - expected_norm).abs() <= f32::EPSILON * window_size,
+        "Normalization of GlobalAveragePool {} doesn't match the averaged area",
+        node.name
+    );
+    // the normalization is part of the layer, so we move on the iterator
+    iter.next().unwrap();
+    let node = ProvableNode::new(
+        vec![Edge::new(input_link.node, input_link.slot)],
+        Layer::Pooling(Pooling::GlobalAveragePool(GlobalAveragePool::new(
+            height, width,
+        ))),
+    );
+    // the next layers refer to the id of the normalization node
+    Ok((next_node_id, node))
+}
+
+fn load_maxpool<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "MaxPool {} must have 1 input",
+        node.name
+    );
+    let max_node = downcast_to::<MaxPool>(node)?;
+    let kernel_shape = &max_node.pool_spec.kernel_shape;
+    ensure_onnx!(
+        kernel_shape.len() == 2 && kernel_shape[0] == kernel_shape[1],
+        "Kernel shape for {} must be square: {:?}",
+        node.name,
+        kernel_shape
+    );
+    let kernel_size = kernel_shape[0];
+    let stride = match max_node.pool_spec.strides {
+        Some(ref strides) => {
+            ensure_onnx!(
+                strides.iter().all(|&x| x == strides[0]),
+                "Strides for {} must be the same on all dimensions: {:?}",
+                node.name,
+                strides
+            );
+            strides[0]
+        }
+        None => 1,
+    };
+    check_pool_padding(node, &max_node.pool_spec.padding)?;
     if let Some(ref dil) = max_node.pool_spec.dilations {
         ensure_onnx!(dil.iter().all(|&x| x == 1), "Dilations must be 1");
     }
-    let zkml_maxpool = Layer::Pooling(Pooling::Maxpool2D(Maxpool2D::default()));
+    let maxpool = Maxpool2D::new(kernel_size, stride)
+        .context(format!("unsupported max pool {}", node.name))?;
+    let zkml_maxpool = Layer::Pooling(Pooling::Maxpool2D(maxpool));
     let node = ProvableNode::new(
         vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
         zkml_maxpool,
@@ -280,6 +772,50 @@ fn load_maxpool<'a, I: Iterator<Item = &'a usize> + Sized>(
     Ok((node_id, node))
 }
 
+fn load_add<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let binop = downcast_to::<TypedBinOp>(node)?;
+    ensure_onnx!(
+        binop.0.is::<tract_core::ops::math::Add>(),
+        "Node {} is not an Add",
+        node.name
+    );
+    ensure_onnx!(
+        node.inputs.len() == 2,
+        "Add {} must have 2 inputs",
+        node.name
+    );
+    // an addition with a constant is only supported as the bias of a Gemm layer
+    ensure_onnx!(
+        node.inputs.iter().all(|x| !is_const(model.node(x.node))),
+        "Add {} with a constant input is not supported",
+        node.name
+    );
+    let input_shapes = node
+        .inputs
+        .iter()
+        .map(|x| get_node_output_shape(model.node(x.node), x.slot))
+        .collect::<Result<Vec<_>>>()?;
+    ensure_onnx!(
+        input_shapes[0] == input_shapes[1],
+        "Inputs of Add {} must have the same shape, no broadcasting supported: {:?}",
+        node.name,
+        input_shapes
+    );
+    let provable_node = ProvableNode::new(
+        node.inputs
+            .iter()
+            .map(|x| Edge::new(x.node, x.slot))
+            .collect(),
+        Layer::Add(Add::new()),
+    );
+    Ok((node_id, provable_node))
+}
+
 fn load_relu<'a, I: Iterator<Item = &'a usize> + Sized>(
     model: &OnnxModel,
     node_id: NodeId,
@@ -313,14 +849,421 @@ fn load_relu<'a, I: Iterator<Item = &'a usize> + Sized>(
     Ok((node_id, provable_node))
 }
 
+/// Loads a sigmoid node. A sigmoid whose output is multiplied by its own input is a SiLU, which is
+/// loaded as a single activation.
+fn load_sigmoid<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "Sigmoid {} must have 1 input",
+        node.name
+    );
+    let input = node.inputs[0];
+    if let Some(&&next_node_id) = iter.peek() {
+        let next_node = model.node(next_node_id);
+        let is_silu = downcast_to::<TypedBinOp>(next_node)
+            .is_ok_and(|binop| binop.0.is::<tract_core::ops::math::Mul>())
+            && next_node.inputs.len() == 2
+            && next_node.inputs.iter().any(|x| x.node == node_id)
+            && next_node.inputs.contains(&input);
+        if is_silu {
+            // the gating is part of the layer, so we move on the iterator
+            iter.next().unwrap();
+            let provable_node = ProvableNode::new(
+                vec![Edge::new(input.node, input.slot)],
+                Layer::Activation(Activation::Committed(CommittedActivation::new(
+                    TableFunction::Silu,
+                ))),
+            );
+            // the next layers refer to the id of the multiplication node
+            return Ok((next_node_id, provable_node));
+        }
+    }
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(input.node, input.slot)],
+        Layer::Activation(Activation::Sigmoid(Sigmoid::new())),
+    );
+    Ok((node_id, provable_node))
+}
+
+fn load_tanh<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    load_committed_activation(node_id, node, TableFunction::Tanh)
+}
+
+fn load_gelu<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    load_committed_activation(node_id, node, TableFunction::Gelu)
+}
+
+fn load_leaky_relu<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "LeakyRelu {} must have 1 input",
+        node.name
+    );
+    let Some(leaky_relu) = downcast_to::<ElementWiseOp>(node)?
+        .0
+        .downcast_ref::<tract_core::ops::nn::LeakyRelu>()
+    else {
+        return err(format!("Node {} is not a LeakyRelu", node.name));
+    };
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
+        Layer::Activation(Activation::LeakyRelu(LeakyRelu::new(leaky_relu.alpha))),
+    );
+    Ok((node_id, provable_node))
+}
+
+/// Loads a clip node: tract turns `Clip(min, max)` into a `Max` with the lower bound followed by a
+/// `Min` with the upper bound, each of them being absent when the corresponding bound is not set.
+/// A clip to `[0, 6]` is a ReLU6.
+fn load_clip<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let (input, bound, is_lower) = clip_bound(model, node)?;
+    let (mut min, mut max) = (None, None);
+    *(if is_lower { &mut min } else { &mut max }) = Some(bound);
+    let mut output_id = node_id;
+    if let Some(&&next_node_id) = iter.peek() {
+        let next_node = model.node(next_node_id);
+        if next_node.name.contains("Clip") && next_node.inputs.iter().any(|x| x.node == node_id) {
+            let (_, bound, is_lower) = clip_bound(model, next_node)?;
+            let other = if is_lower { &mut min } else { &mut max };
+            ensure_onnx!(
+                other.is_none(),
+                "Clip {} has the same bound twice",
+                node.name
+            );
+            *other = Some(bound);
+            // both bounds are part of the layer, so we move on the iterator
+            iter.next().unwrap();
+            output_id = next_node_id;
+        }
+    }
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(input.node, input.slot)],
+        Layer::Activation(Activation::Clip(Clip::new(min, max))),
+    );
+    // the next layers refer to the id of the last node of the clip
+    Ok((output_id, provable_node))
+}
+
+/// Returns the input of a bound of a clip, the value of the bound and whether it is the lower bound.
+fn clip_bound(model: &OnnxModel, node: &OnnxNode) -> Result<(OutletId, f32, bool)> {
+    let binop = downcast_to::<TypedBinOp>(node)?;
+    let is_lower = if binop.0.is::<tract_core::ops::math::Max>() {
+        true
+    } else if binop.0.is::<tract_core::ops::math::Min>() {
+        false
+    } else {
+        return err(format!("Clip {} is neither a Max nor a Min", node.name));
+    };
+    ensure_onnx!(
+        node.inputs.len() == 2,
+        "Clip {} must have 2 inputs",
+        node.name
+    );
+    let Some(bound_idx) = node
+        .inputs
+        .iter()
+        .position(|x| is_const(model.node(x.node)))
+    else {
+        return err(format!("Clip {} has no constant bound", node.name));
+    };
+    let bound = extract_const_tensor(model.node(node.inputs[bound_idx].node))?;
+    ensure_onnx!(
+        bound.get_data().len() == 1,
+        "Bound of Clip {} must be a scalar",
+        node.name
+    );
+    Ok((node.inputs[1 - bound_idx], bound.get_data()[0], is_lower))
+}
+
+/// Loads a softmax node, which is supported over the last axis of a vector, e.g. the logits of a
+/// classifier, or of a matrix, e.g. the attention scores of a transformer.
+fn load_softmax<'a, I: Iterator<Item = &'a usize> + Sized>(
+    _model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "Softmax {} must have 1 input",
+        node.name
+    );
+    let softmax = downcast_to::<tract_core::ops::nn::Softmax>(node)?;
+    let shape = get_node_output_shape(node, 0)?;
+    ensure_onnx!(
+        !shape.is_empty()
+            && softmax.axes.as_slice() == [shape.len() - 1]
+            && shape.iter().rev().skip(2).all(|dim| *dim == 1),
+        "Softmax {} must be over the last axis of a vector or a matrix, found axes {:?} of shape {:?}",
+        node.name,
+        softmax.axes,
+        shape
+    );
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
+        Layer::Softmax(Softmax::new()),
+    );
+    Ok((node_id, provable_node))
+}
+
+/// Loads an argmax node, which is supported over the last axis of a vector, e.g. the logits of a
+/// classifier, when it returns the first index of the maximum.
+fn load_argmax<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "ArgMax {} must have 1 input",
+        node.name
+    );
+    let reduce = downcast_to::<Reduce>(node)?;
+    ensure_onnx!(
+        matches!(reduce.reducer, Reducer::ArgMax(false)),
+        "Reduce {} must be an argmax returning the first index of the maximum",
+        node.name
+    );
+    let input = node.inputs[0];
+    let shape = get_node_output_shape(model.node(input.node), input.slot)?;
+    ensure_onnx!(
+        !shape.is_empty()
+            && reduce.axes.as_slice() == [shape.len() - 1]
+            && shape.iter().rev().skip(1).all(|dim| *dim == 1),
+        "ArgMax {} must be over a vector, found axes {:?} of shape {:?}",
+        node.name,
+        reduce.axes,
+        shape
+    );
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(input.node, input.slot)],
+        Layer::TopK(TopK::argmax()),
+    );
+    if let Some(&&next_node_id) = iter.peek() {
+        let next_node = model.node(next_node_id);
+        // without `keepdims`, the reduced axis is removed by the next node, which is part of the layer
+        if downcast_to::<AxisOp>(next_node).is_ok()
+            && next_node.inputs.len() == 1
+            && next_node.inputs[0].node == node_id
+        {
+            iter.next().unwrap();
+            return Ok((next_node_id, provable_node));
+        }
+    }
+    Ok((node_id, provable_node))
+}
+
+fn load_committed_activation(
+    node_id: NodeId,
+    node: &OnnxNode,
+    function: TableFunction,
+) -> Result<(NodeId, CustomNode)> {
+    ensure_onnx!(
+        node.inputs.len() == 1,
+        "{} {} must have 1 input",
+        function.name(),
+        node.name
+    );
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(node.inputs[0].node, node.inputs[0].slot)],
+        Layer::Activation(Activation::Committed(CommittedActivation::new(function))),
+    );
+    Ok((node_id, provable_node))
+}
+
+/// Loads a matmul node. A product with a constant matrix is a dense layer without bias, while a
+/// product between two tensors computed at inference time is loaded as a matmul layer. The second
+/// tensor can be transposed, and the product can be followed by a multiplication or a division
+/// by a constant, like the scaled product between the queries and the keys of an attention layer
+/// exported by PyTorch.
+fn load_matmul<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let einsum = downcast_to::<EinSum>(node)?;
+    ensure_onnx!(
+        node.inputs.len() == 2,
+        "MatMul {} must have 2 inputs",
+        node.name
+    );
+    if node.inputs.iter().any(|x| is_const(model.node(x.node))) {
+        return load_gemm(model, node_id, node, iter);
+    }
+    let input_shapes = node
+        .inputs
+        .iter()
+        .map(|x| get_node_output_shape(model.node(x.node), x.slot))
+        .collect::<Result<Vec<_>>>()?;
+    ensure_onnx!(
+        input_shapes.iter().all(|shape| shape.len() >= 2),
+        "Inputs of MatMul {} must be matrices: {:?}",
+        node.name,
+        input_shapes
+    );
+    // leading batch dimensions are only supported when they are 1. PyTorch exports multi-head
+    // attention as products over a batch of heads, which must be built with `MultiHeadAttention`
+    ensure_onnx!(
+        input_shapes
+            .iter()
+            .all(|shape| shape.iter().rev().skip(2).all(|dim| *dim == 1)),
+        "MatMul {} multiplies batches of matrices, e.g. the heads of a multi-head attention, which is not supported: only single-head attention can be parsed, found inputs of shapes {:?}",
+        node.name,
+        input_shapes
+    );
+    let (input_axes, output_axes, summed_axis) = einsum_axes(node, einsum)?;
+    // position of the summed axis in each input, counting from the last one
+    let [left_pos, right_pos] = [0, 1].map(|i| {
+        let axes = &input_axes[i];
+        axes.len() - 1 - axes.iter().position(|c| *c == summed_axis).unwrap()
+    });
+    ensure_onnx!(
+        left_pos == 0 && right_pos <= 1,
+        "MatMul {} must sum over the columns of the first input and the rows or the columns of the second one: {}",
+        node.name,
+        einsum.axes
+    );
+    let transpose_right = right_pos == 0;
+    let left_rows = input_axes[0][input_axes[0].len() - 2];
+    let right_cols = input_axes[1][input_axes[1].len() - 1 - transpose_right as usize];
+    ensure_onnx!(
+        output_axes.ends_with(&[left_rows, right_cols]),
+        "Output of MatMul {} must be a matrix with the rows of the first input: {}",
+        node.name,
+        einsum.axes
+    );
+    let inner_dim = *input_shapes[0].last().unwrap();
+    let matmul = if transpose_right {
+        MatMul::new_transposed(inner_dim)
+    } else {
+        MatMul::new(inner_dim)
+    };
+    // a multiplication or a division by a constant following the product is part of the layer,
+    // in which case the next layers refer to the id of that node
+    let next_scale = match iter.peek() {
+        Some(&&next_node_id) => {
+            scalar_factor(model, node_id, model.node(next_node_id))?.map(|s| (next_node_id, s))
+        }
+        None => None,
+    };
+    let (edge_id, matmul) = match next_scale {
+        Some((next_node_id, scale)) => {
+            iter.next().unwrap();
+            (next_node_id, matmul.with_scale(scale))
+        }
+        None => (node_id, matmul),
+    };
+    let provable_node = ProvableNode::new(
+        node.inputs
+            .iter()
+            .map(|x| Edge::new(x.node, x.slot))
+            .collect(),
+        Layer::MatMul(matmul),
+    );
+    Ok((edge_id, provable_node))
+}
+
+/// Parses the axes of a product of two tensors from its einsum expression, e.g. `mk,kn->mn`.
+/// Returns the axes of both inputs, the axes of the output and the axis summed over.
+fn einsum_axes(node: &OnnxNode, einsum: &EinSum) -> Result<([Vec<char>; 2], Vec<char>, char)> {
+    let expr = einsum.axes.to_string();
+    let Some((inputs, output)) = expr.split_once('>') else {
+        return err(format!(
+            "Invalid einsum expression for {}: {}",
+            node.name, expr
+        ));
+    };
+    let inputs = inputs
+        .trim_end_matches('-')
+        .split(',')
+        .map(|axes| axes.chars().collect_vec())
+        .collect_vec();
+    ensure_onnx!(
+        inputs.len() == 2,
+        "Product {} must have 2 inputs: {}",
+        node.name,
+        expr
+    );
+    let output = output.chars().collect_vec();
+    let summed = inputs[0]
+        .iter()
+        .filter(|c| inputs[1].contains(c) && !output.contains(c))
+        .copied()
+        .collect_vec();
+    ensure_onnx!(
+        summed.len() == 1,
+        "Product {} must sum over exactly one axis: {}",
+        node.name,
+        expr
+    );
+    let [left, right]: [Vec<char>; 2] = inputs.try_into().unwrap();
+    Ok(([left, right], output, summed[0]))
+}
+
+/// Returns the constant factor by which `node` multiplies the output of the node `input_id`, if
+/// `node` is a multiplication or a division by a scalar constant
+fn scalar_factor(model: &OnnxModel, input_id: NodeId, node: &OnnxNode) -> Result<Option<f32>> {
+    let Ok(binop) = downcast_to::<TypedBinOp>(node) else {
+        return Ok(None);
+    };
+    if node.inputs.len() != 2 {
+        return Ok(None);
+    }
+    let Some(idx) = node.inputs.iter().position(|x| x.node == input_id) else {
+        return Ok(None);
+    };
+    let constant_node = model.node(node.inputs[1 - idx].node);
+    if !is_const(constant_node) {
+        return Ok(None);
+    }
+    let constant = extract_const_tensor(constant_node)?;
+    if constant.get_data().len() != 1 {
+        return Ok(None);
+    }
+    let constant = constant.get_data()[0];
+    if binop.0.is::<tract_core::ops::math::Mul>() {
+        Ok(Some(constant))
+    } else if binop.0.is::<tract_core::ops::math::Div>() && idx == 0 {
+        Ok(Some(1.0 / constant))
+    } else {
+        Ok(None)
+    }
+}
+
 fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
     model: &OnnxModel,
     node_id: NodeId,
     node: &OnnxNode,
     iter: &mut Peekable<I>,
 ) -> Result<(NodeId, CustomNode)> {
-    let _matrix = downcast_to::<EinSum>(node)?;
-    // TODO: we only support matvec for now for onnx models
+    let einsum = downcast_to::<EinSum>(node)?;
     // Fetch the input which is constant (e.g. the weights)
     ensure_onnx!(
         node.inputs.len() == 2,
@@ -335,13 +1278,13 @@ fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
     else {
         return err(format!("Gemm {} has no constant input", node.name));
     };
-    let mut weight = extract_const_tensor(model.node(weight_link.node))?;
+    // tract adds leading axes to the weights of a product with a batch of vectors
+    let mut weight = squeeze_leading_ones(extract_const_tensor(model.node(weight_link.node))?, 2);
     // find the input node
     let Some(input_link) = node.inputs.iter().find(|&x| x.node != weight_link.node) else {
         return err(format!("Gemm {} has no input", node.name));
     };
 
-    // check if the weight matrix needs to be transposed
     let input_node = model.node(input_link.node);
     let mut input_shape = get_node_output_shape(input_node, input_link.slot)?;
     assert!(
@@ -349,26 +1292,34 @@ fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
         "First dimension of Gemm layer input should be 1."
     );
     input_shape.remove(0);
+    // a matrix input is a sequence of vectors, e.g. tokens, each multiplied by the weights
     ensure_onnx!(
-        input_shape.len() == 1,
-        "Input shape for Gemm must be a vector"
+        input_shape.len() == 1 || input_shape.len() == 2,
+        "Input shape for Gemm must be a vector or a matrix"
     );
     ensure_onnx!(weight.is_matrix(), "Weight for Gemm must be a matrix");
-    let mut weight_shape = weight.get_shape();
-    if weight_shape[1] != input_shape[0] {
+    // the weight matrix is transposed if the product sums over its rows rather than its columns
+    let (input_axes, _, summed_axis) = einsum_axes(node, einsum)?;
+    let weight_idx = node
+        .inputs
+        .iter()
+        .position(|x| x.node == weight_link.node)
+        .unwrap();
+    let weight_axes = &input_axes[weight_idx];
+    if weight_axes.len() >= 2 && weight_axes[weight_axes.len() - 2] == summed_axis {
         weight = weight.transpose();
-        weight_shape = weight.get_shape();
     }
+    let weight_shape = weight.get_shape();
     ensure_onnx!(
-        weight_shape[1] == input_shape[0],
+        weight_shape[1] == *input_shape.last().unwrap(),
         "Incompatible shapes found for Gemm node: input shape is {:?}, weight shape is {:?}",
         input_shape,
         weight_shape,
     );
 
     // also extract the bias if any. If there is one, that means the next node is a Add node and we
-    // must make sure one of the inputs is the current matrix node. Otherwise that's just a normal add that we don't support.
-    // TODO: support general case with Add layer.
+    // must make sure one of the inputs is the current matrix node and the other one is a constant. Otherwise that's
+    // just a normal add, which is parsed as a separate Add layer.
     let (edge_id, bias_node_id) = match iter.peek() {
         // no next node, no bias
         None => (node_id, None),
@@ -390,7 +1341,7 @@ fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
                         .enumerate()
                         .find(|(_i, &x)| x.node == node_id)
                     {
-                        Some((idx, ..)) => {
+                        Some((idx, ..)) if is_const(model.node(next_node.inputs[1 - idx].node)) => {
                             // Now we need to find the bias node, which is the other input to the Add node
                             // and we can extract it as a constant tensor afterwards
                             // since only two elements, we can just do 1 - idx
@@ -401,7 +1352,7 @@ fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
                             iter.next().unwrap();
                             (next_node_id, Some(bias_input.node))
                         }
-                        None => {
+                        _ => {
                             // no bias, just return the matrix node
                             (node_id, None)
                         }
@@ -414,27 +1365,20 @@ fn load_gemm<'a, I: Iterator<Item = &'a usize> + Sized>(
             }
         }
     };
-    let mut bias_tensor = match bias_node_id {
+    let bias_tensor = match bias_node_id {
         Some(bias) => {
             let bias_node = model.node(bias);
-            extract_const_tensor(bias_node)?
+            // the bias is broadcast over the batch dimension, and over the rows of a matrix input
+            squeeze_leading_ones(extract_const_tensor(bias_node)?, 1)
         }
         // we always require a bias tensor in current proving logic
         None => crate::Tensor::zeros(vec![weight.shape[0]]),
     };
     ensure_onnx!(
-        bias_tensor.shape.len() == 1 || bias_tensor.shape.len() == 2,
-        "Bias tensor must be 1D or 2D with batch: {:?}",
+        bias_tensor.shape.len() == 1,
+        "Bias tensor must be 1D, up to leading dimensions of size 1: {:?}",
         bias_tensor.shape
     );
-    if bias_tensor.shape.len() == 2 {
-        ensure_onnx!(
-            bias_tensor.shape[0] == 1,
-            "Bias tensor must be 1D with batch: {:?}",
-            bias_tensor.shape
-        );
-        bias_tensor.shape = bias_tensor.shape[1..].to_vec();
-    }
     ensure_onnx!(
         bias_tensor.shape[0] == weight.shape[0],
         "Bias tensor must have same size as filter's rows"
@@ -456,8 +1400,7 @@ fn load_conv<'a, I: Iterator<Item = &'a usize> + Sized>(
     _iter: &mut Peekable<I>,
 ) -> Result<(NodeId, CustomNode)> {
     let conv_node = downcast_to::<Conv>(node)?;
-    // TODO: once we support different padding and strides, extract the data in this function
-    check_conv2d_attributes(conv_node)?;
+    let (stride, padding) = check_conv2d_attributes(conv_node)?;
     // TODO: support for conv without bias
     ensure_onnx!(
         node.inputs.len() == 3,
@@ -477,14 +1420,152 @@ fn load_conv<'a, I: Iterator<Item = &'a usize> + Sized>(
         Convolution::new_without_bias(filter_const)
     } else {
         Convolution::new(filter_const, bias_const)
+    }
+    .with_stride(stride)?
+    .with_padding(padding);
+    let layer = if conv_node.group > 1 {
+        // grouped convolution, e.g. a depthwise convolution when there is one group per input channel
+        Layer::GroupedConvolution(
+            GroupedConv::new(conv.filter, conv.bias, conv_node.group)
+                .with_context(|| format!("invalid grouped convolution {}", node.name))?
+                .with_stride(stride)?
+                .with_padding(padding),
+        )
+    } else {
+        Layer::Convolution(conv)
     };
     let provable_node = crate::layers::provable::Node::new(
         vec![Edge::new(input_link.node, input_link.slot)],
-        Layer::Convolution(conv),
+        layer,
+    );
+    Ok((node_id, provable_node))
+}
+
+/// Loads an inference-time BatchNormalization, which tract expands into a multiplication and an
+/// addition by constants broadcast over the channels, as an affine layer. It is folded into the
+/// previous layer when possible, see [`fold_affine_layers`].
+fn load_batchnorm<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let binop = downcast_to::<TypedBinOp>(node)?;
+    ensure_onnx!(
+        binop.0.is::<tract_core::ops::math::Mul>() && node.inputs.len() == 2,
+        "BatchNormalization {} must start with a multiplication by the scale",
+        node.name
+    );
+    let Some(scale_link) = node.inputs.iter().find(|x| is_const(model.node(x.node))) else {
+        return err(format!(
+            "BatchNormalization {} has no constant scale",
+            node.name
+        ));
+    };
+    let Some(input_link) = node.inputs.iter().find(|x| x.node != scale_link.node) else {
+        return err(format!("BatchNormalization {} has no input", node.name));
+    };
+    let mut input_shape = get_node_output_shape(model.node(input_link.node), input_link.slot)?;
+    ensure_onnx!(
+        input_shape.len() >= 2 && input_shape[0] == 1,
+        "Input of BatchNormalization {} must be a batch of 1 with channels: {:?}",
+        node.name,
+        input_shape
+    );
+    input_shape.remove(0);
+    let num_channels = input_shape[0];
+    let channel_constant = |const_node: &OnnxNode| -> Result<crate::Tensor<f32>> {
+        let tensor = extract_const_tensor(const_node)?;
+        // the constants are shaped to be broadcast over all the dimensions but the channels
+        ensure_onnx!(
+            tensor.get_data().len() == num_channels,
+            "Constant {} of BatchNormalization {} has shape {:?}, expected {} channels",
+            const_node.name,
+            node.name,
+            tensor.get_shape(),
+            num_channels
+        );
+        Ok(crate::Tensor::new(
+            vec![num_channels],
+            tensor.get_data().to_vec(),
+        ))
+    };
+    let scale = channel_constant(model.node(scale_link.node))?;
+    // the shift is added by the next node, unless it is zero and has been removed by tract
+    let bias_link = iter.peek().and_then(|&&next_node_id| {
+        let next_node = model.node(next_node_id);
+        let is_shift = downcast_to::<TypedBinOp>(next_node)
+            .is_ok_and(|binop| binop.0.is::<tract_core::ops::math::Add>())
+            && next_node.inputs.len() == 2
+            && next_node.inputs.iter().any(|x| x.node == node_id);
+        next_node
+            .inputs
+            .iter()
+            .find(|x| is_shift && is_const(model.node(x.node)))
+            .map(|link| (next_node_id, link.node))
+    });
+    let (last_node_id, bias) = match bias_link {
+        Some((next_node_id, bias_node_id)) => {
+            // the addition is part of the layer, so we move on the iterator
+            iter.next().unwrap();
+            (next_node_id, channel_constant(model.node(bias_node_id))?)
+        }
+        None => (node_id, crate::Tensor::zeros(vec![num_channels])),
+    };
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(input_link.node, input_link.slot)],
+        Layer::Affine(Affine::new(scale, bias)),
+    );
+    Ok((last_node_id, provable_node))
+}
+
+/// Loads a `Gather` of rows of a constant matrix, i.e. the lookup of the embeddings of the token
+/// indices given as input, as an embedding layer.
+fn load_gather<'a, I: Iterator<Item = &'a usize> + Sized>(
+    model: &OnnxModel,
+    node_id: NodeId,
+    node: &OnnxNode,
+    _iter: &mut Peekable<I>,
+) -> Result<(NodeId, CustomNode)> {
+    let gather = downcast_to::<Gather>(node)?;
+    ensure_onnx!(
+        node.inputs.len() == 2,
+        "Gather {} must have 2 inputs",
+        node.name
+    );
+    let (table_link, indices_link) = (node.inputs[0], node.inputs[1]);
+    ensure_onnx!(
+        is_const(model.node(table_link.node)),
+        "Gather {} must select rows of a constant table",
+        node.name
+    );
+    let table = extract_const_tensor(model.node(table_link.node))?;
+    ensure_onnx!(
+        gather.axis == 0 && table.get_shape().len() == 2,
+        "Gather {} must select rows of a matrix, found axis {} of shape {:?}",
+        node.name,
+        gather.axis,
+        table.get_shape()
+    );
+    let provable_node = ProvableNode::new(
+        vec![Edge::new(indices_link.node, indices_link.slot)],
+        Layer::Embedding(Embedding::new(table)),
     );
     Ok((node_id, provable_node))
 }
 
+/// Removes the leading dimensions of size 1 of `tensor`, keeping at least `rank` dimensions
+fn squeeze_leading_ones(mut tensor: crate::Tensor<f32>, rank: usize) -> crate::Tensor<f32> {
+    let leading = tensor
+        .shape
+        .iter()
+        .take(tensor.shape.len().saturating_sub(rank))
+        .take_while(|dim| **dim == 1)
+        .count();
+    tensor.shape.drain(..leading);
+    tensor
+}
+
 fn is_const(node: &OnnxNode) -> bool {
     downcast_to::<Const>(node).is_ok()
 }
@@ -513,28 +1594,40 @@ fn get_node_output_shape(node: &OnnxNode, output_idx: usize) -> Result<Vec<usize
     Ok(shape.to_vec())
 }
 
-/// Get the conv2d attributes and assert if supported by DeepProve
-fn check_conv2d_attributes(node: &Conv) -> Result<()> {
+/// Get the conv2d attributes and assert if supported by DeepProve.
+/// Returns the stride and the zero padding of the convolution.
+fn check_conv2d_attributes(node: &Conv) -> Result<(usize, ConvPadding)> {
     let Some(ref strides) = node.pool_spec.strides else {
         return err(format!("Conv has no strides: {}", node.name()));
     };
-    ensure_onnx!(strides.iter().all(|&x| x == 1), "Strides must be {}", 1);
-    ensure_onnx!(strides.iter().all(|&x| x == 1), "Strides must be {}", 1);
-    let PaddingSpec::Explicit(ref pad0, ref pad1) = &node.pool_spec.padding else {
-        return err(format!("Conv has no pads: {}", node.name()));
-    };
     ensure_onnx!(
-        pad0.iter().all(|&x| x == 0),
-        "Padding for {}must be 0s: {:?}",
+        strides.len() == 2 && strides[0] == strides[1] && strides[0] > 0,
+        "Strides for {} must be positive and equal in both dimensions: {:?}",
         node.name(),
-        pad0,
-    );
-    ensure_onnx!(
-        pad1.iter().all(|&x| x == 0),
-        "Padding for {}must be 0s: {:?}",
-        node.name(),
-        pad1,
+        strides
     );
+    let padding = match &node.pool_spec.padding {
+        PaddingSpec::Valid => ConvPadding::Valid,
+        PaddingSpec::SameUpper => ConvPadding::SameUpper,
+        PaddingSpec::SameLower => ConvPadding::SameLower,
+        PaddingSpec::Explicit(pad0, pad1) => {
+            ensure_onnx!(
+                pad0.len() == 2 && pad1.len() == 2,
+                "Padding for {} must be 2D: {:?} {:?}",
+                node.name(),
+                pad0,
+                pad1,
+            );
+            ConvPadding::Explicit([pad0[0], pad0[1], pad1[0], pad1[1]])
+        }
+        padding => {
+            return err(format!(
+                "Unsupported padding for {}: {:?}",
+                node.name(),
+                padding
+            ));
+        }
+    };
     let Some(ref dilations) = node.pool_spec.dilations else {
         return err(format!("Conv has no dilations: {}", node.name()));
     };
@@ -557,13 +1650,7 @@ fn check_conv2d_attributes(node: &Conv) -> Result<()> {
         node.name(),
         kernel_shape
     );
-    ensure_onnx!(
-        kernel_shape[0] == kernel_shape[1],
-        "Kernel shape for {} must be square: {:?}",
-        node.name(),
-        kernel_shape
-    );
-    Ok(())
+    Ok((strides[0], padding))
 }
 
 fn err<T>(msg: String) -> Result<T> {
@@ -597,4 +1684,86 @@ mod tests {
         let trace = model.run::<GoldilocksExt2>(&[input_tensor]).unwrap();
         assert!(trace.steps.len() >= 1);
     }
+
+    #[test]
+    fn test_move_axis_permutation() {
+        // moving the channels last
+        assert_eq!(move_axis_permutation(3, 0, 2), vec![1, 2, 0]);
+        // moving the channels first
+        assert_eq!(move_axis_permutation(3, 2, 0), vec![2, 0, 1]);
+        // swapping the last two dimensions
+        assert_eq!(move_axis_permutation(3, 1, 2), vec![0, 2, 1]);
+
+        let input = crate::Tensor::<f32>::random(&[2, 3, 4]);
+        let transpose = Transpose::new(move_axis_permutation(3, 0, 2)).unwrap();
+        let output = crate::layers::provable::evaluate_layer::<GoldilocksExt2, _, _>(
+            &transpose,
+            &[&input],
+            None,
+        )
+        .unwrap();
+        assert_eq!(output.outputs()[0].get_shape(), vec![3, 4, 2]);
+    }
+
+    #[test]
+    fn test_redirect_split_outputs() {
+        // slice nodes 3 and 5 are the outputs of the split layer loaded as node 3
+        let split_outputs = HashMap::from([(3, Edge::new(3, 0)), (5, Edge::new(3, 1))]);
+        let edges = vec![Edge::new(5, 0), Edge::new(4, 0), Edge::new_at_edge(0)];
+        assert_eq!(redirect_split_outputs(edges, &split_outputs), vec![
+            Edge::new(3, 1),
+            Edge::new(4, 0),
+            Edge::new_at_edge(0)
+        ]);
+    }
+
+    #[test]
+    fn test_fold_affine_layers() {
+        let (input_size, hidden_size) = (7, 5);
+        let mut model =
+            Model::<f32>::new_from_input_shapes(vec![vec![input_size]], PaddingMode::NoPadding);
+        let dense_node = model
+            .add_consecutive_layer(
+                Layer::Dense(crate::layers::dense::Dense::random(vec![
+                    hidden_size,
+                    input_size,
+                ])),
+                None,
+            )
+            .unwrap();
+        let affine = || {
+            Layer::Affine(Affine::new(
+                crate::Tensor::random(&vec![hidden_size]),
+                crate::Tensor::random(&vec![hidden_size]),
+            ))
+        };
+        let affine_node = model
+            .add_consecutive_layer(affine(), Some(dense_node))
+            .unwrap();
+        let relu_node = model
+            .add_consecutive_layer(
+                Layer::Activation(Activation::Relu(crate::layers::activation::Relu::new())),
+                Some(affine_node),
+            )
+            .unwrap();
+        // an affine layer following an activation can't be folded
+        model
+            .add_consecutive_layer(affine(), Some(relu_node))
+            .unwrap();
+        model.route_output(None).unwrap();
+
+        let input = crate::Tensor::random(&vec![input_size]);
+        let output = model.run_float(&[input.clone()]).unwrap();
+        let folded = fold_affine_layers(model).unwrap();
+        let num_affine = folded
+            .to_unstable_iterator()
+            .filter(|(_, node)| matches!(node.operation, Layer::Affine(_)))
+            .count();
+        assert_eq!(folded.to_unstable_iterator().count(), 3);
+        assert_eq!(num_affine, 1);
+        let folded_output = folded.run_float(&[input]).unwrap();
+        for (x, y) in output[0].get_data().iter().zip(folded_output[0].get_data()) {
+            assert!((x - y).abs() < 1e-4, "folded output {y} differs from {x}");
+        }
+    }
 }
@@ -0,0 +1,344 @@
+//! Calibration of the range of the tensors from the values observed over the representative inputs
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::quantization;
+
+/// Number of bins of the histograms of the observed values
+const NUM_BINS: usize = 2048;
+
+/// Number of bins per quantization level of the histograms used for the entropy calibration, such
+/// that the threshold can clip the range down to `1 / BINS_PER_LEVEL` of the largest value, as in
+/// TensorRT where 128 levels are searched over 2048 bins
+const BINS_PER_LEVEL: usize = 16;
+
+/// Number of bins of the histograms used for the entropy calibration of values quantized over
+/// `bit_len` bits
+fn entropy_num_bins(bit_len: usize) -> usize {
+    NUM_BINS.max(BINS_PER_LEVEL * (quantization::max(bit_len) as usize + 1))
+}
+
+/// Method deriving the range of a tensor, from which its scaling factor is computed, out of the
+/// histogram of its values over the representative inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Calibration {
+    /// The range spans all the observed values
+    #[default]
+    MinMax,
+    /// The range is clipped to the given percentile of the observed values, e.g. `99.99` ignores the
+    /// `0.01%` smallest and `0.01%` largest values
+    Percentile(f64),
+    /// The range is clipped to the threshold minimizing the KL divergence between the distribution of
+    /// the absolute values and its quantized version, as in TensorRT
+    Entropy,
+}
+
+impl Calibration {
+    /// Number of bins of the histograms from which the range of values quantized over `bit_len` bits
+    /// is derived
+    pub(crate) fn num_bins(&self, bit_len: usize) -> usize {
+        match self {
+            Self::MinMax | Self::Percentile(_) => NUM_BINS,
+            Self::Entropy => entropy_num_bins(bit_len),
+        }
+    }
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinMax => write!(f, "minmax"),
+            Self::Percentile(p) => write!(f, "percentile {p}"),
+            Self::Entropy => write!(f, "entropy"),
+        }
+    }
+}
+
+/// Histogram of the values of a tensor over the representative inputs. All the bins have the same
+/// width: when new values fall outside of the histogram, it is widened by merging its bins.
+#[derive(Debug, Clone)]
+pub(crate) struct Histogram {
+    /// Empty until the first values are observed
+    counts: Vec<u64>,
+    /// Number of bins of the histogram
+    num_bins: usize,
+    /// Lower bound of the first bin
+    start: f64,
+    /// Width of each bin
+    width: f64,
+    /// Smallest observed value
+    min: f64,
+    /// Largest observed value
+    max: f64,
+}
+
+impl Histogram {
+    pub(crate) fn new(num_bins: usize) -> Self {
+        Self {
+            counts: vec![],
+            num_bins,
+            start: 0.0,
+            width: 0.0,
+            min: 0.0,
+            max: 0.0,
+        }
+    }
+
+    pub(crate) fn extend(&mut self, values: &[f64]) {
+        let Some((min, max)) = values.iter().copied().minmax().into_option() else {
+            return;
+        };
+        if self.counts.is_empty() {
+            self.counts = vec![0; self.num_bins];
+            self.start = min;
+            self.width = ((max - min) / self.num_bins as f64).max(f64::EPSILON);
+            self.min = min;
+            self.max = max;
+        } else {
+            self.min = self.min.min(min);
+            self.max = self.max.max(max);
+            self.widen();
+        }
+        for v in values {
+            let bin = ((v - self.start) / self.width) as usize;
+            self.counts[bin.min(self.num_bins - 1)] += 1;
+        }
+    }
+
+    /// Widens the histogram to cover `[self.min, self.max]`. The width of the bins is multiplied by
+    /// the smallest power of two such that the old bins can be merged into the new ones.
+    fn widen(&mut self) {
+        let num_bins = self.num_bins;
+        let (offset, log_factor) = (0..)
+            .find_map(|k| {
+                let width = self.width * 2f64.powi(k);
+                let offset = ((self.start - self.min) / width).ceil().max(0.0) as usize;
+                let factor = 1usize.checked_shl(k as u32).unwrap_or(usize::MAX);
+                let end = self.start + (num_bins as f64 - offset as f64) * width;
+                // both the old bins and the new values must fit in the widened histogram
+                (offset + (num_bins - 1) / factor < num_bins && end >= self.max)
+                    .then_some((offset, k))
+            })
+            .unwrap();
+        if offset == 0 && log_factor == 0 {
+            return;
+        }
+        let factor = 1usize.checked_shl(log_factor as u32).unwrap_or(usize::MAX);
+        let mut counts = vec![0; num_bins];
+        for (i, count) in self.counts.iter().enumerate() {
+            counts[offset + i / factor] += count;
+        }
+        self.width *= 2f64.powi(log_factor);
+        self.start -= offset as f64 * self.width;
+        self.counts = counts;
+    }
+
+    /// Returns the range of the observed values according to the given calibration method, for values
+    /// quantized over `bit_len` bits
+    pub(crate) fn range(&self, calibration: &Calibration, bit_len: usize) -> (f64, f64) {
+        match calibration {
+            Calibration::MinMax => (self.min, self.max),
+            Calibration::Percentile(p) => self.percentile_range(*p),
+            Calibration::Entropy => {
+                let threshold = self.entropy_threshold(bit_len);
+                (self.min.max(-threshold), self.max.min(threshold))
+            }
+        }
+    }
+
+    /// Returns the range of the values, once the `100 - p` percents smallest and largest ones are
+    /// clipped
+    fn percentile_range(&self, p: f64) -> (f64, f64) {
+        let total = self.counts.iter().sum::<u64>();
+        let clipped = ((100.0 - p) / 100.0 * total as f64) as u64;
+        // index of the first bin, in the given order, where more than `clipped` values are reached
+        let find_bin = |bins: &mut dyn Iterator<Item = (usize, &u64)>| {
+            bins.scan(0, |acc, (i, count)| {
+                *acc += count;
+                Some((i, *acc))
+            })
+            .find(|(_, acc)| *acc > clipped)
+            .map(|(i, _)| i)
+            .unwrap_or_default()
+        };
+        let lower = find_bin(&mut self.counts.iter().enumerate());
+        let upper = find_bin(&mut self.counts.iter().enumerate().rev());
+        (
+            (self.start + lower as f64 * self.width).max(self.min),
+            (self.start + (upper + 1) as f64 * self.width).min(self.max),
+        )
+    }
+
+    /// Returns the threshold `T` such that quantizing the absolute values of the tensor over `[0, T]`,
+    /// the values above `T` being clipped, minimizes the KL divergence with the distribution of the
+    /// absolute values over `bit_len` bits.
+    fn entropy_threshold(&self, bit_len: usize) -> f64 {
+        let num_bins = entropy_num_bins(bit_len);
+        let bound = self.min.abs().max(self.max.abs());
+        let width = bound / num_bins as f64;
+        if width == 0.0 {
+            return bound;
+        }
+        // histogram of the absolute values, whose bins are filled with the centers of the original ones
+        let mut abs_counts = vec![0.0; num_bins];
+        for (i, count) in self.counts.iter().enumerate() {
+            let center = self.start + (i as f64 + 0.5) * self.width;
+            abs_counts[((center.abs() / width) as usize).min(num_bins - 1)] += *count as f64;
+        }
+        let levels = quantization::max(bit_len) as usize + 1;
+        let sums = PrefixSums::new(&abs_counts);
+        // at most `NUM_BINS` thresholds are tried, so the search does not grow with the bit length
+        let step = (num_bins - levels).div_ceil(NUM_BINS).max(1);
+        let num_bins = (levels..=num_bins)
+            .step_by(step)
+            .map(|i| (i, sums.kl_divergence(i, levels)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+            .unwrap_or(num_bins);
+        num_bins as f64 * width
+    }
+}
+
+/// Smoothing of the probabilities of `q` which are null where those of `p` are not, i.e. where `p`
+/// holds clipped values
+const SMOOTHING: f64 = 1e-9;
+
+/// Prefix sums over the bins of a histogram, such that the KL divergence for a given threshold is
+/// computed in time linear in the number of quantization levels rather than in the number of bins
+struct PrefixSums {
+    /// Counts of the histogram
+    counts: Vec<f64>,
+    /// Sums of the counts of the first bins
+    sums: Vec<f64>,
+    /// Numbers of non empty bins among the first bins
+    non_empty: Vec<f64>,
+    /// Sums of `c * ln(c)` over the counts `c` of the first bins
+    entropies: Vec<f64>,
+}
+
+impl PrefixSums {
+    fn new(counts: &[f64]) -> Self {
+        let prefix = |f: fn(f64) -> f64| {
+            std::iter::once(0.0)
+                .chain(counts.iter().scan(0.0, |acc, c| {
+                    *acc += f(*c);
+                    Some(*acc)
+                }))
+                .collect_vec()
+        };
+        Self {
+            counts: counts.to_vec(),
+            sums: prefix(|c| c),
+            non_empty: prefix(|c| if c > 0.0 { 1.0 } else { 0.0 }),
+            entropies: prefix(entropy),
+        }
+    }
+
+    /// KL divergence between the distribution `p` of the histogram clipped to its first `i` bins,
+    /// the values above the threshold being added to the last bin, and its quantized version `q`,
+    /// where the first `i` bins are merged in `levels` chunks, each chunk being spread uniformly
+    /// over its non empty bins.
+    fn kl_divergence(&self, i: usize, levels: usize) -> f64 {
+        let (p_total, q_total) = (self.sums[self.counts.len()], self.sums[i]);
+        if q_total == 0.0 {
+            return f64::INFINITY;
+        }
+        let clipped = p_total - q_total;
+        // the last bin holds the clipped values in `p` but not in `q`
+        let last = self.counts[i - 1] + clipped;
+        (0..levels)
+            .map(|l| {
+                let (start, end) = (l * i / levels, (l + 1) * i / levels);
+                let (mut p_sum, mut p_entropy) = (
+                    self.sums[end] - self.sums[start],
+                    self.entropies[end] - self.entropies[start],
+                );
+                let q_sum = p_sum;
+                let mut non_empty = self.non_empty[end] - self.non_empty[start];
+                if end == i {
+                    p_sum += clipped;
+                    p_entropy += entropy(last) - entropy(self.counts[i - 1]);
+                    if self.counts[i - 1] == 0.0 && last > 0.0 {
+                        non_empty += 1.0;
+                    }
+                }
+                if non_empty == 0.0 {
+                    return 0.0;
+                }
+                let q = (q_sum / non_empty / q_total).max(SMOOTHING);
+                // sum of `p / p_total * ln(p / p_total / q)` over the bins of the chunk
+                (p_entropy - p_sum * (p_total.ln() + q.ln())) / p_total
+            })
+            .sum()
+    }
+}
+
+/// `c * ln(c)`, which tends to zero with `c`
+fn entropy(c: f64) -> f64 {
+    if c > 0.0 { c * c.ln() } else { 0.0 }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Uniform values in `[-1, 1]` along with a few outliers
+    fn values_with_outliers() -> Vec<f64> {
+        (0..10_000)
+            .map(|i| i as f64 / 5_000.0 - 1.0)
+            .chain([1000.0, -20.0])
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram_widening() {
+        let values = values_with_outliers();
+        let mut histogram = Histogram::new(NUM_BINS);
+        // the outliers are observed after the first values, so the histogram is widened
+        for chunk in values.chunks(3_000) {
+            histogram.extend(chunk);
+        }
+        assert_eq!(histogram.counts.iter().sum::<u64>(), values.len() as u64);
+        assert_eq!(
+            histogram.range(&Calibration::MinMax, quantization::DEFAULT_BIT_LEN),
+            (-20.0, 1000.0)
+        );
+        assert!(histogram.start <= -20.0);
+        assert!(histogram.start + NUM_BINS as f64 * histogram.width >= 1000.0);
+        assert_eq!(
+            histogram.range(
+                &Calibration::Percentile(100.0),
+                quantization::DEFAULT_BIT_LEN
+            ),
+            (-20.0, 1000.0)
+        );
+    }
+
+    #[test]
+    fn test_calibration_clips_outliers() {
+        let mut histogram = Histogram::new(NUM_BINS);
+        histogram.extend(&values_with_outliers());
+        let (min, max) = histogram.range(
+            &Calibration::Percentile(99.9),
+            quantization::DEFAULT_BIT_LEN,
+        );
+        assert!(min >= -20.0 && min <= -1.0 && max >= 1.0 && max < 2.0);
+        let (min, max) = histogram.range(&Calibration::Entropy, quantization::DEFAULT_BIT_LEN);
+        assert_eq!(min, -20.0);
+        assert!(max >= 1.0 && max < 100.0);
+    }
+
+    #[test]
+    fn test_entropy_calibration_clips_outliers_over_16_bits() {
+        let bit_len = 16;
+        let mut histogram = Histogram::new(Calibration::Entropy.num_bins(bit_len));
+        for chunk in values_with_outliers().chunks(3_000) {
+            histogram.extend(chunk);
+        }
+        let (min, max) = histogram.range(&Calibration::Entropy, bit_len);
+        assert_eq!(min, -20.0);
+        // the threshold is searched over more bins than the 2048 bins used over 8 bits
+        assert!(max >= 1.0 && max < 100.0);
+    }
+}
@@ -38,6 +38,15 @@ impl ModelMetadata {
             .get(&node_id)
             .expect(&format!("Node {node_id} not found"))
     }
+
+    /// Number of bits of the quantized outputs of the layer
+    pub fn layer_bit_len(&self, node_id: NodeId) -> usize {
+        self.layer_output_scaling_factor(node_id)
+            .iter()
+            .map(ScalingFactor::bit_len)
+            .max()
+            .unwrap_or(super::DEFAULT_BIT_LEN)
+    }
 }
 
 pub(crate) struct MetadataBuilder {
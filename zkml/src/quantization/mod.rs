@@ -1,47 +1,78 @@
 //! Module that takes care of (re)quantizing
+mod calibration;
 mod metadata;
 mod strategy;
 use derive_more::From;
 use ff_ext::ExtensionField;
 use goldilocks::SmallField;
 use itertools::Itertools;
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::env;
 use tracing::warn;
 
 use crate::{
     Element,
     tensor::{Number, Tensor},
 };
+pub use calibration::Calibration;
 pub use metadata::ModelMetadata;
 pub(crate) use strategy::InferenceTracker;
 pub use strategy::{AbsoluteMax, InferenceObserver, ScalingStrategy};
 
-// Get BIT_LEN from environment variable or use default value
-pub static BIT_LEN: Lazy<usize> = Lazy::new(|| {
-    env::var("ZKML_BIT_LEN")
-        .ok()
-        .and_then(|val| val.parse::<usize>().ok())
-        .unwrap_or(8) // Default value if env var is not set or invalid
-});
-
-/// symmetric quantization range
-pub static MIN: Lazy<Element> = Lazy::new(|| -(1 << (*BIT_LEN - 1)) + 1);
-pub static MAX: Lazy<Element> = Lazy::new(|| (1 << (*BIT_LEN - 1)) - 1);
-pub static RANGE: Lazy<Element> = Lazy::new(|| *MAX - *MIN);
-pub static ZERO: Lazy<Element> = Lazy::new(|| 0);
+/// Number of bits of the quantized values of the layers whose bit length is not configured, see
+/// [`InferenceObserver::with_node_bit_len`]
+pub const DEFAULT_BIT_LEN: usize = 8;
+
+/// Largest supported number of bits of the quantized values, which bounds the size of the range
+/// checking lookup tables
+pub const MAX_BIT_LEN: usize = 16;
+
+pub(crate) fn default_bit_len() -> usize {
+    DEFAULT_BIT_LEN
+}
+
+/// Smallest value of the symmetric quantization range over `bit_len` bits
+pub const fn min(bit_len: usize) -> Element {
+    -(1 << (bit_len - 1)) + 1
+}
+/// Largest value of the symmetric quantization range over `bit_len` bits
+pub const fn max(bit_len: usize) -> Element {
+    (1 << (bit_len - 1)) - 1
+}
+/// Size of the symmetric quantization range over `bit_len` bits
+pub const fn range(bit_len: usize) -> Element {
+    max(bit_len) - min(bit_len)
+}
+/// Quantized domain of the values over `bit_len` bits
+pub const fn domain(bit_len: usize) -> (Element, Element) {
+    (min(bit_len), max(bit_len))
+}
+/// Number of bits of the values in the quantized domain `(min, max)`
+pub fn bit_len(domain: (Element, Element)) -> usize {
+    (domain.1 - domain.0 + 1).ilog2() as usize + 1
+}
+
+/// symmetric quantization range with the default bit length, for the tests: the bit length of the
+/// quantized values is otherwise given by their scaling factor
+#[cfg(test)]
+pub(crate) const MIN: Element = min(DEFAULT_BIT_LEN);
+#[cfg(test)]
+pub(crate) const MAX: Element = max(DEFAULT_BIT_LEN);
 pub const MIN_FLOAT: f32 = -1.0;
 pub const MAX_FLOAT: f32 = 1.0;
 
-/// Symmetric quantization scaling
-/// go from float [-a;a] to int [-2^BIT_LEN;2^BIT_LEN]
-/// S = (a - (-a)) / (2^{BIT_LEN-1}- (-2^{BIT_LEN-1})) = 2a / 2^BIT_LEN
+/// Quantization scaling
+/// go from float [min;max] to int [-2^BIT_LEN;2^BIT_LEN]
+/// S = (max - min) / (2^{BIT_LEN-1}- (-2^{BIT_LEN-1})) = (max - min) / 2^BIT_LEN
+/// The quantization is symmetric, with a zero point equal to zero, when min = -max. The bit length
+/// `BIT_LEN` of the quantized values is given by the quantized domain, see [`ScalingFactor::bit_len`].
 #[derive(Debug, Clone, From, Copy, Serialize, Deserialize)]
 pub struct ScalingFactor {
     min: f32,
     max: f32,
     quantized_domain: (Element, Element),
+    /// Quantized value of the float zero
+    #[serde(default)]
+    zero_point: Element,
 }
 
 impl ScalingFactor {
@@ -59,17 +90,60 @@ impl ScalingFactor {
         Self::from_absolute_max(max_abs.to_f32(), quantized_domain)
     }
 
+    /// Scaling factors of each slice of `weights` along its first dimension, e.g. of each row of a
+    /// matrix or of each output channel of a convolution filter. Like for the scaling factor of the
+    /// whole tensor, the bias of each row is taken into account. The weights are quantized over
+    /// `bit_len` bits.
+    pub(crate) fn from_rows(
+        weights: &Tensor<f32>,
+        bias: &Tensor<f32>,
+        bit_len: usize,
+    ) -> Vec<Self> {
+        let num_rows = bias.get_data().len();
+        weights
+            .get_data()
+            .chunks(weights.get_data().len() / num_rows)
+            .zip(bias.get_data())
+            .map(|(row, b)| {
+                let abs_max = row.iter().fold(b.abs(), |max, w| max.max(w.abs()));
+                // a row of zeros is quantized to zeros with any scaling factor
+                Self::from_absolute_max(
+                    if abs_max == 0.0 { 1.0 } else { abs_max },
+                    Some(domain(bit_len)),
+                )
+            })
+            .collect()
+    }
+
     pub fn from_span(min: f32, max: f32, quantized_domain: Option<(Element, Element)>) -> Self {
         Self {
-            min: min,
-            max: max,
-            quantized_domain: quantized_domain.unwrap_or((*MIN, *MAX)),
+            min,
+            max,
+            quantized_domain: quantized_domain.unwrap_or(domain(DEFAULT_BIT_LEN)),
+            zero_point: 0,
         }
     }
+
+    /// Asymmetric quantization of `[min, max]`, with the zero point such that `min` is quantized to the
+    /// lower bound of the quantized domain. The span is extended to contain zero, so that the float zero
+    /// is quantized exactly.
+    pub fn from_asymmetric_span(
+        min: f32,
+        max: f32,
+        quantized_domain: Option<(Element, Element)>,
+    ) -> Self {
+        let (min, max) = (min.min(0.0), max.max(0.0));
+        let mut s = Self::from_span(min, max, quantized_domain);
+        if max > min {
+            // z = q_min - min / S
+            s.zero_point = (s.quantized_domain.0 as f32 - min / s.scale()).round() as Element;
+        }
+        s
+    }
     /// Initialize a scaling factor in such a way that `self.scale()` is equal to the `scale` value
     /// provided as input.
     pub(crate) fn from_scale(scale: f32, quantized_domain: Option<(Element, Element)>) -> Self {
-        let (min_quantized, max_quantized) = quantized_domain.unwrap_or((*MIN, *MAX));
+        let (min_quantized, max_quantized) = quantized_domain.unwrap_or(domain(DEFAULT_BIT_LEN));
         let max = scale / 2.0 * (max_quantized - min_quantized) as f32;
         Self::from_absolute_max(max, quantized_domain)
     }
@@ -82,6 +156,25 @@ impl ScalingFactor {
         self.max
     }
 
+    pub fn zero_point(&self) -> Element {
+        self.zero_point
+    }
+
+    pub fn quantized_domain(&self) -> (Element, Element) {
+        self.quantized_domain
+    }
+
+    /// Number of bits of the quantized values, i.e. the bit length of the symmetric range of the
+    /// quantized domain
+    pub fn bit_len(&self) -> usize {
+        bit_len(self.quantized_domain)
+    }
+
+    /// Returns true if the float zero is quantized to zero
+    pub fn is_symmetric(&self) -> bool {
+        self.zero_point == 0
+    }
+
     pub fn scale(&self) -> f32 {
         (self.max - self.min) / (self.quantized_domain.1 - self.quantized_domain.0) as f32
     }
@@ -100,11 +193,8 @@ impl ScalingFactor {
     /// Take a floating point number and quantize it to an BIT_LEN-bit integer
     /// S = (a - (-a)) / (2^{BIT_LEN-1}- (-2^{BIT_LEN-1})) = 2a / 2^BIT_LEN
     pub fn quantize(&self, value: &f32) -> Element {
-        const ZERO_POINT: Element = 0;
-
         // formula is q = round(r/S) + z
-        // let scaled =((value.clamp(self.min,self.max) - self.min) / self.scale()).round() * self.scale() + self.min;
-        let scaled = (*value / self.scale()).round() as Element + ZERO_POINT;
+        let scaled = (*value / self.scale()).round() as Element + self.zero_point;
         if scaled < self.quantized_domain.0 || scaled > self.quantized_domain.1 {
             warn!(
                 "Quantized value {} from {} is out of range [{}, {}]",
@@ -115,7 +205,7 @@ impl ScalingFactor {
     }
 
     pub fn dequantize(&self, value: &Element) -> f32 {
-        *value as f32 * self.scale()
+        (*value - self.zero_point) as f32 * self.scale()
     }
 }
 
@@ -124,7 +214,8 @@ impl Default for ScalingFactor {
         Self {
             min: -1.0,
             max: 1.0,
-            quantized_domain: (*MIN, *MAX),
+            quantized_domain: domain(DEFAULT_BIT_LEN),
+            zero_point: 0,
         }
     }
 }
@@ -186,6 +277,24 @@ where
     }
 }
 
+/// Returns the bias of a layer computing `W x + b` along the first dimension of `weights` which
+/// absorbs the zero point `z` of its inputs, i.e. `b - z * sum_j W_ij`, so that the layer computes
+/// `W (x - z) + b` over the quantized inputs.
+pub(crate) fn bias_with_zero_point(
+    weights: &Tensor<Element>,
+    bias: &Tensor<Element>,
+    zero_point: Element,
+) -> Tensor<Element> {
+    let num_rows = bias.get_data().len();
+    let data = weights
+        .get_data()
+        .chunks(weights.get_data().len() / num_rows)
+        .zip(bias.get_data())
+        .map(|(row, b)| b - zero_point * row.iter().sum::<Element>())
+        .collect_vec();
+    Tensor::new(bias.get_shape(), data)
+}
+
 pub fn max_range_from_weight<T: Number>(weight: &T, min_input: &T, max_input: &T) -> (T, T) {
     let min = if weight.is_negative() {
         *weight * *max_input
@@ -243,9 +352,30 @@ mod test {
 
     use crate::Element;
 
-    use super::{MAX, MIN};
+    use super::{MAX, MIN, ScalingFactor};
     type F = goldilocks::GoldilocksExt2;
 
+    #[test]
+    fn test_asymmetric_quantization() {
+        // symmetric span: zero point is zero
+        let s = ScalingFactor::from_asymmetric_span(-2.0, 2.0, None);
+        assert!(s.is_symmetric());
+        assert_eq!(s.quantize(&0.0), 0);
+        // non-negative span, e.g. after a ReLU: the whole quantized domain is used
+        let s = ScalingFactor::from_asymmetric_span(0.0, 6.0, None);
+        assert_eq!(s.zero_point(), MIN);
+        assert_eq!(s.quantize(&0.0), MIN);
+        assert_eq!(s.quantize(&6.0), MAX);
+        assert_eq!(s.dequantize(&MIN), 0.0);
+        for v in [0.1, 1.5, 3.3, 5.9] {
+            assert!((s.dequantize(&s.quantize(&v)) - v).abs() <= s.scale() / 2.0);
+        }
+        // the span is extended to contain zero
+        let s = ScalingFactor::from_asymmetric_span(1.0, 6.0, None);
+        assert_eq!(s.min(), 0.0);
+        assert_eq!(s.quantize(&0.0), s.zero_point());
+    }
+
     #[test]
     fn test_wrapped_arithmetic() {
         #[derive(Clone, Debug)]
@@ -280,7 +410,7 @@ mod test {
     #[test]
     fn test_element_field_roundtrip() {
         // Also test a few specific values explicitly
-        let test_values = [*MIN, -100, -50, -1, 0, 1, 50, 100, *MAX];
+        let test_values = [MIN, -100, -50, -1, 0, 1, 50, 100, MAX];
         for &val in &test_values {
             let field_val: F = val.to_field();
             let roundtrip = field_val.into_element();
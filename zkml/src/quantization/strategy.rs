@@ -1,20 +1,25 @@
 use crate::{
-    layers::provable::{Node, NodeId, QuantizeOp},
+    layers::{
+        Layer,
+        provable::{Edge, Node, NodeId, QuantizeOp, ZeroPointSupport},
+    },
     model::{Model, ToIterator},
     quantization::metadata::{MetadataBuilder, ModelMetadata},
     tensor::Number,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{Element, Tensor, quantization};
 use anyhow::{Result, anyhow, ensure};
 use ark_std::rand;
 use goldilocks::GoldilocksExt2;
 use itertools::Itertools;
-use statrs::statistics::{Data, Max, Min, OrderStatistics};
 use tracing::{debug, info, warn};
 
-use super::ScalingFactor;
+use super::{
+    ScalingFactor,
+    calibration::{Calibration, Histogram},
+};
 
 /// Trait for quantizing a float-based model into a quantized model. The current implementation
 /// simply looks at the absolute maximum value of the model and uses that as the scaling factor
@@ -37,19 +42,65 @@ pub trait ScalingStrategy: std::fmt::Debug {
 }
 
 /// Quantization strategy that observes the inference of the model with different inputs and uses the
-/// min/max values of the output to determine the output scaling factor of each layer that needs
-/// requantization afterwards.
+/// distribution of the values of the output to determine the output scaling factor of each layer that
+/// needs requantization afterwards. By default, the scaling factors span the min/max observed values.
 #[derive(Debug)]
 pub struct InferenceObserver {
     inputs: Vec<Vec<Vec<f32>>>,
+    /// Whether the tensors are quantized over their observed `[min, max]` with a zero point, rather
+    /// than symmetrically
+    asymmetric: bool,
+    /// How the range of each tensor is derived from the histogram of its observed values
+    calibration: Calibration,
+    /// Number of bits of the quantized tensors, unless configured for the node producing them
+    bit_len: usize,
+    /// Number of bits of the quantized outputs of some nodes
+    node_bit_lens: HashMap<NodeId, usize>,
 }
 
 impl InferenceObserver {
     pub fn new_with_representative_input(inputs: Vec<Vec<Vec<f32>>>) -> Self {
-        Self { inputs }
+        Self {
+            inputs,
+            asymmetric: false,
+            calibration: Calibration::default(),
+            bit_len: quantization::DEFAULT_BIT_LEN,
+            node_bit_lens: HashMap::new(),
+        }
     }
     pub fn new() -> Self {
-        Self { inputs: vec![] }
+        Self::new_with_representative_input(vec![])
+    }
+
+    /// Quantizes the tensors asymmetrically, e.g. the non-negative outputs of a ReLU use the whole
+    /// quantized range. Only the tensors whose consumers support a zero point are concerned, the
+    /// other ones are still quantized symmetrically.
+    pub fn with_asymmetric_quantization(mut self) -> Self {
+        self.asymmetric = true;
+        self
+    }
+
+    /// Derives the range of the tensors with the given calibration method, e.g. clipping the outliers
+    /// of the representative inputs with [`Calibration::Percentile`] or [`Calibration::Entropy`].
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Quantizes the inputs of the model and the outputs of the nodes over `bit_len` bits, unless
+    /// configured otherwise with [`Self::with_node_bit_len`].
+    pub fn with_bit_len(mut self, bit_len: usize) -> Self {
+        self.bit_len = bit_len;
+        self
+    }
+
+    /// Quantizes the outputs of the given node over `bit_len` bits, e.g. to keep more precision after
+    /// a sensitive layer. Layers which don't change the scale of their inputs, e.g. activations, keep
+    /// the bit length of their inputs. The layers consuming these outputs quantize their weights and
+    /// lookup tables over the same bit length.
+    pub fn with_node_bit_len(mut self, node_id: NodeId, bit_len: usize) -> Self {
+        self.node_bit_lens.insert(node_id, bit_len);
+        self
     }
 }
 
@@ -58,11 +109,31 @@ impl ScalingStrategy for InferenceObserver {
     type AuxData = InferenceTracker;
 
     fn name(&self) -> String {
-        format!("inference [{},{}]", *quantization::MIN, *quantization::MAX)
+        format!(
+            "inference{}{} [{},{}]",
+            if self.asymmetric { " asymmetric" } else { "" },
+            match self.calibration {
+                Calibration::MinMax => String::new(),
+                calibration => format!(" {calibration}"),
+            },
+            quantization::min(self.bit_len),
+            quantization::max(self.bit_len)
+        )
     }
 
     fn quantize(&self, model: Model<f32>) -> Result<(Model<Element>, ModelMetadata)> {
-        let mut tracker = InferenceTracker::new();
+        for bit_len in std::iter::once(&self.bit_len).chain(self.node_bit_lens.values()) {
+            ensure!(
+                (2..=quantization::MAX_BIT_LEN).contains(bit_len),
+                "Bit length {bit_len} of quantized values is not in [2, {}]",
+                quantization::MAX_BIT_LEN
+            );
+        }
+        let mut tracker =
+            InferenceTracker::new(self.calibration, self.bit_len, self.node_bit_lens.clone());
+        if self.asymmetric {
+            tracker.zero_point_outputs = zero_point_outputs(&model);
+        }
         let input_shapes = model.input_shapes();
         let input_not_padded_shapes = model.unpadded_input_shapes();
         let inputs = if self.inputs.is_empty() {
@@ -106,11 +177,7 @@ impl ScalingStrategy for InferenceObserver {
         // 2. get the scaling factor of the input
         let num_model_inputs = input_not_padded_shapes.len();
         let input_scaling = (0..num_model_inputs)
-            .map(|i| {
-                let (input_min, input_max) =
-                    tracker.distribution_info(INPUT_TRACKING_ID as NodeId, i);
-                ScalingFactor::from_absolute_max(input_min.abs().max(input_max.abs()), None)
-            })
+            .map(|i| tracker.scaling_factor(INPUT_TRACKING_ID as NodeId, i))
             .collect_vec();
         quantize_model::<InferenceObserver>(model, tracker, input_scaling)
     }
@@ -121,52 +188,120 @@ impl ScalingStrategy for InferenceObserver {
         num_outputs: usize,
     ) -> Vec<ScalingFactor> {
         (0..num_outputs)
-            .map(|i| {
-                let (min, max) = tracker.distribution_info(node_id, i);
-                ScalingFactor::from_absolute_max(min.abs().max(max.abs()), None)
-            })
+            .map(|i| tracker.scaling_factor(node_id, i))
             .collect()
     }
 }
 
+/// Returns the outputs of the nodes, and the inputs of the model, which can be quantized with a zero
+/// point: all the layers using them must absorb the zero point, or forward it to layers which do.
+fn zero_point_outputs(model: &Model<f32>) -> HashSet<(NodeId, usize)> {
+    fn supports_zero_point(model: &Model<f32>, edges: &[Edge]) -> bool {
+        edges.iter().all(|edge| match edge.node {
+            // the outputs of the model are dequantized with their scaling factor, zero point included
+            None => true,
+            Some(id) => {
+                let node = &model.nodes[&id];
+                match node.operation.zero_point_support() {
+                    ZeroPointSupport::Unsupported => false,
+                    ZeroPointSupport::Absorbed => true,
+                    ZeroPointSupport::Forwarded => node
+                        .outputs
+                        .iter()
+                        .all(|out| supports_zero_point(model, &out.edges)),
+                }
+            }
+        })
+    }
+    let mut outputs = HashSet::new();
+    for (id, node) in model.to_unstable_iterator() {
+        for (i, out) in node.outputs.iter().enumerate() {
+            if supports_zero_point(model, &out.edges) {
+                outputs.insert((*id, i));
+            }
+        }
+    }
+    for i in 0..model.num_inputs() {
+        let consumers = model
+            .to_unstable_iterator()
+            .filter(|(_, node)| {
+                node.inputs
+                    .iter()
+                    .any(|edge| edge.node.is_none() && edge.index == i)
+            })
+            .map(|(id, _)| Edge::new(*id, 0))
+            .collect_vec();
+        if supports_zero_point(model, &consumers) {
+            outputs.insert((INPUT_TRACKING_ID as NodeId, i));
+        }
+    }
+    outputs
+}
+
 pub struct InferenceTracker {
-    /// For each output of each node in the model of interest, we track all the values of the tensor
-    data: HashMap<(NodeId, usize), Vec<f64>>,
+    /// For each output of each node in the model of interest, we track the histogram of the values of
+    /// the tensor
+    data: HashMap<(NodeId, usize), Histogram>,
+    /// Outputs which are quantized asymmetrically, with a zero point
+    zero_point_outputs: HashSet<(NodeId, usize)>,
+    /// How the range of each output is derived from its histogram
+    calibration: Calibration,
+    /// Number of bits of the quantized outputs, unless configured for the node in `node_bit_lens`
+    bit_len: usize,
+    node_bit_lens: HashMap<NodeId, usize>,
 }
 
 impl InferenceTracker {
-    fn new() -> Self {
+    fn new(
+        calibration: Calibration,
+        bit_len: usize,
+        node_bit_lens: HashMap<NodeId, usize>,
+    ) -> Self {
         Self {
             data: HashMap::new(),
+            zero_point_outputs: HashSet::new(),
+            calibration,
+            bit_len,
+            node_bit_lens,
         }
     }
+
+    /// Returns the scaling factor of the given output, derived from the observed distribution
+    fn scaling_factor(&self, node_id: NodeId, output_index: usize) -> ScalingFactor {
+        let (min, max) = self.distribution_info(node_id, output_index);
+        let domain = Some(quantization::domain(self.bit_len(node_id)));
+        if self.zero_point_outputs.contains(&(node_id, output_index)) {
+            ScalingFactor::from_asymmetric_span(min, max, domain)
+        } else {
+            ScalingFactor::from_absolute_max(min.abs().max(max.abs()), domain)
+        }
+    }
+    /// Number of bits of the quantized outputs of the given node
+    fn bit_len(&self, node_id: NodeId) -> usize {
+        *self.node_bit_lens.get(&node_id).unwrap_or(&self.bit_len)
+    }
+
     pub(crate) fn track(&mut self, node_id: NodeId, output_index: usize, output: Tensor<f32>) {
+        let values = output.get_data().iter().map(|x| *x as f64).collect_vec();
+        let num_bins = self.calibration.num_bins(self.bit_len(node_id));
         self.data
             .entry((node_id, output_index))
-            .or_insert(Vec::new())
-            .extend(output.get_data().iter().map(|x| *x as f64));
+            .or_insert_with(|| Histogram::new(num_bins))
+            .extend(&values);
     }
 
-    /// Returns the 0.05 and 0.95 quantiles of the distribution of the output values of the layer.
+    /// Returns the range of the output values of the layer, derived from their histogram with the
+    /// calibration method of the tracker.
     pub(crate) fn distribution_info(&self, node_id: NodeId, output_index: usize) -> (f32, f32) {
-        let mut d: Data<Vec<f64>> = Data::new(
-            self.data
-                .get(&(node_id, output_index))
-                .expect(&format!(
-                    "No data for output tensor {output_index} of node {node_id}"
-                ))
-                .clone(),
-        );
-        let min = d.percentile(5) as f32;
-        let max = d.percentile(95) as f32;
+        let (min, max) = self
+            .data
+            .get(&(node_id, output_index))
+            .expect(&format!(
+                "No data for output tensor {output_index} of node {node_id}"
+            ))
+            .range(&self.calibration, self.bit_len(node_id));
         assert!(min <= max);
-        //(min, max)
-        (d.min() as f32, d.max() as f32)
-        // let mean = d.mean().unwrap();
-        // let std_dev = d.std_dev().unwrap();
-        // let upper_bound = mean + 3.0 * std_dev;
-        // let lower_bound = mean - 3.0 * std_dev;
-        //(lower_bound as f32, upper_bound as f32)
+        (min as f32, max as f32)
     }
 }
 
@@ -243,25 +378,44 @@ fn quantize_model<S: ScalingStrategy>(
             let quantized_out = node
                 .operation
                 .quantize_op::<S>(&data, node_id, &input_scaling)?;
-            md.set_layers_scaling(node_id, quantized_out.output_scalings, input_scaling);
             if let Some(requant) = quantized_out.requant_layer {
-                requant_layers.push((node_id, requant));
+                // the zero point of the output, if any, is added by the requant layer
+                let output_scaling = quantized_out
+                    .output_scalings
+                    .first()
+                    .copied()
+                    .unwrap_or_default();
+                requant_layers.push((
+                    node_id,
+                    quantized_out.rescale_layer,
+                    requant.with_zero_point(output_scaling.zero_point()),
+                ));
             }
+            md.set_layers_scaling(node_id, quantized_out.output_scalings, input_scaling);
             let quantized_node =
                 Node::new_with_outputs(node.inputs, quantized_out.quanzited_op, node.outputs);
             Ok((node_id, quantized_node))
         })
         .collect::<Result<_>>()?;
     let mut model = Model::new_from_shapes(input_not_padded_shapes, input_shapes, nodes);
-    for (input_node_id, requant) in requant_layers {
-        let node_id = model.add_requant_node(requant, input_node_id)?;
-        // add scaling factor to `md` for requant layers: the scaling factors of the inputs correspond to
-        // the scaling factors of the outputs of the previous node
-        let input_scaling = md.get_output_layer_scaling(&input_node_id).ok_or(anyhow!(
-            "Scaling factors not found for node {input_node_id}"
-        ))?;
-        let output_scaling = input_scaling.to_vec(); // output scaling factors are the same as input ones for requant
-        md.set_layers_scaling(node_id, output_scaling, input_scaling.to_vec());
+    for (input_node_id, rescale, requant) in requant_layers {
+        // the rescale layer, if any, sits between the node and its requant layer
+        let layers = rescale
+            .map(Layer::Rescale)
+            .into_iter()
+            .chain(std::iter::once(Layer::Requant(requant)));
+        let mut input_node_id = input_node_id;
+        for layer in layers {
+            let node_id = model.add_requant_node(layer, input_node_id)?;
+            // add scaling factor to `md` for requant layers: the scaling factors of the inputs correspond to
+            // the scaling factors of the outputs of the previous node
+            let input_scaling = md.get_output_layer_scaling(&input_node_id).ok_or(anyhow!(
+                "Scaling factors not found for node {input_node_id}"
+            ))?;
+            let output_scaling = input_scaling.to_vec(); // output scaling factors are the same as input ones for requant
+            md.set_layers_scaling(node_id, output_scaling, input_scaling.to_vec());
+            input_node_id = node_id;
+        }
     }
     let out_nodes = model.output_nodes();
     let md = md.build(out_nodes)?;
@@ -72,7 +72,8 @@ impl Number for Element {
     const MIN: Element = Element::MIN;
     const MAX: Element = Element::MAX;
     fn random<R: Rng>(rng: &mut R) -> Self {
-        rng.gen_range(*quantization::MIN..=*quantization::MAX)
+        let (min, max) = quantization::domain(quantization::DEFAULT_BIT_LEN);
+        rng.gen_range(min..=max)
     }
     fn absolute_value(&self) -> Self {
         self.abs()
@@ -146,19 +147,17 @@ impl Number for GoldilocksExt2 {
 
 /// Function testing the consistency between the actual convolution implementation and
 /// the FFT one. Used for debugging purposes.
-/// real_tensor is std conv2d (kw, nx-nw+1, nx-nw+1)
-/// padded_tensor is results from fft conv (kw, nx, nx)
+/// real_tensor is std conv2d (kw, nh-kh+1, nw-kw+1)
+/// padded_tensor is results from fft conv (kw, nh, nw)
 pub fn check_tensor_consistency(real_tensor: Tensor<Element>, padded_tensor: Tensor<Element>) {
-    let n_x = padded_tensor.shape[1];
+    let (n_h, n_w) = (padded_tensor.shape[1], padded_tensor.shape[2]);
+    let (real_h, real_w) = (real_tensor.shape[1], real_tensor.shape[2]);
     for i in 0..real_tensor.shape[0] {
-        for j in 0..real_tensor.shape[1] {
-            for k in 0..real_tensor.shape[1] {
-                // TODO: test if real_tensor.shape[2] works here
+        for j in 0..real_h {
+            for k in 0..real_w {
                 assert!(
-                    real_tensor.data[i * real_tensor.shape[1] * real_tensor.shape[1]
-                        + j * real_tensor.shape[1]
-                        + k]
-                        == padded_tensor.data[i * n_x * n_x + j * n_x + k],
+                    real_tensor.data[i * real_h * real_w + j * real_w + k]
+                        == padded_tensor.data[i * n_h * n_w + j * n_w + k],
                     "Error in tensor consistency"
                 );
             }
@@ -184,28 +183,29 @@ pub fn get_root_of_unity<E: ExtensionField>(n: usize) -> E {
 /// Properly pad a filter
 /// We use this function so that filter is amenable to FFT based conv2d
 /// Usually vec and n are powers of 2
-/// Output: [[F[0][0],…,F[0][n_w],0,…,0],[F[1][0],…,F[1][n_w],0,…,0],…]
+/// The filter has `h_real` rows of `w_real` entries, which are laid out with a row stride of `n`
+/// Output: [[F[0][0],…,F[0][w_real],0,…,0],[F[1][0],…,F[1][w_real],0,…,0],…]
 pub fn index_w<E: ExtensionField>(
     w: &[Element],
-    n_real: usize,
+    h_real: usize,
+    w_real: usize,
     n: usize,
     output_len: usize,
 ) -> impl ParallelIterator<Item = E> + use<'_, E> {
     (0..output_len).into_par_iter().map(move |idx| {
         let i = idx / n;
         let j = idx % n;
-        if i < n_real && j < n_real {
-            w[i * n_real + j].to_field()
+        if i < h_real && j < w_real {
+            w[i * w_real + j].to_field()
         } else {
             E::ZERO
         }
     })
 }
-// let u = [u[1],...,u[n*n]]
-// output vec = [u[n*n-1],u[n*n-2],...,u[n*n-n],....,u[0]]
+// let u = [u[1],...,u[len]], with len = n_h*n_w
+// output vec = [u[len-1],u[len-2],...,u[len-n_w],....,u[0]]
 // Note that y_eval =  f_vec(r) = f_u(1-r)
-pub fn index_u<E: ExtensionField>(u: &[E], n: usize) -> impl Iterator<Item = E> + use<'_, E> {
-    let len = n * n;
+pub fn index_u<E: ExtensionField>(u: &[E], len: usize) -> impl Iterator<Item = E> + use<'_, E> {
     (0..u.len() / 2).into_iter().map(move |i| u[len - 1 - i])
 }
 /// flag: false -> FFT
@@ -282,6 +282,8 @@ where
     pub prod: Vec<Vec<E>>,  // FFT(input) * FFT(weights)
     pub output: Vec<Vec<E>>, // iFFT(FFT(input) * FFT(weights)) ==> conv
     pub output_as_element: Vec<Element>, // output as element
+    // Unpadded shape of the input, before the zero padding of the layer
+    pub unpadded_input_shape: Vec<usize>,
 }
 
 impl<E> ConvData<E>
@@ -294,12 +296,12 @@ where
         input_fft: Vec<Vec<E>>,
         prod: Vec<Vec<E>>,
         output: Vec<Vec<E>>,
-        n_x: usize,
+        channel_size: usize,
     ) -> Self {
         let output_elems = output
             .iter()
             .map(|e| {
-                index_u(e.as_slice(), n_x)
+                index_u(e.as_slice(), channel_size)
                     .map(|e| e.into_element())
                     .collect::<Vec<_>>()
             })
@@ -312,6 +314,7 @@ where
             prod,
             output,
             output_as_element: output_elems,
+            unpadded_input_shape: vec![],
         }
     }
     pub fn set_output(&mut self, output: &[Element]) {
@@ -345,14 +348,15 @@ impl Tensor<Element> {
         );
         assert!(shape.len() == 4, "Shape does not match data length.");
         assert!(
-            shape[2].is_power_of_two(),
+            shape[2].is_power_of_two() && shape[3].is_power_of_two(),
             "Filter dimension is not power of two"
         );
         let real_shape = shape.clone();
-        let n_w = (input_shape[1] - shape[2] + 1).next_power_of_two();
+        let n_h = (input_shape[1] - shape[2] + 1).next_power_of_two();
+        let n_w = (input_shape[2] - shape[3] + 1).next_power_of_two();
         Self {
             data,                                      /* Note that field elements are back into Element */
-            shape: vec![shape[0], shape[1], n_w, n_w], // nw is the padded version of the input
+            shape: vec![shape[0], shape[1], n_h, n_w], // nh, nw are the padded version of the input
             og_shape: real_shape,
         }
     }
@@ -368,12 +372,12 @@ impl Tensor<Element> {
     /// This function computes iFFT of the weights so that we can compute the scaling factors used.
     pub fn get_real_weights<F: ExtensionField>(&self) -> Vec<Vec<Vec<Element>>> {
         let mut real_weights =
-            vec![vec![vec![0 as Element; self.nw() * self.nw()]; self.kx()]; self.kw()];
+            vec![vec![vec![0 as Element; self.nh() * self.nw()]; self.kx()]; self.kw()];
 
         let mut ctr = 0;
         for i in 0..self.kw() {
             for j in 0..self.kx() {
-                for k in 0..(self.real_nw() * self.real_nw()) {
+                for k in 0..(self.real_nh() * self.real_nw()) {
                     real_weights[i][j][k] = self.data[ctr];
                     ctr += 1;
                 }
@@ -389,14 +393,35 @@ impl Tensor<Element> {
         &self,
         x: &Tensor<Element>,
     ) -> (Tensor<Element>, ConvData<F>) {
+        self.grouped_fft_conv(x, 1)
+    }
+
+    /// Grouped convolution algorithm using FFTs. The input channels and the filters are split into
+    /// `groups` groups of consecutive channels, and each filter is only applied to the input
+    /// channels of its group. The filter has `k_x` input channels, i.e. the number of input
+    /// channels per group.
+    pub fn grouped_fft_conv<F: ExtensionField>(
+        &self,
+        x: &Tensor<Element>,
+        groups: usize,
+    ) -> (Tensor<Element>, ConvData<F>) {
+        assert_eq!(
+            x.shape[0],
+            self.kx() * groups,
+            "Inconsistency on filter/input channels"
+        );
+        let filters_per_group = self.kw() / groups;
         // input to field elements
-        let n_x = x.shape[1].next_power_of_two();
+        let (n_h, n_w) = (
+            x.shape[1].next_power_of_two(),
+            x.shape[2].next_power_of_two(),
+        );
         let real_input = x.data.par_iter().map(|e| e.to_field()).collect::<Vec<_>>();
-        let new_n = 2 * n_x * n_x;
+        let new_n = 2 * n_h * n_w;
 
         let (x_vec, input): (Vec<Vec<F>>, Vec<Vec<F>>) = real_input
             .par_iter()
-            .chunks(n_x * n_x)
+            .chunks(n_h * n_w)
             .map(|chunk| {
                 let xx_input = chunk.into_iter().cloned().rev().collect::<Vec<_>>();
                 let mut xx_fft = xx_input
@@ -412,24 +437,25 @@ impl Tensor<Element> {
         // let dim1 = x_vec.len();
         // let dim2 = x_vec[0].len();
 
-        let mut out = vec![vec![F::ZERO; 2 * self.nw() * self.nw()]; self.kw()];
+        let mut out = vec![vec![F::ZERO; 2 * self.filter_size()]; self.kw()];
 
+        let real_filter_size = self.real_nh() * self.real_nw();
         for i in 0..self.kw() {
             for j in 0..self.kx() {
-                let range = (i * self.kx() * self.real_nw() * self.real_nw()
-                    + j * self.real_nw() * self.real_nw())
-                    ..(i * self.kx() * self.real_nw() * self.real_nw()
-                        + (j + 1) * self.real_nw() * self.real_nw());
+                let range = (i * self.kx() * real_filter_size + j * real_filter_size)
+                    ..(i * self.kx() * real_filter_size + (j + 1) * real_filter_size);
                 let mut w_fft_temp = index_w(
                     &self.data[range],
+                    self.real_nh(),
                     self.real_nw(),
                     self.nw(),
-                    2 * self.nw() * self.nw(),
+                    2 * self.filter_size(),
                 )
                 .collect::<Vec<F>>();
                 fft(&mut w_fft_temp, false);
+                let channel = (i / filters_per_group) * self.kx() + j;
                 for k in 0..out[i].len() {
-                    out[i][k] += x_vec[j][k] * w_fft_temp[k];
+                    out[i][k] += x_vec[channel][k] * w_fft_temp[k];
                 }
             }
         }
@@ -440,10 +466,10 @@ impl Tensor<Element> {
 
         // TODO: remove the requirement to keep the output value intact
         let output = out;
-        let conv_data = ConvData::new(real_input, input, x_vec, prod, output, n_x);
+        let conv_data = ConvData::new(real_input, input, x_vec, prod, output, n_h * n_w);
         return (
             Tensor::new(
-                vec![self.shape[0], n_x, n_x],
+                vec![self.shape[0], n_h, n_w],
                 conv_data.output_as_element.clone(),
             ),
             conv_data,
@@ -458,17 +484,20 @@ impl Tensor<Element> {
         x: &Tensor<Element>,
     ) -> (Tensor<Element>, ConvData<F>) {
         // input to field elements
-        let n_x = x.shape[1].next_power_of_two();
+        let (n_h, n_w) = (
+            x.shape[1].next_power_of_two(),
+            x.shape[2].next_power_of_two(),
+        );
         let real_input = x.data.par_iter().map(|e| e.to_field()).collect::<Vec<_>>();
         let w_fft: Vec<F> = self
             .data
             .par_iter()
             .map(|e| e.to_field())
             .collect::<Vec<_>>();
-        let new_n = 2 * n_x * n_x;
+        let new_n = 2 * n_h * n_w;
         let (x_vec, input): (Vec<Vec<F>>, Vec<Vec<F>>) = real_input
             .par_iter()
-            .chunks(n_x * n_x)
+            .chunks(n_h * n_w)
             .map(|chunk| {
                 let xx_input = chunk.into_iter().cloned().rev().collect::<Vec<_>>();
                 let mut xx_fft = xx_input
@@ -503,10 +532,10 @@ impl Tensor<Element> {
             .unzip();
         // TODO: remove the requirement to keep the output value intact
         let output = out.clone();
-        let conv_data = ConvData::new(real_input, input, x_vec, prod, output, n_x);
+        let conv_data = ConvData::new(real_input, input, x_vec, prod, output, n_h * n_w);
         return (
             Tensor::new(
-                vec![self.shape[0], n_x, n_x],
+                vec![self.shape[0], n_h, n_w],
                 conv_data.output_as_element.clone(),
             ),
             conv_data,
@@ -562,6 +591,26 @@ impl Tensor<f32> {
             .collect::<Vec<_>>();
         Tensor::new(self.shape, data)
     }
+
+    /// Quantizes each slice of the tensor along its first dimension with its own scaling factor,
+    /// e.g. each row of a matrix or each output channel of a convolution filter
+    pub fn quantize_rows(self, s: &[ScalingFactor]) -> Tensor<Element> {
+        assert_eq!(
+            self.shape.first(),
+            Some(&s.len()),
+            "Found {} scaling factors to quantize a tensor of shape {:?}",
+            s.len(),
+            self.shape
+        );
+        let row_len = self.data.len() / s.len();
+        let data = self
+            .data
+            .par_chunks(row_len)
+            .zip(s.par_iter())
+            .flat_map_iter(|(row, s)| row.iter().map(|x| s.quantize(x)))
+            .collect::<Vec<_>>();
+        Tensor::new(self.shape, data)
+    }
 }
 
 impl<T> Tensor<T> {
@@ -659,18 +708,25 @@ impl<T> Tensor<T> {
     pub fn kw(&self) -> usize {
         self.shape[0]
     }
-    pub fn nw(&self) -> usize {
+    pub fn nh(&self) -> usize {
         self.shape[2]
     }
-    pub fn real_nw(&self) -> usize {
+    pub fn nw(&self) -> usize {
+        self.shape[3]
+    }
+    pub fn real_nh(&self) -> usize {
         self.og_shape[2]
     }
+    pub fn real_nw(&self) -> usize {
+        self.og_shape[3]
+    }
     pub fn real_shape(&self) -> Vec<usize> {
         self.og_shape.clone()
     }
-    // Returns the size of an individual filter
+    // Returns the size of an individual filter, i.e. the product of the last two dimensions
     pub fn filter_size(&self) -> usize {
-        self.shape[2] * self.shape[2]
+        let dims = self.shape.len();
+        self.shape[dims - 2] * self.shape[dims - 1]
     }
 }
 
@@ -1053,18 +1109,18 @@ where
     pub fn cnn_naive_convolution(&self, xt: &Tensor<T>) -> Tensor<T> {
         let k_w = self.shape[0];
         let k_x = self.shape[1];
-        let n_w = self.shape[2];
+        let (n_h, n_w) = (self.shape[2], self.shape[3]);
         let n = xt.shape[0];
+        let (x_h, x_w) = (xt.shape[1], xt.shape[2]);
         let mut ctr = 0;
         assert!(n == k_x, "Inconsistency on filter/input vector");
 
         let mut w: Vec<Vec<Vec<Vec<T>>>> =
-            vec![vec![vec![vec![Default::default(); n_w]; n_w]; k_x]; k_w];
-        let mut x: Vec<Vec<Vec<T>>> =
-            vec![vec![vec![Default::default(); xt.shape[1]]; xt.shape[1]]; n];
+            vec![vec![vec![vec![Default::default(); n_w]; n_h]; k_x]; k_w];
+        let mut x: Vec<Vec<Vec<T>>> = vec![vec![vec![Default::default(); x_w]; x_h]; n];
         for k in 0..k_w {
             for l in 0..k_x {
-                for i in 0..n_w {
+                for i in 0..n_h {
                     for j in 0..n_w {
                         w[k][l][i][j] = self.data[ctr];
                         ctr += 1;
@@ -1074,15 +1130,15 @@ where
         }
         ctr = 0;
         for k in 0..n {
-            for i in 0..xt.shape[1] {
-                for j in 0..xt.shape[1] {
+            for i in 0..x_h {
+                for j in 0..x_w {
                     x[k][i][j] = xt.data[ctr];
                     ctr += 1;
                 }
             }
         }
         let mut conv: Vec<Vec<Vec<T>>> =
-            vec![vec![vec![Default::default(); xt.shape[1] - n_w + 1]; xt.shape[1] - n_w + 1]; k_w];
+            vec![vec![vec![Default::default(); x_w - n_w + 1]; x_h - n_h + 1]; k_w];
 
         for i in 0..k_w {
             for j in 0..k_x {
@@ -1092,7 +1148,7 @@ where
         }
 
         return Tensor::new(
-            vec![k_w, xt.shape[1] - n_w + 1, xt.shape[1] - n_w + 1],
+            vec![k_w, x_h - n_h + 1, x_w - n_w + 1],
             conv.into_iter()
                 .flat_map(|inner_vec| inner_vec.into_iter())
                 .flat_map(|inner_inner_vec| inner_inner_vec.into_iter())
@@ -1117,6 +1173,108 @@ where
 
         result
     }
+    /// Permutes the dimensions of the tensor: dimension `i` of the output is dimension `perm[i]`
+    /// of the input. It generalizes `transpose` to any number of dimensions.
+    pub fn permute_axes(&self, perm: &[usize]) -> Tensor<T> {
+        let shape = self.get_shape();
+        assert!(
+            perm.iter().copied().sorted().eq(0..shape.len()),
+            "Invalid permutation {:?} for a tensor of shape {:?}",
+            perm,
+            shape
+        );
+        let new_shape = perm.iter().map(|&p| shape[p]).collect_vec();
+        // stride of each input dimension, in the order of the output dimensions
+        let strides = perm
+            .iter()
+            .map(|&p| shape[p + 1..].iter().product::<usize>())
+            .collect_vec();
+
+        let mut result = Tensor::zeros(new_shape.clone());
+        result
+            .data
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(idx, val)| {
+                let (_, input_idx) = new_shape.iter().zip(&strides).rev().fold(
+                    (idx, 0),
+                    |(rest, input_idx), (dim, stride)| {
+                        (rest / dim, input_idx + (rest % dim) * stride)
+                    },
+                );
+                *val = self.data[input_idx];
+            });
+
+        result
+    }
+    /// Concatenates the tensors along dimension `axis`. All the tensors must have the same shape,
+    /// except along `axis`.
+    pub fn concat_axis(tensors: &[&Tensor<T>], axis: usize) -> Tensor<T> {
+        assert!(!tensors.is_empty(), "No tensor to concatenate");
+        let shape = tensors[0].get_shape();
+        assert!(
+            axis < shape.len(),
+            "Invalid axis {axis} for shape {shape:?}"
+        );
+        assert!(
+            tensors.iter().all(|t| {
+                let s = t.get_shape();
+                s.len() == shape.len()
+                    && s.iter()
+                        .zip(&shape)
+                        .enumerate()
+                        .all(|(i, (a, b))| i == axis || a == b)
+            }),
+            "Can't concatenate tensors of shapes {:?} along axis {axis}",
+            tensors.iter().map(|t| t.get_shape()).collect_vec()
+        );
+        let outer = shape[..axis].iter().product::<usize>();
+        let inner = shape[axis + 1..].iter().product::<usize>();
+        let mut new_shape = shape.clone();
+        new_shape[axis] = tensors.iter().map(|t| t.get_shape()[axis]).sum();
+        // each tensor contributes a contiguous chunk to every index of the outer dimensions
+        let data = (0..outer)
+            .flat_map(|o| {
+                tensors.iter().flat_map(move |t| {
+                    let chunk = t.get_shape()[axis] * inner;
+                    t.data[o * chunk..(o + 1) * chunk].iter().cloned()
+                })
+            })
+            .collect_vec();
+        Tensor::new(new_shape, data)
+    }
+    /// Splits the tensor along dimension `axis` into tensors of sizes `sizes` along this dimension.
+    /// It is the inverse of `concat_axis`.
+    pub fn split_axis(&self, axis: usize, sizes: &[usize]) -> Vec<Tensor<T>> {
+        let shape = self.get_shape();
+        assert!(
+            axis < shape.len(),
+            "Invalid axis {axis} for shape {shape:?}"
+        );
+        assert_eq!(
+            sizes.iter().sum::<usize>(),
+            shape[axis],
+            "Can't split dimension {axis} of shape {shape:?} into sizes {sizes:?}"
+        );
+        let outer = shape[..axis].iter().product::<usize>();
+        let inner = shape[axis + 1..].iter().product::<usize>();
+        let mut offset = 0;
+        sizes
+            .iter()
+            .map(|&size| {
+                let data = (0..outer)
+                    .flat_map(|o| {
+                        let start = (o * shape[axis] + offset) * inner;
+                        self.data[start..start + size * inner].iter().cloned()
+                    })
+                    .collect_vec();
+                offset += size;
+                let mut new_shape = shape.clone();
+                new_shape[axis] = size;
+                Tensor::new(new_shape, data)
+            })
+            .collect()
+    }
     /// Concatenate a matrix (2D tensor) with a vector (1D tensor) as columns
     pub fn concat_matvec_col(&self, vector: &Tensor<T>) -> Tensor<T> {
         assert!(self.is_matrix(), "First tensor is not a matrix.");
@@ -1235,6 +1393,83 @@ where
         }
     }
 
+    /// Sums the values of each `kernel_size x kernel_size` window over the last two dimensions
+    /// of the tensor. It corresponds to an average pooling without the final division.
+    pub fn sum_pool2d(&self, kernel_size: usize, stride: usize) -> Tensor<T> {
+        let dims = self.get_shape().len();
+        assert!(dims >= 2, "Input tensor must have at least 2 dimensions.");
+
+        let (h, w) = (self.shape[dims - 2], self.shape[dims - 1]);
+        assert!(
+            h >= kernel_size && w >= kernel_size,
+            "Kernel size ({}) is larger than input dimensions ({}, {})",
+            kernel_size,
+            h,
+            w
+        );
+        let out_h = (h - kernel_size) / stride + 1;
+        let out_w = (w - kernel_size) / stride + 1;
+
+        let outer_dims: usize = self.shape[..dims - 2].iter().product();
+        let output: Vec<T> = (0..outer_dims * out_h * out_w)
+            .into_par_iter()
+            .map(|flat_idx| {
+                let n = flat_idx / (out_h * out_w);
+                let i = (flat_idx / out_w) % out_h;
+                let j = flat_idx % out_w;
+
+                let matrix_idx = n * (h * w);
+                (0..kernel_size)
+                    .flat_map(|ki| {
+                        (0..kernel_size).map(move |kj| {
+                            self.data[matrix_idx + (i * stride + ki) * w + (j * stride + kj)]
+                        })
+                    })
+                    .sum()
+            })
+            .collect();
+
+        let mut new_shape = self.shape.clone();
+        new_shape[dims - 2] = out_h;
+        new_shape[dims - 1] = out_w;
+
+        Tensor::new(new_shape, output)
+    }
+
+    /// Sums, for each matrix in the last two dimensions of the tensor, the values in the top-left
+    /// `height x width` corner, ignoring the remaining (e.g. padded) rows and columns.
+    /// The output has shape `[..., 1, 1]`.
+    pub fn spatial_sum(&self, height: usize, width: usize) -> Tensor<T> {
+        let dims = self.get_shape().len();
+        assert!(dims >= 2, "Input tensor must have at least 2 dimensions.");
+
+        let (h, w) = (self.shape[dims - 2], self.shape[dims - 1]);
+        assert!(
+            height <= h && width <= w,
+            "Summed area ({}, {}) is larger than input dimensions ({}, {})",
+            height,
+            width,
+            h,
+            w
+        );
+        let outer_dims: usize = self.shape[..dims - 2].iter().product();
+        let output: Vec<T> = (0..outer_dims)
+            .into_par_iter()
+            .map(|n| {
+                let matrix_idx = n * (h * w);
+                (0..height)
+                    .flat_map(|i| (0..width).map(move |j| self.data[matrix_idx + i * w + j]))
+                    .sum()
+            })
+            .collect();
+
+        let mut new_shape = self.shape.clone();
+        new_shape[dims - 2] = 1;
+        new_shape[dims - 1] = 1;
+
+        Tensor::new(new_shape, output)
+    }
+
     // Replaces every value of a tensor with the maxpool of its kernel
     pub fn padded_maxpool2d(&self) -> (Tensor<T>, Tensor<T>) {
         let kernel_size = MAXPOOL2D_KERNEL_SIZE;
@@ -1626,6 +1861,42 @@ mod test {
         assert_eq!(result, matrix_b, "Matrix transpose failed.");
     }
 
+    #[test]
+    fn test_tensor_permute_axes() {
+        let matrix = Tensor::<Element>::random(&[3, 5]);
+        assert_eq!(matrix.permute_axes(&[1, 0]), matrix.transpose());
+
+        let shape = vec![2, 3, 4];
+        let tensor = Tensor::<Element>::random(&shape);
+        let result = tensor.permute_axes(&[2, 0, 1]);
+        assert_eq!(result.get_shape(), vec![4, 2, 3]);
+        for i in 0..2 {
+            for j in 0..3 {
+                for k in 0..4 {
+                    assert_eq!(
+                        result.get_data()[k * 6 + i * 3 + j],
+                        tensor.get_data()[i * 12 + j * 4 + k],
+                        "Permutation of axes failed."
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tensor_concat_split_axis() {
+        let a = Tensor::new(vec![2, 2], vec![1, 2, 3, 4]);
+        let b = Tensor::new(vec![2, 1], vec![5, 6]);
+        let result = Tensor::concat_axis(&[&a, &b], 1);
+        assert_eq!(result, Tensor::new(vec![2, 3], vec![1, 2, 5, 3, 4, 6]));
+        assert_eq!(result.split_axis(1, &[2, 1]), vec![a.clone(), b]);
+
+        let c = Tensor::new(vec![1, 2], vec![7, 8]);
+        let result = Tensor::concat_axis(&[&a, &c], 0);
+        assert_eq!(result, Tensor::new(vec![3, 2], vec![1, 2, 3, 4, 7, 8]));
+        assert_eq!(result.split_axis(0, &[2, 1]), vec![a, c]);
+    }
+
     #[test]
     fn test_tensor_next_pow_of_two() {
         let shape = vec![3usize, 3];
@@ -1720,6 +1991,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_conv_rectangular() {
+        // non-square filters on non-square inputs
+        for (filter_shape, input_shape) in [
+            (vec![2, 3, 2, 4], vec![3, 8, 16]),
+            (vec![4, 2, 4, 1], vec![2, 16, 4]),
+            (vec![1, 1, 1, 8], vec![1, 4, 32]),
+        ] {
+            let filter = Tensor::random(&filter_shape);
+            let fft_filter = filter.clone().into_fft_conv(&input_shape);
+            assert_eq!(
+                (fft_filter.nh(), fft_filter.nw()),
+                (input_shape[1], input_shape[2])
+            );
+            let input = Tensor::random(&input_shape);
+            let (fft_output, _) = fft_filter.fft_conv::<GoldilocksExt2>(&input);
+            assert_eq!(fft_output.get_shape(), vec![
+                filter_shape[0],
+                input_shape[1],
+                input_shape[2]
+            ]);
+            check_tensor_consistency(filter.cnn_naive_convolution(&input), fft_output);
+        }
+    }
+
     #[test]
     fn test_tensor_ext_ops() {
         let matrix_a_data = vec![1 as Element, 2, 3, 4, 5, 6, 7, 8, 9];
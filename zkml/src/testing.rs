@@ -13,7 +13,7 @@ pub fn _random_vector<E: ExtensionField>(n: usize) -> Vec<E> {
 pub fn random_vector(n: usize) -> Vec<Element> {
     let mut rng = thread_rng();
     (0..n)
-        .map(|_| rng.gen_range(*quantization::MIN..=*quantization::MAX))
+        .map(|_| rng.gen_range(quantization::MIN..=quantization::MAX))
         .collect_vec()
 }
 
@@ -34,6 +34,6 @@ pub fn random_vector_seed(n: usize, seed: Option<u64>) -> Vec<Element> {
     let seed = seed.unwrap_or(rand::random::<u64>()); // Use provided seed or default
     let mut rng = StdRng::seed_from_u64(seed);
     (0..n)
-        .map(|_| rng.gen_range(*quantization::MIN..=*quantization::MAX))
+        .map(|_| rng.gen_range(quantization::MIN..=quantization::MAX))
         .collect_vec()
 }